@@ -0,0 +1,241 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! The few OpenGL functions needed for state CSFML does not expose.
+
+use std::ffi::CStr;
+use libc::{c_char, c_int, c_uint, c_uchar, c_float, c_void};
+
+pub type GLenum = c_uint;
+pub type GLint = c_int;
+pub type GLuint = c_uint;
+pub type GLsizei = c_int;
+pub type GLboolean = c_uchar;
+pub type GLubyte = c_uchar;
+pub type GLbitfield = c_uint;
+pub type GLfloat = c_float;
+pub type GLsizeiptr = isize;
+pub type GLintptr = isize;
+
+pub const GL_FALSE: GLboolean = 0;
+pub const GL_TRUE: GLboolean = 1;
+
+pub const GL_VERSION: GLenum = 0x1F02;
+
+pub const GL_STENCIL_BUFFER_BIT: GLbitfield = 0x0400;
+pub const GL_STENCIL_TEST: GLenum = 0x0B90;
+pub const GL_SCISSOR_TEST: GLenum = 0x0C11;
+
+pub const GL_NEVER: GLenum = 0x0200;
+pub const GL_LESS: GLenum = 0x0201;
+pub const GL_EQUAL: GLenum = 0x0202;
+pub const GL_LEQUAL: GLenum = 0x0203;
+pub const GL_GREATER: GLenum = 0x0204;
+pub const GL_NOTEQUAL: GLenum = 0x0205;
+pub const GL_GEQUAL: GLenum = 0x0206;
+pub const GL_ALWAYS: GLenum = 0x0207;
+
+pub const GL_ZERO: GLenum = 0;
+pub const GL_KEEP: GLenum = 0x1E00;
+pub const GL_REPLACE: GLenum = 0x1E01;
+pub const GL_INCR: GLenum = 0x1E02;
+pub const GL_DECR: GLenum = 0x1E03;
+pub const GL_INVERT: GLenum = 0x150A;
+pub const GL_INCR_WRAP: GLenum = 0x8507;
+pub const GL_DECR_WRAP: GLenum = 0x8508;
+
+pub const GL_ONE: GLenum = 1;
+pub const GL_SRC_COLOR: GLenum = 0x0300;
+pub const GL_ONE_MINUS_SRC_COLOR: GLenum = 0x0301;
+pub const GL_SRC_ALPHA: GLenum = 0x0302;
+pub const GL_ONE_MINUS_SRC_ALPHA: GLenum = 0x0303;
+pub const GL_DST_ALPHA: GLenum = 0x0304;
+pub const GL_ONE_MINUS_DST_ALPHA: GLenum = 0x0305;
+pub const GL_DST_COLOR: GLenum = 0x0306;
+pub const GL_ONE_MINUS_DST_COLOR: GLenum = 0x0307;
+pub const GL_CONSTANT_COLOR: GLenum = 0x8001;
+pub const GL_ONE_MINUS_CONSTANT_COLOR: GLenum = 0x8002;
+pub const GL_CONSTANT_ALPHA: GLenum = 0x8003;
+pub const GL_ONE_MINUS_CONSTANT_ALPHA: GLenum = 0x8004;
+
+pub const GL_FUNC_ADD: GLenum = 0x8006;
+pub const GL_MIN: GLenum = 0x8007;
+pub const GL_MAX: GLenum = 0x8008;
+pub const GL_FUNC_SUBTRACT: GLenum = 0x800A;
+pub const GL_FUNC_REVERSE_SUBTRACT: GLenum = 0x800B;
+
+pub const GL_POINTS: GLenum = 0x0000;
+pub const GL_LINES: GLenum = 0x0001;
+pub const GL_LINE_STRIP: GLenum = 0x0003;
+pub const GL_TRIANGLES: GLenum = 0x0004;
+pub const GL_TRIANGLE_STRIP: GLenum = 0x0005;
+pub const GL_TRIANGLE_FAN: GLenum = 0x0006;
+pub const GL_QUADS: GLenum = 0x0007;
+
+pub const GL_FLOAT: GLenum = 0x1406;
+pub const GL_UNSIGNED_BYTE: GLenum = 0x1401;
+
+pub const GL_ARRAY_BUFFER: GLenum = 0x8892;
+pub const GL_STREAM_DRAW: GLenum = 0x88E0;
+pub const GL_STATIC_DRAW: GLenum = 0x88E4;
+pub const GL_DYNAMIC_DRAW: GLenum = 0x88E8;
+
+#[cfg_attr(target_os="linux", link(name="GL"))]
+#[cfg_attr(target_os="windows", link(name="opengl32"))]
+#[cfg_attr(target_os="macos", link(name="OpenGL", kind="framework"))]
+extern "system" {
+	pub fn glEnable(cap: GLenum);
+	pub fn glDisable(cap: GLenum);
+	pub fn glStencilFunc(func: GLenum, reference: GLint, mask: GLuint);
+	pub fn glStencilOp(fail: GLenum, zfail: GLenum, zpass: GLenum);
+	pub fn glStencilMask(mask: GLuint);
+	pub fn glColorMask(red: GLboolean, green: GLboolean, blue: GLboolean, alpha: GLboolean);
+	pub fn glScissor(x: GLint, y: GLint, width: GLsizei, height: GLsizei);
+	pub fn glClearStencil(s: GLint);
+	pub fn glClear(mask: GLbitfield);
+	pub fn glVertexPointer(size: GLint, kind: GLenum, stride: GLsizei, pointer: *const c_void);
+	pub fn glColorPointer(size: GLint, kind: GLenum, stride: GLsizei, pointer: *const c_void);
+	pub fn glTexCoordPointer(size: GLint, kind: GLenum, stride: GLsizei, pointer: *const c_void);
+	pub fn glDrawArrays(mode: GLenum, first: GLint, count: GLsizei);
+	pub fn glGetString(name: GLenum) -> *const GLubyte;
+}
+
+/// Get the OpenGL version of the active context as (major, minor), or
+/// (0, 0) if no context is active.
+pub fn get_version() -> (u32, u32) {
+	let version = unsafe { glGetString(GL_VERSION) };
+	if version.is_null() {
+		return (0, 0)
+	}
+	parse_version(unsafe { CStr::from_ptr(version as *const c_char) }.to_bytes())
+}
+
+// Parse the "major.minor" at the start of a version string, skipping any
+// vendor prefix such as "OpenGL ES ".
+fn parse_version(version: &[u8]) -> (u32, u32) {
+	let start = version.iter().position(|c| c.is_ascii_digit()).unwrap_or(version.len());
+	let mut numbers = version[start..].split(|c| *c == b'.').map(|part| {
+		part.iter().take_while(|c| c.is_ascii_digit())
+			.fold(0u32, |n, c| n.saturating_mul(10).saturating_add((c - b'0') as u32))
+	});
+	(numbers.next().unwrap_or(0), numbers.next().unwrap_or(0))
+}
+
+// Functions from OpenGL 1.4 to 2.0. Windows only exports OpenGL 1.1, so
+// they are looked up in the current context there.
+#[cfg(not(target_os="windows"))]
+#[cfg_attr(target_os="linux", link(name="GL"))]
+#[cfg_attr(target_os="macos", link(name="OpenGL", kind="framework"))]
+extern "system" {
+	pub fn glBlendFuncSeparate(src_rgb: GLenum, dst_rgb: GLenum, src_alpha: GLenum, dst_alpha: GLenum);
+	pub fn glBlendEquationSeparate(mode_rgb: GLenum, mode_alpha: GLenum);
+	pub fn glBlendColor(red: GLfloat, green: GLfloat, blue: GLfloat, alpha: GLfloat);
+	pub fn glGenBuffers(n: GLsizei, buffers: *mut GLuint);
+	pub fn glDeleteBuffers(n: GLsizei, buffers: *const GLuint);
+	pub fn glBindBuffer(target: GLenum, buffer: GLuint);
+	pub fn glBufferData(target: GLenum, size: GLsizeiptr, data: *const c_void, usage: GLenum);
+	pub fn glBufferSubData(target: GLenum, offset: GLintptr, size: GLsizeiptr, data: *const c_void);
+}
+
+/// Tell whether the blending functions of OpenGL 2.0 can be used. Needs an
+/// active context.
+#[cfg(not(target_os="windows"))]
+pub fn has_blend_functions() -> bool {
+	get_version() >= (2, 0)
+}
+
+/// Tell whether the buffer object functions of OpenGL 1.5 can be used.
+/// Needs an active context.
+#[cfg(not(target_os="windows"))]
+pub fn has_buffer_objects() -> bool {
+	get_version() >= (1, 5)
+}
+
+#[cfg(target_os="windows")]
+pub use self::windows::*;
+
+#[cfg(target_os="windows")]
+#[allow(non_snake_case)]
+mod windows {
+	use std::mem;
+	use libc::{c_char, c_void};
+	use super::{get_version, GLenum, GLfloat, GLintptr, GLsizei, GLsizeiptr, GLuint};
+
+	#[link(name="opengl32")]
+	extern "system" {
+		fn wglGetProcAddress(name: *const c_char) -> *const c_void;
+	}
+
+	// Look up an extension function. Callers check `has_blend_functions` or
+	// `has_buffer_objects` first; a function the driver lacks is never
+	// called through a null pointer all the same.
+	macro_rules! gl_extension {
+		($name:ident($($arg:ident: $ty:ty),*)) => {
+			pub unsafe fn $name($($arg: $ty),*) {
+				let function = wglGetProcAddress(concat!(stringify!($name), "\0").as_ptr() as *const c_char);
+				if !function.is_null() {
+					let function: extern "system" fn($($ty),*) = mem::transmute(function);
+					function($($arg),*)
+				}
+			}
+		}
+	}
+
+	gl_extension!(glBlendFuncSeparate(src_rgb: GLenum, dst_rgb: GLenum, src_alpha: GLenum, dst_alpha: GLenum));
+	gl_extension!(glBlendEquationSeparate(mode_rgb: GLenum, mode_alpha: GLenum));
+	gl_extension!(glBlendColor(red: GLfloat, green: GLfloat, blue: GLfloat, alpha: GLfloat));
+	gl_extension!(glGenBuffers(n: GLsizei, buffers: *mut GLuint));
+	gl_extension!(glDeleteBuffers(n: GLsizei, buffers: *const GLuint));
+	gl_extension!(glBindBuffer(target: GLenum, buffer: GLuint));
+	gl_extension!(glBufferData(target: GLenum, size: GLsizeiptr, data: *const c_void, usage: GLenum));
+	gl_extension!(glBufferSubData(target: GLenum, offset: GLintptr, size: GLsizeiptr, data: *const c_void));
+
+	fn has_functions(names: &[&[u8]]) -> bool {
+		names.iter().all(|name| unsafe { !wglGetProcAddress(name.as_ptr() as *const c_char).is_null() })
+	}
+
+	/// Tell whether the blending functions of OpenGL 2.0 can be used.
+	/// Needs an active context.
+	pub fn has_blend_functions() -> bool {
+		get_version() >= (2, 0) &&
+			has_functions(&[b"glBlendFuncSeparate\0", b"glBlendEquationSeparate\0", b"glBlendColor\0"])
+	}
+
+	/// Tell whether the buffer object functions of OpenGL 1.5 can be used.
+	/// Needs an active context.
+	pub fn has_buffer_objects() -> bool {
+		get_version() >= (1, 5) &&
+			has_functions(&[b"glGenBuffers\0", b"glDeleteBuffers\0", b"glBindBuffer\0", b"glBufferData\0",
+			                b"glBufferSubData\0"])
+	}
+}
+
+#[test]
+fn gl_version_strings() {
+	assert_eq!(parse_version(b"2.1 Mesa 20.0.8"), (2, 1));
+	assert_eq!(parse_version(b"4.6.0 NVIDIA 535.54"), (4, 6));
+	assert_eq!(parse_version(b"OpenGL ES 3.2"), (3, 2));
+	assert_eq!(parse_version(b"1.4"), (1, 4));
+	assert_eq!(parse_version(b""), (0, 0));
+}
@@ -46,6 +46,7 @@ macro_rules! foreign_type {
 pub mod window;
 pub mod graphics;
 pub mod audio;
+pub mod gl;
 
 /// Encode a string in UTF-32 for passing into SFML.
 pub fn to_utf32(string: &str) -> Vec<u32> {
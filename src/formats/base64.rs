@@ -0,0 +1,89 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Base64 decoding (RFC 4648, standard alphabet).
+
+fn value(byte: u8) -> Option<u32> {
+	match byte {
+		b'A'...b'Z' => Some((byte - b'A') as u32),
+		b'a'...b'z' => Some((byte - b'a') as u32 + 26),
+		b'0'...b'9' => Some((byte - b'0') as u32 + 52),
+		b'+' => Some(62),
+		b'/' => Some(63),
+		_ => None
+	}
+}
+
+/// Decode a base64 string, ignoring any whitespace it contains.
+pub fn decode(text: &str) -> Result<Vec<u8>, &'static str> {
+	let mut out = Vec::with_capacity(text.len() * 3 / 4);
+	let mut acc = 0u32;
+	let mut count = 0;
+	let mut padding = 0;
+	for &byte in text.as_bytes() {
+		match byte {
+			b' ' | b'\t' | b'\r' | b'\n' => continue,
+			b'=' => {
+				padding += 1;
+				continue
+			}
+			_ => {}
+		}
+		if padding > 0 {
+			return Err("base64 data after padding")
+		}
+		let v = match value(byte) {
+			Some(v) => v,
+			None => return Err("invalid base64 character")
+		};
+		acc = (acc << 6) | v;
+		count += 1;
+		if count == 4 {
+			out.push((acc >> 16) as u8);
+			out.push((acc >> 8) as u8);
+			out.push(acc as u8);
+			acc = 0;
+			count = 0;
+		}
+	}
+	match count {
+		0 => {}
+		2 => out.push((acc >> 4) as u8),
+		3 => {
+			out.push((acc >> 10) as u8);
+			out.push((acc >> 2) as u8);
+		}
+		_ => return Err("truncated base64 data")
+	}
+	Ok(out)
+}
+
+#[test]
+fn base64_decode() {
+	assert_eq!(decode("aGVsbG8=").unwrap(), b"hello");
+	assert_eq!(decode(" aGVs\nbG8h ").unwrap(), b"hello!");
+	assert_eq!(decode("aGk").unwrap(), b"hi");
+	assert!(decode("a").is_err());
+	assert!(decode("aG=k").is_err());
+}
@@ -0,0 +1,114 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! BMP encoding of 8-bit RGBA images, and reading of BMP headers.
+//!
+//! Images are written with 32 bits per pixel and a version 4 header, whose
+//! channel masks keep the alpha channel.
+
+use formats::frames::Header;
+
+fn push_u16(out: &mut Vec<u8>, value: u16) {
+	out.extend(&[value as u8, (value >> 8) as u8]);
+}
+
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+	out.extend(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+/// Encode RGBA pixels as a BMP image.
+pub fn encode(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
+	const HEADERS: u32 = 14 + 108;
+	let size = width * height * 4;
+	let mut out = Vec::with_capacity((HEADERS + size) as usize);
+	out.extend(b"BM");
+	push_u32(&mut out, HEADERS + size);
+	push_u32(&mut out, 0);
+	push_u32(&mut out, HEADERS);
+
+	push_u32(&mut out, 108);
+	push_u32(&mut out, width);
+	push_u32(&mut out, height);
+	push_u16(&mut out, 1);
+	push_u16(&mut out, 32);
+	// BI_BITFIELDS, with the red, green, blue and alpha masks below
+	push_u32(&mut out, 3);
+	push_u32(&mut out, size);
+	push_u32(&mut out, 2835);
+	push_u32(&mut out, 2835);
+	push_u32(&mut out, 0);
+	push_u32(&mut out, 0);
+	for &mask in &[0x00ff0000, 0x0000ff00, 0x000000ff, 0xff000000] {
+		push_u32(&mut out, mask);
+	}
+	out.extend(b"BGRs");
+	out.extend(&[0; 48]);
+
+	// Rows are stored bottom to top
+	let stride = width as usize * 4;
+	for row in pixels.chunks(stride.max(1)).rev() {
+		for p in row.chunks(4) {
+			out.extend(&[p[2], p[1], p[0], p[3]]);
+		}
+	}
+	out
+}
+
+/// Read the properties of a BMP image.
+pub fn read_header(data: &[u8]) -> Result<Header, &'static str> {
+	if data.len() < 30 || &data[..2] != b"BM" {
+		return Err("not a BMP image")
+	}
+	let read = |at: usize| data[at] as u32 | (data[at + 1] as u32) << 8 | (data[at + 2] as u32) << 16 | (data[at + 3] as u32) << 24;
+	let bits_per_pixel = data[28] as u32 | (data[29] as u32) << 8;
+	let (bit_depth, channels) = match bits_per_pixel {
+		32 => (8, 4),
+		24 => (8, 3),
+		16 => (5, 3),
+		bits => (bits as u8, 1)
+	};
+	Ok(Header {
+		width: read(18),
+		// Negative heights are used by images stored top to bottom
+		height: (read(22) as i32).abs() as u32,
+		bit_depth: bit_depth,
+		channels: channels,
+		gamma: None,
+		frame_count: 1,
+		loop_count: 0
+	})
+}
+
+#[test]
+fn bmp_encode() {
+	let pixels = [1, 2, 3, 4, 5, 6, 7, 8];
+	let bmp = encode(&pixels, 1, 2);
+	assert_eq!(&bmp[..2], b"BM");
+	assert_eq!(bmp.len(), 122 + 8);
+	assert_eq!(&bmp[2..6], &[130, 0, 0, 0]);
+	assert_eq!(&bmp[10..14], &[122, 0, 0, 0]);
+	assert_eq!(&bmp[122..], &[7, 6, 5, 8, 3, 2, 1, 4]);
+	let header = read_header(&bmp).unwrap();
+	assert_eq!((header.width, header.height, header.bit_depth, header.channels), (1, 2, 8, 4));
+}
@@ -0,0 +1,289 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Decoding of DirectDraw Surface (DDS) images.
+//!
+//! Supports uncompressed RGB, luminance and alpha surfaces of 8 to 32 bits
+//! per pixel, and DXT1, DXT3 and DXT5 compression. Only the main surface is
+//! decoded: mipmaps, cube maps and volume textures are ignored.
+
+use formats::frames::{self, Decoded, Frame, Header};
+
+type DdsResult<T> = Result<T, &'static str>;
+
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_ALPHA: u32 = 0x2;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x20000;
+
+// Offset of the pixels, after the magic number and the header.
+const DATA_OFFSET: usize = 128;
+
+enum PixelFormat {
+	// Bytes per pixel, and the red, green, blue and alpha masks
+	Masks(usize, [u32; 4]),
+	Dxt1,
+	Dxt3,
+	Dxt5
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+	data[at] as u32 | (data[at + 1] as u32) << 8 | (data[at + 2] as u32) << 16 | (data[at + 3] as u32) << 24
+}
+
+fn parse_header(data: &[u8]) -> DdsResult<(Header, PixelFormat)> {
+	if data.len() < DATA_OFFSET || &data[..4] != b"DDS " || read_u32(data, 4) != 124 {
+		return Err("not a DDS image")
+	}
+	let flags = read_u32(data, 80);
+	let (format, bit_depth, channels) = if flags & DDPF_FOURCC != 0 {
+		match &data[84..88] {
+			b"DXT1" => (PixelFormat::Dxt1, 8, 4),
+			b"DXT3" => (PixelFormat::Dxt3, 8, 4),
+			b"DXT5" => (PixelFormat::Dxt5, 8, 4),
+			_ => return Err("unsupported DDS compression")
+		}
+	} else {
+		let bits = read_u32(data, 88);
+		if bits != 8 && bits != 16 && bits != 24 && bits != 32 {
+			return Err("unsupported DDS pixel size")
+		}
+		let alpha = if flags & (DDPF_ALPHAPIXELS | DDPF_ALPHA) != 0 { read_u32(data, 104) } else { 0 };
+		let (masks, color_channels) = if flags & DDPF_RGB != 0 {
+			([read_u32(data, 92), read_u32(data, 96), read_u32(data, 100), alpha], 3)
+		} else if flags & DDPF_LUMINANCE != 0 {
+			let luminance = read_u32(data, 92);
+			([luminance, luminance, luminance, alpha], 1)
+		} else if flags & DDPF_ALPHA != 0 {
+			([0, 0, 0, alpha], 0)
+		} else {
+			return Err("unsupported DDS pixel format")
+		};
+		let bit_depth = masks.iter().map(|mask| mask.count_ones()).max().unwrap_or(0);
+		(PixelFormat::Masks(bits as usize / 8, masks), bit_depth as u8, color_channels + if alpha != 0 { 1 } else { 0 })
+	};
+	Ok((Header {
+		width: read_u32(data, 16),
+		height: read_u32(data, 12),
+		bit_depth: bit_depth,
+		channels: channels,
+		gamma: None,
+		frame_count: 1,
+		loop_count: 0
+	}, format))
+}
+
+/// Read the properties of a DDS image.
+pub fn read_header(data: &[u8]) -> DdsResult<Header> {
+	parse_header(data).map(|(header, _)| header)
+}
+
+// Extract the channel under `mask` from a pixel, scaled to 8 bits. Missing
+// color channels are black, a missing alpha channel is opaque.
+fn channel(value: u32, mask: u32, alpha: bool) -> u8 {
+	if mask == 0 {
+		return if alpha { 255 } else { 0 }
+	}
+	let shift = mask.trailing_zeros();
+	let max = (mask >> shift) as u64;
+	((((value & mask) >> shift) as u64 * 255 + max / 2) / max) as u8
+}
+
+// Expand a 5:6:5 color to 8 bits per channel.
+fn rgb565(color: u16) -> [u8; 4] {
+	let (r, g, b) = ((color >> 11) as u32, (color >> 5 & 0x3f) as u32, (color & 0x1f) as u32);
+	[((r * 255 + 15) / 31) as u8, ((g * 255 + 31) / 63) as u8, ((b * 255 + 15) / 31) as u8, 255]
+}
+
+// Mix `a` and `b` with weights `wa` and `wb`.
+fn mix(a: [u8; 4], b: [u8; 4], wa: u32, wb: u32) -> [u8; 4] {
+	let mut out = [255; 4];
+	for i in 0..3 {
+		out[i] = ((a[i] as u32 * wa + b[i] as u32 * wb) / (wa + wb)) as u8;
+	}
+	out
+}
+
+// Decode the 16 pixels of a color block. DXT1 blocks whose first color is
+// not greater than the second have three colors and transparent black.
+fn decode_colors(block: &[u8], dxt1: bool, out: &mut [[u8; 4]; 16]) {
+	let c0 = block[0] as u16 | (block[1] as u16) << 8;
+	let c1 = block[2] as u16 | (block[3] as u16) << 8;
+	let (a, b) = (rgb565(c0), rgb565(c1));
+	let palette = if c0 > c1 || !dxt1 {
+		[a, b, mix(a, b, 2, 1), mix(a, b, 1, 2)]
+	} else {
+		[a, b, mix(a, b, 1, 1), [0, 0, 0, 0]]
+	};
+	let indices = read_u32(block, 4);
+	for (i, pixel) in out.iter_mut().enumerate() {
+		*pixel = palette[(indices >> (i * 2) & 3) as usize];
+	}
+}
+
+// Decode the 4-bit alphas of a DXT3 block.
+fn decode_explicit_alpha(block: &[u8], out: &mut [[u8; 4]; 16]) {
+	for (i, pixel) in out.iter_mut().enumerate() {
+		pixel[3] = (block[i / 2] >> (i % 2 * 4) & 0xf) * 17;
+	}
+}
+
+// Decode the interpolated alphas of a DXT5 block.
+fn decode_interpolated_alpha(block: &[u8], out: &mut [[u8; 4]; 16]) {
+	let (a0, a1) = (block[0] as u32, block[1] as u32);
+	let mut palette = [a0, a1, 0, 0, 0, 0, 0, 255];
+	if a0 > a1 {
+		for i in 1..7 {
+			palette[i + 1] = (a0 * (7 - i as u32) + a1 * i as u32) / 7;
+		}
+	} else {
+		for i in 1..5 {
+			palette[i + 1] = (a0 * (5 - i as u32) + a1 * i as u32) / 5;
+		}
+	}
+	let indices = block[2..8].iter().rev().fold(0u64, |bits, &b| bits << 8 | b as u64);
+	for (i, pixel) in out.iter_mut().enumerate() {
+		pixel[3] = palette[(indices >> (i * 3) & 7) as usize] as u8;
+	}
+}
+
+/// Decode the main surface of a DDS image to 8-bit RGBA.
+pub fn decode(data: &[u8]) -> DdsResult<Decoded> {
+	let (header, format) = try!(parse_header(data));
+	let (width, height) = (header.width as usize, header.height as usize);
+	// The canvas size bounds width * height, so the sizes below cannot overflow
+	let mut pixels = vec![0; try!(frames::canvas_size(header.width, header.height))];
+	let surface = &data[DATA_OFFSET..];
+	match format {
+		PixelFormat::Masks(bytes, masks) => {
+			if surface.len() < width * height * bytes {
+				return Err("DDS data is truncated")
+			}
+			for (pixel, source) in pixels.chunks_mut(4).zip(surface.chunks(bytes)) {
+				let value = source.iter().rev().fold(0u32, |value, &b| value << 8 | b as u32);
+				for i in 0..4 {
+					pixel[i] = channel(value, masks[i], i == 3);
+				}
+			}
+		}
+		_ => {
+			let block_size = match format { PixelFormat::Dxt1 => 8, _ => 16 };
+			let (columns, rows) = ((width + 3) / 4, (height + 3) / 4);
+			if surface.len() < columns * rows * block_size {
+				return Err("DDS data is truncated")
+			}
+			let mut texels = [[0; 4]; 16];
+			for (n, block) in surface.chunks(block_size).take(columns * rows).enumerate() {
+				match format {
+					PixelFormat::Dxt1 => decode_colors(block, true, &mut texels),
+					PixelFormat::Dxt3 => {
+						decode_colors(&block[8..], false, &mut texels);
+						decode_explicit_alpha(block, &mut texels);
+					}
+					_ => {
+						decode_colors(&block[8..], false, &mut texels);
+						decode_interpolated_alpha(block, &mut texels);
+					}
+				}
+				let (bx, by) = (n % columns * 4, n / columns * 4);
+				for (i, texel) in texels.iter().enumerate() {
+					let (x, y) = (bx + i % 4, by + i / 4);
+					if x < width && y < height {
+						let at = (y * width + x) * 4;
+						pixels[at..at + 4].copy_from_slice(texel);
+					}
+				}
+			}
+		}
+	}
+	Ok(Decoded {
+		header: header,
+		frames: vec![Frame { pixels: pixels, delay: 0 }]
+	})
+}
+
+#[cfg(test)]
+fn dds_image(width: u32, height: u32, flags: u32, four_cc: &[u8], bits: u32, masks: [u32; 4], surface: &[u8]) -> Vec<u8> {
+	let mut data = vec![0; DATA_OFFSET];
+	data[..4].copy_from_slice(b"DDS ");
+	{
+		let mut put = |at: usize, value: u32| for i in 0..4 {
+			data[at + i] = (value >> (i * 8)) as u8;
+		};
+		put(4, 124);
+		put(12, height);
+		put(16, width);
+		put(76, 32);
+		put(80, flags);
+		put(88, bits);
+		for (i, &mask) in masks.iter().enumerate() {
+			put(92 + i * 4, mask);
+		}
+	}
+	data[84..88].copy_from_slice(four_cc);
+	data.extend(surface);
+	data
+}
+
+#[test]
+fn dds_uncompressed() {
+	let bgra = [0x00ff0000, 0x0000ff00, 0x000000ff, 0xff000000];
+	let data = dds_image(2, 1, DDPF_RGB | DDPF_ALPHAPIXELS, b"\0\0\0\0", 32, bgra, &[1, 2, 3, 4, 5, 6, 7, 8]);
+	let header = read_header(&data).unwrap();
+	assert_eq!((header.width, header.height, header.bit_depth, header.channels), (2, 1, 8, 4));
+	assert_eq!(decode(&data).unwrap().frames[0].pixels, vec![3, 2, 1, 4, 7, 6, 5, 8]);
+	assert!(decode(&data[..data.len() - 1]).is_err());
+	// 5:6:5 without alpha, and 8-bit luminance
+	let data = dds_image(1, 1, DDPF_RGB, b"\0\0\0\0", 16, [0xf800, 0x07e0, 0x001f, 0], &[0xe0, 0x07]);
+	assert_eq!(read_header(&data).unwrap().channels, 3);
+	assert_eq!(decode(&data).unwrap().frames[0].pixels, vec![0, 255, 0, 255]);
+	let data = dds_image(1, 1, DDPF_LUMINANCE, b"\0\0\0\0", 8, [0xff, 0, 0, 0], &[100]);
+	assert_eq!(decode(&data).unwrap().frames[0].pixels, vec![100, 100, 100, 255]);
+}
+
+#[test]
+fn dds_compressed() {
+	// Red and blue, then the colors between them
+	let block = [0x00, 0xf8, 0x1f, 0x00, 0b00_00_01_00, 0b00_00_11_10, 0, 0];
+	let data = dds_image(2, 2, DDPF_FOURCC, b"DXT1", 0, [0; 4], &block);
+	let pixels = decode(&data).unwrap().frames.remove(0).pixels;
+	assert_eq!(pixels, vec![255, 0, 0, 255, 0, 0, 255, 255, 170, 0, 85, 255, 85, 0, 170, 255]);
+	// Three colors and transparent black when the first color is smaller
+	let block = [0x1f, 0x00, 0x00, 0xf8, 0b00_00_11_10, 0, 0, 0];
+	let data = dds_image(2, 1, DDPF_FOURCC, b"DXT1", 0, [0; 4], &block);
+	assert_eq!(decode(&data).unwrap().frames[0].pixels, vec![127, 0, 127, 255, 0, 0, 0, 0]);
+
+	let mut block = vec![0x0f, 0, 0, 0, 0, 0, 0, 0];
+	block.extend(&[0x00, 0xf8, 0x1f, 0x00, 0, 0, 0, 0]);
+	let data = dds_image(2, 1, DDPF_FOURCC, b"DXT3", 0, [0; 4], &block);
+	assert_eq!(decode(&data).unwrap().frames[0].pixels, vec![255, 0, 0, 255, 255, 0, 0, 0]);
+	// Alpha indices 0, 1 and 2 of 255 and 0
+	block[..8].copy_from_slice(&[255, 0, 0b10_001_000, 0, 0, 0, 0, 0]);
+	let data = dds_image(3, 1, DDPF_FOURCC, b"DXT5", 0, [0; 4], &block);
+	let pixels = decode(&data).unwrap().frames.remove(0).pixels;
+	assert_eq!((pixels[3], pixels[7], pixels[11]), (255, 0, 218));
+	assert!(decode(&data[..data.len() - 1]).is_err());
+	assert!(read_header(&dds_image(1, 1, DDPF_FOURCC, b"DX10", 0, [0; 4], &[])).is_err());
+}
@@ -0,0 +1,184 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! DEFLATE compression (RFC 1951) with the zlib (RFC 1950) wrapper.
+//!
+//! Matches are found with hash chains and encoded with the fixed Huffman
+//! codes, which compresses images well enough without the complexity of
+//! building dynamic codes.
+
+use formats::inflate::adler32;
+
+const WINDOW: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27,
+                                31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2,
+                                2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257,
+                              385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193,
+                              12289, 16385, 24577];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7,
+                              7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+struct BitWriter {
+	out: Vec<u8>,
+	buffer: u32,
+	count: u32
+}
+
+impl BitWriter {
+	fn bits(&mut self, value: u32, n: u32) {
+		self.buffer |= value << self.count;
+		self.count += n;
+		while self.count >= 8 {
+			self.out.push(self.buffer as u8);
+			self.buffer >>= 8;
+			self.count -= 8;
+		}
+	}
+
+	// Huffman codes are packed starting from their most significant bit.
+	fn code(&mut self, code: u32, n: u32) {
+		let reversed = (0..n).fold(0, |r, i| r | ((code >> i) & 1) << (n - 1 - i));
+		self.bits(reversed, n);
+	}
+
+	fn finish(mut self) -> Vec<u8> {
+		if self.count > 0 {
+			self.out.push(self.buffer as u8);
+		}
+		self.out
+	}
+}
+
+fn literal(writer: &mut BitWriter, symbol: u32) {
+	match symbol {
+		0...143 => writer.code(0x30 + symbol, 8),
+		144...255 => writer.code(0x190 + symbol - 144, 9),
+		256...279 => writer.code(symbol - 256, 7),
+		_ => writer.code(0xc0 + symbol - 280, 8)
+	}
+}
+
+fn back_reference(writer: &mut BitWriter, length: usize, distance: usize) {
+	let code = LENGTH_BASE.iter().rposition(|&base| base as usize <= length).unwrap();
+	literal(writer, 257 + code as u32);
+	writer.bits((length - LENGTH_BASE[code] as usize) as u32, LENGTH_EXTRA[code] as u32);
+	let code = DIST_BASE.iter().rposition(|&base| base as usize <= distance).unwrap();
+	writer.code(code as u32, 5);
+	writer.bits((distance - DIST_BASE[code] as usize) as u32, DIST_EXTRA[code] as u32);
+}
+
+fn hash(data: &[u8]) -> usize {
+	let value = (data[0] as u32) << 16 | (data[1] as u32) << 8 | data[2] as u32;
+	(value.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+}
+
+/// Compress data into a raw DEFLATE stream.
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+	let mut writer = BitWriter { out: Vec::new(), buffer: 0, count: 0 };
+	// A single final block with fixed codes
+	writer.bits(1, 1);
+	writer.bits(1, 2);
+
+	let mut head = vec![usize::max_value(); 1 << HASH_BITS];
+	let mut prev = vec![usize::max_value(); WINDOW];
+	let insert = |head: &mut Vec<usize>, prev: &mut Vec<usize>, pos: usize| {
+		if pos + MIN_MATCH <= data.len() {
+			let h = hash(&data[pos..]);
+			prev[pos % WINDOW] = head[h];
+			head[h] = pos;
+		}
+	};
+
+	let mut pos = 0;
+	while pos < data.len() {
+		let (mut best_length, mut best_distance) = (0, 0);
+		if pos + MIN_MATCH <= data.len() {
+			let max_length = (data.len() - pos).min(MAX_MATCH);
+			let mut candidate = head[hash(&data[pos..])];
+			let mut chain = 0;
+			while candidate != usize::max_value() && pos - candidate <= WINDOW && chain < MAX_CHAIN {
+				let length = data[candidate..].iter().zip(&data[pos..pos + max_length])
+					.take_while(|&(a, b)| a == b).count();
+				if length > best_length {
+					best_length = length;
+					best_distance = pos - candidate;
+					if length == max_length {
+						break
+					}
+				}
+				let next = prev[candidate % WINDOW];
+				if next == usize::max_value() || next >= candidate {
+					break
+				}
+				candidate = next;
+				chain += 1;
+			}
+		}
+		if best_length >= MIN_MATCH {
+			back_reference(&mut writer, best_length, best_distance);
+			for p in pos..pos + best_length {
+				insert(&mut head, &mut prev, p);
+			}
+			pos += best_length;
+		} else {
+			literal(&mut writer, data[pos] as u32);
+			insert(&mut head, &mut prev, pos);
+			pos += 1;
+		}
+	}
+	literal(&mut writer, 256);
+	writer.finish()
+}
+
+/// Compress data into a zlib stream.
+pub fn zlib_compress(data: &[u8]) -> Vec<u8> {
+	let mut out = vec![0x78, 0x5e];
+	out.extend(deflate(data));
+	let checksum = adler32(data);
+	out.extend(&[(checksum >> 24) as u8, (checksum >> 16) as u8, (checksum >> 8) as u8, checksum as u8]);
+	out
+}
+
+#[test]
+fn deflate_round_trip() {
+	use formats::inflate::zlib_decompress;
+
+	let mut x = 7u32;
+	let noise: Vec<u8> = (0..5000).map(|_| {
+		x = x.wrapping_mul(1103515245).wrapping_add(12345);
+		(x >> 16) as u8
+	}).collect();
+	let repeated: Vec<u8> = b"sfml tiles, ".iter().cycle().take(70000).cloned().collect();
+	for data in &[Vec::new(), b"a".to_vec(), b"abcabcabcabd".to_vec(), noise, repeated.clone()] {
+		assert_eq!(&zlib_decompress(&zlib_compress(data)).unwrap(), data);
+	}
+	assert!(zlib_compress(&repeated).len() < 1000);
+}
@@ -0,0 +1,131 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Decoded images with their frames, shared by the GIF and PNG decoders.
+
+/// Properties of an image file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Header {
+	/// Width of the image, or of the animation's canvas.
+	pub width: u32,
+	/// Height of the image, or of the animation's canvas.
+	pub height: u32,
+	/// Bits per channel, or per palette index.
+	pub bit_depth: u8,
+	/// Channels per pixel; 1 for palette images.
+	pub channels: u8,
+	/// Gamma the image was encoded with, if stored.
+	pub gamma: Option<f32>,
+	/// Number of frames.
+	pub frame_count: u32,
+	/// Number of times the animation plays, 0 meaning forever.
+	pub loop_count: u32
+}
+
+/// A frame of an animation, composited onto the full canvas.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+	/// RGBA pixels of the whole canvas.
+	pub pixels: Vec<u8>,
+	/// Time the frame stays on screen, in milliseconds.
+	pub delay: u32
+}
+
+/// A decoded image and its frames.
+pub struct Decoded {
+	/// Properties of the image.
+	pub header: Header,
+	/// Frames of the image; still images have one.
+	pub frames: Vec<Frame>
+}
+
+/// Largest canvas the decoders allocate, in bytes.
+pub const MAX_CANVAS_BYTES: usize = 1 << 30;
+
+/// Get the size in bytes of RGBA pixels of size `width` x `height`.
+///
+/// Fails if it exceeds `MAX_CANVAS_BYTES`, which protects against headers
+/// claiming huge images.
+pub fn canvas_size(width: u32, height: u32) -> Result<usize, &'static str> {
+	match (width as usize).checked_mul(height as usize).and_then(|pixels| pixels.checked_mul(4)) {
+		Some(size) if size <= MAX_CANVAS_BYTES => Ok(size),
+		_ => Err("image is too large")
+	}
+}
+
+/// Copy RGBA pixels of size `width` x `height` onto a canvas at `(x, y)`,
+/// clipped to the canvas. With `blend`, pixels are composited over the
+/// canvas; otherwise they replace it.
+pub fn blit(canvas: &mut [u8], canvas_width: u32, canvas_height: u32,
+            pixels: &[u8], x: u32, y: u32, width: u32, height: u32, blend: bool) {
+	let (x, y, width, canvas_width) = (x as usize, y as usize, width as usize, canvas_width as usize);
+	for row in 0..(height as usize).min((canvas_height as usize).saturating_sub(y)) {
+		for column in 0..width.min(canvas_width.saturating_sub(x)) {
+			let from = (row * width + column) * 4;
+			let to = ((y + row) * canvas_width + x + column) * 4;
+			let source = &pixels[from..from + 4];
+			let target = &mut canvas[to..to + 4];
+			if !blend || source[3] == 255 {
+				target.copy_from_slice(source);
+			} else if source[3] > 0 {
+				let (sa, da) = (source[3] as f32 / 255., target[3] as f32 / 255.);
+				let alpha = sa + da * (1. - sa);
+				for c in 0..3 {
+					let value = (source[c] as f32 * sa + target[c] as f32 * da * (1. - sa)) / alpha;
+					target[c] = (value + 0.5) as u8;
+				}
+				target[3] = (alpha * 255. + 0.5) as u8;
+			}
+		}
+	}
+}
+
+/// Make a rectangle of the canvas transparent, clipped to the canvas.
+pub fn clear(canvas: &mut [u8], canvas_width: u32, canvas_height: u32, x: u32, y: u32, width: u32, height: u32) {
+	let (x, width, canvas_width) = (x as usize, width as usize, canvas_width as usize);
+	for row in y as usize..(y as usize + height as usize).min(canvas_height as usize) {
+		let start = (row * canvas_width + x.min(canvas_width)) * 4;
+		let end = (row * canvas_width + (x + width).min(canvas_width)) * 4;
+		for value in &mut canvas[start..end] {
+			*value = 0;
+		}
+	}
+}
+
+#[test]
+fn frames_blit() {
+	let mut canvas = vec![0; 2 * 2 * 4];
+	blit(&mut canvas, 2, 2, &[10, 20, 30, 255, 1, 2, 3, 4], 1, 1, 2, 1, false);
+	assert_eq!(&canvas[12..], &[10, 20, 30, 255]);
+	blit(&mut canvas, 2, 2, &[110, 20, 30, 0, 0, 0, 0, 0], 1, 1, 1, 1, true);
+	assert_eq!(&canvas[12..], &[10, 20, 30, 255]);
+	blit(&mut canvas, 2, 2, &[110, 20, 30, 0, 210, 20, 30, 128], 0, 1, 2, 1, true);
+	assert_eq!(&canvas[8..], &[0, 0, 0, 0, 110, 20, 30, 255]);
+	clear(&mut canvas, 2, 2, 1, 0, 5, 5);
+	assert_eq!(canvas, vec![0; 16]);
+
+	assert_eq!(canvas_size(3, 2), Ok(24));
+	assert!(canvas_size(65535, 65535).is_err());
+	assert!(canvas_size(0xffffffff, 0xffffffff).is_err());
+}
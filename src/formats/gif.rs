@@ -0,0 +1,550 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! GIF decoding and encoding, including animations.
+
+use std::collections::HashMap;
+
+use formats::frames::{self, Decoded, Frame, Header};
+
+type GifResult<T> = Result<T, &'static str>;
+
+struct Reader<'a> {
+	data: &'a [u8],
+	pos: usize
+}
+
+impl<'a> Reader<'a> {
+	fn byte(&mut self) -> GifResult<u8> {
+		match self.data.get(self.pos) {
+			Some(&byte) => {
+				self.pos += 1;
+				Ok(byte)
+			}
+			None => Err("unexpected end of GIF data")
+		}
+	}
+
+	fn u16(&mut self) -> GifResult<u16> {
+		let low = try!(self.byte()) as u16;
+		Ok(low | (try!(self.byte()) as u16) << 8)
+	}
+
+	fn bytes(&mut self, n: usize) -> GifResult<&'a [u8]> {
+		if self.pos + n > self.data.len() {
+			return Err("unexpected end of GIF data")
+		}
+		self.pos += n;
+		Ok(&self.data[self.pos - n..self.pos])
+	}
+
+	// Concatenated data sub-blocks, up to their terminator.
+	fn sub_blocks(&mut self) -> GifResult<Vec<u8>> {
+		let mut data = Vec::new();
+		loop {
+			let size = try!(self.byte()) as usize;
+			if size == 0 {
+				return Ok(data)
+			}
+			data.extend(try!(self.bytes(size)));
+		}
+	}
+}
+
+/// Decompress GIF image data into `count` color indices.
+///
+/// Missing data at the end is filled with index 0, as many files are
+/// truncated.
+pub fn lzw_decode(data: &[u8], min_code_size: u8, count: usize) -> GifResult<Vec<u8>> {
+	if min_code_size < 1 || min_code_size > 11 {
+		return Err("invalid LZW code size")
+	}
+	let clear = 1usize << min_code_size;
+	let end = clear + 1;
+	let mut prefix = vec![0u16; 4096];
+	let mut suffix = vec![0u8; 4096];
+	let mut first = vec![0u8; 4096];
+	for i in 0..clear {
+		suffix[i] = i as u8;
+		first[i] = i as u8;
+	}
+	let mut code_size = min_code_size as u32 + 1;
+	let mut next = clear + 2;
+	let mut previous: Option<usize> = None;
+	let (mut buffer, mut bits, mut pos) = (0u32, 0u32, 0);
+	let mut out = Vec::with_capacity(count);
+	let mut stack = Vec::new();
+
+	while out.len() < count {
+		while bits < code_size && pos < data.len() {
+			buffer |= (data[pos] as u32) << bits;
+			bits += 8;
+			pos += 1;
+		}
+		if bits < code_size {
+			break
+		}
+		let code = (buffer & ((1 << code_size) - 1)) as usize;
+		buffer >>= code_size;
+		bits -= code_size;
+
+		if code == clear {
+			code_size = min_code_size as u32 + 1;
+			next = clear + 2;
+			previous = None;
+			continue
+		}
+		if code == end {
+			break
+		}
+		match previous {
+			Some(previous) => {
+				if code > next || (code == next && next >= 4096) {
+					return Err("invalid LZW code")
+				}
+				if next < 4096 {
+					prefix[next] = previous as u16;
+					first[next] = first[previous];
+					suffix[next] = if code == next { first[previous] } else { first[code] };
+					next += 1;
+					if next == 1 << code_size && code_size < 12 {
+						code_size += 1;
+					}
+				}
+			}
+			None => if code >= clear {
+				return Err("invalid LZW code")
+			}
+		}
+		let mut c = code;
+		while c >= clear {
+			stack.push(suffix[c]);
+			c = prefix[c] as usize;
+		}
+		stack.push(suffix[c]);
+		while let Some(index) = stack.pop() {
+			out.push(index);
+		}
+		previous = Some(code);
+	}
+	out.resize(count, 0);
+	Ok(out)
+}
+
+fn color_table<'a>(reader: &mut Reader<'a>, packed: u8) -> GifResult<Option<&'a [u8]>> {
+	if packed & 0x80 == 0 {
+		return Ok(None)
+	}
+	reader.bytes(3 << ((packed & 7) + 1)).map(Some)
+}
+
+/// Read the properties of a GIF image without decoding its frames.
+pub fn read_header(data: &[u8]) -> GifResult<Header> {
+	decode_frames(data, false).map(|decoded| decoded.header)
+}
+
+/// Decode a GIF image and its frames.
+///
+/// Frames are composited as browsers do: the background of the canvas is
+/// transparent, and frames without a delay get no special treatment.
+pub fn decode(data: &[u8]) -> GifResult<Decoded> {
+	decode_frames(data, true)
+}
+
+fn decode_frames(data: &[u8], decode_pixels: bool) -> GifResult<Decoded> {
+	let mut reader = Reader { data: data, pos: 0 };
+	let signature = try!(reader.bytes(6));
+	if signature != b"GIF87a" && signature != b"GIF89a" {
+		return Err("not a GIF image")
+	}
+	let (width, height) = (try!(reader.u16()) as u32, try!(reader.u16()) as u32);
+	let packed = try!(reader.byte());
+	try!(reader.bytes(2));
+	let global = try!(color_table(&mut reader, packed));
+	let mut bit_depth = if global.is_some() { (packed & 7) + 1 } else { 0 };
+
+	// Only decoding needs the canvas
+	let mut canvas = if decode_pixels { vec![0; try!(frames::canvas_size(width, height))] } else { Vec::new() };
+	let mut frames = Vec::new();
+	let mut frame_count = 0;
+	let mut loop_count = 1;
+	// Graphic control of the next image: disposal, delay, transparent index
+	let mut control = (0, 0, None);
+
+	loop {
+		match try!(reader.byte()) {
+			0x21 => {
+				let label = try!(reader.byte());
+				let block = try!(reader.sub_blocks());
+				if label == 0xf9 && block.len() >= 4 {
+					let transparent = if block[0] & 1 != 0 { Some(block[3]) } else { None };
+					control = ((block[0] >> 2) & 7, (block[1] as u32 | (block[2] as u32) << 8) * 10, transparent);
+				} else if label == 0xff && block.len() >= 14 && &block[..11] == b"NETSCAPE2.0" && block[11] == 1 {
+					let loops = block[12] as u32 | (block[13] as u32) << 8;
+					loop_count = if loops == 0 { 0 } else { loops + 1 };
+				}
+			}
+			0x2c => {
+				let (x, y) = (try!(reader.u16()) as u32, try!(reader.u16()) as u32);
+				let (w, h) = (try!(reader.u16()) as u32, try!(reader.u16()) as u32);
+				let packed = try!(reader.byte());
+				let local = try!(color_table(&mut reader, packed));
+				let min_code_size = try!(reader.byte());
+				let compressed = try!(reader.sub_blocks());
+				let (disposal, delay, transparent) = control;
+				control = (0, 0, None);
+				frame_count += 1;
+				if bit_depth == 0 && local.is_some() {
+					bit_depth = (packed & 7) + 1;
+				}
+				if !decode_pixels {
+					continue
+				}
+
+				let table = match local.or(global) {
+					Some(table) => table,
+					None => return Err("GIF image without a color table")
+				};
+				let size = try!(frames::canvas_size(w, h));
+				let indices = try!(lzw_decode(&compressed, min_code_size, size / 4));
+				let mut rows: Vec<u32> = (0..h).collect();
+				if packed & 0x40 != 0 {
+					// Interlaced rows come in four passes
+					rows.clear();
+					for &(start, step) in &[(0, 8), (4, 8), (2, 4), (1, 2)] {
+						let mut row = start;
+						while row < h {
+							rows.push(row);
+							row += step;
+						}
+					}
+				}
+				let mut pixels = vec![0; size];
+				for (i, &row) in rows.iter().enumerate() {
+					for column in 0..w as usize {
+						let index = indices[i * w as usize + column];
+						let at = (row as usize * w as usize + column) * 4;
+						if Some(index) != transparent && (index as usize) * 3 + 2 < table.len() {
+							let color = &table[index as usize * 3..index as usize * 3 + 3];
+							pixels[at..at + 4].copy_from_slice(&[color[0], color[1], color[2], 255]);
+						}
+					}
+				}
+
+				let saved = if disposal == 3 { Some(canvas.clone()) } else { None };
+				frames::blit(&mut canvas, width, height, &pixels, x, y, w, h, true);
+				frames.push(Frame { pixels: canvas.clone(), delay: delay });
+				match (disposal, saved) {
+					(_, Some(saved)) => canvas = saved,
+					(2, None) => frames::clear(&mut canvas, width, height, x, y, w, h),
+					_ => ()
+				}
+			}
+			0x3b => break,
+			_ => return Err("invalid GIF block")
+		}
+	}
+	if frame_count == 0 {
+		return Err("GIF without images")
+	}
+	Ok(Decoded {
+		header: Header {
+			width: width,
+			height: height,
+			bit_depth: bit_depth,
+			channels: 1,
+			gamma: None,
+			frame_count: frame_count,
+			loop_count: loop_count
+		},
+		frames: frames
+	})
+}
+
+// A box of colors for median cut quantization: (color, count) entries.
+type ColorBox = Vec<([u32; 3], u32)>;
+
+fn box_range(colors: &ColorBox) -> (usize, u32) {
+	(0..3).map(|c| {
+		let min = colors.iter().map(|e| e.0[c]).min().unwrap_or(0);
+		let max = colors.iter().map(|e| e.0[c]).max().unwrap_or(0);
+		(c, max - min)
+	}).max_by_key(|&(_, range)| range).unwrap()
+}
+
+/// Reduce the opaque pixels of an RGBA image to at most `count` colors with
+/// the median cut algorithm.
+pub fn quantize(pixels: &[u8], count: usize) -> Vec<[u8; 3]> {
+	// Histogram of colors reduced to 5 bits per channel
+	let mut histogram: HashMap<u16, ([u32; 3], u32)> = HashMap::new();
+	for p in pixels.chunks(4).filter(|p| p[3] >= 128) {
+		let key = (p[0] as u16 >> 3) << 10 | (p[1] as u16 >> 3) << 5 | p[2] as u16 >> 3;
+		let entry = histogram.entry(key).or_insert(([0; 3], 0));
+		for c in 0..3 {
+			entry.0[c] += p[c] as u32;
+		}
+		entry.1 += 1;
+	}
+	let colors: ColorBox = histogram.values().map(|&(sum, n)| ([sum[0] / n, sum[1] / n, sum[2] / n], n)).collect();
+	let mut boxes = vec![colors];
+	while boxes.len() < count {
+		// Split the box with the widest range of colors
+		let widest = boxes.iter().enumerate().filter(|&(_, b)| b.len() > 1)
+			.max_by_key(|&(_, b)| box_range(b).1).map(|(i, _)| i);
+		let index = match widest {
+			Some(index) => index,
+			None => break
+		};
+		let mut colors = boxes.swap_remove(index);
+		let channel = box_range(&colors).0;
+		colors.sort_by_key(|e| e.0[channel]);
+		let total: u32 = colors.iter().map(|e| e.1).sum();
+		let (mut seen, mut split) = (0, 1);
+		for (i, e) in colors.iter().enumerate() {
+			seen += e.1;
+			if seen * 2 >= total {
+				split = (i + 1).max(1).min(colors.len() - 1);
+				break
+			}
+		}
+		let upper = colors.split_off(split);
+		boxes.push(colors);
+		boxes.push(upper);
+	}
+	boxes.iter().filter(|b| !b.is_empty()).map(|b| {
+		let n: u32 = b.iter().map(|e| e.1).sum();
+		let mut average = [0u8; 3];
+		for c in 0..3 {
+			average[c] = ((b.iter().map(|e| e.0[c] as u64 * e.1 as u64).sum::<u64>() + n as u64 / 2) / n as u64) as u8;
+		}
+		average
+	}).collect()
+}
+
+/// Compress color indices with GIF's variant of LZW.
+pub fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+	let clear = 1u16 << min_code_size;
+	let end = clear + 1;
+	let mut out = Vec::new();
+	let (mut buffer, mut bits) = (0u32, 0u32);
+	let mut code_size = min_code_size as u32 + 1;
+	{
+		let mut emit = |code: u16, size: u32| {
+			buffer |= (code as u32) << bits;
+			bits += size;
+			while bits >= 8 {
+				out.push(buffer as u8);
+				buffer >>= 8;
+				bits -= 8;
+			}
+		};
+		let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+		let mut next = clear + 2;
+		emit(clear, code_size);
+		let mut current: Option<u16> = None;
+		for &index in indices {
+			let prefix = match current {
+				Some(prefix) => prefix,
+				None => {
+					current = Some(index as u16);
+					continue
+				}
+			};
+			if let Some(&code) = table.get(&(prefix, index)) {
+				current = Some(code);
+				continue
+			}
+			emit(prefix, code_size);
+			if next < 4096 {
+				table.insert((prefix, index), next);
+				next += 1;
+				// Decoders add codes one step later, and widen them as soon
+				// as the last code of the current size is defined
+				if next as u32 > 1 << code_size && code_size < 12 {
+					code_size += 1;
+				}
+			} else {
+				emit(clear, code_size);
+				table.clear();
+				next = clear + 2;
+				code_size = min_code_size as u32 + 1;
+			}
+			current = Some(index as u16);
+		}
+		if let Some(code) = current {
+			emit(code, code_size);
+			// The decoder defines one more code after the last one
+			if next < 4096 && next as u32 + 1 > 1 << code_size && code_size < 12 {
+				code_size += 1;
+			}
+		}
+		emit(end, code_size);
+	}
+	if bits > 0 {
+		out.push(buffer as u8);
+	}
+	out
+}
+
+fn push_u16(out: &mut Vec<u8>, value: u16) {
+	out.extend(&[value as u8, (value >> 8) as u8]);
+}
+
+fn push_sub_blocks(out: &mut Vec<u8>, data: &[u8]) {
+	for block in data.chunks(255) {
+		out.push(block.len() as u8);
+		out.extend(block);
+	}
+	out.push(0);
+}
+
+/// Encode RGBA frames as an animated GIF.
+///
+/// Each frame is a `(pixels, delay)` pair, with the delay in hundredths of
+/// a second, and gets its own palette of up to 255 colors. Pixels with an
+/// alpha below 128 are transparent. `loop_count` is the number of times the
+/// animation plays, 0 meaning forever.
+///
+/// GIF sizes are limited to 65535 pixels; returns None for larger images.
+pub fn encode(frames: &[(&[u8], u16)], width: u32, height: u32, loop_count: u32) -> Option<Vec<u8>> {
+	if width > 0xffff || height > 0xffff {
+		return None
+	}
+	let mut out = b"GIF89a".to_vec();
+	push_u16(&mut out, width as u16);
+	push_u16(&mut out, height as u16);
+	// No global color table
+	out.extend(&[0, 0, 0]);
+	if loop_count != 1 {
+		out.extend(b"\x21\xff\x0bNETSCAPE2.0\x03\x01");
+		push_u16(&mut out, loop_count.saturating_sub(1).min(0xffff) as u16);
+		out.push(0);
+	}
+
+	for &(pixels, delay) in frames {
+		let palette = quantize(pixels, 255);
+		let transparent = palette.len() as u8;
+		let table_bits = (1..9).find(|&bits| 1 << bits > palette.len()).unwrap_or(8);
+
+		// Nearest palette color, cached per color reduced to 5 bits
+		let mut cache = vec![0xffffu16; 32768];
+		let indices: Vec<u8> = pixels.chunks(4).map(|p| {
+			if p[3] < 128 {
+				return transparent
+			}
+			let key = ((p[0] as usize >> 3) << 10) | ((p[1] as usize >> 3) << 5) | (p[2] as usize >> 3);
+			if cache[key] == 0xffff {
+				let distance = |c: &[u8; 3]| (0..3).map(|i| (c[i] as i32 - p[i] as i32).pow(2)).sum::<i32>();
+				cache[key] = (0..palette.len()).min_by_key(|&i| distance(&palette[i])).unwrap_or(0) as u16;
+			}
+			cache[key] as u8
+		}).collect();
+
+		// Graphic control: restore to background, delay, transparent index
+		out.extend(&[0x21, 0xf9, 4, 2 << 2 | 1]);
+		push_u16(&mut out, delay);
+		out.extend(&[transparent, 0]);
+
+		out.push(0x2c);
+		push_u16(&mut out, 0);
+		push_u16(&mut out, 0);
+		push_u16(&mut out, width as u16);
+		push_u16(&mut out, height as u16);
+		out.push(0x80 | (table_bits - 1));
+		for i in 0..1 << table_bits {
+			out.extend(palette.get(i).unwrap_or(&[0, 0, 0]));
+		}
+		let min_code_size = table_bits.max(2);
+		out.push(min_code_size);
+		push_sub_blocks(&mut out, &lzw_encode(&indices, min_code_size));
+	}
+	out.push(0x3b);
+	Some(out)
+}
+
+#[test]
+fn gif_lzw_decode() {
+	// With 2-bit indices: clear, 0, 1, 6 (defined as "0 1"), 6, 0, end
+	let codes = [4u32, 0, 1, 6, 6, 0, 5];
+	let mut data = Vec::new();
+	let (mut buffer, mut bits) = (0u32, 0);
+	for (i, &code) in codes.iter().enumerate() {
+		// Codes grow to 4 bits once code 7 has been assigned
+		let size = if i >= 4 { 4 } else { 3 };
+		buffer |= code << bits;
+		bits += size;
+		while bits >= 8 {
+			data.push(buffer as u8);
+			buffer >>= 8;
+			bits -= 8;
+		}
+	}
+	data.push(buffer as u8);
+	assert_eq!(lzw_decode(&data, 2, 8).unwrap(), vec![0, 1, 0, 1, 0, 1, 0, 0]);
+	assert!(lzw_decode(&[0x07], 2, 4).is_err());
+}
+
+#[test]
+fn gif_huge_canvas() {
+	// A 65535x65535 canvas holding a single pixel without color table
+	let data = b"GIF89a\xff\xff\xff\xff\x00\x00\x00\x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x00\x3b";
+	let header = read_header(data).unwrap();
+	assert_eq!((header.width, header.height, header.frame_count), (65535, 65535, 1));
+	assert_eq!(decode(data).err(), Some("image is too large"));
+}
+
+#[test]
+fn gif_lzw_round_trip() {
+	let mut x = 5u32;
+	let noise: Vec<u8> = (0..20000).map(|_| {
+		x = x.wrapping_mul(1103515245).wrapping_add(12345);
+		((x >> 16) % 7) as u8
+	}).collect();
+	for data in &[vec![3], vec![1, 1, 1, 1, 1, 1, 1, 1, 1], noise] {
+		let encoded = lzw_encode(data, 3);
+		assert_eq!(&lzw_decode(&encoded, 3, data.len()).unwrap(), data);
+	}
+}
+
+#[test]
+fn gif_encode() {
+	let colors = [[255, 0, 0, 255], [0, 128, 255, 255], [0, 0, 0, 0], [10, 20, 30, 255]];
+	let first: Vec<u8> = (0..12).flat_map(|i| colors[i % 4].iter().cloned()).collect();
+	let second: Vec<u8> = (0..12).flat_map(|i| colors[(i + 1) % 4].iter().cloned()).collect();
+	let gif = encode(&[(&first, 5), (&second, 20)], 4, 3, 0).unwrap();
+	let decoded = decode(&gif).unwrap();
+	assert_eq!((decoded.header.width, decoded.header.height, decoded.header.loop_count), (4, 3, 0));
+	assert_eq!(decoded.frames.len(), 2);
+	assert_eq!(decoded.frames[0], Frame { pixels: first, delay: 50 });
+	assert_eq!(decoded.frames[1], Frame { pixels: second, delay: 200 });
+
+	// Many colors are reduced to a palette of close colors
+	let gradient: Vec<u8> = (0..4096).flat_map(|i| vec![(i % 64 * 4) as u8, (i / 64 * 4) as u8, 128, 255]).collect();
+	let palette = quantize(&gradient, 255);
+	assert!(palette.len() <= 255 && palette.len() > 200);
+	let decoded = decode(&encode(&[(&gradient, 10)], 64, 64, 1).unwrap()).unwrap();
+	assert_eq!(decoded.header.loop_count, 1);
+	let error = decoded.frames[0].pixels.iter().zip(&gradient).map(|(&a, &b)| (a as i32 - b as i32).abs()).max();
+	assert!(error.unwrap() <= 16);
+}
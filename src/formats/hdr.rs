@@ -0,0 +1,230 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Decoding of Radiance HDR images with RGBE pixels.
+//!
+//! Pixels hold three 8-bit mantissas sharing an exponent. They are decoded to
+//! linear floating point values, then gamma corrected and clamped to 8-bit
+//! RGBA. Scanlines may be flat, or run-length encoded in the old or the new
+//! (per channel) scheme.
+
+use std::str;
+
+use formats::frames::{self, Decoded, Frame, Header};
+
+type HdrResult<T> = Result<T, &'static str>;
+
+// Read a line ending with '\n' at `pos`, without the newline.
+fn next_line<'a>(data: &'a [u8], pos: &mut usize) -> HdrResult<&'a [u8]> {
+	let rest = &data[*pos..];
+	match rest.iter().position(|&b| b == b'\n') {
+		Some(end) => {
+			*pos += end + 1;
+			Ok(&rest[..end])
+		}
+		None => Err("unexpected end of HDR data")
+	}
+}
+
+// Read the header and the resolution line, returning the offset of the pixels.
+fn parse_header(data: &[u8]) -> HdrResult<(Header, usize)> {
+	if !data.starts_with(b"#?RADIANCE\n") && !data.starts_with(b"#?RGBE\n") {
+		return Err("not an HDR image")
+	}
+	let mut pos = 0;
+	let mut gamma = None;
+	try!(next_line(data, &mut pos));
+	loop {
+		let line = try!(next_line(data, &mut pos));
+		if line.is_empty() {
+			break
+		} else if line.starts_with(b"FORMAT=") {
+			if &line[7..] != b"32-bit_rle_rgbe" {
+				return Err("only RGBE HDR images are supported")
+			}
+		} else if line.starts_with(b"GAMMA=") {
+			gamma = str::from_utf8(&line[6..]).ok().and_then(|g| g.trim().parse::<f32>().ok());
+		}
+	}
+	let line = try!(next_line(data, &mut pos));
+	let fields: Vec<&str> = try!(str::from_utf8(line).map_err(|_| "malformed HDR resolution")).split_whitespace().collect();
+	if fields.len() != 4 || fields[0] != "-Y" || fields[2] != "+X" {
+		return Err("only top to bottom, left to right HDR images are supported")
+	}
+	let height = try!(fields[1].parse::<u32>().map_err(|_| "malformed HDR resolution"));
+	let width = try!(fields[3].parse::<u32>().map_err(|_| "malformed HDR resolution"));
+	Ok((Header {
+		width: width,
+		height: height,
+		bit_depth: 32,
+		channels: 3,
+		gamma: gamma,
+		frame_count: 1,
+		loop_count: 0
+	}, pos))
+}
+
+/// Read the properties of an HDR image.
+pub fn read_header(data: &[u8]) -> HdrResult<Header> {
+	parse_header(data).map(|(header, _)| header)
+}
+
+// Read a scanline of `out.len() / 4` RGBE pixels at `pos`.
+fn read_scanline(data: &[u8], pos: &mut usize, out: &mut [u8]) -> HdrResult<()> {
+	let width = out.len() / 4;
+	let new_rle = width >= 8 && width < 0x8000 && data.len() >= *pos + 4 &&
+		data[*pos] == 2 && data[*pos + 1] == 2 && data[*pos + 2] & 0x80 == 0;
+	if new_rle {
+		if (data[*pos + 2] as usize) << 8 | data[*pos + 3] as usize != width {
+			return Err("HDR scanline has the wrong width")
+		}
+		*pos += 4;
+		// Each channel is stored in turn, as runs and literal spans
+		for channel in 0..4 {
+			let mut x = 0;
+			while x < width {
+				let count = *try!(data.get(*pos).ok_or("unexpected end of HDR data")) as usize;
+				*pos += 1;
+				if count > 128 {
+					let count = count - 128;
+					let value = *try!(data.get(*pos).ok_or("unexpected end of HDR data"));
+					*pos += 1;
+					if count > width - x {
+						return Err("HDR run overflows its scanline")
+					}
+					for i in x..x + count {
+						out[i * 4 + channel] = value;
+					}
+					x += count;
+				} else {
+					if count == 0 || count > width - x {
+						return Err("HDR run overflows its scanline")
+					}
+					let values = try!(data.get(*pos..*pos + count).ok_or("unexpected end of HDR data"));
+					*pos += count;
+					for (i, &value) in values.iter().enumerate() {
+						out[(x + i) * 4 + channel] = value;
+					}
+					x += count;
+				}
+			}
+		}
+	} else {
+		// Flat pixels, where (1, 1, 1, n) repeats the previous pixel; consecutive
+		// repeats hold higher bytes of the count
+		let (mut x, mut shift) = (0, 0);
+		while x < width {
+			let pixel = try!(data.get(*pos..*pos + 4).ok_or("unexpected end of HDR data"));
+			*pos += 4;
+			if pixel[..3] == [1, 1, 1] {
+				if x == 0 || shift > 16 {
+					return Err("malformed HDR run")
+				}
+				let count = (pixel[3] as usize) << shift;
+				if count > width - x {
+					return Err("HDR run overflows its scanline")
+				}
+				for i in x..x + count {
+					let (before, after) = out.split_at_mut(i * 4);
+					after[..4].copy_from_slice(&before[before.len() - 4..]);
+				}
+				x += count;
+				shift += 8;
+			} else {
+				out[x * 4..x * 4 + 4].copy_from_slice(pixel);
+				x += 1;
+				shift = 0;
+			}
+		}
+	}
+	Ok(())
+}
+
+// Convert an RGBE pixel in place to gamma corrected 8-bit RGBA.
+fn to_rgba(pixel: &mut [u8]) {
+	if pixel[3] == 0 {
+		pixel.copy_from_slice(&[0, 0, 0, 255]);
+		return
+	}
+	// The mantissas are fractions of 256
+	let scale = 2f32.powi(pixel[3] as i32 - 136);
+	for value in &mut pixel[..3] {
+		let linear = (*value as f32 * scale).min(1.);
+		*value = (linear.powf(1. / 2.2) * 255. + 0.5) as u8;
+	}
+	pixel[3] = 255;
+}
+
+/// Decode an HDR image to 8-bit RGBA.
+pub fn decode(data: &[u8]) -> HdrResult<Decoded> {
+	let (header, mut pos) = try!(parse_header(data));
+	let mut pixels = vec![0; try!(frames::canvas_size(header.width, header.height))];
+	let stride = header.width as usize * 4;
+	for row in pixels.chunks_mut(stride.max(1)) {
+		try!(read_scanline(data, &mut pos, row));
+	}
+	for pixel in pixels.chunks_mut(4) {
+		to_rgba(pixel);
+	}
+	Ok(Decoded {
+		header: header,
+		frames: vec![Frame { pixels: pixels, delay: 0 }]
+	})
+}
+
+#[cfg(test)]
+fn hdr_image(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+	let mut data = format!("#?RADIANCE\n# made by hand\nFORMAT=32-bit_rle_rgbe\nGAMMA=1.0\n\n-Y {} +X {}\n", height, width).into_bytes();
+	data.extend(pixels);
+	data
+}
+
+#[test]
+fn hdr_flat() {
+	let data = hdr_image(3, 1, &[128, 64, 0, 129, 1, 1, 1, 1, 255, 255, 255, 140]);
+	let header = read_header(&data).unwrap();
+	assert_eq!((header.width, header.height, header.bit_depth, header.channels, header.gamma), (3, 1, 32, 3, Some(1.)));
+	let pixels = decode(&data).unwrap().frames.remove(0).pixels;
+	// 1.0 and 0.5 in linear light, the second pixel repeats the first
+	assert_eq!(pixels, vec![255, 186, 0, 255, 255, 186, 0, 255, 255, 255, 255, 255]);
+	assert!(decode(&data[..data.len() - 1]).is_err());
+	assert!(decode(&hdr_image(2, 1, &[1, 1, 1, 1, 0, 0, 0, 0])).is_err());
+}
+
+#[test]
+fn hdr_rle() {
+	let mut scanline = vec![2, 2, 0, 8];
+	// Red in a run, green literally, blue and the exponent in runs
+	scanline.extend(&[136, 128]);
+	scanline.extend(&[8, 0, 0, 0, 0, 128, 128, 128, 128]);
+	scanline.extend(&[136, 0]);
+	scanline.extend(&[136, 129]);
+	let pixels = decode(&hdr_image(8, 1, &scanline)).unwrap().frames.remove(0).pixels;
+	assert_eq!(&pixels[..4], &[255, 0, 0, 255]);
+	assert_eq!(&pixels[28..], &[255, 255, 0, 255]);
+	scanline[4] = 137;
+	assert!(decode(&hdr_image(8, 1, &scanline)).is_err());
+	assert!(decode(b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n\0\0\0\0").is_err());
+	assert!(decode(b"#?RADIANCE\n\n+Y 1 +X 1\n\0\0\0\0").is_err());
+}
@@ -0,0 +1,348 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! DEFLATE decompression (RFC 1951), with zlib (RFC 1950) and gzip (RFC 1952)
+//! wrappers.
+
+use std::cmp;
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27,
+                                31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2,
+                                2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257,
+                              385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193,
+                              12289, 16385, 24577];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7,
+                              7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2,
+                                        14, 1, 15];
+
+type InflateResult<T> = Result<T, &'static str>;
+
+struct BitReader<'a> {
+	data: &'a [u8],
+	pos: usize,
+	buffer: u32,
+	count: u32
+}
+
+impl<'a> BitReader<'a> {
+	fn bits(&mut self, n: u32) -> InflateResult<u32> {
+		while self.count < n {
+			if self.pos >= self.data.len() {
+				return Err("unexpected end of compressed data")
+			}
+			self.buffer |= (self.data[self.pos] as u32) << self.count;
+			self.pos += 1;
+			self.count += 8;
+		}
+		let value = self.buffer & ((1u32 << n) - 1);
+		self.buffer = if n == 32 { 0 } else { self.buffer >> n };
+		self.count -= n;
+		Ok(value)
+	}
+
+	fn align(&mut self) {
+		let extra = self.count % 8;
+		self.buffer >>= extra;
+		self.count -= extra;
+	}
+
+	// Position of the first byte not consumed by the bit reader.
+	fn byte_pos(&self) -> usize {
+		self.pos - (self.count / 8) as usize
+	}
+}
+
+// Canonical Huffman decoding table, as described in RFC 1951.
+struct Huffman {
+	counts: [u16; 16],
+	symbols: Vec<u16>
+}
+
+impl Huffman {
+	fn new(lengths: &[u8]) -> InflateResult<Huffman> {
+		let mut counts = [0u16; 16];
+		for &len in lengths {
+			counts[len as usize] += 1;
+		}
+		counts[0] = 0;
+
+		let mut offsets = [0u16; 16];
+		for len in 1..15 {
+			offsets[len + 1] = offsets[len] + counts[len];
+		}
+		let mut symbols = vec![0; lengths.len()];
+		for (symbol, &len) in lengths.iter().enumerate() {
+			if len != 0 {
+				symbols[offsets[len as usize] as usize] = symbol as u16;
+				offsets[len as usize] += 1;
+			}
+		}
+		Ok(Huffman {
+			counts: counts,
+			symbols: symbols
+		})
+	}
+
+	fn decode(&self, reader: &mut BitReader) -> InflateResult<u16> {
+		let mut code = 0i32;
+		let mut first = 0i32;
+		let mut index = 0i32;
+		for len in 1..16 {
+			code |= try!(reader.bits(1)) as i32;
+			let count = self.counts[len] as i32;
+			if code - count < first {
+				return Ok(self.symbols[(index + code - first) as usize])
+			}
+			index += count;
+			first = (first + count) << 1;
+			code <<= 1;
+		}
+		Err("invalid Huffman code")
+	}
+}
+
+fn fixed_tables() -> InflateResult<(Huffman, Huffman)> {
+	let mut lengths = [0u8; 288];
+	for (i, len) in lengths.iter_mut().enumerate() {
+		*len = match i {
+			0...143 => 8,
+			144...255 => 9,
+			256...279 => 7,
+			_ => 8
+		};
+	}
+	Ok((try!(Huffman::new(&lengths)), try!(Huffman::new(&[5; 30]))))
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> InflateResult<(Huffman, Huffman)> {
+	let literal_count = try!(reader.bits(5)) as usize + 257;
+	let distance_count = try!(reader.bits(5)) as usize + 1;
+	let code_count = try!(reader.bits(4)) as usize + 4;
+
+	let mut code_lengths = [0u8; 19];
+	for &index in &CODE_LENGTH_ORDER[..code_count] {
+		code_lengths[index] = try!(reader.bits(3)) as u8;
+	}
+	let code_table = try!(Huffman::new(&code_lengths));
+
+	let mut lengths = Vec::with_capacity(literal_count + distance_count);
+	while lengths.len() < literal_count + distance_count {
+		let symbol = try!(code_table.decode(reader));
+		let (value, repeat) = match symbol {
+			0...15 => (symbol as u8, 1),
+			16 => match lengths.last() {
+				Some(&previous) => (previous, 3 + try!(reader.bits(2))),
+				None => return Err("repeated code length with no previous length")
+			},
+			17 => (0, 3 + try!(reader.bits(3))),
+			_ => (0, 11 + try!(reader.bits(7)))
+		};
+		for _ in 0..repeat {
+			lengths.push(value);
+		}
+	}
+	if lengths.len() > literal_count + distance_count {
+		return Err("too many code lengths")
+	}
+	Ok((try!(Huffman::new(&lengths[..literal_count])),
+	    try!(Huffman::new(&lengths[literal_count..]))))
+}
+
+fn inflate_block(reader: &mut BitReader, out: &mut Vec<u8>,
+                 literals: &Huffman, distances: &Huffman) -> InflateResult<()> {
+	loop {
+		let symbol = try!(literals.decode(reader)) as usize;
+		if symbol < 256 {
+			out.push(symbol as u8);
+		} else if symbol == 256 {
+			return Ok(())
+		} else {
+			let index = symbol - 257;
+			if index >= LENGTH_BASE.len() {
+				return Err("invalid length symbol")
+			}
+			let length = LENGTH_BASE[index] as usize + try!(reader.bits(LENGTH_EXTRA[index] as u32)) as usize;
+			let index = try!(distances.decode(reader)) as usize;
+			if index >= DIST_BASE.len() {
+				return Err("invalid distance symbol")
+			}
+			let distance = DIST_BASE[index] as usize + try!(reader.bits(DIST_EXTRA[index] as u32)) as usize;
+			if distance > out.len() {
+				return Err("distance too far back")
+			}
+			let start = out.len() - distance;
+			for i in 0..length {
+				let byte = out[start + i];
+				out.push(byte);
+			}
+		}
+	}
+}
+
+// Decompress raw DEFLATE data, returning the output and the number of input
+// bytes consumed.
+fn inflate_raw(data: &[u8]) -> InflateResult<(Vec<u8>, usize)> {
+	let mut reader = BitReader { data: data, pos: 0, buffer: 0, count: 0 };
+	let mut out = Vec::with_capacity(data.len() * 4);
+	loop {
+		let last = try!(reader.bits(1)) == 1;
+		match try!(reader.bits(2)) {
+			0 => {
+				reader.align();
+				let len = try!(reader.bits(16));
+				let nlen = try!(reader.bits(16));
+				if len != !nlen & 0xffff {
+					return Err("corrupt stored block length")
+				}
+				for _ in 0..len {
+					out.push(try!(reader.bits(8)) as u8);
+				}
+			}
+			1 => {
+				let (literals, distances) = try!(fixed_tables());
+				try!(inflate_block(&mut reader, &mut out, &literals, &distances));
+			}
+			2 => {
+				let (literals, distances) = try!(dynamic_tables(&mut reader));
+				try!(inflate_block(&mut reader, &mut out, &literals, &distances));
+			}
+			_ => return Err("invalid block type")
+		}
+		if last {
+			break
+		}
+	}
+	reader.align();
+	Ok((out, reader.byte_pos()))
+}
+
+/// Decompress raw DEFLATE data.
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+	inflate_raw(data).map(|(out, _)| out)
+}
+
+/// Compute the Adler-32 checksum of some data.
+pub fn adler32(data: &[u8]) -> u32 {
+	let (mut a, mut b) = (1u32, 0u32);
+	for chunk in data.chunks(5552) {
+		for &byte in chunk {
+			a += byte as u32;
+			b += a;
+		}
+		a %= 65521;
+		b %= 65521;
+	}
+	(b << 16) | a
+}
+
+/// Decompress zlib-wrapped DEFLATE data, verifying its checksum.
+pub fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+	if data.len() < 6 {
+		return Err("zlib stream too short")
+	}
+	let (cmf, flg) = (data[0], data[1]);
+	if cmf & 0x0f != 8 || ((cmf as u16) << 8 | flg as u16) % 31 != 0 {
+		return Err("invalid zlib header")
+	}
+	if flg & 0x20 != 0 {
+		return Err("zlib preset dictionaries are not supported")
+	}
+	let (out, used) = try!(inflate_raw(&data[2..]));
+	let trailer = &data[cmp::min(2 + used, data.len())..];
+	if trailer.len() < 4 {
+		return Err("missing zlib checksum")
+	}
+	let expected = (trailer[0] as u32) << 24 | (trailer[1] as u32) << 16 |
+	               (trailer[2] as u32) << 8 | trailer[3] as u32;
+	if adler32(&out) != expected {
+		return Err("zlib checksum mismatch")
+	}
+	Ok(out)
+}
+
+/// Decompress gzip-wrapped DEFLATE data.
+pub fn gzip_decompress(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+	if data.len() < 18 || data[0] != 0x1f || data[1] != 0x8b || data[2] != 8 {
+		return Err("invalid gzip header")
+	}
+	let flags = data[3];
+	let mut pos = 10;
+	if flags & 0x04 != 0 {
+		let extra = data[pos] as usize | (data[pos + 1] as usize) << 8;
+		pos += 2 + extra;
+	}
+	for &flag in &[0x08, 0x10] {
+		if flags & flag != 0 {
+			while pos < data.len() && data[pos] != 0 {
+				pos += 1;
+			}
+			pos += 1;
+		}
+	}
+	if flags & 0x02 != 0 {
+		pos += 2;
+	}
+	if pos >= data.len() {
+		return Err("truncated gzip header")
+	}
+	inflate(&data[pos..])
+}
+
+#[test]
+fn inflate_blocks() {
+	// Stored block.
+	assert_eq!(zlib_decompress(&[120, 1, 1, 3, 0, 252, 255, 97, 98, 99, 2, 77, 1, 39]).unwrap(), b"abc");
+	// Fixed Huffman block with back-references.
+	let fixed = [120, 218, 243, 72, 205, 201, 201, 215, 81, 240, 64, 161, 74, 50, 115, 82, 139, 21,
+	             209, 4, 169, 35, 7, 0, 28, 64, 36, 137];
+	let expected: Vec<u8> = b"Hello, Hello, Hello, tiles! ".iter().cycle().take(112).cloned().collect();
+	assert_eq!(zlib_decompress(&fixed).unwrap(), expected);
+	// Gzip wrapper.
+	let gzip = [31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 75, 175, 202, 44, 80, 72, 73, 44, 73, 4, 0, 23, 114,
+	            225, 187, 9, 0, 0, 0];
+	assert_eq!(gzip_decompress(&gzip).unwrap(), b"gzip data");
+	// Corrupt checksum.
+	assert!(zlib_decompress(&[120, 1, 1, 3, 0, 252, 255, 97, 98, 99, 2, 77, 1, 40]).is_err());
+}
+
+#[test]
+fn inflate_dynamic_block() {
+	let data = [120, 218, 37, 144, 73, 14, 192, 48, 12, 2, 127, 203, 9, 78, 254, 191, 20, 134, 40,
+	            173, 226, 218, 108, 110, 34, 219, 241, 245, 190, 147, 123, 146, 172, 165, 180, 65,
+	            29, 233, 152, 181, 112, 210, 218, 64, 52, 98, 153, 130, 145, 235, 211, 202, 155, 91,
+	            215, 163, 171, 70, 231, 52, 247, 238, 163, 178, 69, 22, 14, 26, 237, 224, 83, 18,
+	            238, 195, 27, 101, 33, 128, 7, 52, 32, 33, 233, 196, 179, 48, 85, 15, 62, 36, 166,
+	            102, 129, 31, 6, 64, 165, 15, 171, 182, 128, 174, 87, 241, 242, 14, 137, 35, 139,
+	            151, 124, 134, 172, 197, 70, 33, 41, 72, 66, 240, 79, 188, 181, 126, 210, 7, 127,
+	            100, 126, 179];
+	let mut x = 1u32;
+	let expected: Vec<u8> = (0..300).map(|_| {
+		x = x.wrapping_mul(1103515245).wrapping_add(12345) & 0x7fffffff;
+		b"sfml"[((x >> 16) & 3) as usize]
+	}).collect();
+	assert_eq!(zlib_decompress(&data).unwrap(), expected);
+}
@@ -0,0 +1,353 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Baseline JPEG encoding of 8-bit RGBA images, and reading of JPEG headers.
+//!
+//! Images are stored as YCbCr without chroma subsampling, with the example
+//! quantization and Huffman tables of the JPEG specification. The alpha
+//! channel is dropped.
+
+use std::f32::consts::PI;
+
+use formats::frames::Header;
+
+const ZIGZAG: [usize; 64] = [0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5,
+                             12, 19, 26, 33, 40, 48, 41, 34, 27, 20, 13, 6, 7, 14, 21, 28,
+                             35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51,
+                             58, 59, 52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63];
+
+const LUMA_QUANTIZATION: [u8; 64] = [16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55,
+                                     14, 13, 16, 24, 40, 57, 69, 56, 14, 17, 22, 29, 51, 87, 80, 62,
+                                     18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113, 92,
+                                     49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99];
+
+const CHROMA_QUANTIZATION: [u8; 64] = [17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99,
+                                       24, 26, 56, 99, 99, 99, 99, 99, 47, 66, 99, 99, 99, 99, 99, 99,
+                                       99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+                                       99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99];
+
+const DC_LUMA_BITS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+const DC_CHROMA_BITS: [u8; 16] = [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+const DC_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+const AC_LUMA_BITS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7d];
+const AC_LUMA_VALUES: [u8; 162] = [
+	0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+	0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xa1, 0x08, 0x23, 0x42, 0xb1, 0xc1, 0x15, 0x52, 0xd1, 0xf0,
+	0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0a, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x25, 0x26, 0x27, 0x28,
+	0x29, 0x2a, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+	0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+	0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+	0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7,
+	0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5,
+	0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda, 0xe1, 0xe2,
+	0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+	0xf9, 0xfa];
+
+const AC_CHROMA_BITS: [u8; 16] = [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77];
+const AC_CHROMA_VALUES: [u8; 162] = [
+	0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71,
+	0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xa1, 0xb1, 0xc1, 0x09, 0x23, 0x33, 0x52, 0xf0,
+	0x15, 0x62, 0x72, 0xd1, 0x0a, 0x16, 0x24, 0x34, 0xe1, 0x25, 0xf1, 0x17, 0x18, 0x19, 0x1a, 0x26,
+	0x27, 0x28, 0x29, 0x2a, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+	0x49, 0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+	0x69, 0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+	0x88, 0x89, 0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5,
+	0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3,
+	0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda,
+	0xe2, 0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+	0xf9, 0xfa];
+
+// Canonical Huffman codes, as (code, length) indexed by symbol.
+fn huffman_codes(bits: &[u8; 16], values: &[u8]) -> [(u16, u8); 256] {
+	let mut codes = [(0, 0); 256];
+	let (mut code, mut k) = (0u16, 0);
+	for length in 0..16 {
+		for _ in 0..bits[length] {
+			codes[values[k] as usize] = (code, length as u8 + 1);
+			code += 1;
+			k += 1;
+		}
+		code <<= 1;
+	}
+	codes
+}
+
+// Quantization table for a quality, in zigzag order, scaled as libjpeg does.
+fn quantization(base: &[u8; 64], quality: u8) -> [u8; 64] {
+	let quality = quality.max(1).min(100) as u32;
+	let scale = if quality < 50 { 5000 / quality } else { 200 - 2 * quality };
+	let mut table = [0; 64];
+	for (i, &natural) in ZIGZAG.iter().enumerate() {
+		table[i] = ((base[natural] as u32 * scale + 50) / 100).max(1).min(255) as u8;
+	}
+	table
+}
+
+struct BitWriter {
+	out: Vec<u8>,
+	buffer: u32,
+	count: u32
+}
+
+impl BitWriter {
+	fn bits(&mut self, value: u32, n: u32) {
+		for i in (0..n).rev() {
+			self.buffer = self.buffer << 1 | (value >> i) & 1;
+			self.count += 1;
+			if self.count == 8 {
+				self.out.push(self.buffer as u8);
+				// Stuff a zero after 0xff bytes so they are not taken as markers
+				if self.buffer == 0xff {
+					self.out.push(0);
+				}
+				self.buffer = 0;
+				self.count = 0;
+			}
+		}
+	}
+
+	fn flush(&mut self) {
+		// Pad with one bits
+		while self.count != 0 {
+			self.bits(1, 1);
+		}
+	}
+}
+
+fn push_u16(out: &mut Vec<u8>, value: u16) {
+	out.extend(&[(value >> 8) as u8, value as u8]);
+}
+
+fn segment(out: &mut Vec<u8>, marker: u8, data: &[u8]) {
+	out.extend(&[0xff, marker]);
+	push_u16(out, data.len() as u16 + 2);
+	out.extend(data);
+}
+
+struct Component {
+	quantization: [u8; 64],
+	dc: [(u16, u8); 256],
+	ac: [(u16, u8); 256],
+	previous_dc: i32
+}
+
+// Bit length and bits of a coefficient, as encoded after its category.
+fn magnitude(value: i32) -> (u32, u32) {
+	let category = 32 - value.abs().leading_zeros();
+	let bits = if value < 0 { value - 1 } else { value };
+	(category, bits as u32 & ((1u32 << category) - 1))
+}
+
+fn encode_block(writer: &mut BitWriter, block: &[f32; 64], component: &mut Component, cosines: &[[f32; 8]; 8]) {
+	let mut coefficients = [0.; 64];
+	for v in 0..8 {
+		for u in 0..8 {
+			let mut sum = 0.;
+			for y in 0..8 {
+				for x in 0..8 {
+					sum += block[y * 8 + x] * cosines[x][u] * cosines[y][v];
+				}
+			}
+			let (cu, cv) = (if u == 0 { 0.5f32.sqrt() } else { 1. }, if v == 0 { 0.5f32.sqrt() } else { 1. });
+			coefficients[v * 8 + u] = 0.25 * cu * cv * sum;
+		}
+	}
+	let mut quantized = [0i32; 64];
+	for (i, &natural) in ZIGZAG.iter().enumerate() {
+		let step = component.quantization[i] as f32;
+		quantized[i] = (coefficients[natural] / step).round() as i32;
+	}
+
+	let difference = quantized[0] - component.previous_dc;
+	component.previous_dc = quantized[0];
+	let (category, bits) = magnitude(difference);
+	let (code, length) = component.dc[category as usize];
+	writer.bits(code as u32, length as u32);
+	writer.bits(bits, category);
+
+	let mut run = 0;
+	for &value in &quantized[1..] {
+		if value == 0 {
+			run += 1;
+			continue
+		}
+		while run > 15 {
+			let (code, length) = component.ac[0xf0];
+			writer.bits(code as u32, length as u32);
+			run -= 16;
+		}
+		let (category, bits) = magnitude(value);
+		let (code, length) = component.ac[(run << 4 | category) as usize];
+		writer.bits(code as u32, length as u32);
+		writer.bits(bits, category);
+		run = 0;
+	}
+	if run > 0 {
+		let (code, length) = component.ac[0x00];
+		writer.bits(code as u32, length as u32);
+	}
+}
+
+/// Encode RGBA pixels as a JPEG image, with a quality between 1 and 100.
+///
+/// JPEG sizes are limited to 65535 pixels; returns None for larger images.
+pub fn encode(pixels: &[u8], width: u32, height: u32, quality: u8) -> Option<Vec<u8>> {
+	if width > 0xffff || height > 0xffff {
+		return None
+	}
+	let luma = quantization(&LUMA_QUANTIZATION, quality);
+	let chroma = quantization(&CHROMA_QUANTIZATION, quality);
+
+	let mut out = vec![0xff, 0xd8];
+	segment(&mut out, 0xe0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+	for (id, table) in [luma, chroma].iter().enumerate() {
+		let mut data = vec![id as u8];
+		data.extend(table.iter());
+		segment(&mut out, 0xdb, &data);
+	}
+	let mut frame = vec![8];
+	push_u16(&mut frame, height as u16);
+	push_u16(&mut frame, width as u16);
+	frame.extend(&[3, 1, 0x11, 0, 2, 0x11, 1, 3, 0x11, 1]);
+	segment(&mut out, 0xc0, &frame);
+	let tables: [(u8, &[u8; 16], &[u8]); 4] = [(0x00, &DC_LUMA_BITS, &DC_VALUES), (0x10, &AC_LUMA_BITS, &AC_LUMA_VALUES),
+	                                           (0x01, &DC_CHROMA_BITS, &DC_VALUES), (0x11, &AC_CHROMA_BITS, &AC_CHROMA_VALUES)];
+	for &(class, bits, values) in &tables {
+		let mut data = vec![class];
+		data.extend(bits.iter());
+		data.extend(values);
+		segment(&mut out, 0xc4, &data);
+	}
+	segment(&mut out, 0xda, &[3, 1, 0x00, 2, 0x11, 3, 0x11, 0, 63, 0]);
+
+	let mut components = [
+		Component { quantization: luma, dc: huffman_codes(&DC_LUMA_BITS, &DC_VALUES),
+		            ac: huffman_codes(&AC_LUMA_BITS, &AC_LUMA_VALUES), previous_dc: 0 },
+		Component { quantization: chroma, dc: huffman_codes(&DC_CHROMA_BITS, &DC_VALUES),
+		            ac: huffman_codes(&AC_CHROMA_BITS, &AC_CHROMA_VALUES), previous_dc: 0 },
+		Component { quantization: chroma, dc: huffman_codes(&DC_CHROMA_BITS, &DC_VALUES),
+		            ac: huffman_codes(&AC_CHROMA_BITS, &AC_CHROMA_VALUES), previous_dc: 0 }
+	];
+	let mut cosines = [[0.; 8]; 8];
+	for x in 0..8 {
+		for u in 0..8 {
+			cosines[x][u] = ((2 * x + 1) as f32 * u as f32 * PI / 16.).cos();
+		}
+	}
+
+	let mut writer = BitWriter { out: out, buffer: 0, count: 0 };
+	let mut blocks = [[0.; 64]; 3];
+	for block_y in 0..(height + 7) / 8 {
+		for block_x in 0..(width + 7) / 8 {
+			for i in 0..64 {
+				// Repeat the edge pixels to fill partial blocks
+				let x = (block_x * 8 + i as u32 % 8).min(width - 1);
+				let y = (block_y * 8 + i as u32 / 8).min(height - 1);
+				let at = ((y * width + x) * 4) as usize;
+				let (r, g, b) = (pixels[at] as f32, pixels[at + 1] as f32, pixels[at + 2] as f32);
+				blocks[0][i] = 0.299 * r + 0.587 * g + 0.114 * b - 128.;
+				blocks[1][i] = -0.168736 * r - 0.331264 * g + 0.5 * b;
+				blocks[2][i] = 0.5 * r - 0.418688 * g - 0.081312 * b;
+			}
+			for (block, component) in blocks.iter().zip(components.iter_mut()) {
+				encode_block(&mut writer, block, component, &cosines);
+			}
+		}
+	}
+	writer.flush();
+	let mut out = writer.out;
+	out.extend(&[0xff, 0xd9]);
+	Some(out)
+}
+
+/// Read the properties of a JPEG image from its frame header.
+pub fn read_header(data: &[u8]) -> Result<Header, &'static str> {
+	if data.len() < 4 || data[..2] != [0xff, 0xd8] {
+		return Err("not a JPEG image")
+	}
+	let mut pos = 2;
+	while pos + 4 <= data.len() {
+		if data[pos] != 0xff {
+			return Err("invalid JPEG marker")
+		}
+		let marker = data[pos + 1];
+		if marker == 0xff {
+			// Fill byte
+			pos += 1;
+			continue
+		}
+		let length = (data[pos + 2] as usize) << 8 | data[pos + 3] as usize;
+		// Any start of frame, except the Huffman, arithmetic and restart
+		// table markers sharing the range
+		if marker >= 0xc0 && marker <= 0xcf && marker != 0xc4 && marker != 0xc8 && marker != 0xcc {
+			if pos + 10 > data.len() {
+				break
+			}
+			let segment = &data[pos + 4..];
+			return Ok(Header {
+				width: (segment[3] as u32) << 8 | segment[4] as u32,
+				height: (segment[1] as u32) << 8 | segment[2] as u32,
+				bit_depth: segment[0],
+				channels: segment[5],
+				gamma: None,
+				frame_count: 1,
+				loop_count: 0
+			})
+		}
+		pos += 2 + length;
+	}
+	Err("JPEG image without a frame header")
+}
+
+#[test]
+fn jpeg_encode() {
+	assert_eq!(AC_LUMA_BITS.iter().map(|&b| b as usize).sum::<usize>(), AC_LUMA_VALUES.len());
+	assert_eq!(AC_CHROMA_BITS.iter().map(|&b| b as usize).sum::<usize>(), AC_CHROMA_VALUES.len());
+	assert_eq!(magnitude(0), (0, 0));
+	assert_eq!(magnitude(5), (3, 5));
+	assert_eq!(magnitude(-5), (3, 2));
+	assert_eq!(quantization(&LUMA_QUANTIZATION, 50)[0], 16);
+	assert_eq!(quantization(&LUMA_QUANTIZATION, 100)[0], 1);
+
+	let mut x = 3u32;
+	let noise: Vec<u8> = (0..4 * 20 * 13).map(|_| {
+		x = x.wrapping_mul(1103515245).wrapping_add(12345);
+		(x >> 16) as u8
+	}).collect();
+	let low = encode(&noise, 20, 13, 10).unwrap();
+	let high = encode(&noise, 20, 13, 95).unwrap();
+	assert!(low.len() < high.len());
+	for jpeg in &[low, high] {
+		assert_eq!(&jpeg[..4], &[0xff, 0xd8, 0xff, 0xe0]);
+		assert_eq!(&jpeg[jpeg.len() - 2..], &[0xff, 0xd9]);
+		// No markers inside the entropy-coded data
+		let scan = jpeg.windows(2).position(|w| w == [0xff, 0xda]).unwrap() + 14;
+		let data = &jpeg[scan..jpeg.len() - 2];
+		assert!(data.windows(2).all(|w| w[0] != 0xff || w[1] == 0));
+	}
+	assert!(encode(&[], 0x10000, 0, 90).is_none());
+	let header = read_header(&encode(&noise, 20, 13, 50).unwrap()).unwrap();
+	assert_eq!((header.width, header.height, header.bit_depth, header.channels), (20, 13, 8, 3));
+}
@@ -0,0 +1,285 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! A minimal JSON (RFC 7159) reader.
+
+use std::collections::BTreeMap;
+
+/// A parsed JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+	/// `null`
+	Null,
+	/// `true` or `false`
+	Bool(bool),
+	/// Any number; JSON does not distinguish integers.
+	Number(f64),
+	/// A string.
+	String(String),
+	/// An array of values.
+	Array(Vec<Json>),
+	/// An object, with keys in sorted order.
+	Object(BTreeMap<String, Json>)
+}
+
+impl Json {
+	/// Look up a key if this value is an object.
+	pub fn get(&self, key: &str) -> Option<&Json> {
+		match *self {
+			Json::Object(ref map) => map.get(key),
+			_ => None
+		}
+	}
+
+	/// Get the value as a number.
+	pub fn as_f64(&self) -> Option<f64> {
+		match *self {
+			Json::Number(n) => Some(n),
+			_ => None
+		}
+	}
+
+	/// Get the value as a string slice.
+	pub fn as_str(&self) -> Option<&str> {
+		match *self {
+			Json::String(ref s) => Some(s),
+			_ => None
+		}
+	}
+
+	/// Get the value as a boolean.
+	pub fn as_bool(&self) -> Option<bool> {
+		match *self {
+			Json::Bool(b) => Some(b),
+			_ => None
+		}
+	}
+
+	/// Get the value as an array slice.
+	pub fn as_array(&self) -> Option<&[Json]> {
+		match *self {
+			Json::Array(ref values) => Some(values),
+			_ => None
+		}
+	}
+}
+
+struct Parser<'a> {
+	bytes: &'a [u8],
+	pos: usize
+}
+
+type JsonResult<T> = Result<T, String>;
+
+impl<'a> Parser<'a> {
+	fn error<T>(&self, message: &str) -> JsonResult<T> {
+		Err(format!("{} at offset {}", message, self.pos))
+	}
+
+	fn peek(&self) -> Option<u8> {
+		self.bytes.get(self.pos).cloned()
+	}
+
+	fn skip_whitespace(&mut self) {
+		while let Some(b' ') | Some(b'\t') | Some(b'\r') | Some(b'\n') = self.peek() {
+			self.pos += 1;
+		}
+	}
+
+	fn literal(&mut self, word: &str, value: Json) -> JsonResult<Json> {
+		if self.bytes[self.pos..].starts_with(word.as_bytes()) {
+			self.pos += word.len();
+			Ok(value)
+		} else {
+			self.error("invalid literal")
+		}
+	}
+
+	fn value(&mut self) -> JsonResult<Json> {
+		self.skip_whitespace();
+		match self.peek() {
+			Some(b'{') => self.object(),
+			Some(b'[') => self.array(),
+			Some(b'"') => self.string().map(Json::String),
+			Some(b't') => self.literal("true", Json::Bool(true)),
+			Some(b'f') => self.literal("false", Json::Bool(false)),
+			Some(b'n') => self.literal("null", Json::Null),
+			Some(b'-') | Some(b'0'...b'9') => self.number(),
+			Some(_) => self.error("unexpected character"),
+			None => self.error("unexpected end of input")
+		}
+	}
+
+	fn number(&mut self) -> JsonResult<Json> {
+		let start = self.pos;
+		while let Some(b'-') | Some(b'+') | Some(b'.') | Some(b'e') | Some(b'E') | Some(b'0'...b'9') = self.peek() {
+			self.pos += 1;
+		}
+		let text = ::std::str::from_utf8(&self.bytes[start..self.pos]).unwrap_or("");
+		match text.parse() {
+			Ok(n) => Ok(Json::Number(n)),
+			Err(_) => self.error("invalid number")
+		}
+	}
+
+	fn hex4(&mut self) -> JsonResult<u32> {
+		if self.pos + 4 > self.bytes.len() {
+			return self.error("truncated unicode escape")
+		}
+		let text = ::std::str::from_utf8(&self.bytes[self.pos..self.pos + 4]).unwrap_or("");
+		self.pos += 4;
+		match u32::from_str_radix(text, 16) {
+			Ok(n) => Ok(n),
+			Err(_) => self.error("invalid unicode escape")
+		}
+	}
+
+	fn string(&mut self) -> JsonResult<String> {
+		self.pos += 1;
+		let mut out = Vec::new();
+		loop {
+			match self.peek() {
+				None => return self.error("unterminated string"),
+				Some(b'"') => {
+					self.pos += 1;
+					break
+				}
+				Some(b'\\') => {
+					self.pos += 1;
+					let escape = self.peek();
+					self.pos += 1;
+					let ch = match escape {
+						Some(b'"') => '"',
+						Some(b'\\') => '\\',
+						Some(b'/') => '/',
+						Some(b'b') => '\u{8}',
+						Some(b'f') => '\u{c}',
+						Some(b'n') => '\n',
+						Some(b'r') => '\r',
+						Some(b't') => '\t',
+						Some(b'u') => {
+							let mut code = try!(self.hex4());
+							if code >= 0xd800 && code < 0xdc00 && self.bytes[self.pos..].starts_with(b"\\u") {
+								self.pos += 2;
+								let low = try!(self.hex4());
+								code = 0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+							}
+							::std::char::from_u32(code).unwrap_or('\u{fffd}')
+						}
+						_ => return self.error("invalid escape")
+					};
+					let mut buf = String::new();
+					buf.push(ch);
+					out.extend(buf.bytes());
+				}
+				Some(byte) => {
+					out.push(byte);
+					self.pos += 1;
+				}
+			}
+		}
+		match String::from_utf8(out) {
+			Ok(s) => Ok(s),
+			Err(_) => self.error("invalid UTF-8 in string")
+		}
+	}
+
+	fn array(&mut self) -> JsonResult<Json> {
+		self.pos += 1;
+		let mut values = Vec::new();
+		self.skip_whitespace();
+		if self.peek() == Some(b']') {
+			self.pos += 1;
+			return Ok(Json::Array(values))
+		}
+		loop {
+			values.push(try!(self.value()));
+			self.skip_whitespace();
+			match self.peek() {
+				Some(b',') => self.pos += 1,
+				Some(b']') => {
+					self.pos += 1;
+					return Ok(Json::Array(values))
+				}
+				_ => return self.error("expected `,` or `]`")
+			}
+		}
+	}
+
+	fn object(&mut self) -> JsonResult<Json> {
+		self.pos += 1;
+		let mut map = BTreeMap::new();
+		self.skip_whitespace();
+		if self.peek() == Some(b'}') {
+			self.pos += 1;
+			return Ok(Json::Object(map))
+		}
+		loop {
+			self.skip_whitespace();
+			if self.peek() != Some(b'"') {
+				return self.error("expected a string key")
+			}
+			let key = try!(self.string());
+			self.skip_whitespace();
+			if self.peek() != Some(b':') {
+				return self.error("expected `:`")
+			}
+			self.pos += 1;
+			let value = try!(self.value());
+			map.insert(key, value);
+			self.skip_whitespace();
+			match self.peek() {
+				Some(b',') => self.pos += 1,
+				Some(b'}') => {
+					self.pos += 1;
+					return Ok(Json::Object(map))
+				}
+				_ => return self.error("expected `,` or `}`")
+			}
+		}
+	}
+}
+
+/// Parse a JSON document.
+pub fn parse(text: &str) -> Result<Json, String> {
+	let mut parser = Parser { bytes: text.as_bytes(), pos: 0 };
+	let value = try!(parser.value());
+	parser.skip_whitespace();
+	if parser.pos != parser.bytes.len() {
+		return parser.error("unexpected trailing characters")
+	}
+	Ok(value)
+}
+
+#[test]
+fn json_parse() {
+	let value = parse(r#" {"a": [1, -2.5e1, true, null], "b": "x\"é😀", "c": {}} "#).unwrap();
+	let a = value.get("a").and_then(Json::as_array).unwrap();
+	assert_eq!(a[1].as_f64(), Some(-25.));
+	assert_eq!(a[2].as_bool(), Some(true));
+	assert_eq!(a[3], Json::Null);
+	assert_eq!(value.get("b").and_then(Json::as_str), Some("x\"\u{e9}\u{1f600}"));
+	assert!(parse("[1, 2").is_err());
+	assert!(parse("{} x").is_err());
+}
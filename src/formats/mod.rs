@@ -0,0 +1,44 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Small decoders for the data formats used by the higher-level loaders.
+//!
+//! These are deliberately minimal: they support what the loaders need (Tiled
+//! maps, font descriptors and character maps, image containers) and nothing
+//! more. A few encoders back the image saving functions.
+
+pub mod base64;
+pub mod inflate;
+pub mod deflate;
+pub mod png;
+pub mod truetype;
+pub mod bmp;
+pub mod tga;
+pub mod jpeg;
+pub mod frames;
+pub mod gif;
+pub mod xml;
+pub mod json;
+pub mod hdr;
+pub mod dds;
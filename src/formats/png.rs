@@ -0,0 +1,461 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! PNG encoding of 8-bit RGBA images, and decoding of PNG and APNG images.
+
+use formats::deflate::zlib_compress;
+use formats::frames::{self, Decoded, Frame, Header};
+use formats::inflate::zlib_decompress;
+
+type PngResult<T> = Result<T, &'static str>;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+/// Compute the CRC-32 checksum used by PNG and gzip.
+pub fn crc32(data: &[u8]) -> u32 {
+	let mut crc = 0xffffffffu32;
+	for &byte in data {
+		crc ^= byte as u32;
+		for _ in 0..8 {
+			crc = if crc & 1 != 0 { 0xedb88320 ^ (crc >> 1) } else { crc >> 1 };
+		}
+	}
+	!crc
+}
+
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+	out.extend(&[(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]);
+}
+
+/// Append a chunk, with its length and checksum, to a PNG stream.
+pub fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+	push_u32(out, data.len() as u32);
+	let start = out.len();
+	out.extend(kind);
+	out.extend(data);
+	let crc = crc32(&out[start..]);
+	push_u32(out, crc);
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+	let p = a as i16 + b as i16 - c as i16;
+	let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+	if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+}
+
+// Filter each row with the filter giving the smallest sum of absolute
+// differences, a common heuristic for better compression.
+fn filter_rows(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
+	let stride = width as usize * 4;
+	let mut out = Vec::with_capacity((stride + 1) * height as usize);
+	let zero = vec![0; stride];
+	let mut candidate = vec![0; stride];
+	let mut best = vec![0; stride];
+	for y in 0..height as usize {
+		let row = &pixels[y * stride..(y + 1) * stride];
+		let above = if y == 0 { &zero[..] } else { &pixels[(y - 1) * stride..y * stride] };
+		let mut best_filter = 0;
+		let mut best_score = u64::max_value();
+		for filter in 0..5 {
+			for i in 0..stride {
+				let left = if i >= 4 { row[i - 4] } else { 0 };
+				let corner = if i >= 4 { above[i - 4] } else { 0 };
+				let predicted = match filter {
+					0 => 0,
+					1 => left,
+					2 => above[i],
+					3 => ((left as u16 + above[i] as u16) / 2) as u8,
+					_ => paeth(left, above[i], corner)
+				};
+				candidate[i] = row[i].wrapping_sub(predicted);
+			}
+			let score = candidate.iter().map(|&v| (v as i8 as i32).abs() as u64).sum();
+			if score < best_score {
+				best_score = score;
+				best_filter = filter;
+				::std::mem::swap(&mut best, &mut candidate);
+			}
+		}
+		out.push(best_filter as u8);
+		out.extend(&best);
+	}
+	out
+}
+
+/// Encode RGBA pixels as a PNG image.
+pub fn encode(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
+	let mut out = SIGNATURE.to_vec();
+	let mut header = Vec::new();
+	push_u32(&mut header, width);
+	push_u32(&mut header, height);
+	// 8 bits per channel, RGBA, deflate, adaptive filtering, no interlacing
+	header.extend(&[8, 6, 0, 0, 0]);
+	write_chunk(&mut out, b"IHDR", &header);
+	write_chunk(&mut out, b"IDAT", &zlib_compress(&filter_rows(pixels, width, height)));
+	write_chunk(&mut out, b"IEND", &[]);
+	out
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+	(data[0] as u32) << 24 | (data[1] as u32) << 16 | (data[2] as u32) << 8 | data[3] as u32
+}
+
+fn read_u16(data: &[u8]) -> u16 {
+	(data[0] as u16) << 8 | data[1] as u16
+}
+
+// Pixel format of the image data.
+struct Format {
+	bit_depth: u8,
+	color_type: u8,
+	interlaced: bool,
+	palette: Vec<u8>,
+	transparency: Vec<u8>
+}
+
+impl Format {
+	fn channels(&self) -> usize {
+		match self.color_type {
+			2 => 3,
+			4 => 2,
+			6 => 4,
+			_ => 1
+		}
+	}
+
+	// Sample `index` of a row, as stored.
+	fn sample(&self, row: &[u8], index: usize) -> u16 {
+		match self.bit_depth {
+			16 => read_u16(&row[index * 2..]),
+			8 => row[index] as u16,
+			depth => {
+				let bit = index * depth as usize;
+				((row[bit / 8] >> (8 - depth as usize - bit % 8)) & ((1 << depth) - 1)) as u16
+			}
+		}
+	}
+
+	fn to_u8(&self, sample: u16) -> u8 {
+		match self.bit_depth {
+			16 => (sample >> 8) as u8,
+			depth => (sample as u32 * 255 / ((1 << depth) - 1)) as u8
+		}
+	}
+
+	// Convert a row of samples to RGBA.
+	fn convert(&self, row: &[u8], width: usize, out: &mut Vec<u8>) {
+		let channels = self.channels();
+		let transparent = |i: usize| if self.transparency.len() >= 2 * (i + 1) { Some(read_u16(&self.transparency[2 * i..])) } else { None };
+		for x in 0..width {
+			let samples: Vec<u16> = (0..channels).map(|c| self.sample(row, x * channels + c)).collect();
+			match self.color_type {
+				0 => {
+					let gray = self.to_u8(samples[0]);
+					let alpha = if transparent(0) == Some(samples[0]) { 0 } else { 255 };
+					out.extend(&[gray, gray, gray, alpha]);
+				}
+				2 => {
+					let opaque = (0..3).any(|c| transparent(c) != Some(samples[c]));
+					out.extend(&[self.to_u8(samples[0]), self.to_u8(samples[1]), self.to_u8(samples[2]),
+					             if opaque { 255 } else { 0 }]);
+				}
+				3 => {
+					let index = samples[0] as usize;
+					let color = self.palette.get(index * 3..index * 3 + 3).unwrap_or(&[0, 0, 0]);
+					out.extend(color);
+					out.push(*self.transparency.get(index).unwrap_or(&255));
+				}
+				4 => {
+					let gray = self.to_u8(samples[0]);
+					out.extend(&[gray, gray, gray, self.to_u8(samples[1])]);
+				}
+				_ => out.extend(samples.iter().map(|&s| self.to_u8(s)))
+			}
+		}
+	}
+}
+
+// Undo the filters of `height` rows of `stride` bytes.
+fn unfilter(data: &[u8], stride: usize, height: usize, pixel_bytes: usize) -> PngResult<Vec<u8>> {
+	if data.len() < (stride + 1) * height {
+		return Err("missing PNG image data")
+	}
+	let mut out = vec![0; stride * height];
+	for y in 0..height {
+		let filter = data[y * (stride + 1)];
+		let line = &data[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+		for i in 0..stride {
+			let left = if i >= pixel_bytes { out[y * stride + i - pixel_bytes] } else { 0 };
+			let above = if y > 0 { out[(y - 1) * stride + i] } else { 0 };
+			let corner = if y > 0 && i >= pixel_bytes { out[(y - 1) * stride + i - pixel_bytes] } else { 0 };
+			let predicted = match filter {
+				0 => 0,
+				1 => left,
+				2 => above,
+				3 => ((left as u16 + above as u16) / 2) as u8,
+				4 => paeth(left, above, corner),
+				_ => return Err("invalid PNG filter")
+			};
+			out[y * stride + i] = line[i].wrapping_add(predicted);
+		}
+	}
+	Ok(out)
+}
+
+// Decode compressed image data of the given size to RGBA.
+fn decode_pixels(format: &Format, compressed: &[u8], width: u32, height: u32) -> PngResult<Vec<u8>> {
+	let mut pixels = vec![0; try!(frames::canvas_size(width, height))];
+	let data = try!(zlib_decompress(compressed));
+	let bits = format.channels() * format.bit_depth as usize;
+	let pixel_bytes = (bits / 8).max(1);
+	let (width, height) = (width as usize, height as usize);
+	let passes: &[(usize, usize, usize, usize)] = if format.interlaced {
+		&[(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)]
+	} else {
+		&[(0, 0, 1, 1)]
+	};
+	let mut offset = 0;
+	for &(x0, y0, dx, dy) in passes {
+		let (w, h) = ((width + dx - 1 - x0.min(width)) / dx, (height + dy - 1 - y0.min(height)) / dy);
+		if w == 0 || h == 0 {
+			continue
+		}
+		let stride = (w * bits + 7) / 8;
+		let rows = try!(unfilter(&data[offset.min(data.len())..], stride, h, pixel_bytes));
+		offset += (stride + 1) * h;
+		let mut line = Vec::with_capacity(w * 4);
+		for y in 0..h {
+			line.clear();
+			format.convert(&rows[y * stride..(y + 1) * stride], w, &mut line);
+			for x in 0..w {
+				let at = ((y0 + y * dy) * width + x0 + x * dx) * 4;
+				pixels[at..at + 4].copy_from_slice(&line[x * 4..x * 4 + 4]);
+			}
+		}
+	}
+	Ok(pixels)
+}
+
+// Frame control of an APNG frame: position, size, delay, disposal and
+// blending.
+struct FrameControl {
+	x: u32,
+	y: u32,
+	width: u32,
+	height: u32,
+	delay: u32,
+	dispose: u8,
+	blend: bool
+}
+
+/// Read the properties of a PNG image without decoding its pixels.
+pub fn read_header(data: &[u8]) -> PngResult<Header> {
+	decode_frames(data, false).map(|decoded| decoded.header)
+}
+
+/// Decode a PNG image; APNG images are decoded with all their frames.
+///
+/// Samples are converted to 8-bit RGBA.
+pub fn decode(data: &[u8]) -> PngResult<Decoded> {
+	decode_frames(data, true)
+}
+
+fn decode_frames(data: &[u8], decode: bool) -> PngResult<Decoded> {
+	if data.len() < 8 || data[..8] != SIGNATURE {
+		return Err("not a PNG image")
+	}
+	let mut header = None;
+	let mut format = Format { bit_depth: 0, color_type: 0, interlaced: false, palette: Vec::new(), transparency: Vec::new() };
+	let mut gamma = None;
+	let mut animation: Option<(u32, u32)> = None;
+	let mut image_data = Vec::new();
+	let mut controls: Vec<(FrameControl, Vec<u8>)> = Vec::new();
+	let mut seen_image_data = false;
+
+	let mut pos = 8;
+	loop {
+		if pos + 12 > data.len() {
+			return Err("unexpected end of PNG data")
+		}
+		let length = read_u32(&data[pos..]) as usize;
+		if pos + 12 + length > data.len() {
+			return Err("unexpected end of PNG data")
+		}
+		let kind = &data[pos + 4..pos + 8];
+		let chunk = &data[pos + 8..pos + 8 + length];
+		if crc32(&data[pos + 4..pos + 8 + length]) != read_u32(&data[pos + 8 + length..]) {
+			return Err("PNG chunk checksum mismatch")
+		}
+		pos += 12 + length;
+
+		match kind {
+			b"IHDR" if length == 13 => {
+				format.bit_depth = chunk[8];
+				format.color_type = chunk[9];
+				format.interlaced = chunk[12] == 1;
+				let valid = match format.color_type {
+					0 => [1, 2, 4, 8, 16].contains(&format.bit_depth),
+					3 => [1, 2, 4, 8].contains(&format.bit_depth),
+					2 | 4 | 6 => [8, 16].contains(&format.bit_depth),
+					_ => false
+				};
+				if !valid || chunk[10] != 0 || chunk[11] != 0 || chunk[12] > 1 {
+					return Err("unsupported PNG format")
+				}
+				header = Some((read_u32(chunk), read_u32(&chunk[4..])));
+			}
+			b"PLTE" => format.palette = chunk.to_vec(),
+			b"tRNS" => format.transparency = chunk.to_vec(),
+			b"gAMA" if length == 4 => gamma = Some(read_u32(chunk) as f32 / 100000.),
+			b"acTL" if length == 8 => animation = Some((read_u32(chunk), read_u32(&chunk[4..]))),
+			b"fcTL" if length == 26 => {
+				let (numerator, denominator) = (read_u16(&chunk[20..]) as u32, read_u16(&chunk[22..]) as u32);
+				let control = FrameControl {
+					width: read_u32(&chunk[4..]),
+					height: read_u32(&chunk[8..]),
+					x: read_u32(&chunk[12..]),
+					y: read_u32(&chunk[16..]),
+					delay: numerator * 1000 / if denominator == 0 { 100 } else { denominator },
+					dispose: chunk[24],
+					blend: chunk[25] == 1
+				};
+				controls.push((control, Vec::new()));
+			}
+			b"IDAT" => {
+				image_data.extend(chunk);
+				// The default image is the first frame if a frame control
+				// precedes it
+				if let Some(&mut (_, ref mut frame)) = controls.last_mut() {
+					frame.extend(chunk);
+				}
+				seen_image_data = true;
+			}
+			b"fdAT" if length >= 4 => {
+				if let Some(&mut (_, ref mut frame)) = controls.last_mut() {
+					frame.extend(&chunk[4..]);
+				}
+			}
+			b"IEND" => break,
+			_ => ()
+		}
+		if header.is_none() {
+			return Err("missing PNG header")
+		}
+	}
+	let (width, height) = header.unwrap();
+	if !seen_image_data {
+		return Err("missing PNG image data")
+	}
+	if format.color_type == 3 && format.palette.is_empty() {
+		return Err("missing PNG palette")
+	}
+	// Without a valid animation control, the file is a still image
+	let animated = animation.map_or(false, |(count, _)| count > 0 && controls.len() > 0);
+	let (frame_count, loop_count) = if animated { (controls.len() as u32, animation.unwrap().1) } else { (1, 0) };
+	let result_header = Header {
+		width: width,
+		height: height,
+		bit_depth: format.bit_depth,
+		channels: format.channels() as u8,
+		gamma: gamma,
+		frame_count: frame_count,
+		loop_count: loop_count
+	};
+	if !decode {
+		return Ok(Decoded { header: result_header, frames: Vec::new() })
+	}
+	if !animated {
+		let pixels = try!(decode_pixels(&format, &image_data, width, height));
+		return Ok(Decoded { header: result_header, frames: vec![Frame { pixels: pixels, delay: 0 }] })
+	}
+
+	let mut canvas = vec![0; try!(frames::canvas_size(width, height))];
+	let mut frames = Vec::with_capacity(controls.len());
+	for (i, &(ref control, ref compressed)) in controls.iter().enumerate() {
+		if control.x as u64 + control.width as u64 > width as u64 ||
+		   control.y as u64 + control.height as u64 > height as u64 {
+			return Err("APNG frame outside of the image")
+		}
+		let pixels = try!(decode_pixels(&format, compressed, control.width, control.height));
+		// Restoring the previous canvas before the first frame clears it
+		let saved = if control.dispose == 2 && i > 0 { Some(canvas.clone()) } else { None };
+		frames::blit(&mut canvas, width, height, &pixels, control.x, control.y, control.width, control.height, control.blend);
+		frames.push(Frame { pixels: canvas.clone(), delay: control.delay });
+		match (control.dispose, saved) {
+			(_, Some(saved)) => canvas = saved,
+			(1, None) | (2, None) => frames::clear(&mut canvas, width, height, control.x, control.y, control.width, control.height),
+			_ => ()
+		}
+	}
+	Ok(Decoded { header: result_header, frames: frames })
+}
+
+#[test]
+fn png_encode() {
+	assert_eq!(crc32(b"123456789"), 0xcbf43926);
+	let pixels: Vec<u8> = (0..4 * 3 * 2).map(|i| (i * 10) as u8).collect();
+	let png = encode(&pixels, 3, 2);
+	assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]);
+	assert_eq!(&png[12..16], b"IHDR");
+	assert_eq!(&png[16..29], &[0, 0, 0, 3, 0, 0, 0, 2, 8, 6, 0, 0, 0]);
+	// The empty IEND chunk has a well-known checksum
+	assert_eq!(&png[png.len() - 12..], &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]);
+	let length = ((png[33] as usize) << 24) | (png[34] as usize) << 16 | (png[35] as usize) << 8 | png[36] as usize;
+	assert_eq!(&png[37..41], b"IDAT");
+	let rows = ::formats::inflate::zlib_decompress(&png[41..41 + length]).unwrap();
+	assert_eq!(rows.len(), 2 * (1 + 12));
+}
+
+#[cfg(test)]
+fn unfilter_rows(rows: &[u8], width: usize) -> Vec<u8> {
+	let stride = width * 4;
+	let mut out: Vec<u8> = Vec::new();
+	for (y, row) in rows.chunks(stride + 1).enumerate() {
+		for i in 0..stride {
+			let left = if i >= 4 { out[y * stride + i - 4] } else { 0 };
+			let above = if y > 0 { out[(y - 1) * stride + i] } else { 0 };
+			let corner = if i >= 4 && y > 0 { out[(y - 1) * stride + i - 4] } else { 0 };
+			let predicted = match row[0] {
+				0 => 0,
+				1 => left,
+				2 => above,
+				3 => ((left as u16 + above as u16) / 2) as u8,
+				_ => paeth(left, above, corner)
+			};
+			out.push(row[i + 1].wrapping_add(predicted));
+		}
+	}
+	out
+}
+
+#[test]
+fn png_encode_filtered_rows() {
+	// Differences of 128 between neighbours must not overflow the filter
+	// heuristic
+	let pixels: Vec<u8> = (0..4 * 4 * 3).map(|i| if (i / 4 + i / 16) % 2 == 0 { 0 } else { 128 }).collect();
+	let png = encode(&pixels, 4, 3);
+	let length = ((png[33] as usize) << 24) | (png[34] as usize) << 16 | (png[35] as usize) << 8 | png[36] as usize;
+	let rows = ::formats::inflate::zlib_decompress(&png[41..41 + length]).unwrap();
+	assert_eq!(unfilter_rows(&rows, 4), pixels);
+}
@@ -0,0 +1,80 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! TGA encoding of 8-bit RGBA images, run-length compressed.
+
+fn push_u16(out: &mut Vec<u8>, value: u16) {
+	out.extend(&[value as u8, (value >> 8) as u8]);
+}
+
+/// Encode RGBA pixels as a TGA image.
+///
+/// TGA sizes are limited to 65535 pixels; returns None for larger images.
+pub fn encode(pixels: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
+	if width > 0xffff || height > 0xffff {
+		return None
+	}
+	// No identifier or color map, run-length encoded true color
+	let mut out = vec![0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+	push_u16(&mut out, width as u16);
+	push_u16(&mut out, height as u16);
+	// 32 bits per pixel, 8 of them alpha, first row at the top
+	out.extend(&[32, 0x28]);
+
+	let bgra: Vec<[u8; 4]> = pixels.chunks(4).map(|p| [p[2], p[1], p[0], p[3]]).collect();
+	// Packets can not span rows
+	for row in bgra.chunks((width as usize).max(1)) {
+		let mut i = 0;
+		while i < row.len() {
+			let run = row[i..].iter().take(128).take_while(|&p| *p == row[i]).count();
+			if run > 1 {
+				out.push(0x80 | (run - 1) as u8);
+				out.extend(&row[i]);
+				i += run;
+			} else {
+				// Raw packet up to the next run of identical pixels
+				let mut end = i + 1;
+				while end < row.len() && end - i < 128 && (end + 1 >= row.len() || row[end] != row[end + 1]) {
+					end += 1;
+				}
+				out.push((end - i - 1) as u8);
+				for p in &row[i..end] {
+					out.extend(p);
+				}
+				i = end;
+			}
+		}
+	}
+	Some(out)
+}
+
+#[test]
+fn tga_encode() {
+	let (a, b, c) = ([1, 2, 3, 255], [4, 5, 6, 255], [7, 8, 9, 0]);
+	let pixels: Vec<u8> = [a, a, a, b, c, c].iter().flat_map(|p| p.iter().cloned()).collect();
+	let tga = encode(&pixels, 3, 2).unwrap();
+	assert_eq!(&tga[..18], &[0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 2, 0, 32, 0x28]);
+	assert_eq!(&tga[18..], &[0x82, 3, 2, 1, 255, 0, 6, 5, 4, 255, 0x81, 9, 8, 7, 0]);
+	assert!(encode(&[], 70000, 0).is_none());
+}
@@ -0,0 +1,205 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Character maps of TrueType and OpenType fonts.
+
+type FontResult<T> = Result<T, &'static str>;
+
+fn read_u16(data: &[u8], offset: usize) -> FontResult<u16> {
+	match data.get(offset..offset + 2) {
+		Some(b) => Ok((b[0] as u16) << 8 | b[1] as u16),
+		None => Err("font data is truncated")
+	}
+}
+
+fn read_u32(data: &[u8], offset: usize) -> FontResult<u32> {
+	Ok((try!(read_u16(data, offset)) as u32) << 16 | try!(read_u16(data, offset + 2)) as u32)
+}
+
+/// The characters a font has glyphs for, as sorted, disjoint and inclusive
+/// ranges of codepoints.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CharacterMap {
+	/// The ranges of codepoints.
+	pub ranges: Vec<(u32, u32)>
+}
+
+impl CharacterMap {
+	/// Tell whether the font has a glyph for a character.
+	pub fn contains(&self, codepoint: char) -> bool {
+		let c = codepoint as u32;
+		match self.ranges.binary_search_by(|&(start, _)| start.cmp(&c)) {
+			Ok(_) => true,
+			Err(0) => false,
+			Err(index) => c <= self.ranges[index - 1].1
+		}
+	}
+
+	// Add a range, merging it with the previous one when they touch.
+	fn push(&mut self, start: u32, end: u32) {
+		if let Some(last) = self.ranges.last_mut() {
+			if start <= last.1 + 1 {
+				last.1 = last.1.max(end);
+				return
+			}
+		}
+		self.ranges.push((start, end));
+	}
+}
+
+/// Read the character map of a TrueType or OpenType font, or of the first
+/// font of a collection.
+///
+/// Only the Unicode subtables in formats 4 and 12 are supported, which
+/// every Unicode font has.
+pub fn read_character_map(data: &[u8]) -> FontResult<CharacterMap> {
+	let mut font = 0;
+	if data.starts_with(b"ttcf") {
+		font = try!(read_u32(data, 12)) as usize;
+	}
+	match try!(read_u32(data, font)) {
+		0x00010000 | 0x4f54544f | 0x74727565 => {}, // 1.0, "OTTO", "true"
+		_ => return Err("not a TrueType or OpenType font")
+	}
+	let tables = try!(read_u16(data, font + 4)) as usize;
+	let mut cmap = None;
+	for i in 0..tables {
+		let record = font + 12 + i * 16;
+		if data.get(record..record + 4) == Some(b"cmap") {
+			cmap = Some(try!(read_u32(data, record + 8)) as usize);
+		}
+	}
+	let cmap = match cmap {
+		Some(cmap) => cmap,
+		None => return Err("font has no character map")
+	};
+
+	// Prefer full Unicode subtables, then the Basic Multilingual Plane
+	let mut best = None;
+	for i in 0..try!(read_u16(data, cmap + 2)) as usize {
+		let record = cmap + 4 + i * 8;
+		let score = match (try!(read_u16(data, record)), try!(read_u16(data, record + 2))) {
+			(3, 10) | (0, 4) | (0, 6) => 2,
+			(3, 1) | (0, _) => 1,
+			_ => continue
+		};
+		let offset = cmap + try!(read_u32(data, record + 4)) as usize;
+		let format = try!(read_u16(data, offset));
+		if (format == 4 || format == 12) && best.map_or(true, |(best_score, _)| score > best_score) {
+			best = Some((score, offset));
+		}
+	}
+	match best {
+		Some((_, offset)) if try!(read_u16(data, offset)) == 4 => read_format_4(data, offset),
+		Some((_, offset)) => read_format_12(data, offset),
+		None => Err("font has no Unicode character map")
+	}
+}
+
+// Segments of 16-bit codepoints, with glyph ids computed by an offset or
+// looked up in an array.
+fn read_format_4(data: &[u8], offset: usize) -> FontResult<CharacterMap> {
+	let segments = try!(read_u16(data, offset + 6)) as usize / 2;
+	let ends = offset + 14;
+	let starts = ends + segments * 2 + 2;
+	let deltas = starts + segments * 2;
+	let range_offsets = deltas + segments * 2;
+	let mut map = CharacterMap { ranges: Vec::new() };
+	for i in 0..segments {
+		let end = try!(read_u16(data, ends + i * 2)) as u32;
+		let start = try!(read_u16(data, starts + i * 2)) as u32;
+		let delta = try!(read_u16(data, deltas + i * 2)) as u32;
+		let range_offset = try!(read_u16(data, range_offsets + i * 2)) as usize;
+		// The last segment only maps 0xFFFF to the missing glyph
+		for c in start..end.min(0xfffe) + 1 {
+			let glyph = if range_offset == 0 {
+				(c + delta) & 0xffff
+			} else {
+				let address = range_offsets + i * 2 + range_offset + (c - start) as usize * 2;
+				match try!(read_u16(data, address)) as u32 {
+					0 => 0,
+					glyph => (glyph + delta) & 0xffff
+				}
+			};
+			if glyph != 0 {
+				map.push(c, c);
+			}
+		}
+	}
+	Ok(map)
+}
+
+// Groups of consecutive codepoints mapped to consecutive glyph ids.
+fn read_format_12(data: &[u8], offset: usize) -> FontResult<CharacterMap> {
+	let groups = try!(read_u32(data, offset + 12)) as usize;
+	if groups > data.len() / 12 {
+		return Err("font data is truncated")
+	}
+	let mut ranges: Vec<(u32, u32)> = Vec::with_capacity(groups);
+	for i in 0..groups {
+		let group = offset + 16 + i * 12;
+		let (start, end) = (try!(read_u32(data, group)), try!(read_u32(data, group + 4)).min(0x10ffff));
+		if start > 0x10ffff {
+			continue
+		}
+		// Only the first character of a group can map to the missing glyph
+		let start = if try!(read_u32(data, group + 8)) == 0 { start + 1 } else { start };
+		if start <= end {
+			ranges.push((start, end));
+		}
+	}
+	ranges.sort();
+	let mut map = CharacterMap { ranges: Vec::new() };
+	for (start, end) in ranges {
+		map.push(start, end);
+	}
+	Ok(map)
+}
+
+#[test]
+fn truetype_character_map() {
+	use std::fs::File;
+	use std::io::Read;
+
+	let mut data = Vec::new();
+	File::open("resources/sansation.ttf").unwrap().read_to_end(&mut data).unwrap();
+	let map = read_character_map(&data).unwrap();
+	for c in "AZaz09 ?!éÉ".chars() {
+		assert!(map.contains(c), "{:?} is missing", c);
+	}
+	for &c in &['\u{0}', '\u{4e2d}', '\u{1f600}', '\u{10ffff}'] {
+		assert!(!map.contains(c), "{:?} is present", c);
+	}
+	assert!(map.ranges.windows(2).all(|pair| pair[0].1 + 1 < pair[1].0));
+
+	assert!(read_character_map(b"GIF89a").is_err());
+	assert!(read_character_map(&data[..100]).is_err());
+
+	// Groups beyond Unicode are skipped, even when mapped to the missing glyph
+	let mut table = vec![0, 12, 0, 0, 0, 0, 0, 40, 0, 0, 0, 0, 0, 0, 0, 2];
+	table.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+	table.extend_from_slice(&[0, 0, 0, 0x41, 0, 0, 0, 0x5a, 0, 0, 0, 0]);
+	let map = read_format_12(&table, 0).unwrap();
+	assert_eq!(map.ranges, vec![(0x42, 0x5a)]);
+}
@@ -0,0 +1,256 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! A minimal, non-validating XML reader producing an element tree.
+//!
+//! Supports elements, attributes, text, the predefined and numeric character
+//! entities, comments, CDATA sections, and skips processing instructions and
+//! doctype declarations. Namespaces are not interpreted.
+
+/// An XML element with its attributes, child elements and text content.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Element {
+	/// Tag name of the element.
+	pub name: String,
+	/// Attributes, in document order.
+	pub attributes: Vec<(String, String)>,
+	/// Child elements, in document order.
+	pub children: Vec<Element>,
+	/// Concatenated text content directly inside this element.
+	pub text: String
+}
+
+impl Element {
+	/// Get the value of an attribute.
+	pub fn attr(&self, name: &str) -> Option<&str> {
+		self.attributes.iter().find(|&&(ref key, _)| key == name).map(|&(_, ref value)| &value[..])
+	}
+
+	/// Get the first child element with the given name.
+	pub fn child(&self, name: &str) -> Option<&Element> {
+		self.children.iter().find(|child| child.name == name)
+	}
+
+	/// Get all child elements with the given name.
+	pub fn children_named(&self, name: &str) -> Vec<&Element> {
+		self.children.iter().filter(|child| child.name == name).collect()
+	}
+}
+
+struct Parser<'a> {
+	text: &'a str,
+	pos: usize
+}
+
+type XmlResult<T> = Result<T, String>;
+
+impl<'a> Parser<'a> {
+	fn rest(&self) -> &'a str {
+		&self.text[self.pos..]
+	}
+
+	fn error<T>(&self, message: &str) -> XmlResult<T> {
+		let line = self.text[..self.pos].matches('\n').count() + 1;
+		Err(format!("{} (line {})", message, line))
+	}
+
+	fn skip_whitespace(&mut self) {
+		let rest = self.rest();
+		self.pos += rest.len() - rest.trim_left().len();
+	}
+
+	fn skip_past(&mut self, end: &str) -> XmlResult<()> {
+		match self.rest().find(end) {
+			Some(index) => {
+				self.pos += index + end.len();
+				Ok(())
+			}
+			None => self.error(&format!("expected `{}`", end))
+		}
+	}
+
+	fn expect(&mut self, token: &str) -> XmlResult<()> {
+		if self.rest().starts_with(token) {
+			self.pos += token.len();
+			Ok(())
+		} else {
+			self.error(&format!("expected `{}`", token))
+		}
+	}
+
+	fn name(&mut self) -> XmlResult<String> {
+		let rest = self.rest();
+		let len = rest.find(|c: char| c.is_whitespace() || "=/>".contains(c)).unwrap_or(rest.len());
+		if len == 0 {
+			return self.error("expected a name")
+		}
+		self.pos += len;
+		Ok(rest[..len].to_owned())
+	}
+
+	// Skip comments, processing instructions and declarations.
+	fn skip_misc(&mut self) -> XmlResult<bool> {
+		if self.rest().starts_with("<!--") {
+			try!(self.skip_past("-->"));
+		} else if self.rest().starts_with("<?") {
+			try!(self.skip_past("?>"));
+		} else if self.rest().starts_with("<!") && !self.rest().starts_with("<![CDATA[") {
+			try!(self.skip_past(">"));
+		} else {
+			return Ok(false)
+		}
+		Ok(true)
+	}
+
+	fn element(&mut self) -> XmlResult<Element> {
+		try!(self.expect("<"));
+		let mut element = Element { name: try!(self.name()), ..Element::default() };
+		loop {
+			self.skip_whitespace();
+			if self.rest().starts_with("/>") {
+				self.pos += 2;
+				return Ok(element)
+			} else if self.rest().starts_with(">") {
+				self.pos += 1;
+				break
+			}
+			let key = try!(self.name());
+			self.skip_whitespace();
+			try!(self.expect("="));
+			self.skip_whitespace();
+			let quote = match self.rest().chars().next() {
+				Some(c @ '"') | Some(c @ '\'') => c,
+				_ => return self.error("expected a quoted attribute value")
+			};
+			self.pos += 1;
+			let len = match self.rest().find(quote) {
+				Some(len) => len,
+				None => return self.error("unterminated attribute value")
+			};
+			let value = try!(unescape(&self.rest()[..len]));
+			self.pos += len + 1;
+			element.attributes.push((key, value));
+		}
+
+		loop {
+			if try!(self.skip_misc()) {
+				continue
+			}
+			let rest = self.rest();
+			if rest.starts_with("<![CDATA[") {
+				self.pos += 9;
+				let len = match self.rest().find("]]>") {
+					Some(len) => len,
+					None => return self.error("unterminated CDATA section")
+				};
+				element.text.push_str(&self.rest()[..len]);
+				self.pos += len + 3;
+			} else if rest.starts_with("</") {
+				self.pos += 2;
+				let name = try!(self.name());
+				if name != element.name {
+					return self.error(&format!("mismatched closing tag `{}`", name))
+				}
+				self.skip_whitespace();
+				try!(self.expect(">"));
+				return Ok(element)
+			} else if rest.starts_with("<") {
+				element.children.push(try!(self.element()));
+			} else if rest.is_empty() {
+				return self.error(&format!("unclosed element `{}`", element.name))
+			} else {
+				let len = rest.find('<').unwrap_or(rest.len());
+				element.text.push_str(&try!(unescape(&rest[..len])));
+				self.pos += len;
+			}
+		}
+	}
+}
+
+/// Replace entity and character references in some text.
+pub fn unescape(text: &str) -> XmlResult<String> {
+	let mut out = String::with_capacity(text.len());
+	let mut rest = text;
+	while let Some(start) = rest.find('&') {
+		out.push_str(&rest[..start]);
+		let end = match rest[start..].find(';') {
+			Some(end) => start + end,
+			None => return Err("unterminated entity reference".to_owned())
+		};
+		let entity = &rest[start + 1..end];
+		let ch = match entity {
+			"lt" => Some('<'),
+			"gt" => Some('>'),
+			"amp" => Some('&'),
+			"quot" => Some('"'),
+			"apos" => Some('\''),
+			_ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16).ok().and_then(::std::char::from_u32),
+			_ if entity.starts_with("#") => entity[1..].parse().ok().and_then(::std::char::from_u32),
+			_ => None
+		};
+		match ch {
+			Some(ch) => out.push(ch),
+			None => return Err(format!("unknown entity `&{};`", entity))
+		}
+		rest = &rest[end + 1..];
+	}
+	out.push_str(rest);
+	Ok(out)
+}
+
+/// Parse an XML document, returning its root element.
+pub fn parse(text: &str) -> Result<Element, String> {
+	let text = if text.starts_with("\u{feff}") { &text[3..] } else { text };
+	let mut parser = Parser { text: text, pos: 0 };
+	loop {
+		parser.skip_whitespace();
+		if !try!(parser.skip_misc()) {
+			break
+		}
+	}
+	let root = try!(parser.element());
+	loop {
+		parser.skip_whitespace();
+		if !try!(parser.skip_misc()) {
+			break
+		}
+	}
+	if !parser.rest().is_empty() {
+		return parser.error("unexpected content after the root element")
+	}
+	Ok(root)
+}
+
+#[test]
+fn xml_parse() {
+	let doc = parse("<?xml version=\"1.0\"?>\n<!-- comment -->\n<map a='1' b=\"x &amp; y\">\
+	                 <layer name=\"one\"/><data>1,2&#x2C;3<![CDATA[<raw>]]></data>\
+	                 <layer name=\"two\"></layer></map>").unwrap();
+	assert_eq!(doc.name, "map");
+	assert_eq!(doc.attr("b"), Some("x & y"));
+	assert_eq!(doc.children_named("layer").len(), 2);
+	assert_eq!(doc.child("data").unwrap().text, "1,2,3<raw>");
+	assert!(parse("<a><b></a>").is_err());
+	assert!(parse("<a></a><b/>").is_err());
+}
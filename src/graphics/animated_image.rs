@@ -0,0 +1,486 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+
+use formats::{bmp, dds, gif, hdr, jpeg, png};
+use formats::frames::{self, Decoded, Header};
+use graphics::{Animation, Frame, Image, IntRect, PlayMode};
+use system::{Time, Vector2u};
+
+/// File formats recognized by `ImageInfo`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ImageFileFormat {
+	/// PNG, including animated PNG (APNG).
+	Png,
+	/// GIF, including animated GIF.
+	Gif,
+	/// JPEG.
+	Jpeg,
+	/// Windows bitmap.
+	Bmp,
+	/// Radiance HDR with RGBE pixels, reported with a bit depth of 32 and
+	/// tone mapped to 8 bits per channel when decoded.
+	Hdr,
+	/// DirectDraw Surface, uncompressed or with DXT1, DXT3 or DXT5
+	/// compression. Only the main surface is decoded, without its mipmaps.
+	Dds
+}
+
+impl ImageFileFormat {
+	/// Detect the format of image data from its signature.
+	pub fn detect(data: &[u8]) -> Option<ImageFileFormat> {
+		if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+			Some(ImageFileFormat::Png)
+		} else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+			Some(ImageFileFormat::Gif)
+		} else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+			Some(ImageFileFormat::Jpeg)
+		} else if data.starts_with(b"BM") {
+			Some(ImageFileFormat::Bmp)
+		} else if data.starts_with(b"#?RADIANCE") || data.starts_with(b"#?RGBE") {
+			Some(ImageFileFormat::Hdr)
+		} else if data.starts_with(b"DDS ") {
+			Some(ImageFileFormat::Dds)
+		} else {
+			None
+		}
+	}
+}
+
+/// Errors returned when reading image files with `ImageInfo` and
+/// `AnimatedImage`.
+#[derive(Debug)]
+pub enum ImageDecodeError {
+	/// The file could not be read.
+	Io(io::Error),
+	/// The file is malformed.
+	Parse(String),
+	/// The file format is not supported.
+	Unsupported(String)
+}
+
+impl fmt::Display for ImageDecodeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			ImageDecodeError::Io(ref err) => write!(f, "I/O error: {}", err),
+			ImageDecodeError::Parse(ref msg) => write!(f, "malformed image: {}", msg),
+			ImageDecodeError::Unsupported(ref msg) => write!(f, "unsupported image: {}", msg)
+		}
+	}
+}
+
+impl Error for ImageDecodeError {
+	fn description(&self) -> &str {
+		match *self {
+			ImageDecodeError::Io(_) => "I/O error",
+			ImageDecodeError::Parse(_) => "malformed image",
+			ImageDecodeError::Unsupported(_) => "unsupported image"
+		}
+	}
+
+	fn cause(&self) -> Option<&Error> {
+		match *self {
+			ImageDecodeError::Io(ref err) => Some(err),
+			_ => None
+		}
+	}
+}
+
+impl From<io::Error> for ImageDecodeError {
+	fn from(err: io::Error) -> ImageDecodeError {
+		ImageDecodeError::Io(err)
+	}
+}
+
+fn parse_error(msg: &'static str) -> ImageDecodeError {
+	ImageDecodeError::Parse(msg.to_owned())
+}
+
+fn unknown_format() -> ImageDecodeError {
+	ImageDecodeError::Unsupported("unknown image format".to_owned())
+}
+
+/// Properties of an image file, read without decoding its pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageInfo {
+	/// Format of the file.
+	pub format: ImageFileFormat,
+	/// Width of the image in pixels.
+	pub width: u32,
+	/// Height of the image in pixels.
+	pub height: u32,
+	/// Bits per channel, or per color index for palette images.
+	pub bit_depth: u8,
+	/// Channels per pixel, 1 for palette and grayscale images.
+	pub channels: u8,
+	/// Gamma the image was encoded with, if the file stores it (PNG and HDR only).
+	pub gamma: Option<f32>,
+	/// Number of frames, 1 for still images.
+	pub frame_count: u32,
+	/// Number of times an animation plays, 0 meaning forever.
+	pub loop_count: u32
+}
+
+impl ImageInfo {
+	fn new(format: ImageFileFormat, header: Header) -> ImageInfo {
+		ImageInfo {
+			format: format,
+			width: header.width,
+			height: header.height,
+			bit_depth: header.bit_depth,
+			channels: header.channels,
+			gamma: header.gamma,
+			frame_count: header.frame_count,
+			loop_count: header.loop_count
+		}
+	}
+
+	/// Read the properties of an image file in memory.
+	pub fn from_memory(data: &[u8]) -> Result<ImageInfo, ImageDecodeError> {
+		let format = match ImageFileFormat::detect(data) {
+			Some(format) => format,
+			None => return Err(unknown_format())
+		};
+		let header = match format {
+			ImageFileFormat::Png => png::read_header(data),
+			ImageFileFormat::Gif => gif::read_header(data),
+			ImageFileFormat::Jpeg => jpeg::read_header(data),
+			ImageFileFormat::Bmp => bmp::read_header(data),
+			ImageFileFormat::Hdr => hdr::read_header(data),
+			ImageFileFormat::Dds => dds::read_header(data)
+		};
+		header.map(|header| ImageInfo::new(format, header)).map_err(parse_error)
+	}
+
+	/// Read the properties of an image file.
+	pub fn from_file(filename: &str) -> Result<ImageInfo, ImageDecodeError> {
+		let mut data = Vec::new();
+		try!(try!(File::open(filename)).read_to_end(&mut data));
+		ImageInfo::from_memory(&data)
+	}
+}
+
+/// A frame of an `AnimatedImage`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimatedFrame {
+	pixels: Vec<u8>,
+	delay: Time
+}
+
+impl AnimatedFrame {
+	/// Get the RGBA pixels of the frame, as large as the whole animation.
+	pub fn get_pixels(&self) -> &[u8] {
+		&self.pixels
+	}
+
+	/// Get the time the frame stays on screen.
+	pub fn get_delay(&self) -> Time {
+		self.delay
+	}
+}
+
+/// An animated GIF or PNG image, decoded into a sequence of frames.
+///
+/// Every frame holds the complete picture, with the parts of the previous
+/// frames it does not cover already composited. Frames can be turned into
+/// `Image`s, or into a sprite sheet and an `Animation` to play with an
+/// `AnimatedSprite`:
+///
+/// ```ignore
+/// let gif = AnimatedImage::from_file("explosion.gif").unwrap();
+/// let texture = Texture::new_from_image(&gif.to_sprite_sheet(8).unwrap(), None).unwrap();
+/// let animation = gif.get_animation(8, PlayMode::Once).unwrap();
+/// let sprite = AnimatedSprite::new_with_animation(&texture, &animation).unwrap();
+/// ```
+///
+/// Like browsers, frames with a delay of 10 milliseconds or less are shown
+/// for 100 milliseconds. Still PNG images, and HDR and DDS images, are
+/// loaded as a single frame.
+#[derive(Clone, Debug)]
+pub struct AnimatedImage {
+	info: ImageInfo,
+	frames: Vec<AnimatedFrame>
+}
+
+impl AnimatedImage {
+	/// Decode an animated GIF or PNG image, or an HDR or DDS image, in memory.
+	pub fn from_memory(data: &[u8]) -> Result<AnimatedImage, ImageDecodeError> {
+		let (format, decoded) = match ImageFileFormat::detect(data) {
+			Some(ImageFileFormat::Png) => (ImageFileFormat::Png, png::decode(data)),
+			Some(ImageFileFormat::Gif) => (ImageFileFormat::Gif, gif::decode(data)),
+			Some(ImageFileFormat::Hdr) => (ImageFileFormat::Hdr, hdr::decode(data)),
+			Some(ImageFileFormat::Dds) => (ImageFileFormat::Dds, dds::decode(data)),
+			Some(_) => return Err(ImageDecodeError::Unsupported("JPEG and BMP images can only be loaded by Image".to_owned())),
+			None => return Err(unknown_format())
+		};
+		let Decoded { header, frames } = try!(decoded.map_err(parse_error));
+		Ok(AnimatedImage {
+			info: ImageInfo::new(format, header),
+			frames: frames.into_iter().map(|frame| AnimatedFrame {
+				pixels: frame.pixels,
+				delay: Time::with_milliseconds(if frame.delay <= 10 { 100 } else { frame.delay as i32 })
+			}).collect()
+		})
+	}
+
+	/// Decode an animated GIF or PNG file, or an HDR or DDS file.
+	pub fn from_file(filename: &str) -> Result<AnimatedImage, ImageDecodeError> {
+		let mut data = Vec::new();
+		try!(try!(File::open(filename)).read_to_end(&mut data));
+		AnimatedImage::from_memory(&data)
+	}
+
+	/// Decode an animated GIF or PNG image, or an HDR or DDS image, read from
+	/// a stream.
+	pub fn from_stream<T: Read>(stream: &mut T) -> Result<AnimatedImage, ImageDecodeError> {
+		let mut data = Vec::new();
+		try!(stream.read_to_end(&mut data));
+		AnimatedImage::from_memory(&data)
+	}
+
+	/// Get the properties of the image file.
+	pub fn get_info(&self) -> &ImageInfo {
+		&self.info
+	}
+
+	/// Get the size of the frames.
+	pub fn get_size(&self) -> Vector2u {
+		Vector2u::new(self.info.width, self.info.height)
+	}
+
+	/// Get the frames, in order.
+	pub fn get_frames(&self) -> &[AnimatedFrame] {
+		&self.frames
+	}
+
+	/// Get the number of frames.
+	pub fn get_frame_count(&self) -> usize {
+		self.frames.len()
+	}
+
+	/// Get the number of times the animation plays, 0 meaning forever.
+	pub fn get_loop_count(&self) -> u32 {
+		self.info.loop_count
+	}
+
+	/// Get the total duration of one play of the animation.
+	pub fn get_duration(&self) -> Time {
+		self.frames.iter().fold(Time::with_microseconds(0), |total, frame| total + frame.delay)
+	}
+
+	/// Create an image from a frame.
+	///
+	/// Returns None if the index is out of range or on failure.
+	pub fn frame_to_image(&self, index: usize) -> Option<Image> {
+		self.frames.get(index).and_then(|frame| Image::create_from_pixels(self.info.width, self.info.height, &frame.pixels))
+	}
+
+	// Number of columns and size of the sprite sheet, or None if it is too
+	// large to allocate.
+	fn sheet_layout(&self, columns: u32) -> Option<(u32, Vector2u)> {
+		let frames = self.frames.len().max(1);
+		let columns = (columns.max(1) as usize).min(frames);
+		let rows = (frames + columns - 1) / columns;
+		let width = (self.info.width as usize).checked_mul(columns);
+		let height = (self.info.height as usize).checked_mul(rows);
+		match (width, height) {
+			(Some(width), Some(height)) if width <= u32::max_value() as usize && height <= u32::max_value() as usize => {
+				let size = Vector2u::new(width as u32, height as u32);
+				frames::canvas_size(size.x, size.y).ok().map(|_| (columns as u32, size))
+			}
+			_ => None
+		}
+	}
+
+	// Rectangle of a frame in a sprite sheet laid out by `sheet_layout`, whose
+	// size fits in an i32 since the sheet is smaller than `MAX_CANVAS_BYTES`.
+	fn sheet_rect(&self, index: usize, columns: u32) -> IntRect {
+		let columns = columns as usize;
+		let (w, h) = (self.info.width as usize, self.info.height as usize);
+		IntRect::new((index % columns * w) as i32, (index / columns * h) as i32, w as i32, h as i32)
+	}
+
+	/// Get the size of the sprite sheet made by `to_sprite_sheet`.
+	///
+	/// Returns None if the sprite sheet is too large.
+	pub fn get_sprite_sheet_size(&self, columns: u32) -> Option<Vector2u> {
+		self.sheet_layout(columns).map(|(_, size)| size)
+	}
+
+	/// Get the pixels of the sprite sheet made by `to_sprite_sheet`.
+	///
+	/// Returns None if the sprite sheet is too large.
+	pub fn get_sprite_sheet_pixels(&self, columns: u32) -> Option<Vec<u8>> {
+		let (columns, size) = match self.sheet_layout(columns) {
+			Some(layout) => layout,
+			None => return None
+		};
+		let mut pixels = vec![0; size.x as usize * size.y as usize * 4];
+		let stride = self.info.width as usize * 4;
+		for (i, frame) in self.frames.iter().enumerate() {
+			let rect = self.sheet_rect(i, columns);
+			for y in 0..self.info.height as usize {
+				let at = ((rect.top as usize + y) * size.x as usize + rect.left as usize) * 4;
+				pixels[at..at + stride].copy_from_slice(&frame.pixels[y * stride..(y + 1) * stride]);
+			}
+		}
+		Some(pixels)
+	}
+
+	/// Lay the frames out in a sprite sheet, `columns` frames per row, from
+	/// left to right and top to bottom.
+	///
+	/// Returns None if the sprite sheet is too large or on failure.
+	pub fn to_sprite_sheet(&self, columns: u32) -> Option<Image> {
+		let size = match self.get_sprite_sheet_size(columns) {
+			Some(size) => size,
+			None => return None
+		};
+		self.get_sprite_sheet_pixels(columns).and_then(|pixels| Image::create_from_pixels(size.x, size.y, &pixels))
+	}
+
+	/// Get the animation playing the frames from the sprite sheet made by
+	/// `to_sprite_sheet` with the same number of columns.
+	///
+	/// Returns None if the sprite sheet is too large.
+	pub fn get_animation(&self, columns: u32, mode: PlayMode) -> Option<Animation> {
+		self.sheet_layout(columns).map(|(columns, _)| {
+			Animation::from_frames(mode, self.frames.iter().enumerate()
+				.map(|(i, frame)| Frame::new(self.sheet_rect(i, columns), frame.delay))
+				.collect())
+		})
+	}
+}
+
+#[cfg(test)]
+fn pixel(frame: &AnimatedFrame, x: usize, y: usize) -> &[u8] {
+	&frame.get_pixels()[(y * 16 + x) * 4..(y * 16 + x) * 4 + 4]
+}
+
+#[test]
+fn animated_image_gif() {
+	let data = include_bytes!("../../resources/animated.gif");
+	let info = ImageInfo::from_memory(data).unwrap();
+	assert_eq!(info, ImageInfo {
+		format: ImageFileFormat::Gif, width: 16, height: 16, bit_depth: 2, channels: 1,
+		gamma: None, frame_count: 3, loop_count: 0
+	});
+	let gif = AnimatedImage::from_memory(data).unwrap();
+	let delays: Vec<i32> = gif.get_frames().iter().map(|f| f.get_delay().as_milliseconds()).collect();
+	assert_eq!(delays, vec![100, 200, 300]);
+	let frames = gif.get_frames();
+	assert!((0..16).all(|i| pixel(&frames[0], i, 15 - i) == [255, 0, 0, 255]));
+	// A green ring drawn over the first frame, through its transparent center
+	assert_eq!(pixel(&frames[1], 4, 4), [0, 255, 0, 255]);
+	assert_eq!(pixel(&frames[1], 11, 9), [0, 255, 0, 255]);
+	assert_eq!(pixel(&frames[1], 7, 7), [255, 0, 0, 255]);
+	assert_eq!(pixel(&frames[1], 12, 12), [255, 0, 0, 255]);
+	// The ring is disposed to transparency before the blue corner
+	assert_eq!(pixel(&frames[2], 1, 2), [0, 0, 255, 255]);
+	assert_eq!(pixel(&frames[2], 7, 7), [0, 0, 0, 0]);
+	assert_eq!(pixel(&frames[2], 4, 0), [255, 0, 0, 255]);
+}
+
+#[test]
+fn animated_image_apng() {
+	let data = include_bytes!("../../resources/animated.png");
+	let info = ImageInfo::from_memory(data).unwrap();
+	assert_eq!((info.format, info.bit_depth, info.channels, info.frame_count), (ImageFileFormat::Png, 8, 4, 3));
+	assert!((info.gamma.unwrap() - 0.45455).abs() < 1e-6);
+	let png = AnimatedImage::from_memory(data).unwrap();
+	assert_eq!(png.get_duration().as_milliseconds(), 600);
+	let frames = png.get_frames();
+	assert_eq!(pixel(&frames[0], 9, 3), [255, 0, 0, 255]);
+	// Blended over the first frame, transparent on the left
+	assert_eq!(pixel(&frames[1], 5, 5), [255, 0, 0, 255]);
+	assert_eq!(pixel(&frames[1], 10, 5), [0, 255, 0, 255]);
+	// The second frame's region is cleared, the third replaces its pixels
+	assert_eq!(pixel(&frames[2], 10, 5), [0, 0, 0, 0]);
+	assert_eq!(pixel(&frames[2], 3, 3), [0, 0, 255, 128]);
+	assert_eq!(pixel(&frames[2], 15, 0), [255, 0, 0, 255]);
+}
+
+#[test]
+fn animated_image_sprite_sheet() {
+	let gif = AnimatedImage::from_memory(include_bytes!("../../resources/animated.gif")).unwrap();
+	assert_eq!(gif.get_sprite_sheet_size(2), Some(Vector2u::new(32, 32)));
+	assert_eq!(gif.get_sprite_sheet_size(5), Some(Vector2u::new(48, 16)));
+	let animation = gif.get_animation(2, PlayMode::Loop).unwrap();
+	assert_eq!(animation.get_frame_count(), 3);
+	assert_eq!(animation.get_frame(2).unwrap().rect, IntRect::new(0, 16, 16, 16));
+	assert_eq!(animation.get_duration().as_milliseconds(), 600);
+	let sheet = gif.get_sprite_sheet_pixels(2).unwrap();
+	let at = ((16 + 1) * 32 + 2) * 4;
+	assert_eq!(&sheet[at..at + 4], &[0, 0, 255, 255]);
+	assert!(AnimatedImage::from_memory(b"BM not animated").is_err());
+}
+
+#[test]
+fn animated_image_sprite_sheet_too_large() {
+	let frame = AnimatedFrame { pixels: Vec::new(), delay: Time::with_milliseconds(100) };
+	let image = AnimatedImage {
+		info: ImageInfo {
+			format: ImageFileFormat::Gif, width: 0x10000, height: 0x8000, bit_depth: 8, channels: 1,
+			gamma: None, frame_count: 3, loop_count: 0
+		},
+		frames: vec![frame.clone(), frame.clone(), frame]
+	};
+	for &columns in &[1, 3, u32::max_value()] {
+		assert_eq!(image.get_sprite_sheet_size(columns), None);
+		assert!(image.get_sprite_sheet_pixels(columns).is_none());
+		assert!(image.to_sprite_sheet(columns).is_none());
+		assert!(image.get_animation(columns, PlayMode::Loop).is_none());
+	}
+}
+
+#[test]
+fn animated_image_hdr_and_dds() {
+	let hdr = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 1\n\x80\x40\x00\x81";
+	let info = ImageInfo::from_memory(hdr).unwrap();
+	assert_eq!((info.format, info.width, info.height, info.frame_count), (ImageFileFormat::Hdr, 1, 1, 1));
+	let image = AnimatedImage::from_memory(hdr).unwrap();
+	assert_eq!(image.get_frames()[0].get_pixels(), &[255, 186, 0, 255]);
+
+	let mut dds = vec![0; 128];
+	dds[..8].copy_from_slice(b"DDS \x7c\x00\x00\x00");
+	dds[12] = 1;
+	dds[16] = 1;
+	dds[80] = 0x4;
+	dds[84..88].copy_from_slice(b"DXT1");
+	dds.extend(&[0xe0, 0x07, 0, 0, 0, 0, 0, 0]);
+	let info = ImageInfo::from_memory(&dds).unwrap();
+	assert_eq!((info.format, info.width, info.height, info.channels), (ImageFileFormat::Dds, 1, 1, 4));
+	let image = AnimatedImage::from_memory(&dds).unwrap();
+	assert_eq!(image.get_frames()[0].get_pixels(), &[0, 255, 0, 255]);
+	assert!(AnimatedImage::from_memory(&dds[..130]).is_err());
+
+	match ImageInfo::from_memory(b"\x00\x01\x02") {
+		Err(ImageDecodeError::Unsupported(ref err)) if err == "unknown image format" => {}
+		other => panic!("unexpected result: {:?}", other)
+	}
+}
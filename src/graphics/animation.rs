@@ -0,0 +1,579 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+
+use graphics::{IntRect, Sprite, Texture, Transformable, Transform,
+               RenderTarget, RenderStates, Drawable};
+use system::{Time, Vector2f, Vector2u};
+
+/// How an `Animation` behaves once its last frame has been shown.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum PlayMode {
+	/// Play the frames once and stop on the last one.
+	Once,
+	/// Start over from the first frame after the last one.
+	Loop,
+	/// Play the frames forward, then backward, then forward again, and so on.
+	PingPong
+}
+
+/// A single frame of an `Animation`: a sub-rectangle of the texture and the
+/// time it stays on screen.
+#[derive(Clone, PartialEq, Debug, Copy)]
+pub struct Frame {
+	/// Sub-rectangle of the texture displayed during this frame.
+	pub rect: IntRect,
+	/// How long this frame is displayed.
+	pub duration: Time
+}
+
+impl Frame {
+	/// Create a new frame from its texture rectangle and duration.
+	pub fn new(rect: IntRect, duration: Time) -> Frame {
+		Frame {
+			rect: rect,
+			duration: duration
+		}
+	}
+}
+
+/// Notification emitted by an `AnimationPlayer` when a frame carrying an event
+/// is entered.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AnimationEvent {
+	/// Index of the frame that was entered.
+	pub frame: usize,
+	/// Name given to the event with `Animation::add_event`.
+	pub name: String
+}
+
+/// An ordered list of sprite-sheet frames with per-frame durations.
+///
+/// An `Animation` only describes the frames; it holds no playback state and
+/// can therefore be shared by any number of `AnimatedSprite`s.
+///
+/// Events can be attached to frames with `add_event`, for example to play a
+/// footstep sound when a foot touches the ground. They are reported by
+/// `AnimationPlayer::poll_event` every time the frame is entered.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Animation {
+	frames: Vec<Frame>,
+	mode: PlayMode,
+	events: Vec<(usize, String)>
+}
+
+impl Animation {
+	/// Create a new animation with no frames.
+	pub fn new(mode: PlayMode) -> Animation {
+		Animation::from_frames(mode, Vec::new())
+	}
+
+	/// Create a new animation from existing frames.
+	pub fn from_frames(mode: PlayMode, frames: Vec<Frame>) -> Animation {
+		Animation {
+			frames: frames,
+			mode: mode,
+			events: Vec::new()
+		}
+	}
+
+	/// Create a new animation from a regular grid sprite sheet, giving every
+	/// frame the same duration.
+	///
+	/// See `slice_grid` for the order in which frames are produced.
+	pub fn from_grid(mode: PlayMode,
+	                 sheet_size: Vector2u,
+	                 frame_size: Vector2u,
+	                 frame_duration: Time) -> Animation {
+		let frames = Animation::slice_grid(sheet_size, frame_size)
+			.into_iter()
+			.map(|rect| Frame::new(rect, frame_duration))
+			.collect();
+		Animation::from_frames(mode, frames)
+	}
+
+	/// Slice a sprite sheet into frame rectangles of the given size.
+	///
+	/// Rectangles are returned row by row, from left to right and top to
+	/// bottom. Partial cells on the right and bottom edges of the sheet are
+	/// ignored, as are frame sizes with a zero component.
+	pub fn slice_grid(sheet_size: Vector2u, frame_size: Vector2u) -> Vec<IntRect> {
+		if frame_size.x == 0 || frame_size.y == 0 {
+			return Vec::new()
+		}
+		let columns = sheet_size.x / frame_size.x;
+		let rows = sheet_size.y / frame_size.y;
+		let mut rects = Vec::with_capacity((columns * rows) as usize);
+		for row in 0..rows {
+			for column in 0..columns {
+				rects.push(IntRect::new((column * frame_size.x) as i32,
+				                        (row * frame_size.y) as i32,
+				                        frame_size.x as i32,
+				                        frame_size.y as i32));
+			}
+		}
+		rects
+	}
+
+	/// Append a frame to the animation.
+	pub fn add_frame(&mut self, rect: IntRect, duration: Time) {
+		self.frames.push(Frame::new(rect, duration));
+	}
+
+	/// Get the frames of the animation.
+	pub fn frames(&self) -> &[Frame] {
+		&self.frames
+	}
+
+	/// Mutably access the frames of the animation.
+	pub fn frames_mut(&mut self) -> &mut Vec<Frame> {
+		&mut self.frames
+	}
+
+	/// Get the number of frames in the animation.
+	pub fn get_frame_count(&self) -> usize {
+		self.frames.len()
+	}
+
+	/// Get a frame by index, or None if the index is out of range.
+	pub fn get_frame(&self, index: usize) -> Option<&Frame> {
+		self.frames.get(index)
+	}
+
+	/// Get the play mode of the animation.
+	pub fn get_mode(&self) -> PlayMode {
+		self.mode
+	}
+
+	/// Set the play mode of the animation.
+	pub fn set_mode(&mut self, mode: PlayMode) {
+		self.mode = mode;
+	}
+
+	/// Get the summed duration of all frames, played once forward.
+	pub fn get_duration(&self) -> Time {
+		self.frames.iter().fold(Time::with_microseconds(0), |total, frame| total + frame.duration)
+	}
+
+	/// Attach a named event to a frame.
+	///
+	/// Several events may be attached to the same frame; they are reported in
+	/// the order they were added.
+	pub fn add_event(&mut self, frame: usize, name: &str) {
+		self.events.push((frame, name.to_owned()));
+	}
+
+	/// Remove every event attached to the animation.
+	pub fn clear_events(&mut self) {
+		self.events.clear();
+	}
+}
+
+/// Playback state of an `Animation`.
+///
+/// The player keeps track of the current frame and the time spent in it, and
+/// is advanced by calling `update` with the time elapsed since the last call.
+/// It does not draw anything, which makes it usable on its own for driving
+/// custom drawables; `AnimatedSprite` combines it with a `Sprite`.
+#[derive(Clone, Debug)]
+pub struct AnimationPlayer {
+	frame: usize,
+	elapsed: Time,
+	forward: bool,
+	playing: bool,
+	finished: bool,
+	entered: bool,
+	events: VecDeque<AnimationEvent>
+}
+
+impl AnimationPlayer {
+	/// Create a new player, playing from the first frame.
+	pub fn new() -> AnimationPlayer {
+		AnimationPlayer {
+			frame: 0,
+			elapsed: Time::with_microseconds(0),
+			forward: true,
+			playing: true,
+			finished: false,
+			entered: false,
+			events: VecDeque::new()
+		}
+	}
+
+	/// Resume playback.
+	///
+	/// Has no effect on a finished `PlayMode::Once` animation; use `restart`
+	/// to play it again.
+	pub fn play(&mut self) {
+		self.playing = true;
+	}
+
+	/// Pause playback, keeping the current frame.
+	pub fn pause(&mut self) {
+		self.playing = false;
+	}
+
+	/// Pause playback and rewind to the first frame.
+	pub fn stop(&mut self) {
+		self.rewind();
+		self.playing = false;
+	}
+
+	/// Rewind to the first frame and resume playback.
+	pub fn restart(&mut self) {
+		self.rewind();
+		self.playing = true;
+	}
+
+	fn rewind(&mut self) {
+		self.frame = 0;
+		self.elapsed = Time::with_microseconds(0);
+		self.forward = true;
+		self.finished = false;
+		self.entered = false;
+		self.events.clear();
+	}
+
+	/// Tell whether the player is currently advancing.
+	pub fn is_playing(&self) -> bool {
+		self.playing && !self.finished
+	}
+
+	/// Tell whether a `PlayMode::Once` animation has reached its end.
+	pub fn is_finished(&self) -> bool {
+		self.finished
+	}
+
+	/// Get the index of the frame currently displayed.
+	pub fn get_frame_index(&self) -> usize {
+		self.frame
+	}
+
+	/// Jump to a frame, resetting the time spent in it.
+	///
+	/// Events attached to the frame are reported on the next `update`.
+	pub fn set_frame_index(&mut self, frame: usize) {
+		self.frame = frame;
+		self.elapsed = Time::with_microseconds(0);
+		self.finished = false;
+		self.entered = false;
+	}
+
+	/// Advance the animation by `delta`.
+	///
+	/// Several frames may be skipped if `delta` is longer than the current
+	/// frame; the events of every frame entered are still reported. Returns
+	/// true if the current frame changed.
+	pub fn update(&mut self, animation: &Animation, delta: Time) -> bool {
+		let count = animation.get_frame_count();
+		if count == 0 {
+			return false
+		}
+		if self.frame >= count {
+			self.frame = count - 1;
+		}
+
+		let start = self.frame;
+		if !self.entered {
+			self.entered = true;
+			self.enter(animation);
+		}
+		if !self.is_playing() || animation.get_duration() <= Time::with_microseconds(0) {
+			return self.frame != start
+		}
+
+		self.elapsed = self.elapsed + delta;
+		while self.elapsed >= animation.frames[self.frame].duration {
+			self.elapsed = self.elapsed - animation.frames[self.frame].duration;
+			if !self.advance(animation.mode, count) {
+				self.finished = true;
+				self.elapsed = Time::with_microseconds(0);
+				break
+			}
+			self.enter(animation);
+		}
+		self.frame != start
+	}
+
+	// Move to the next frame, returning false if the animation is over.
+	fn advance(&mut self, mode: PlayMode, count: usize) -> bool {
+		let last = count - 1;
+		match mode {
+			PlayMode::Once => {
+				if self.frame == last {
+					return false
+				}
+				self.frame += 1;
+			}
+			PlayMode::Loop => {
+				self.frame = if self.frame == last { 0 } else { self.frame + 1 };
+			}
+			PlayMode::PingPong => {
+				if last == 0 {
+					return true
+				}
+				if self.forward && self.frame == last {
+					self.forward = false;
+				} else if !self.forward && self.frame == 0 {
+					self.forward = true;
+				}
+				if self.forward {
+					self.frame += 1;
+				} else {
+					self.frame -= 1;
+				}
+			}
+		}
+		true
+	}
+
+	fn enter(&mut self, animation: &Animation) {
+		for &(frame, ref name) in &animation.events {
+			if frame == self.frame {
+				self.events.push_back(AnimationEvent {
+					frame: frame,
+					name: name.clone()
+				});
+			}
+		}
+	}
+
+	/// Pop the next pending animation event, if any.
+	pub fn poll_event(&mut self) -> Option<AnimationEvent> {
+		self.events.pop_front()
+	}
+}
+
+impl Default for AnimationPlayer {
+	fn default() -> AnimationPlayer {
+		AnimationPlayer::new()
+	}
+}
+
+/// A `Sprite` whose texture rectangle is driven by an `Animation`.
+///
+/// `AnimatedSprite` implements `Deref` and `DerefMut` to `Sprite`, so the
+/// usual sprite properties (color, texture, bounds) stay available. Call
+/// `update` once per frame with the elapsed time to advance the animation.
+pub struct AnimatedSprite<'s> {
+	sprite: Sprite<'s>,
+	animation: Option<&'s Animation>,
+	player: AnimationPlayer
+}
+
+impl<'s> AnimatedSprite<'s> {
+	/// Create a new animated sprite using the given sprite sheet, with no
+	/// animation.
+	///
+	/// Returns Some(AnimatedSprite) or None on failure.
+	pub fn new(texture: &'s Texture) -> Option<AnimatedSprite<'s>> {
+		Sprite::new_with_texture(texture).map(|sprite| AnimatedSprite {
+			sprite: sprite,
+			animation: None,
+			player: AnimationPlayer::new()
+		})
+	}
+
+	/// Create a new animated sprite playing the given animation.
+	///
+	/// Returns Some(AnimatedSprite) or None on failure.
+	pub fn new_with_animation(texture: &'s Texture, animation: &'s Animation) -> Option<AnimatedSprite<'s>> {
+		AnimatedSprite::new(texture).map(|mut sprite| {
+			sprite.set_animation(animation);
+			sprite
+		})
+	}
+
+	/// Change the animation, restarting playback from its first frame.
+	pub fn set_animation(&mut self, animation: &'s Animation) {
+		self.animation = Some(animation);
+		self.player.restart();
+		self.sync_rect();
+	}
+
+	/// Get the animation being played, if any.
+	pub fn get_animation(&self) -> Option<&'s Animation> {
+		self.animation
+	}
+
+	/// Advance the animation by `delta` and update the texture rectangle.
+	pub fn update(&mut self, delta: Time) {
+		if let Some(animation) = self.animation {
+			if self.player.update(animation, delta) {
+				self.sync_rect();
+			}
+		}
+	}
+
+	fn sync_rect(&mut self) {
+		let frame = self.animation.and_then(|a| a.get_frame(self.player.get_frame_index()));
+		if let Some(frame) = frame {
+			self.sprite.set_texture_rect(frame.rect);
+		}
+	}
+
+	/// Resume playback.
+	pub fn play(&mut self) {
+		self.player.play();
+	}
+
+	/// Pause playback, keeping the current frame.
+	pub fn pause(&mut self) {
+		self.player.pause();
+	}
+
+	/// Pause playback and rewind to the first frame.
+	pub fn stop(&mut self) {
+		self.player.stop();
+		self.sync_rect();
+	}
+
+	/// Rewind to the first frame and resume playback.
+	pub fn restart(&mut self) {
+		self.player.restart();
+		self.sync_rect();
+	}
+
+	/// Tell whether the animation is currently advancing.
+	pub fn is_playing(&self) -> bool {
+		self.player.is_playing()
+	}
+
+	/// Tell whether a `PlayMode::Once` animation has reached its end.
+	pub fn is_finished(&self) -> bool {
+		self.player.is_finished()
+	}
+
+	/// Get the index of the frame currently displayed.
+	pub fn get_frame_index(&self) -> usize {
+		self.player.get_frame_index()
+	}
+
+	/// Jump to a frame of the current animation.
+	pub fn set_frame_index(&mut self, frame: usize) {
+		self.player.set_frame_index(frame);
+		self.sync_rect();
+	}
+
+	/// Pop the next pending animation event, if any.
+	pub fn poll_event(&mut self) -> Option<AnimationEvent> {
+		self.player.poll_event()
+	}
+}
+
+impl<'s> Deref for AnimatedSprite<'s> {
+	type Target = Sprite<'s>;
+	fn deref(&self) -> &Sprite<'s> {
+		&self.sprite
+	}
+}
+
+impl<'s> DerefMut for AnimatedSprite<'s> {
+	fn deref_mut(&mut self) -> &mut Sprite<'s> {
+		&mut self.sprite
+	}
+}
+
+impl<'s> Transformable for AnimatedSprite<'s> {
+	fn set_position(&mut self, position: Vector2f) { self.sprite.set_position(position) }
+	fn set_rotation(&mut self, angle: f32) { self.sprite.set_rotation(angle) }
+	fn set_scale(&mut self, scale: Vector2f) { self.sprite.set_scale(scale) }
+	fn set_origin(&mut self, origin: Vector2f) { self.sprite.set_origin(origin) }
+	fn get_position(&self) -> Vector2f { self.sprite.get_position() }
+	fn get_rotation(&self) -> f32 { self.sprite.get_rotation() }
+	fn get_scale(&self) -> Vector2f { self.sprite.get_scale() }
+	fn get_origin(&self) -> Vector2f { self.sprite.get_origin() }
+	fn move_(&mut self, offset: Vector2f) { self.sprite.move_(offset) }
+	fn rotate(&mut self, angle: f32) { self.sprite.rotate(angle) }
+	fn scale(&mut self, factors: Vector2f) { self.sprite.scale(factors) }
+	fn get_transform(&self) -> Transform { self.sprite.get_transform() }
+	fn get_inverse_transform(&self) -> Transform { self.sprite.get_inverse_transform() }
+}
+
+impl<'s> Drawable for AnimatedSprite<'s> {
+	fn draw(&self, target: &mut RenderTarget, states: &RenderStates) {
+		self.sprite.draw(target, states)
+	}
+}
+
+#[cfg(test)]
+fn frames(count: usize, millis: i32) -> Vec<Frame> {
+	(0..count).map(|i| Frame::new(IntRect::new(i as i32 * 16, 0, 16, 16),
+	                              Time::with_milliseconds(millis))).collect()
+}
+
+#[test]
+fn animation_slice_grid() {
+	let rects = Animation::slice_grid(Vector2u::new(50, 32), Vector2u::new(16, 16));
+	assert_eq!(rects.len(), 6);
+	assert_eq!(rects[0], IntRect::new(0, 0, 16, 16));
+	assert_eq!(rects[2], IntRect::new(32, 0, 16, 16));
+	assert_eq!(rects[3], IntRect::new(0, 16, 16, 16));
+	assert!(Animation::slice_grid(Vector2u::new(50, 32), Vector2u::new(0, 16)).is_empty());
+}
+
+#[test]
+fn animation_player_modes() {
+	let step = Time::with_milliseconds(100);
+
+	let once = Animation::from_frames(PlayMode::Once, frames(3, 100));
+	let mut player = AnimationPlayer::new();
+	let indices: Vec<usize> = (0..4).map(|_| { player.update(&once, step); player.get_frame_index() }).collect();
+	assert_eq!(indices, vec![1, 2, 2, 2]);
+	assert!(player.is_finished());
+
+	let looping = Animation::from_frames(PlayMode::Loop, frames(3, 100));
+	let mut player = AnimationPlayer::new();
+	player.update(&looping, Time::with_milliseconds(350));
+	assert_eq!(player.get_frame_index(), 0);
+	assert!(!player.is_finished());
+
+	let ping_pong = Animation::from_frames(PlayMode::PingPong, frames(3, 100));
+	let mut player = AnimationPlayer::new();
+	let indices: Vec<usize> = (0..6).map(|_| { player.update(&ping_pong, step); player.get_frame_index() }).collect();
+	assert_eq!(indices, vec![1, 2, 1, 0, 1, 2]);
+}
+
+#[test]
+fn animation_player_events() {
+	let mut animation = Animation::from_frames(PlayMode::Loop, frames(3, 100));
+	animation.add_event(0, "start");
+	animation.add_event(2, "step");
+	let mut player = AnimationPlayer::new();
+
+	player.update(&animation, Time::with_milliseconds(50));
+	assert_eq!(player.poll_event().map(|e| e.name), Some("start".to_owned()));
+	assert_eq!(player.poll_event(), None);
+
+	// Skipping over several frames still reports each of them.
+	player.update(&animation, Time::with_milliseconds(300));
+	assert_eq!(player.poll_event(), Some(AnimationEvent { frame: 2, name: "step".to_owned() }));
+	assert_eq!(player.poll_event(), Some(AnimationEvent { frame: 0, name: "start".to_owned() }));
+	assert_eq!(player.poll_event(), None);
+
+	player.pause();
+	assert!(!player.update(&animation, Time::with_milliseconds(500)));
+}
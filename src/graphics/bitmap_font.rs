@@ -0,0 +1,735 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use graphics::{Color, Drawable, FloatRect, Glyph, IntRect, RenderStates, RenderTarget, Texture,
+               TextStyle, Transform, Transformable};
+use graphics::text_layout::{GlyphSource, LayoutOptions, RichText, Span, SpanStyle, TextLayout};
+use system::Vector2f;
+use ffi::Ref;
+use formats::xml;
+
+/// Error returned when loading a bitmap font fails.
+#[derive(Debug)]
+pub enum BitmapFontError {
+	/// The descriptor could not be read.
+	Io(io::Error),
+	/// The descriptor is malformed.
+	Parse(String),
+	/// A page texture could not be loaded, with its path.
+	Texture(String)
+}
+
+impl fmt::Display for BitmapFontError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			BitmapFontError::Io(ref err) => write!(f, "I/O error: {}", err),
+			BitmapFontError::Parse(ref msg) => write!(f, "malformed bitmap font: {}", msg),
+			BitmapFontError::Texture(ref path) => write!(f, "failed to load bitmap font page {}", path)
+		}
+	}
+}
+
+impl Error for BitmapFontError {
+	fn description(&self) -> &str {
+		match *self {
+			BitmapFontError::Io(_) => "I/O error",
+			BitmapFontError::Parse(_) => "malformed bitmap font",
+			BitmapFontError::Texture(_) => "failed to load bitmap font page"
+		}
+	}
+
+	fn cause(&self) -> Option<&Error> {
+		match *self {
+			BitmapFontError::Io(ref err) => Some(err),
+			_ => None
+		}
+	}
+}
+
+impl From<io::Error> for BitmapFontError {
+	fn from(err: io::Error) -> BitmapFontError {
+		BitmapFontError::Io(err)
+	}
+}
+
+fn parse_error<T>(msg: &str) -> Result<T, BitmapFontError> {
+	Err(BitmapFontError::Parse(msg.to_owned()))
+}
+
+/// A character of a bitmap font, as described in its descriptor.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub struct BitmapChar {
+	/// Rectangle of the character in its page, in pixels.
+	pub rect: IntRect,
+	/// Offset from the pen position to the top left corner of the rectangle,
+	/// where the pen is on the top of the line.
+	pub offset: (i32, i32),
+	/// Horizontal distance to the next character.
+	pub advance: i32,
+	/// Index of the page holding the character.
+	pub page: u32
+}
+
+/// The contents of an AngelCode BMFont descriptor.
+///
+/// Descriptors come in three formats, text, XML and binary, which are all
+/// read by `parse`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BitmapFontData {
+	/// Name of the font the bitmap font was generated from.
+	pub face: String,
+	/// Size the font was rendered at, in pixels.
+	pub size: u32,
+	/// Distance between two lines, in pixels.
+	pub line_height: u32,
+	/// Distance from the top of a line to the baseline, in pixels.
+	pub base: u32,
+	/// Paths of the page images, indexed by page.
+	pub pages: Vec<String>,
+	/// Characters of the font.
+	pub chars: HashMap<char, BitmapChar>,
+	/// Kerning amounts between pairs of characters, in pixels.
+	pub kernings: HashMap<(char, char), i32>
+}
+
+// Size of the font, which is negative when it was matched to the height of
+// the characters rather than the cell.
+fn font_size(size: i64) -> Result<u32, BitmapFontError> {
+	if size < -(i32::MAX as i64) || size > i32::MAX as i64 {
+		return parse_error(&format!("font size {} is out of range", size))
+	}
+	Ok(size.abs() as u32)
+}
+
+fn to_char(id: i64) -> Option<char> {
+	if id < 0 || id > 0x10FFFF { None } else { ::std::char::from_u32(id as u32) }
+}
+
+impl BitmapFontData {
+	fn empty() -> BitmapFontData {
+		BitmapFontData {
+			face: String::new(),
+			size: 0,
+			line_height: 0,
+			base: 0,
+			pages: Vec::new(),
+			chars: HashMap::new(),
+			kernings: HashMap::new()
+		}
+	}
+
+	/// Parse a descriptor in any of the three formats.
+	///
+	/// Page paths are resolved relative to `dir`.
+	pub fn parse(data: &[u8], dir: &str) -> Result<BitmapFontData, BitmapFontError> {
+		let mut font = if data.starts_with(b"BMF") {
+			try!(BitmapFontData::parse_binary(data))
+		} else {
+			let text = match ::std::str::from_utf8(data) {
+				Ok(text) => text.trim_left_matches('\u{feff}'),
+				Err(_) => return parse_error("descriptor is neither binary nor UTF-8 text")
+			};
+			if text.trim_left().starts_with('<') {
+				try!(BitmapFontData::parse_xml(text))
+			} else {
+				try!(BitmapFontData::parse_text(text))
+			}
+		};
+		for page in &mut font.pages {
+			if !dir.is_empty() {
+				*page = Path::new(dir).join(&page[..]).to_string_lossy().into_owned();
+			}
+		}
+		Ok(font)
+	}
+
+	// Store a block of attributes, shared by the text and XML formats.
+	fn apply(&mut self, tag: &str, get: &Fn(&str) -> Option<String>) -> Result<(), BitmapFontError> {
+		let int = |name: &str| -> Result<i64, BitmapFontError> {
+			match get(name).map(|value| value.trim().parse::<i64>()) {
+				Some(Ok(value)) => Ok(value),
+				_ => Err(BitmapFontError::Parse(format!("{} is missing a valid {} attribute", tag, name)))
+			}
+		};
+		let ranged = |name: &str, min: i64, max: i64| -> Result<i64, BitmapFontError> {
+			match try!(int(name)) {
+				value if value < min || value > max =>
+					Err(BitmapFontError::Parse(format!("{} {} {} is out of range", tag, name, value))),
+				value => Ok(value)
+			}
+		};
+		let i32_attr = |name: &str| ranged(name, i32::MIN as i64, i32::MAX as i64).map(|value| value as i32);
+		let u32_attr = |name: &str| ranged(name, 0, i32::MAX as i64).map(|value| value as u32);
+		match tag {
+			"info" => {
+				self.face = get("face").unwrap_or_default();
+				self.size = try!(font_size(try!(int("size"))));
+			}
+			"common" => {
+				self.line_height = try!(u32_attr("lineHeight"));
+				self.base = try!(u32_attr("base"));
+			}
+			"page" => {
+				// The binary format stores page numbers in a byte
+				let id = match try!(int("id")) {
+					id @ 0...255 => id as usize,
+					id => return parse_error(&format!("page id {} is out of range", id))
+				};
+				let file = match get("file") {
+					Some(file) => file,
+					None => return parse_error("page is missing a file")
+				};
+				if self.pages.len() <= id {
+					self.pages.resize(id + 1, String::new());
+				}
+				self.pages[id] = file;
+			}
+			"char" => {
+				if let Some(c) = to_char(try!(int("id"))) {
+					let page = match get("page") {
+						Some(_) => try!(ranged("page", 0, 255)) as u32,
+						None => 0
+					};
+					self.chars.insert(c, BitmapChar {
+						rect: IntRect::new(try!(i32_attr("x")), try!(i32_attr("y")),
+						                   try!(i32_attr("width")), try!(i32_attr("height"))),
+						offset: (try!(i32_attr("xoffset")), try!(i32_attr("yoffset"))),
+						advance: try!(i32_attr("xadvance")),
+						page: page
+					});
+				}
+			}
+			"kerning" => {
+				if let (Some(first), Some(second)) = (to_char(try!(int("first"))), to_char(try!(int("second")))) {
+					self.kernings.insert((first, second), try!(i32_attr("amount")));
+				}
+			}
+			_ => {}
+		}
+		Ok(())
+	}
+
+	fn parse_text(text: &str) -> Result<BitmapFontData, BitmapFontError> {
+		let mut font = BitmapFontData::empty();
+		for line in text.lines() {
+			let line = line.trim();
+			let tag = line.split_whitespace().next().unwrap_or("");
+			let mut attributes = Vec::new();
+			let mut rest = line[tag.len()..].trim_left();
+			while !rest.is_empty() {
+				let equals = match rest.find('=') {
+					Some(equals) => equals,
+					None => return parse_error(&format!("expected key=value in {:?}", line))
+				};
+				let key = rest[..equals].trim();
+				rest = &rest[equals + 1..];
+				let value = if rest.starts_with('"') {
+					match rest[1..].find('"') {
+						Some(end) => {
+							let value = &rest[1..end + 1];
+							rest = &rest[end + 2..];
+							value
+						}
+						None => return parse_error(&format!("unterminated string in {:?}", line))
+					}
+				} else {
+					let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+					let value = &rest[..end];
+					rest = &rest[end..];
+					value
+				};
+				attributes.push((key, value));
+				rest = rest.trim_left();
+			}
+			try!(font.apply(tag, &|name| {
+				attributes.iter().find(|&&(key, _)| key == name).map(|&(_, value)| value.to_owned())
+			}));
+		}
+		Ok(font)
+	}
+
+	fn parse_xml(text: &str) -> Result<BitmapFontData, BitmapFontError> {
+		let root = match xml::parse(text) {
+			Ok(root) => root,
+			Err(msg) => return Err(BitmapFontError::Parse(msg))
+		};
+		if root.name != "font" {
+			return parse_error("root element is not <font>")
+		}
+		let mut font = BitmapFontData::empty();
+		let mut elements = Vec::new();
+		for child in &root.children {
+			match &child.name[..] {
+				"pages" | "chars" | "kernings" => elements.extend(child.children.iter()),
+				_ => elements.push(child)
+			}
+		}
+		for element in elements {
+			try!(font.apply(&element.name, &|name| element.attr(name).map(|value| value.to_owned())));
+		}
+		Ok(font)
+	}
+
+	fn parse_binary(data: &[u8]) -> Result<BitmapFontData, BitmapFontError> {
+		if data.len() < 4 || data[3] != 3 {
+			return parse_error("only version 3 of the binary format is supported")
+		}
+		let u16_at = |b: &[u8], i: usize| b[i] as u32 | (b[i + 1] as u32) << 8;
+		let u32_at = |b: &[u8], i: usize| u16_at(b, i) | u16_at(b, i + 2) << 16;
+		let i16_at = |b: &[u8], i: usize| u16_at(b, i) as u16 as i16 as i32;
+
+		let mut font = BitmapFontData::empty();
+		let mut position = 4;
+		while position < data.len() {
+			if position + 5 > data.len() {
+				return parse_error("truncated block header")
+			}
+			let kind = data[position];
+			let size = u32_at(data, position + 1) as usize;
+			let block = match data.get(position + 5..position + 5 + size) {
+				Some(block) => block,
+				None => return parse_error("truncated block")
+			};
+			position += 5 + size;
+			match kind {
+				1 if size >= 14 => {
+					font.size = try!(font_size(i16_at(block, 0) as i64));
+					let name = &block[14..];
+					let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+					font.face = String::from_utf8_lossy(&name[..end]).into_owned();
+				}
+				2 if size >= 4 => {
+					font.line_height = u16_at(block, 0);
+					font.base = u16_at(block, 2);
+				}
+				3 => {
+					font.pages = block.split(|&b| b == 0).filter(|name| !name.is_empty())
+						.map(|name| String::from_utf8_lossy(name).into_owned()).collect();
+				}
+				4 => for record in block.chunks(20).filter(|record| record.len() == 20) {
+					if let Some(c) = to_char(u32_at(record, 0) as i64) {
+						font.chars.insert(c, BitmapChar {
+							rect: IntRect::new(u16_at(record, 4) as i32, u16_at(record, 6) as i32,
+							                   u16_at(record, 8) as i32, u16_at(record, 10) as i32),
+							offset: (i16_at(record, 12), i16_at(record, 14)),
+							advance: i16_at(record, 16),
+							page: record[18] as u32
+						});
+					}
+				},
+				5 => for record in block.chunks(10).filter(|record| record.len() == 10) {
+					if let (Some(first), Some(second)) = (to_char(u32_at(record, 0) as i64),
+					                                      to_char(u32_at(record, 4) as i64)) {
+						font.kernings.insert((first, second), i16_at(record, 8));
+					}
+				},
+				1 | 2 => return parse_error("truncated info or common block"),
+				_ => {}
+			}
+		}
+		Ok(font)
+	}
+
+	fn scale(&self, character_size: u32) -> f32 {
+		if character_size == 0 || self.size == 0 { 1. } else { character_size as f32 / self.size as f32 }
+	}
+
+	/// Get the glyph of a character at a character size.
+	///
+	/// The glyph's bounds are relative to the baseline, as with `Font`.
+	/// Characters missing from the font give an empty glyph.
+	pub fn get_glyph(&self, codepoint: char, character_size: u32) -> Glyph {
+		let scale = self.scale(character_size);
+		match self.chars.get(&codepoint) {
+			Some(c) => Glyph {
+				advance: c.advance as f32 * scale,
+				bounds: FloatRect::new(c.offset.0 as f32 * scale, (c.offset.1 - self.base as i32) as f32 * scale,
+				                       c.rect.width as f32 * scale, c.rect.height as f32 * scale),
+				texture_rect: c.rect
+			},
+			None => Glyph {
+				advance: 0.,
+				bounds: FloatRect::new(0., 0., 0., 0.),
+				texture_rect: IntRect::new(0, 0, 0, 0)
+			}
+		}
+	}
+
+	/// Get the kerning offset between two characters at a character size.
+	pub fn get_kerning(&self, first: char, second: char, character_size: u32) -> f32 {
+		self.kernings.get(&(first, second)).map_or(0., |&amount| amount as f32 * self.scale(character_size))
+	}
+
+	/// Get the line spacing at a character size.
+	pub fn get_line_spacing(&self, character_size: u32) -> f32 {
+		self.line_height as f32 * self.scale(character_size)
+	}
+}
+
+/// A font made of pre-rendered glyphs, in the AngelCode BMFont format.
+///
+/// Bitmap fonts are drawn from page textures generated by a tool such as
+/// BMFont or Hiero, which makes them ideal for pixel art. They are drawn at
+/// their native size (`get_size()`) by default; other character sizes scale
+/// the glyphs, which stay crisp at integer multiples if smoothing is disabled
+/// on the pages.
+///
+/// A `BitmapFont` implements `GlyphSource`, so it can be used with the rich
+/// text layout engine, and is drawn by `BitmapText`. Underlines and
+/// strikethroughs are drawn with the pixel at (1, 1) of the page, so
+/// generate fonts with some solid padding to use them.
+pub struct BitmapFont {
+	data: BitmapFontData,
+	pages: Vec<Texture>
+}
+
+impl BitmapFont {
+	/// Load a bitmap font from a descriptor file, with its page textures.
+	///
+	/// Page images are resolved relative to the directory of the descriptor.
+	pub fn new_from_file(filename: &str) -> Result<BitmapFont, BitmapFontError> {
+		let mut bytes = Vec::new();
+		try!(try!(File::open(filename)).read_to_end(&mut bytes));
+		let dir = Path::new(filename).parent().map_or(String::new(), |p| p.to_string_lossy().into_owned());
+		let data = try!(BitmapFontData::parse(&bytes, &dir));
+		let mut pages = Vec::with_capacity(data.pages.len());
+		for page in &data.pages {
+			match Texture::new_from_file(page) {
+				Some(texture) => pages.push(texture),
+				None => return Err(BitmapFontError::Texture(page.clone()))
+			}
+		}
+		Ok(BitmapFont::from_data(data, pages))
+	}
+
+	/// Create a bitmap font from a parsed descriptor and its page textures,
+	/// in page order.
+	pub fn from_data(data: BitmapFontData, pages: Vec<Texture>) -> BitmapFont {
+		BitmapFont {
+			data: data,
+			pages: pages
+		}
+	}
+
+	/// Get the parsed descriptor of the font.
+	pub fn get_data(&self) -> &BitmapFontData {
+		&self.data
+	}
+
+	/// Get the size the font was rendered at.
+	pub fn get_size(&self) -> u32 {
+		self.data.size
+	}
+
+	/// Get a page texture.
+	pub fn get_page(&self, index: u32) -> Option<&Texture> {
+		self.pages.get(index as usize)
+	}
+
+	/// Get a page texture, for modification (for example to disable
+	/// smoothing).
+	pub fn get_page_mut(&mut self, index: u32) -> Option<&mut Texture> {
+		self.pages.get_mut(index as usize)
+	}
+
+	/// Tell whether the font has a glyph for a character.
+	pub fn has_glyph(&self, codepoint: char) -> bool {
+		self.data.chars.contains_key(&codepoint)
+	}
+
+	/// Take the descriptor and page textures out of the font.
+	pub fn into_parts(self) -> (BitmapFontData, Vec<Texture>) {
+		(self.data, self.pages)
+	}
+}
+
+impl GlyphSource for BitmapFont {
+	fn get_glyph(&self, codepoint: char, character_size: u32, _bold: bool) -> Glyph {
+		self.data.get_glyph(codepoint, character_size)
+	}
+
+	fn get_kerning(&self, first: char, second: char, character_size: u32) -> f32 {
+		self.data.get_kerning(first, second, character_size)
+	}
+
+	fn get_line_spacing(&self, character_size: u32) -> f32 {
+		self.data.get_line_spacing(character_size)
+	}
+
+	fn get_underline_position(&self, character_size: u32) -> f32 {
+		let descent = self.data.line_height.saturating_sub(self.data.base).max(2);
+		(descent / 2) as f32 * self.data.scale(character_size)
+	}
+
+	fn get_underline_thickness(&self, character_size: u32) -> f32 {
+		self.data.scale(character_size).max(1.)
+	}
+
+	fn get_texture<'a>(&'a self, codepoint: char, _character_size: u32, _bold: bool) -> Option<Ref<'a, Texture>> {
+		self.pages.get(self.get_texture_index(codepoint)).and_then(|texture| unsafe { Ref::new(texture.unwrap()) })
+	}
+
+	fn get_texture_index(&self, codepoint: char) -> usize {
+		self.data.chars.get(&codepoint).map_or(0, |c| c.page as usize)
+	}
+}
+
+/// Text drawn with a `BitmapFont`.
+///
+/// `BitmapText` has the same interface as `Text`, and lays its string out
+/// the same way.
+#[derive(Clone)]
+pub struct BitmapText<'s> {
+	text: RichText<'s>,
+	font: &'s BitmapFont,
+	string: String,
+	style: SpanStyle
+}
+
+impl<'s> BitmapText<'s> {
+	/// Create a new empty text drawn with `font` at its native size.
+	///
+	/// Returns Some(BitmapText) or None on failure.
+	pub fn new(font: &'s BitmapFont) -> Option<BitmapText<'s>> {
+		RichText::new(font, LayoutOptions::new()).map(|text| BitmapText {
+			text: text,
+			font: font,
+			string: String::new(),
+			style: SpanStyle::new(Color::white(), font.get_size())
+		})
+	}
+
+	/// Create a new text from a string, font, and size.
+	///
+	/// Returns Some(BitmapText) or None on failure.
+	pub fn new_init(string: &str, font: &'s BitmapFont, character_size: u32) -> Option<BitmapText<'s>> {
+		BitmapText::new(font).map(|mut text| {
+			text.style.character_size = character_size;
+			text.set_string(string);
+			text
+		})
+	}
+
+	fn update(&mut self) {
+		self.text.set_spans(vec![Span::new(&self.string, self.style)]);
+	}
+
+	/// Set the text's string.
+	pub fn set_string(&mut self, string: &str) {
+		self.string = string.to_owned();
+		self.update();
+	}
+
+	/// Get the text's string.
+	pub fn get_string(&self) -> &str {
+		&self.string
+	}
+
+	/// Set the text's font.
+	pub fn set_font(&mut self, font: &'s BitmapFont) {
+		self.font = font;
+		self.text.set_source(font);
+	}
+
+	/// Get the text's font.
+	pub fn get_font(&self) -> &'s BitmapFont {
+		self.font
+	}
+
+	/// Set the character size in pixels.
+	pub fn set_character_size(&mut self, size: u32) {
+		self.style.character_size = size;
+		self.update();
+	}
+
+	/// Get the character size, in pixels.
+	pub fn get_character_size(&self) -> u32 {
+		self.style.character_size
+	}
+
+	/// Set the text's style. Bold is not available for bitmap fonts.
+	pub fn set_style(&mut self, style: TextStyle) {
+		self.style.style = style;
+		self.update();
+	}
+
+	/// Get the text's style.
+	pub fn get_style(&self) -> TextStyle {
+		self.style.style
+	}
+
+	/// Set the global color of the text.
+	pub fn set_color(&mut self, color: Color) {
+		self.style.color = color;
+		self.update();
+	}
+
+	/// Get the global color of the text.
+	pub fn get_color(&self) -> Color {
+		self.style.color
+	}
+
+	/// Get the layout of the text, in local coordinates.
+	pub fn get_layout(&self) -> &TextLayout {
+		self.text.get_layout()
+	}
+
+	/// Return the position of the `index`-th character, in global
+	/// coordinates.
+	pub fn find_character_pos(&self, index: usize) -> Vector2f {
+		self.get_transform().transform_point(self.get_layout().find_character_pos(index))
+	}
+
+	/// Get the local bounding rectangle of the text.
+	pub fn get_local_bounds(&self) -> FloatRect {
+		self.text.get_local_bounds()
+	}
+
+	/// Get the global bounding rectangle of the text.
+	pub fn get_global_bounds(&self) -> FloatRect {
+		self.text.get_global_bounds()
+	}
+}
+
+impl<'s> Transformable for BitmapText<'s> {
+	fn set_position(&mut self, position: Vector2f) { self.text.set_position(position) }
+	fn set_rotation(&mut self, angle: f32) { self.text.set_rotation(angle) }
+	fn set_scale(&mut self, scale: Vector2f) { self.text.set_scale(scale) }
+	fn set_origin(&mut self, origin: Vector2f) { self.text.set_origin(origin) }
+	fn get_position(&self) -> Vector2f { self.text.get_position() }
+	fn get_rotation(&self) -> f32 { self.text.get_rotation() }
+	fn get_scale(&self) -> Vector2f { self.text.get_scale() }
+	fn get_origin(&self) -> Vector2f { self.text.get_origin() }
+	fn move_(&mut self, offset: Vector2f) { self.text.move_(offset) }
+	fn rotate(&mut self, angle: f32) { self.text.rotate(angle) }
+	fn scale(&mut self, factors: Vector2f) { self.text.scale(factors) }
+	fn get_transform(&self) -> Transform { self.text.get_transform() }
+	fn get_inverse_transform(&self) -> Transform { self.text.get_inverse_transform() }
+}
+
+impl<'s> Drawable for BitmapText<'s> {
+	fn draw(&self, target: &mut RenderTarget, states: &RenderStates) {
+		self.text.draw(target, states)
+	}
+}
+
+#[cfg(test)]
+fn check_font(font: &BitmapFontData) {
+	assert_eq!(font.face, "Pixel Sans");
+	assert_eq!((font.size, font.line_height, font.base), (16, 18, 14));
+	assert_eq!(font.pages, vec!["fonts/pixel_0.png".to_owned(), "fonts/pixel_1.png".to_owned()]);
+	assert_eq!(font.chars.len(), 2);
+	assert_eq!(font.chars[&'A'], BitmapChar {
+		rect: IntRect::new(10, 20, 8, 12), offset: (1, 2), advance: 9, page: 1
+	});
+	let glyph = font.get_glyph('A', 32);
+	assert_eq!(glyph.advance, 18.);
+	assert_eq!(glyph.bounds, FloatRect::new(2., -24., 16., 24.));
+	assert_eq!(glyph.texture_rect, IntRect::new(10, 20, 8, 12));
+	assert_eq!(font.get_kerning('A', 'V', 16), -1.);
+	assert_eq!(font.get_kerning('V', 'A', 16), 0.);
+	assert_eq!(font.get_glyph('Z', 16).advance, 0.);
+}
+
+#[test]
+fn bitmap_font_text_format() {
+	let text = "info face=\"Pixel Sans\" size=-16 bold=0 padding=0,0,0,0\n\
+	            common lineHeight=18 base=14 scaleW=128 scaleH=128 pages=2\n\
+	            page id=0 file=\"pixel_0.png\"\n\
+	            page id=1 file=\"pixel_1.png\"\n\
+	            chars count=2\n\
+	            char id=65   x=10  y=20  width=8  height=12 xoffset=1 yoffset=2 xadvance=9 page=1 chnl=15\n\
+	            char id=86   x=0   y=0   width=8  height=12 xoffset=0 yoffset=2 xadvance=9 page=0 chnl=15\n\
+	            kernings count=1\n\
+	            kerning first=65 second=86 amount=-1\n";
+	check_font(&BitmapFontData::parse(text.as_bytes(), "fonts").unwrap());
+	assert!(BitmapFontData::parse(b"char id=65 x=1", "").is_err());
+	for id in &["-1", "256", "4000000000"] {
+		let page = format!("page id={} file=\"pixel.png\"", id);
+		match BitmapFontData::parse(page.as_bytes(), "") {
+			Err(BitmapFontError::Parse(_)) => {}
+			other => panic!("page id {} was accepted: {:?}", id, other)
+		}
+	}
+	for line in &["info size=-9223372036854775808", "common lineHeight=-18 base=14",
+	              "common lineHeight=18 base=4294967310", "char id=65 x=4294967306 y=0 width=8 height=12 \
+	               xoffset=0 yoffset=0 xadvance=9", "char id=65 x=0 y=0 width=8 height=12 xoffset=0 \
+	               yoffset=0 xadvance=9 page=-1", "kerning first=65 second=86 amount=2147483648"] {
+		match BitmapFontData::parse(line.as_bytes(), "") {
+			Err(BitmapFontError::Parse(_)) => {}
+			other => panic!("{:?} was accepted: {:?}", line, other)
+		}
+	}
+}
+
+#[test]
+fn bitmap_font_xml_format() {
+	let text = r#"<?xml version="1.0"?>
+		<font>
+			<info face="Pixel Sans" size="16"/>
+			<common lineHeight="18" base="14" scaleW="128" scaleH="128" pages="2"/>
+			<pages><page id="0" file="pixel_0.png"/><page id="1" file="pixel_1.png"/></pages>
+			<chars count="2">
+				<char id="65" x="10" y="20" width="8" height="12" xoffset="1" yoffset="2" xadvance="9" page="1"/>
+				<char id="86" x="0" y="0" width="8" height="12" xoffset="0" yoffset="2" xadvance="9" page="0"/>
+			</chars>
+			<kernings count="1"><kerning first="65" second="86" amount="-1"/></kernings>
+		</font>"#;
+	check_font(&BitmapFontData::parse(text.as_bytes(), "fonts").unwrap());
+}
+
+#[test]
+fn bitmap_font_binary_format() {
+	fn block(data: &mut Vec<u8>, kind: u8, contents: &[u8]) {
+		let size = contents.len() as u32;
+		data.push(kind);
+		data.extend_from_slice(&[size as u8, (size >> 8) as u8, (size >> 16) as u8, (size >> 24) as u8]);
+		data.extend_from_slice(contents);
+	}
+	let mut data = b"BMF\x03".to_vec();
+	let mut info = vec![0xF0, 0xFF, 0, 0, 100, 0, 1, 0, 0, 0, 0, 1, 1, 0];
+	info.extend_from_slice(b"Pixel Sans\0");
+	block(&mut data, 1, &info);
+	block(&mut data, 2, &[18, 0, 14, 0, 128, 0, 128, 0, 2, 0, 0, 0, 0, 0, 0]);
+	block(&mut data, 3, b"pixel_0.png\0pixel_1.png\0");
+	block(&mut data, 4, &[65, 0, 0, 0, 10, 0, 20, 0, 8, 0, 12, 0, 1, 0, 2, 0, 9, 0, 1, 15,
+	                      86, 0, 0, 0, 0, 0, 0, 0, 8, 0, 12, 0, 0, 0, 2, 0, 9, 0, 0, 15]);
+	block(&mut data, 5, &[65, 0, 0, 0, 86, 0, 0, 0, 0xFF, 0xFF]);
+	check_font(&BitmapFontData::parse(&data, "fonts").unwrap());
+
+	data.truncate(data.len() - 3);
+	assert!(BitmapFontData::parse(&data, "").is_err());
+
+	let mut data = b"BMF\x03".to_vec();
+	block(&mut data, 1, &[0x00, 0x80, 0, 0, 100, 0, 1, 0, 0, 0, 0, 1, 1, 0, 0]);
+	assert_eq!(BitmapFontData::parse(&data, "").unwrap().size, 32768);
+	assert!(BitmapFontData::parse(b"BMF\x02", "").is_err());
+}
@@ -22,6 +22,11 @@
 * 3. This notice may not be removed or altered from any source distribution.
 */
 
+use std::error::Error;
+use std::fmt;
+
+use ffi::gl;
+
 /// Available factors for blend computations.
 ///
 /// The factors are mapped directly to their OpenGL equivalents, specified by
@@ -49,6 +54,15 @@ pub enum BlendFactor {
 	DstAlpha = 8,
 	/// (1, 1, 1, 1) - (dst.a, dst.a, dst.a, dst.a)
 	OneMinusDstAlpha = 9,
+	/// (c.r, c.g, c.b, c.a), where `c` is the blend color of the
+	/// `RenderStates`
+	ConstantColor = 10,
+	/// (1, 1, 1, 1) - (c.r, c.g, c.b, c.a)
+	OneMinusConstantColor = 11,
+	/// (c.a, c.a, c.a, c.a)
+	ConstantAlpha = 12,
+	/// (1, 1, 1, 1) - (c.a, c.a, c.a, c.a)
+	OneMinusConstantAlpha = 13,
 }
 
 /// Available equations for blend computations.
@@ -62,6 +76,42 @@ pub enum BlendEquation {
 	Add = 0,
 	/// Pixel = Src * SrcFactor - Dst * DstFactor
 	Subtract = 1,
+	/// Pixel = Dst * DstFactor - Src * SrcFactor
+	ReverseSubtract = 2,
+	/// Pixel = min(Dst, Src), ignoring the factors
+	Min = 3,
+	/// Pixel = max(Dst, Src), ignoring the factors
+	Max = 4,
+}
+
+/// Error returned by `BlendMode::check` for blend modes the OpenGL version
+/// of the active context cannot apply.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum BlendModeError {
+	/// OpenGL 2.0 is needed for this factor.
+	UnsupportedFactor(BlendFactor),
+	/// OpenGL 2.0 is needed for this equation.
+	UnsupportedEquation(BlendEquation)
+}
+
+impl fmt::Display for BlendModeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			BlendModeError::UnsupportedFactor(factor) =>
+				write!(f, "blend factor {:?} needs OpenGL 2.0", factor),
+			BlendModeError::UnsupportedEquation(equation) =>
+				write!(f, "blend equation {:?} needs OpenGL 2.0", equation)
+		}
+	}
+}
+
+impl Error for BlendModeError {
+	fn description(&self) -> &str {
+		match *self {
+			BlendModeError::UnsupportedFactor(_) => "unsupported blend factor",
+			BlendModeError::UnsupportedEquation(_) => "unsupported blend equation"
+		}
+	}
 }
 
 /// A combination of factors and equations describing a blend mode.
@@ -91,8 +141,16 @@ pub enum BlendEquation {
 /// and 1. Where necessary, the result is clamped to fit in that range.
 ///
 /// The most common blending modes are available through the `alpha()`,
-/// `add()`, `multiply()`, and `none()` methods.
+/// `add()`, `multiply()`, and `none()` methods; `premultiplied_alpha()`,
+/// `screen()`, `erase()`, `subtract()`, `lighten()` and `darken()` cover
+/// other frequent needs.
 ///
+/// The CSFML version this crate binds only implements the `Add` and
+/// `Subtract` equations and the factors up to `OneMinusDstAlpha`. Other
+/// blend modes are set up through OpenGL around each draw, which is a bit
+/// slower and needs OpenGL 2.0; see `is_native()` and `check()`. Drawing
+/// with a blend mode the context cannot apply draws nothing and prints the
+/// error to the standard error output.
 ///
 /// In SFML, a blend mode can be specified every time you draw a `Drawable`
 /// object to a render target. It is part of the `RenderStates` compound that is
@@ -159,6 +217,87 @@ impl BlendMode {
 	pub fn none() -> BlendMode {
 		BlendMode::new(BlendFactor::One, BlendFactor::Zero, BlendEquation::Add)
 	}
+	/// Get the blend mode for textures whose colors are already multiplied
+	/// by their alpha.
+	pub fn premultiplied_alpha() -> BlendMode {
+		BlendMode::new(BlendFactor::One, BlendFactor::OneMinusSrcAlpha, BlendEquation::Add)
+	}
+	/// Get the Screen blend mode, which lightens the destination like
+	/// projecting two images on the same screen.
+	pub fn screen() -> BlendMode {
+		BlendMode::new_all(BlendFactor::One, BlendFactor::OneMinusSrcColor, BlendEquation::Add,
+		                   BlendFactor::One, BlendFactor::OneMinusSrcAlpha, BlendEquation::Add)
+	}
+	/// Get the Erase blend mode, which makes the destination transparent
+	/// where the source is opaque. Useful to cut holes in masks.
+	pub fn erase() -> BlendMode {
+		BlendMode::new(BlendFactor::Zero, BlendFactor::OneMinusSrcAlpha, BlendEquation::Add)
+	}
+	/// Get the Subtract blend mode, which subtracts the source from the
+	/// destination.
+	pub fn subtract() -> BlendMode {
+		BlendMode::new_all(BlendFactor::SrcAlpha, BlendFactor::One, BlendEquation::ReverseSubtract,
+		                   BlendFactor::Zero, BlendFactor::One, BlendEquation::Add)
+	}
+	/// Get the Lighten blend mode, which keeps the maximum of the source
+	/// and the destination.
+	pub fn lighten() -> BlendMode {
+		BlendMode::new(BlendFactor::One, BlendFactor::One, BlendEquation::Max)
+	}
+	/// Get the Darken blend mode, which keeps the minimum of the source and
+	/// the destination.
+	pub fn darken() -> BlendMode {
+		BlendMode::new(BlendFactor::One, BlendFactor::One, BlendEquation::Min)
+	}
+
+	/// Tell whether CSFML applies this blend mode by itself. Other modes
+	/// are applied through OpenGL, which requires version 2.0.
+	pub fn is_native(&self) -> bool {
+		[self.color_src_factor, self.color_dst_factor, self.alpha_src_factor, self.alpha_dst_factor]
+			.iter().all(|f| *f <= BlendFactor::OneMinusDstAlpha) &&
+			self.color_equation <= BlendEquation::Subtract && self.alpha_equation <= BlendEquation::Subtract
+	}
+
+	/// Tell whether this blend mode uses the blend color of the
+	/// `RenderStates`.
+	pub fn uses_constant(&self) -> bool {
+		[self.color_src_factor, self.color_dst_factor, self.alpha_src_factor, self.alpha_dst_factor]
+			.iter().any(|f| *f >= BlendFactor::ConstantColor)
+	}
+
+	/// Check that this blend mode can be applied in the active OpenGL
+	/// context, such as the one of the window being drawn to.
+	///
+	/// Native blend modes always can; the others need OpenGL 2.0.
+	pub fn check(&self) -> Result<(), BlendModeError> {
+		if self.is_native() || gl::has_blend_functions() {
+			Ok(())
+		} else {
+			Err(self.native_error())
+		}
+	}
+
+	/// Tell whether this blend mode can be applied in the active OpenGL
+	/// context. See `check()`.
+	pub fn is_supported(&self) -> bool {
+		self.check().is_ok()
+	}
+
+	// The error for the first part of a non-native blend mode CSFML cannot
+	// apply.
+	fn native_error(&self) -> BlendModeError {
+		for &factor in &[self.color_src_factor, self.color_dst_factor,
+		                 self.alpha_src_factor, self.alpha_dst_factor] {
+			if factor > BlendFactor::OneMinusDstAlpha {
+				return BlendModeError::UnsupportedFactor(factor)
+			}
+		}
+		if self.color_equation > BlendEquation::Subtract {
+			BlendModeError::UnsupportedEquation(self.color_equation)
+		} else {
+			BlendModeError::UnsupportedEquation(self.alpha_equation)
+		}
+	}
 }
 
 impl Default for BlendMode {
@@ -167,3 +306,24 @@ impl Default for BlendMode {
 		BlendMode::alpha()
 	}
 }
+
+#[test]
+fn blend_mode_support() {
+	for mode in &[BlendMode::alpha(), BlendMode::add(), BlendMode::multiply(), BlendMode::none(),
+	              BlendMode::premultiplied_alpha(), BlendMode::screen(), BlendMode::erase()] {
+		assert!(mode.is_native());
+		assert!(!mode.uses_constant());
+	}
+	for mode in &[BlendMode::subtract(), BlendMode::lighten(), BlendMode::darken()] {
+		assert!(!mode.is_native());
+		assert!(!mode.uses_constant());
+	}
+	let constant = BlendMode::new(BlendFactor::ConstantAlpha, BlendFactor::OneMinusConstantAlpha,
+	                              BlendEquation::Add);
+	assert!(constant.uses_constant());
+	assert!(!constant.is_native());
+	assert_eq!(constant.native_error(), BlendModeError::UnsupportedFactor(BlendFactor::ConstantAlpha));
+	assert_eq!(BlendMode::subtract().native_error(),
+	           BlendModeError::UnsupportedEquation(BlendEquation::ReverseSubtract));
+	assert_eq!(BlendMode::lighten().native_error(), BlendModeError::UnsupportedEquation(BlendEquation::Max));
+}
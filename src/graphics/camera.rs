@@ -0,0 +1,378 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use graphics::{FloatRect, RenderTarget, View};
+use system::{Time, Vector2f, Vector2i};
+use window::Event;
+
+/// Camera controller wrapping a `View`.
+///
+/// A camera keeps a logical center, size, zoom and rotation, and writes them
+/// to its view every `update`. On top of that it provides:
+///
+/// * smooth following of a target with a dead zone (`follow`),
+/// * clamping to world bounds (`set_bounds`),
+/// * trauma-based screen shake (`add_trauma`),
+/// * zooming around a pixel (`zoom_at`),
+/// * aspect-ratio-preserving letterboxing on resize (`handle_event`).
+///
+/// ```ignore
+/// let mut camera = Camera::new(Vector2f::new(400., 300.), Vector2f::new(800., 600.)).unwrap();
+/// camera.set_dead_zone(Vector2f::new(64., 48.));
+/// camera.set_smoothing(8.);
+/// camera.set_bounds(Some(FloatRect::new(0., 0., 3200., 1200.)));
+/// // Each frame:
+/// for event in window.events() {
+///     camera.handle_event(&event);
+/// }
+/// let dt = clock.restart();
+/// camera.follow(player.get_position(), dt);
+/// camera.update(dt);
+/// window.set_view(camera.get_view());
+/// ```
+pub struct Camera {
+	view: View,
+	center: Vector2f,
+	size: Vector2f,
+	zoom: f32,
+	rotation: f32,
+	dead_zone: Vector2f,
+	smoothing: f32,
+	bounds: Option<FloatRect>,
+	trauma: f32,
+	trauma_decay: f32,
+	max_shake_offset: Vector2f,
+	max_shake_angle: f32,
+	shake_frequency: f32,
+	shake_time: f32,
+	shake_offset: Vector2f,
+	shake_angle: f32
+}
+
+impl Camera {
+	/// Create a camera showing an area of `size` world units around `center`.
+	///
+	/// Return None if the view could not be created.
+	pub fn new(center: Vector2f, size: Vector2f) -> Option<Camera> {
+		let view = match View::new_init(center, size) {
+			Some(view) => view,
+			None => return None
+		};
+		Some(Camera {
+			view: view,
+			center: center,
+			size: size,
+			zoom: 1.,
+			rotation: 0.,
+			dead_zone: Vector2f::new(0., 0.),
+			smoothing: 0.,
+			bounds: None,
+			trauma: 0.,
+			trauma_decay: 1.,
+			max_shake_offset: Vector2f::new(16., 16.),
+			max_shake_angle: 3.,
+			shake_frequency: 15.,
+			shake_time: 0.,
+			shake_offset: Vector2f::new(0., 0.),
+			shake_angle: 0.
+		})
+	}
+
+	/// Get the view, as of the last call to `update`.
+	pub fn get_view(&self) -> &View {
+		&self.view
+	}
+
+	/// Set the logical center of the camera, clamped to the bounds.
+	pub fn set_center(&mut self, center: Vector2f) {
+		self.center = clamp_center(center, self.get_visible_size(), self.bounds);
+	}
+
+	/// Get the logical center of the camera, without shake.
+	pub fn get_center(&self) -> Vector2f {
+		self.center
+	}
+
+	/// Set the size of the area shown at zoom 1.
+	pub fn set_size(&mut self, size: Vector2f) {
+		self.size = size;
+		self.center = clamp_center(self.center, self.get_visible_size(), self.bounds);
+	}
+
+	/// Get the size of the area shown at zoom 1.
+	pub fn get_size(&self) -> Vector2f {
+		self.size
+	}
+
+	/// Get the size of the area currently shown, taking the zoom into
+	/// account.
+	pub fn get_visible_size(&self) -> Vector2f {
+		self.size / self.zoom
+	}
+
+	/// Set the magnification of the camera.
+	///
+	/// At 2 objects look twice as large, at 0.5 twice as small. The default
+	/// is 1. Zero, negative and non-finite values are ignored.
+	pub fn set_zoom(&mut self, zoom: f32) {
+		if !is_valid_zoom(zoom) {
+			return
+		}
+		self.zoom = zoom;
+		self.center = clamp_center(self.center, self.get_visible_size(), self.bounds);
+	}
+
+	/// Get the magnification of the camera.
+	pub fn get_zoom(&self) -> f32 {
+		self.zoom
+	}
+
+	/// Set the rotation of the camera, in degrees.
+	pub fn set_rotation(&mut self, angle: f32) {
+		self.rotation = angle;
+	}
+
+	/// Get the rotation of the camera, in degrees, without shake.
+	pub fn get_rotation(&self) -> f32 {
+		self.rotation
+	}
+
+	/// Set the size of the dead zone, a rectangle around the center in which
+	/// the followed target can move without moving the camera.
+	///
+	/// The default is (0, 0): the camera always aims at the target.
+	pub fn set_dead_zone(&mut self, size: Vector2f) {
+		self.dead_zone = size;
+	}
+
+	/// Get the size of the dead zone.
+	pub fn get_dead_zone(&self) -> Vector2f {
+		self.dead_zone
+	}
+
+	/// Set how fast the camera catches up with the followed target.
+	///
+	/// The camera covers about `1 - e^(-smoothing)` of the remaining distance
+	/// every second, independently of the frame rate. 0, the default, snaps
+	/// instantly.
+	pub fn set_smoothing(&mut self, smoothing: f32) {
+		self.smoothing = smoothing;
+	}
+
+	/// Get how fast the camera catches up with the followed target.
+	pub fn get_smoothing(&self) -> f32 {
+		self.smoothing
+	}
+
+	/// Restrict the visible area to a rectangle of the world, or remove the
+	/// restriction with None.
+	///
+	/// If the visible area is larger than the bounds on an axis, the camera
+	/// is centered on the bounds on that axis.
+	pub fn set_bounds(&mut self, bounds: Option<FloatRect>) {
+		self.bounds = bounds;
+		self.center = clamp_center(self.center, self.get_visible_size(), self.bounds);
+	}
+
+	/// Get the world bounds of the camera.
+	pub fn get_bounds(&self) -> Option<FloatRect> {
+		self.bounds
+	}
+
+	/// Move the camera toward `target`, for a time step of `delta`.
+	///
+	/// The camera only moves when the target leaves the dead zone, and then
+	/// just enough to bring it back to the edge of the zone.
+	pub fn follow(&mut self, target: Vector2f, delta: Time) {
+		let desired = dead_zone_center(self.center, target, self.dead_zone);
+		let factor = if self.smoothing > 0. {
+			1. - (-self.smoothing * delta.as_seconds()).exp()
+		} else {
+			1.
+		};
+		let center = self.center + (desired - self.center) * factor;
+		self.center = clamp_center(center, self.get_visible_size(), self.bounds);
+	}
+
+	/// Add trauma, which makes the camera shake. Trauma is capped at 1.
+	///
+	/// The shake amplitude grows with the square of the trauma, so small hits
+	/// barely register while large ones are violent.
+	pub fn add_trauma(&mut self, amount: f32) {
+		self.trauma = (self.trauma + amount).max(0.).min(1.);
+	}
+
+	/// Get the current trauma, in `[0, 1]`.
+	pub fn get_trauma(&self) -> f32 {
+		self.trauma
+	}
+
+	/// Configure the shake.
+	///
+	/// * `max_offset` - offset of the center at full trauma, in world units
+	/// * `max_angle` - rotation at full trauma, in degrees
+	/// * `frequency` - speed of the shake noise, in oscillations per second
+	/// * `decay` - trauma lost per second
+	///
+	/// The defaults are (16, 16), 3, 15 and 1.
+	pub fn set_shake(&mut self, max_offset: Vector2f, max_angle: f32, frequency: f32, decay: f32) {
+		self.max_shake_offset = max_offset;
+		self.max_shake_angle = max_angle;
+		self.shake_frequency = frequency;
+		self.trauma_decay = decay;
+	}
+
+	/// Zoom by `factor` while keeping the world point under `pixel` fixed.
+	///
+	/// Typically used with the mouse position on `MouseWheelMoved` events. A
+	/// factor above 1 zooms in. A factor that would make the zoom zero,
+	/// negative or non-finite is ignored.
+	pub fn zoom_at(&mut self, pixel: Vector2i, factor: f32, target: &RenderTarget) {
+		let zoom = self.zoom * factor;
+		if !is_valid_zoom(zoom) {
+			return
+		}
+		let before = target.map_pixel_to_coords(pixel, &self.view);
+		self.zoom = zoom;
+		self.sync();
+		let after = target.map_pixel_to_coords(pixel, &self.view);
+		self.center = clamp_center(self.center + before - after, self.get_visible_size(), self.bounds);
+		self.sync();
+	}
+
+	/// Update the viewport when the window is resized.
+	///
+	/// The camera keeps the aspect ratio of its size and fills the rest of
+	/// the window with black bars. Other events are ignored.
+	pub fn handle_event(&mut self, event: &Event) {
+		if let Event::Resized { width, height } = *event {
+			let viewport = letterbox_viewport(self.size, width, height);
+			self.view.set_viewport(viewport);
+		}
+	}
+
+	/// Decay the trauma, advance the shake and write the camera state to the
+	/// view.
+	pub fn update(&mut self, delta: Time) {
+		let seconds = delta.as_seconds();
+		self.trauma = (self.trauma - self.trauma_decay * seconds).max(0.);
+		self.shake_time += seconds * self.shake_frequency;
+		let amount = self.trauma * self.trauma;
+		self.shake_offset = Vector2f::new(self.max_shake_offset.x * amount * noise(1., self.shake_time),
+		                                  self.max_shake_offset.y * amount * noise(2., self.shake_time));
+		self.shake_angle = self.max_shake_angle * amount * noise(3., self.shake_time);
+		self.sync();
+	}
+
+	fn sync(&mut self) {
+		self.view.set_center(self.center + self.shake_offset);
+		self.view.set_size(self.size / self.zoom);
+		self.view.set_rotation(self.rotation + self.shake_angle);
+	}
+}
+
+/// Compute the viewport that shows an area of `size` in a window of
+/// `width` x `height` pixels without distortion.
+///
+/// The viewport is centered, spanning the whole window on one axis and
+/// leaving equal bars on the other.
+pub fn letterbox_viewport(size: Vector2f, width: u32, height: u32) -> FloatRect {
+	if width == 0 || height == 0 || size.x <= 0. || size.y <= 0. {
+		return FloatRect::new(0., 0., 1., 1.)
+	}
+	let window_ratio = width as f32 / height as f32;
+	let view_ratio = size.x / size.y;
+	if window_ratio > view_ratio {
+		let w = view_ratio / window_ratio;
+		FloatRect::new((1. - w) / 2., 0., w, 1.)
+	} else {
+		let h = window_ratio / view_ratio;
+		FloatRect::new(0., (1. - h) / 2., 1., h)
+	}
+}
+
+fn dead_zone_center(center: Vector2f, target: Vector2f, dead_zone: Vector2f) -> Vector2f {
+	fn axis(center: f32, target: f32, half: f32) -> f32 {
+		if target > center + half {
+			target - half
+		} else if target < center - half {
+			target + half
+		} else {
+			center
+		}
+	}
+	Vector2f::new(axis(center.x, target.x, dead_zone.x / 2.),
+	              axis(center.y, target.y, dead_zone.y / 2.))
+}
+
+fn clamp_center(center: Vector2f, size: Vector2f, bounds: Option<FloatRect>) -> Vector2f {
+	fn axis(center: f32, size: f32, start: f32, length: f32) -> f32 {
+		if size >= length {
+			start + length / 2.
+		} else {
+			center.max(start + size / 2.).min(start + length - size / 2.)
+		}
+	}
+	match bounds {
+		Some(b) => Vector2f::new(axis(center.x, size.x, b.left, b.width),
+		                         axis(center.y, size.y, b.top, b.height)),
+		None => center
+	}
+}
+
+// Tell whether the visible size can be divided by a zoom.
+fn is_valid_zoom(zoom: f32) -> bool {
+	zoom > 0. && zoom.is_finite()
+}
+
+// Smooth noise in [-1, 1], built from incommensurate sines.
+fn noise(seed: f32, time: f32) -> f32 {
+	((time + seed * 17.).sin() + (time * 2.17 + seed * 31.).sin() * 0.5) / 1.5
+}
+
+#[test]
+fn camera_helpers() {
+	let center = Vector2f::new(100., 100.);
+	let zone = Vector2f::new(20., 10.);
+	assert_eq!(dead_zone_center(center, Vector2f::new(105., 103.), zone), center);
+	assert_eq!(dead_zone_center(center, Vector2f::new(130., 80.), zone), Vector2f::new(120., 85.));
+
+	let bounds = Some(FloatRect::new(0., 0., 1000., 100.));
+	let size = Vector2f::new(200., 150.);
+	assert_eq!(clamp_center(Vector2f::new(50., 20.), size, bounds), Vector2f::new(100., 50.));
+	assert_eq!(clamp_center(Vector2f::new(950., 20.), size, bounds), Vector2f::new(900., 50.));
+	assert_eq!(clamp_center(Vector2f::new(950., 20.), size, None), Vector2f::new(950., 20.));
+
+	let size = Vector2f::new(800., 600.);
+	assert_eq!(letterbox_viewport(size, 800, 600), FloatRect::new(0., 0., 1., 1.));
+	assert_eq!(letterbox_viewport(size, 1600, 600), FloatRect::new(0.25, 0., 0.5, 1.));
+	assert_eq!(letterbox_viewport(size, 800, 1200), FloatRect::new(0., 0.25, 1., 0.5));
+
+	assert!(is_valid_zoom(0.5));
+	assert!(!is_valid_zoom(0.));
+	assert!(!is_valid_zoom(-2.));
+	assert!(!is_valid_zoom(::std::f32::INFINITY));
+	assert!(!is_valid_zoom(::std::f32::NAN));
+	assert!(!is_valid_zoom(1e-30 * 1e-30));
+}
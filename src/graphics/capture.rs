@@ -0,0 +1,385 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Screenshots and frame capture from render targets.
+
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use formats::{gif, png};
+use graphics::{Image, ImageSaveError, RenderTarget};
+use system::{Time, Vector2u};
+
+// Frames the sequence writer may lag behind before captures block.
+const SEQUENCE_BACKLOG: usize = 16;
+
+/// A frame recorded by `FrameCapture`.
+#[derive(Clone, Debug)]
+pub struct CapturedFrame {
+	pixels: Vec<u8>,
+	width: u32,
+	height: u32,
+	delay: Time
+}
+
+impl CapturedFrame {
+	/// Get the RGBA pixels of the frame.
+	pub fn get_pixels(&self) -> &[u8] {
+		&self.pixels
+	}
+
+	/// Get the size of the frame in pixels.
+	pub fn get_size(&self) -> Vector2u {
+		Vector2u::new(self.width, self.height)
+	}
+
+	/// Get the time elapsed between the previous capture and this one.
+	pub fn get_delay(&self) -> Time {
+		self.delay
+	}
+
+	/// Copy the frame to a new image.
+	pub fn to_image(&self) -> Option<Image> {
+		Image::create_from_pixels(self.width, self.height, &self.pixels)
+	}
+}
+
+// Background thread writing numbered PNG files.
+struct SequenceWriter {
+	sender: SyncSender<CapturedFrame>,
+	thread: JoinHandle<Result<u32, ImageSaveError>>
+}
+
+impl SequenceWriter {
+	fn start(directory: PathBuf, prefix: String) -> SequenceWriter {
+		let (sender, receiver) = mpsc::sync_channel::<CapturedFrame>(SEQUENCE_BACKLOG);
+		let thread = thread::spawn(move || {
+			let mut written = 0;
+			let mut result = Ok(());
+			// Keep receiving after an error so that captures never block
+			for frame in receiver {
+				if result.is_err() {
+					continue
+				}
+				let path = directory.join(format!("{}{:05}.png", prefix, written));
+				let data = png::encode(&frame.pixels, frame.width, frame.height);
+				result = File::create(&path).and_then(|mut file| file.write_all(&data));
+				written += 1;
+			}
+			try!(result);
+			Ok(written)
+		});
+		SequenceWriter {
+			sender: sender,
+			thread: thread
+		}
+	}
+
+	fn finish(self) -> Result<u32, ImageSaveError> {
+		drop(self.sender);
+		match self.thread.join() {
+			Ok(result) => result,
+			Err(_) => Err(ImageSaveError::Io(io::Error::new(io::ErrorKind::Other, "image sequence writer panicked")))
+		}
+	}
+}
+
+/// Records frames from a render target.
+///
+/// Captured frames can be saved as a sequence of numbered PNG files, which
+/// are encoded and written on a background thread, and kept in memory for a
+/// limited duration to be exported as an animated GIF, e.g. to save the last
+/// seconds of gameplay when a bug happens.
+///
+/// Call `update` once per frame, after drawing and before `display()`.
+/// Capturing reads the pixels back from the graphics card, which is slow:
+/// use `set_interval` to capture at a lower rate than the application runs.
+///
+/// For single screenshots, use `RenderTarget::capture` and `Image::save`.
+pub struct FrameCapture {
+	interval: Time,
+	elapsed: Time,
+	sequence: Option<SequenceWriter>,
+	history_duration: Time,
+	history_length: Time,
+	history: VecDeque<CapturedFrame>
+}
+
+impl FrameCapture {
+	/// Create a frame capture that records nothing until a sequence is
+	/// started or a history duration is set.
+	///
+	/// Frames are captured every time `update` is called.
+	pub fn new() -> FrameCapture {
+		FrameCapture {
+			interval: Time::with_microseconds(0),
+			elapsed: Time::with_microseconds(0),
+			sequence: None,
+			history_duration: Time::with_microseconds(0),
+			history_length: Time::with_microseconds(0),
+			history: VecDeque::new()
+		}
+	}
+
+	/// Set the minimum time between two captures made by `update`.
+	///
+	/// A zero interval, the default, captures every frame.
+	pub fn set_interval(&mut self, interval: Time) {
+		self.interval = interval;
+	}
+
+	/// Get the minimum time between two captures made by `update`.
+	pub fn get_interval(&self) -> Time {
+		self.interval
+	}
+
+	/// Check whether the capture is recording anything.
+	pub fn is_recording(&self) -> bool {
+		self.sequence.is_some() || self.history_duration > Time::with_microseconds(0)
+	}
+
+	/// Advance the capture clock by `delta`, and capture `target` if the
+	/// interval has elapsed since the previous capture.
+	///
+	/// Returns true if a frame was captured.
+	pub fn update(&mut self, target: &RenderTarget, delta: Time) -> bool {
+		self.elapsed = self.elapsed + delta;
+		if self.elapsed < self.interval {
+			return false
+		}
+		self.capture(target)
+	}
+
+	/// Capture `target` immediately, regardless of the interval.
+	///
+	/// Returns false if nothing is being recorded or the capture failed.
+	pub fn capture(&mut self, target: &RenderTarget) -> bool {
+		if !self.is_recording() {
+			return false
+		}
+		let image = match target.capture() {
+			Some(image) => image,
+			None => return false
+		};
+		let size = image.get_size();
+		self.record(image.get_pixels().to_vec(), size.x, size.y);
+		true
+	}
+
+	fn record(&mut self, pixels: Vec<u8>, width: u32, height: u32) {
+		let frame = CapturedFrame {
+			pixels: pixels,
+			width: width,
+			height: height,
+			delay: self.elapsed
+		};
+		self.elapsed = Time::with_microseconds(0);
+
+		if self.history_duration <= Time::with_microseconds(0) {
+			if let Some(ref sequence) = self.sequence {
+				let _ = sequence.sender.send(frame);
+			}
+			return
+		}
+		if let Some(ref sequence) = self.sequence {
+			let _ = sequence.sender.send(frame.clone());
+		}
+		self.push_history(frame);
+	}
+
+	/// Start writing captured frames to `directory`, which is created if
+	/// needed, as PNG files named `prefix` followed by a five-digit frame
+	/// number.
+	///
+	/// A sequence already being written is stopped first; if it failed, its
+	/// error is returned and no new sequence is started.
+	pub fn start_sequence(&mut self, directory: &str, prefix: &str) -> Result<(), ImageSaveError> {
+		try!(self.stop_sequence());
+		try!(fs::create_dir_all(directory));
+		self.sequence = Some(SequenceWriter::start(PathBuf::from(directory), prefix.to_owned()));
+		Ok(())
+	}
+
+	/// Stop writing frames, and wait for the pending ones to be written.
+	///
+	/// Returns the number of files written, or the first error met.
+	pub fn stop_sequence(&mut self) -> Result<u32, ImageSaveError> {
+		match self.sequence.take() {
+			Some(sequence) => sequence.finish(),
+			None => Ok(0)
+		}
+	}
+
+	/// Check whether frames are being written to files.
+	pub fn is_writing_sequence(&self) -> bool {
+		self.sequence.is_some()
+	}
+
+	/// Keep the frames captured during the last `duration` in memory.
+	///
+	/// A zero duration, the default, disables the history and clears it.
+	pub fn set_history_duration(&mut self, duration: Time) {
+		self.history_duration = duration;
+		self.trim_history();
+	}
+
+	/// Get the duration of the frame history.
+	pub fn get_history_duration(&self) -> Time {
+		self.history_duration
+	}
+
+	/// Get the frames in the history, oldest first.
+	pub fn get_history(&self) -> &VecDeque<CapturedFrame> {
+		&self.history
+	}
+
+	/// Remove all frames from the history.
+	pub fn clear_history(&mut self) {
+		self.history.clear();
+		self.history_length = Time::with_microseconds(0);
+	}
+
+	fn push_history(&mut self, frame: CapturedFrame) {
+		// All frames of an animation share the same size
+		if self.history.front().map_or(false, |f| (f.width, f.height) != (frame.width, frame.height)) {
+			self.clear_history();
+		}
+		self.history_length = self.history_length + frame.delay;
+		self.history.push_back(frame);
+		self.trim_history();
+	}
+
+	fn trim_history(&mut self) {
+		if self.history_duration <= Time::with_microseconds(0) {
+			self.clear_history();
+			return
+		}
+		// The oldest frame's delay is the time before it, not shown anymore
+		while self.history.len() > 1 && self.history_length - self.history[0].delay > self.history_duration {
+			let frame = self.history.pop_front().unwrap();
+			self.history_length = self.history_length - frame.delay;
+		}
+	}
+
+	/// Encode the history as an animated GIF looping forever.
+	///
+	/// Each frame is shown until the time the next one was captured. Fails
+	/// with `InvalidSize` if the history is empty or frames are larger than
+	/// 65535 pixels.
+	pub fn save_history_to_memory(&self) -> Result<Vec<u8>, ImageSaveError> {
+		let (width, height) = match self.history.front() {
+			Some(frame) => (frame.width, frame.height),
+			None => return Err(ImageSaveError::InvalidSize(0, 0))
+		};
+		let count = self.history.len();
+		let frames: Vec<(&[u8], u16)> = (0..count).map(|i| {
+			let delay = self.history.get(i + 1).unwrap_or(&self.history[i]).delay;
+			// Most viewers ignore delays below 2 hundredths of a second
+			let centiseconds = (delay.as_milliseconds() + 5) / 10;
+			(&self.history[i].pixels[..], centiseconds.max(2).min(0xffff) as u16)
+		}).collect();
+		gif::encode(&frames, width, height, 0).ok_or(ImageSaveError::InvalidSize(width, height))
+	}
+
+	/// Encode the history as an animated GIF and write it to `writer`.
+	pub fn save_history_to_writer<W: Write>(&self, writer: &mut W) -> Result<(), ImageSaveError> {
+		let data = try!(self.save_history_to_memory());
+		try!(writer.write_all(&data));
+		Ok(())
+	}
+
+	/// Save the history to a file as an animated GIF.
+	pub fn save_history(&self, filename: &str) -> Result<(), ImageSaveError> {
+		let mut file = try!(File::create(filename));
+		self.save_history_to_writer(&mut file)
+	}
+}
+
+impl Drop for FrameCapture {
+	fn drop(&mut self) {
+		let _ = self.stop_sequence();
+	}
+}
+
+#[cfg(test)]
+fn solid(color: u8, count: usize) -> Vec<u8> {
+	(0..count).flat_map(|_| vec![color, color, color, 255]).collect()
+}
+
+#[test]
+fn frame_capture_history() {
+	let mut capture = FrameCapture::new();
+	capture.record(solid(0, 4), 2, 2);
+	assert!(capture.get_history().is_empty());
+
+	capture.set_history_duration(Time::with_milliseconds(300));
+	for i in 0..10 {
+		capture.elapsed = Time::with_milliseconds(100);
+		capture.record(solid(i, 4), 2, 2);
+	}
+	let kept: Vec<u8> = capture.get_history().iter().map(|f| f.get_pixels()[0]).collect();
+	assert_eq!(kept, vec![6, 7, 8, 9]);
+
+	// A new size restarts the history
+	capture.elapsed = Time::with_milliseconds(50);
+	capture.record(solid(1, 6), 3, 2);
+	assert_eq!(capture.get_history().len(), 1);
+
+	capture.elapsed = Time::with_milliseconds(70);
+	capture.record(solid(2, 6), 3, 2);
+	let decoded = gif::decode(&capture.save_history_to_memory().unwrap()).unwrap();
+	assert_eq!((decoded.header.width, decoded.header.height, decoded.header.loop_count), (3, 2, 0));
+	assert_eq!(decoded.frames.len(), 2);
+	assert_eq!(decoded.frames[0].pixels, solid(1, 6));
+	assert_eq!(decoded.frames[1].pixels, solid(2, 6));
+	assert_eq!((decoded.frames[0].delay, decoded.frames[1].delay), (70, 70));
+
+	capture.set_history_duration(Time::with_microseconds(0));
+	assert!(capture.get_history().is_empty());
+	assert!(capture.save_history_to_memory().is_err());
+}
+
+#[test]
+fn frame_capture_sequence() {
+	let directory = ::std::env::temp_dir().join(format!("rsfml-capture-{}", ::std::process::id()));
+	let mut capture = FrameCapture::new();
+	capture.start_sequence(directory.to_str().unwrap(), "shot").unwrap();
+	assert!(capture.is_recording());
+	for i in 0..3 {
+		capture.record(solid(i * 100, 4), 2, 2);
+	}
+	assert_eq!(capture.stop_sequence().unwrap(), 3);
+	assert!(!capture.is_writing_sequence());
+	for i in 0..3 {
+		let mut data = Vec::new();
+		let mut file = File::open(directory.join(format!("shot{:05}.png", i))).unwrap();
+		::std::io::Read::read_to_end(&mut file, &mut data).unwrap();
+		assert_eq!(png::decode(&data).unwrap().frames[0].pixels, solid(i as u8 * 100, 4));
+	}
+	fs::remove_dir_all(&directory).unwrap();
+}
@@ -23,11 +23,13 @@
 */
 
 use libc::{c_uint, size_t};
-use std::ffi::CString;
-use std::io::{Read, Seek};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::rc::Rc;
 
 use system::InputStream;
 use graphics::{Glyph, Texture};
+use formats::truetype::{self, CharacterMap};
 
 use ffi::{SfBool, Foreign, Ref, from_c_str};
 use ffi::graphics as ffi;
@@ -61,7 +63,7 @@ use ffi::graphics as ffi;
 /// consideration when using `Text`. If you need to display text of a certain
 /// size, make sure the corresponding bitmap font that supports that size is
 /// used.
-pub struct Font(Foreign<ffi::sfFont>);
+pub struct Font(Foreign<ffi::sfFont>, Option<CharacterMap>, Option<Rc<Vec<u8>>>);
 
 impl Font {
     /// Create a new font from a file.
@@ -71,35 +73,46 @@ impl Font {
 	///
     /// Returns Some(Font) or None on failure.
     pub fn new_from_file(filename: &str) -> Option<Font> {
-        let c_str = match CString::new(filename.as_bytes()) {
-			Ok(c_str) => c_str,
-			Err(_) => return None
-		};
-        unsafe {
-            Foreign::new(ffi::sfFont_createFromFile(c_str.as_ptr()))
-        }.map(Font)
+        let mut data = Vec::new();
+        match File::open(filename).and_then(|mut file| file.read_to_end(&mut data)) {
+			Ok(_) => Font::new_from_data(data),
+			Err(_) => None
+		}
     }
 
     /// Create a new font from a file contained in memory.
     ///
-	/// The buffer must be in one of the supported formats.
+	/// The buffer must be in one of the supported formats. It is copied,
+	/// because CSFML reads the font data as long as the font exists.
 	///
     /// Returns Some(Font) or None on failure.
     pub fn new_from_memory(memory: &[u8]) -> Option<Font> {
-        unsafe {
-            Foreign::new(ffi::sfFont_createFromMemory(memory.as_ptr(), memory.len() as size_t))
-        }.map(Font)
+        Font::new_from_data(memory.to_vec())
     }
 
+	// Load a font from data kept alive by the font and its copies.
+	fn new_from_data(data: Vec<u8>) -> Option<Font> {
+		let characters = truetype::read_character_map(&data).ok();
+		let data = Rc::new(data);
+		unsafe {
+			Foreign::new(ffi::sfFont_createFromMemory(data.as_ptr(), data.len() as size_t))
+		}.map(|font| Font(font, characters, Some(data)))
+	}
+
 	/// Create a new font from an input stream.
     ///
 	/// The stream must be in one of the supported formats.
 	///
 	/// Returns Some(Font) or None on failure.
 	pub fn new_from_stream<T: Read + Seek>(stream: &mut T) -> Option<Font> {
+		let mut data = Vec::new();
+		let characters = stream.seek(SeekFrom::Current(0)).and_then(|start| {
+			try!(stream.read_to_end(&mut data));
+			stream.seek(SeekFrom::Start(start))
+		}).ok().and_then(|_| truetype::read_character_map(&data).ok());
 		unsafe {
 			Foreign::new(ffi::sfFont_createFromStream(&mut InputStream::new(stream)))
-		}.map(Font)
+		}.map(|font| Font(font, characters, None))
 	}
 	
 	fn raw(&self) -> &ffi::sfFont { self.0.as_ref() }
@@ -113,7 +126,7 @@ impl Font {
     pub fn clone_opt(&self) -> Option<Font> {
         unsafe {
 			Foreign::new(ffi::sfFont_copy(self.raw()))
-		}.map(Font)
+		}.map(|font| Font(font, self.1.clone(), self.2.clone()))
     }
 
 	/// Get the font information.
@@ -166,6 +179,21 @@ impl Font {
 		}
     }
 
+	/// Retrieve the texture of a certain size through a shared reference.
+	///
+	/// CSFML keeps a single texture per font and refills it with the
+	/// glyphs of the requested size on each call, so a texture obtained
+	/// earlier now shows the last size requested. It stays valid as long
+	/// as the font, which makes this safe, but it must be used before the
+	/// next call, like text layouts do.
+	#[doc(hidden)]
+	pub fn get_shared_texture<'a>(&'a self, character_size: u32) -> Option<Ref<'a, Texture>> {
+		unsafe {
+			let raw = self.raw() as *const ffi::sfFont as *mut ffi::sfFont;
+			Ref::new(ffi::sfFont_getTexture(raw, character_size as c_uint))
+		}
+	}
+
     /// Retrieve a glyph of the font.
 	///
 	/// If the font is a bitmap font, not all character sizes might be
@@ -182,6 +210,32 @@ impl Font {
         }
     }
 
+	/// Tell whether the font has a glyph for a character.
+	///
+	/// The answer is exact when the font has a character map, see
+	/// `has_character_map`. Otherwise it is a heuristic: a character is
+	/// considered missing when its glyph has the metrics of the one drawn
+	/// for the unassigned codepoint U+10FFFF, which is wrong for characters
+	/// drawn like it, and both glyphs are rendered at a character size of
+	/// 30 if they were not already.
+	pub fn has_glyph(&self, codepoint: char) -> bool {
+		if let Some(ref characters) = self.1 {
+			return characters.contains(codepoint)
+		}
+		let glyph = self.get_glyph(codepoint, 30, false);
+		let missing = self.get_glyph('\u{10FFFF}', 30, false);
+		glyph.advance != missing.advance || glyph.bounds != missing.bounds
+	}
+
+	/// Tell whether the character map of the font was read when it was
+	/// loaded, which makes `has_glyph` exact.
+	///
+	/// Only TrueType and OpenType fonts have one; CSFML does not expose the
+	/// character maps of other formats, such as bitmap fonts.
+	pub fn has_character_map(&self) -> bool {
+		self.1.is_some()
+	}
+
 	/// Get the position of the underline for a character size.
 	///
 	/// Underline position is the vertical offset to apply between the baseline
@@ -0,0 +1,171 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use graphics::{Font, Glyph, FloatRect, IntRect, Texture};
+use graphics::text_layout::GlyphSource;
+use ffi::Ref;
+
+/// A list of fonts tried in order for each character.
+///
+/// Each character is drawn with the first font of the family that has a
+/// glyph for it, so that, for example, a Latin font can be completed by CJK
+/// and emoji fonts. Kerning only applies between characters drawn with the
+/// same font, and lines are spaced for the tallest font of the family.
+/// Characters that no font covers are drawn with the first font's
+/// replacement glyph; `get_missing_characters` reports them.
+///
+/// A `FontFamily` is a `GlyphSource`, to be used with `RichText` and
+/// `TextLayout`.
+pub struct FontFamily<'s> {
+	fonts: Vec<&'s Font>,
+	coverage: RefCell<HashMap<char, Option<usize>>>
+}
+
+impl<'s> FontFamily<'s> {
+	/// Create a family from fonts, in order of preference.
+	pub fn new(fonts: &[&'s Font]) -> FontFamily<'s> {
+		FontFamily {
+			fonts: fonts.to_vec(),
+			coverage: RefCell::new(HashMap::new())
+		}
+	}
+
+	/// Add a font at the end of the family, used for characters that the
+	/// other fonts do not have.
+	pub fn push_font(&mut self, font: &'s Font) {
+		self.fonts.push(font);
+		self.coverage.borrow_mut().retain(|_, index| index.is_some());
+	}
+
+	/// Get the fonts of the family, in order of preference.
+	pub fn get_fonts(&self) -> &[&'s Font] {
+		&self.fonts
+	}
+
+	/// Get the index of the first font that has a glyph for a character.
+	///
+	/// Lookups are cached, see `Font::has_glyph`.
+	pub fn get_font_index(&self, codepoint: char) -> Option<usize> {
+		if let Some(&index) = self.coverage.borrow().get(&codepoint) {
+			return index
+		}
+		let index = self.fonts.iter().position(|font| font.has_glyph(codepoint));
+		self.coverage.borrow_mut().insert(codepoint, index);
+		index
+	}
+
+	/// Get the first font that has a glyph for a character.
+	pub fn get_font(&self, codepoint: char) -> Option<&'s Font> {
+		self.get_font_index(codepoint).map(|index| self.fonts[index])
+	}
+
+	/// Tell whether a font of the family has a glyph for a character.
+	pub fn has_glyph(&self, codepoint: char) -> bool {
+		self.get_font_index(codepoint).is_some()
+	}
+
+	/// Get the characters of a string that no font of the family has, in
+	/// order of first appearance and without duplicates.
+	///
+	/// Control characters such as line feeds and tabs are never reported.
+	pub fn get_missing_characters(&self, string: &str) -> Vec<char> {
+		let mut missing = Vec::new();
+		for c in string.chars() {
+			if !c.is_control() && !missing.contains(&c) && !self.has_glyph(c) {
+				missing.push(c);
+			}
+		}
+		missing
+	}
+
+	// The font a character is drawn with.
+	fn font_for(&self, codepoint: char) -> Option<&'s Font> {
+		self.get_font(codepoint).or(self.fonts.first().map(|&font| font))
+	}
+}
+
+impl<'s> GlyphSource for FontFamily<'s> {
+	fn get_glyph(&self, codepoint: char, character_size: u32, bold: bool) -> Glyph {
+		match self.font_for(codepoint) {
+			Some(font) => font.get_glyph(codepoint, character_size, bold),
+			None => Glyph {
+				advance: 0.,
+				bounds: FloatRect::new(0., 0., 0., 0.),
+				texture_rect: IntRect::new(0, 0, 0, 0)
+			}
+		}
+	}
+
+	fn get_kerning(&self, first: char, second: char, character_size: u32) -> f32 {
+		match (self.get_font_index(first), self.get_font_index(second)) {
+			(Some(a), Some(b)) if a == b => self.fonts[a].get_kerning(first, second, character_size),
+			_ => 0.
+		}
+	}
+
+	fn get_line_spacing(&self, character_size: u32) -> f32 {
+		self.fonts.iter().fold(0., |spacing: f32, font| spacing.max(font.get_line_spacing(character_size)))
+	}
+
+	fn get_underline_position(&self, character_size: u32) -> f32 {
+		self.fonts.first().map_or(0., |font| font.get_underline_position(character_size))
+	}
+
+	fn get_underline_thickness(&self, character_size: u32) -> f32 {
+		self.fonts.first().map_or(0., |font| font.get_underline_thickness(character_size))
+	}
+
+	fn get_texture<'a>(&'a self, codepoint: char, character_size: u32, _bold: bool) -> Option<Ref<'a, Texture>> {
+		self.font_for(codepoint).and_then(|font| font.get_shared_texture(character_size))
+	}
+
+	fn get_texture_index(&self, codepoint: char) -> usize {
+		self.font_for(codepoint).and_then(|font| {
+			self.fonts.iter().position(|&other| other as *const Font == font as *const Font)
+		}).unwrap_or(0)
+	}
+}
+
+#[test]
+fn font_family_missing_characters() {
+	let family = FontFamily::new(&[]);
+	assert!(!family.has_glyph('a'));
+	assert_eq!(family.get_missing_characters("abba\n\tc"), vec!['a', 'b', 'c']);
+	assert_eq!(family.get_line_spacing(30), 0.);
+}
+
+#[test]
+fn font_family_bundled_font() {
+	let font = Font::new_from_file("resources/sansation.ttf").unwrap();
+	assert!(font.has_glyph('a'));
+	assert!(!font.has_glyph('\u{4e2d}'));
+
+	let family = FontFamily::new(&[&font]);
+	assert_eq!(family.get_font_index('a'), Some(0));
+	assert_eq!(family.get_font_index('\u{4e2d}'), None);
+	assert_eq!(family.get_missing_characters("a\u{4e2d}b\u{1f600}\u{4e2d}"), vec!['\u{4e2d}', '\u{1f600}']);
+}
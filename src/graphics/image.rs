@@ -24,10 +24,14 @@
 
 use libc::{c_uint, size_t};
 use std::ffi::CString;
-use std::io::{Read, Seek};
+use std::fs::File;
+use std::io::{Read, Seek, Write};
 
 use system::{Vector2u, InputStream};
 use graphics::{Color, IntRect};
+use graphics::image_ops::{self, Filter};
+use graphics::{ImageFormat, ImageSaveError};
+use graphics::image_format;
 
 use ffi::{SfBool, Foreign};
 use ffi::graphics as ffi;
@@ -153,6 +157,37 @@ impl Image {
         unsafe { ffi::sfImage_saveToFile(self.raw(), c_str.as_ptr()) }.to_bool()
     }
 
+    /// Encode the image in memory.
+    ///
+    /// Fails with `InvalidSize` if the image is empty or too large for the
+    /// format.
+    pub fn save_to_memory(&self, format: ImageFormat) -> Result<Vec<u8>, ImageSaveError> {
+		let size = self.get_size();
+		image_format::encode(self.get_pixels(), size.x, size.y, format)
+    }
+
+    /// Encode the image and write it to `writer`.
+    pub fn save_to_writer<W: Write>(&self, writer: &mut W, format: ImageFormat) -> Result<(), ImageSaveError> {
+		let data = try!(self.save_to_memory(format));
+		try!(writer.write_all(&data));
+		Ok(())
+    }
+
+    /// Save the image to a file, in the format given by its extension (see
+    /// `ImageFormat::from_path`).
+    ///
+    /// Unlike `save_to_file`, this reports why saving failed.
+    pub fn save(&self, filename: &str) -> Result<(), ImageSaveError> {
+		let format = match ImageFormat::from_path(filename) {
+			Some(format) => format,
+			None => return Err(ImageSaveError::UnknownFormat(filename.to_owned()))
+		};
+		let data = try!(self.save_to_memory(format));
+		let mut file = try!(File::create(filename));
+		try!(file.write_all(&data));
+		Ok(())
+    }
+
     /// Return the size (width and height) of the image in pixels.
     pub fn get_size(&self) -> Vector2u {
         unsafe { ffi::sfImage_getSize(self.raw()) }
@@ -215,6 +250,22 @@ impl Image {
 		}
 	}
 
+	/// Get a mutable reference to the array of pixels.
+	///
+	/// The layout is the same as for `get_pixels`.
+	pub fn get_pixels_mut(&mut self) -> &mut [u8] {
+		unsafe {
+			let pixels = ffi::sfImage_getPixelsPtr(self.raw()) as *mut u8;
+			if pixels.is_null() {
+				&mut []
+			} else {
+				let size = self.get_size();
+				let len = 4 * size.x as usize * size.y as usize;
+				::std::slice::from_raw_parts_mut(pixels, len)
+			}
+		}
+	}
+
 	/// Get a read-only reference to the array of pixels, as a color array.
 	///
 	/// The returned slice has the size `width * height`.
@@ -263,6 +314,76 @@ impl Image {
     }
 }
 
+/// Image processing, see the `image_ops` module.
+impl Image {
+	/// Create a resized copy of the image.
+	///
+	/// Returns None if a size is zero or on failure.
+	pub fn resize(&self, width: u32, height: u32, filter: Filter) -> Option<Image> {
+		let size = self.get_size();
+		let pixels = image_ops::resize(self.get_pixels(), size.x, size.y, width, height, filter);
+		if pixels.is_empty() { None } else { Image::create_from_pixels(width, height, &pixels) }
+	}
+
+	/// Create a new image from the part of this one inside `rect`, clipped
+	/// to the image.
+	///
+	/// Returns None if the rectangle is outside the image or on failure.
+	pub fn crop(&self, rect: IntRect) -> Option<Image> {
+		let size = self.get_size();
+		let (pixels, width, height) = image_ops::crop(self.get_pixels(), size.x, size.y, rect);
+		if pixels.is_empty() { None } else { Image::create_from_pixels(width, height, &pixels) }
+	}
+
+	/// Create a copy of the image rotated clockwise by `turns` quarter
+	/// turns; negative turns rotate counterclockwise.
+	///
+	/// Returns Some(Image) or None on failure.
+	pub fn rotate90(&self, turns: i32) -> Option<Image> {
+		let size = self.get_size();
+		let (pixels, width, height) = image_ops::rotate90(self.get_pixels(), size.x, size.y, turns);
+		Image::create_from_pixels(width, height, &pixels)
+	}
+
+	/// Blur the image, see `image_ops::blur`.
+	pub fn blur(&mut self, radius: f32) {
+		let size = self.get_size();
+		image_ops::blur(self.get_pixels_mut(), size.x, size.y, radius)
+	}
+
+	/// Brighten or darken the image by `amount`, between -1 and 1.
+	pub fn adjust_brightness(&mut self, amount: f32) {
+		image_ops::adjust_brightness(self.get_pixels_mut(), amount)
+	}
+
+	/// Scale the contrast of the image by `factor`; 1 leaves it unchanged.
+	pub fn adjust_contrast(&mut self, factor: f32) {
+		image_ops::adjust_contrast(self.get_pixels_mut(), factor)
+	}
+
+	/// Scale the saturation of the image by `factor`; 0 makes it
+	/// grayscale and 1 leaves it unchanged.
+	pub fn adjust_saturation(&mut self, factor: f32) {
+		image_ops::adjust_saturation(self.get_pixels_mut(), factor)
+	}
+
+	/// Multiply all the pixels, including their alpha, by a color.
+	pub fn tint(&mut self, color: Color) {
+		image_ops::tint(self.get_pixels_mut(), color)
+	}
+
+	/// Multiply the color of the pixels by their alpha.
+	pub fn premultiply_alpha(&mut self) {
+		image_ops::premultiply_alpha(self.get_pixels_mut())
+	}
+
+	/// Divide the color of the pixels by their alpha, undoing
+	/// `premultiply_alpha`.
+	pub fn unpremultiply_alpha(&mut self) {
+		image_ops::unpremultiply_alpha(self.get_pixels_mut())
+	}
+}
+
 impl Clone for Image {
     fn clone(&self) -> Image {
 		self.clone_opt().expect("Failed to clone Image")
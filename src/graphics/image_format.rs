@@ -0,0 +1,131 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use formats::{bmp, jpeg, png, tga};
+
+/// File formats images can be encoded to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ImageFormat {
+	/// Lossless, compressed PNG.
+	Png,
+	/// Uncompressed 32-bit BMP, keeping the alpha channel.
+	Bmp,
+	/// Run-length compressed 32-bit TGA.
+	Tga,
+	/// Lossy JPEG with a quality between 1 and 100; the alpha channel is
+	/// dropped.
+	Jpeg(u8)
+}
+
+impl ImageFormat {
+	/// Guess the format of a file from its extension, ignoring case.
+	///
+	/// JPEG files get a quality of 90. Returns None for unknown extensions.
+	pub fn from_path(filename: &str) -> Option<ImageFormat> {
+		let extension = Path::new(filename).extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+		match extension.as_ref().map(|e| &e[..]) {
+			Some("png") => Some(ImageFormat::Png),
+			Some("bmp") => Some(ImageFormat::Bmp),
+			Some("tga") => Some(ImageFormat::Tga),
+			Some("jpg") | Some("jpeg") => Some(ImageFormat::Jpeg(90)),
+			_ => None
+		}
+	}
+}
+
+/// Errors returned when saving images.
+#[derive(Debug)]
+pub enum ImageSaveError {
+	/// The encoded image could not be written.
+	Io(io::Error),
+	/// The format could not be deduced from the file name.
+	UnknownFormat(String),
+	/// The image is empty, or too large for the format.
+	InvalidSize(u32, u32)
+}
+
+impl fmt::Display for ImageSaveError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			ImageSaveError::Io(ref err) => write!(f, "I/O error: {}", err),
+			ImageSaveError::UnknownFormat(ref name) => write!(f, "unknown image format for {}", name),
+			ImageSaveError::InvalidSize(w, h) => write!(f, "a {}x{} image can not be saved in this format", w, h)
+		}
+	}
+}
+
+impl Error for ImageSaveError {
+	fn description(&self) -> &str {
+		match *self {
+			ImageSaveError::Io(_) => "I/O error",
+			ImageSaveError::UnknownFormat(_) => "unknown image format",
+			ImageSaveError::InvalidSize(..) => "invalid image size"
+		}
+	}
+
+	fn cause(&self) -> Option<&Error> {
+		match *self {
+			ImageSaveError::Io(ref err) => Some(err),
+			_ => None
+		}
+	}
+}
+
+impl From<io::Error> for ImageSaveError {
+	fn from(err: io::Error) -> ImageSaveError {
+		ImageSaveError::Io(err)
+	}
+}
+
+/// Encode RGBA pixels in a format.
+pub fn encode(pixels: &[u8], width: u32, height: u32, format: ImageFormat) -> Result<Vec<u8>, ImageSaveError> {
+	if width == 0 || height == 0 {
+		return Err(ImageSaveError::InvalidSize(width, height))
+	}
+	let encoded = match format {
+		ImageFormat::Png => Some(png::encode(pixels, width, height)),
+		ImageFormat::Bmp => Some(bmp::encode(pixels, width, height)),
+		ImageFormat::Tga => tga::encode(pixels, width, height),
+		ImageFormat::Jpeg(quality) => jpeg::encode(pixels, width, height, quality)
+	};
+	encoded.ok_or(ImageSaveError::InvalidSize(width, height))
+}
+
+#[test]
+fn image_format_from_path() {
+	assert_eq!(ImageFormat::from_path("shots/frame.PNG"), Some(ImageFormat::Png));
+	assert_eq!(ImageFormat::from_path("a.b/photo.jpeg"), Some(ImageFormat::Jpeg(90)));
+	assert_eq!(ImageFormat::from_path("sprite.tga"), Some(ImageFormat::Tga));
+	assert_eq!(ImageFormat::from_path("noextension"), None);
+	assert_eq!(ImageFormat::from_path("image.gif"), None);
+	match encode(&[], 0, 4, ImageFormat::Png) {
+		Err(ImageSaveError::InvalidSize(0, 4)) => (),
+		_ => panic!("empty image encoded")
+	}
+}
@@ -0,0 +1,381 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Image processing on RGBA pixel buffers.
+//!
+//! The functions of this module work on arrays of 32-bit RGBA pixels, such
+//! as the one returned by `Image::get_pixels`, `width * height * 4` bytes
+//! long. `Image` wraps them as methods (`resize`, `crop`, `rotate90`, `blur`,
+//! `adjust_brightness`, ...), which are usually more convenient:
+//!
+//! ```ignore
+//! let thumbnail = image.resize(64, 64, Filter::Bicubic).unwrap();
+//! let mut icon = thumbnail.rotate90(1).unwrap();
+//! icon.adjust_saturation(0.);
+//! ```
+//!
+//! Filtering (resizing and blurring) is done on premultiplied colors, so
+//! that the colors of transparent pixels do not bleed into their neighbours.
+
+use graphics::{Color, IntRect};
+
+/// Resampling filter used to resize images.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Filter {
+	/// Take the closest pixel; keeps hard edges, for pixel art.
+	Nearest,
+	/// Interpolate linearly between neighbouring pixels.
+	Bilinear,
+	/// Interpolate with a Catmull-Rom cubic; sharper than bilinear.
+	Bicubic
+}
+
+fn to_u8(value: f32) -> u8 {
+	(value.max(0.).min(255.) + 0.5) as u8
+}
+
+fn premultiplied(pixels: &[u8]) -> Vec<f32> {
+	pixels.chunks(4).flat_map(|p| {
+		let alpha = p[3] as f32 / 255.;
+		vec![p[0] as f32 * alpha, p[1] as f32 * alpha, p[2] as f32 * alpha, p[3] as f32]
+	}).collect()
+}
+
+fn unpremultiplied(values: &[f32]) -> Vec<u8> {
+	let mut pixels = Vec::with_capacity(values.len());
+	for p in values.chunks(4) {
+		let alpha = to_u8(p[3]);
+		let factor = if alpha == 0 { 0. } else { 255. / p[3] };
+		pixels.push(to_u8(p[0] * factor));
+		pixels.push(to_u8(p[1] * factor));
+		pixels.push(to_u8(p[2] * factor));
+		pixels.push(alpha);
+	}
+	pixels
+}
+
+fn kernel(filter: Filter, x: f32) -> f32 {
+	let x = x.abs();
+	match filter {
+		Filter::Nearest => if x < 0.5 { 1. } else { 0. },
+		Filter::Bilinear => (1. - x).max(0.),
+		Filter::Bicubic => if x < 1. {
+			1.5 * x * x * x - 2.5 * x * x + 1.
+		} else if x < 2. {
+			-0.5 * x * x * x + 2.5 * x * x - 4. * x + 2.
+		} else {
+			0.
+		}
+	}
+}
+
+// Weights of the source pixels contributing to each destination pixel along
+// one axis, as (source index, weight) pairs. Pixels outside the image repeat
+// its edges.
+fn weights(filter: Filter, source: u32, destination: u32) -> Vec<Vec<(usize, f32)>> {
+	let scale = source as f32 / destination as f32;
+	let stretch = scale.max(1.);
+	let support = match filter {
+		Filter::Nearest => 0.5,
+		Filter::Bilinear => 1.,
+		Filter::Bicubic => 2.
+	} * stretch;
+	(0..destination).map(|i| {
+		let center = (i as f32 + 0.5) * scale - 0.5;
+		if filter == Filter::Nearest {
+			let nearest = ((i as f32 + 0.5) * scale) as usize;
+			return vec![(nearest.min(source as usize - 1), 1.)]
+		}
+		let (first, last) = ((center - support).ceil() as i32, (center + support).floor() as i32);
+		let mut taps: Vec<(usize, f32)> = (first..last + 1).map(|j| {
+			(j.max(0).min(source as i32 - 1) as usize, kernel(filter, (j as f32 - center) / stretch))
+		}).collect();
+		let total: f32 = taps.iter().map(|&(_, weight)| weight).sum();
+		if total != 0. {
+			for tap in &mut taps {
+				tap.1 /= total;
+			}
+		}
+		taps
+	}).collect()
+}
+
+/// Resize an image to `new_width` by `new_height` pixels.
+///
+/// When shrinking, the filters are widened to average all the covered
+/// pixels. Returns an empty buffer if any size is zero.
+pub fn resize(pixels: &[u8], width: u32, height: u32, new_width: u32, new_height: u32, filter: Filter) -> Vec<u8> {
+	if width == 0 || height == 0 || new_width == 0 || new_height == 0 {
+		return Vec::new()
+	}
+	let source = premultiplied(pixels);
+	let columns = weights(filter, width, new_width);
+	let rows = weights(filter, height, new_height);
+
+	let mut horizontal = vec![0.; new_width as usize * height as usize * 4];
+	for y in 0..height as usize {
+		for (x, taps) in columns.iter().enumerate() {
+			let out = (y * new_width as usize + x) * 4;
+			for &(column, tap) in taps {
+				let at = (y * width as usize + column) * 4;
+				for c in 0..4 {
+					horizontal[out + c] += source[at + c] * tap;
+				}
+			}
+		}
+	}
+	let mut result = vec![0.; new_width as usize * new_height as usize * 4];
+	for (y, taps) in rows.iter().enumerate() {
+		for x in 0..new_width as usize {
+			let out = (y * new_width as usize + x) * 4;
+			for &(row, tap) in taps {
+				let at = (row * new_width as usize + x) * 4;
+				for c in 0..4 {
+					result[out + c] += horizontal[at + c] * tap;
+				}
+			}
+		}
+	}
+	unpremultiplied(&result)
+}
+
+/// Copy the part of an image inside `rect`.
+///
+/// The rectangle is clipped to the image. Returns the pixels with their
+/// width and height, which are zero if the rectangle is outside the image.
+pub fn crop(pixels: &[u8], width: u32, height: u32, rect: IntRect) -> (Vec<u8>, u32, u32) {
+	// In i64, so that the right and bottom edges cannot overflow
+	let left = (rect.left as i64).max(0).min(width as i64);
+	let top = (rect.top as i64).max(0).min(height as i64);
+	let right = (rect.left as i64 + rect.width as i64).max(left).min(width as i64);
+	let bottom = (rect.top as i64 + rect.height as i64).max(top).min(height as i64);
+	let (w, h) = ((right - left) as usize, (bottom - top) as usize);
+	let mut result = Vec::with_capacity(w * h * 4);
+	for y in top as usize..bottom as usize {
+		let start = (y * width as usize + left as usize) * 4;
+		result.extend_from_slice(&pixels[start..start + w * 4]);
+	}
+	(result, w as u32, h as u32)
+}
+
+/// Rotate an image clockwise by `turns` quarter turns.
+///
+/// Negative turns rotate counterclockwise. Returns the pixels with their
+/// width and height, which are swapped by odd turns.
+pub fn rotate90(pixels: &[u8], width: u32, height: u32, turns: i32) -> (Vec<u8>, u32, u32) {
+	let turns = ((turns % 4) + 4) % 4;
+	let (w, h) = if turns % 2 == 0 { (width, height) } else { (height, width) };
+	let mut result = vec![0; pixels.len()];
+	for y in 0..height {
+		for x in 0..width {
+			let (nx, ny) = match turns {
+				0 => (x, y),
+				1 => (height - 1 - y, x),
+				2 => (width - 1 - x, height - 1 - y),
+				_ => (y, width - 1 - x)
+			};
+			let (from, to) = (((y * width + x) * 4) as usize, ((ny * w + nx) * 4) as usize);
+			result[to..to + 4].copy_from_slice(&pixels[from..from + 4]);
+		}
+	}
+	(result, w, h)
+}
+
+/// Blur an image with a gaussian of standard deviation `radius / 3`, whose
+/// effect extends `radius` pixels around each pixel.
+///
+/// Pixels outside the image repeat its edges.
+pub fn blur(pixels: &mut [u8], width: u32, height: u32, radius: f32) {
+	if radius <= 0. || width == 0 || height == 0 {
+		return
+	}
+	let reach = radius.ceil() as i32;
+	let sigma = radius / 3.;
+	let mut taps: Vec<f32> = (-reach..reach + 1).map(|i| (-(i * i) as f32 / (2. * sigma * sigma)).exp()).collect();
+	let total: f32 = taps.iter().sum();
+	for tap in &mut taps {
+		*tap /= total;
+	}
+	let (w, h) = (width as i32, height as i32);
+	let source = premultiplied(pixels);
+	let mut horizontal = vec![0.; source.len()];
+	for y in 0..h {
+		for x in 0..w {
+			let out = ((y * w + x) * 4) as usize;
+			for (k, tap) in taps.iter().enumerate() {
+				let sx = (x + k as i32 - reach).max(0).min(w - 1);
+				let at = ((y * w + sx) * 4) as usize;
+				for c in 0..4 {
+					horizontal[out + c] += source[at + c] * tap;
+				}
+			}
+		}
+	}
+	let mut result = vec![0.; source.len()];
+	for y in 0..h {
+		for x in 0..w {
+			let out = ((y * w + x) * 4) as usize;
+			for (k, tap) in taps.iter().enumerate() {
+				let sy = (y + k as i32 - reach).max(0).min(h - 1);
+				let at = ((sy * w + x) * 4) as usize;
+				for c in 0..4 {
+					result[out + c] += horizontal[at + c] * tap;
+				}
+			}
+		}
+	}
+	pixels.copy_from_slice(&unpremultiplied(&result));
+}
+
+fn map_colors<F: Fn(f32) -> f32>(pixels: &mut [u8], f: F) {
+	for p in pixels.chunks_mut(4) {
+		for c in &mut p[..3] {
+			*c = to_u8(f(*c as f32));
+		}
+	}
+}
+
+/// Add `amount` to the color components; -1 turns all pixels black and 1
+/// turns them white. Alpha is left unchanged.
+pub fn adjust_brightness(pixels: &mut [u8], amount: f32) {
+	map_colors(pixels, |c| c + amount * 255.);
+}
+
+/// Scale the differences of the color components from mid-gray by
+/// `factor`; 0 makes all pixels gray and 1 leaves them unchanged.
+pub fn adjust_contrast(pixels: &mut [u8], factor: f32) {
+	map_colors(pixels, |c| (c - 127.5) * factor + 127.5);
+}
+
+/// Scale the saturation of the pixels by `factor`; 0 turns them into
+/// grayscale (using Rec. 601 luma) and 1 leaves them unchanged.
+pub fn adjust_saturation(pixels: &mut [u8], factor: f32) {
+	for p in pixels.chunks_mut(4) {
+		let luma = 0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32;
+		for c in &mut p[..3] {
+			*c = to_u8(luma + (*c as f32 - luma) * factor);
+		}
+	}
+}
+
+/// Multiply the pixels by a color, as a sprite's color does when drawing.
+pub fn tint(pixels: &mut [u8], color: Color) {
+	let color = [color.red, color.green, color.blue, color.alpha];
+	for p in pixels.chunks_mut(4) {
+		for (c, &m) in p.iter_mut().zip(color.iter()) {
+			*c = ((*c as u32 * m as u32 + 127) / 255) as u8;
+		}
+	}
+}
+
+/// Multiply the color components of the pixels by their alpha, as expected
+/// by premultiplied alpha blending.
+pub fn premultiply_alpha(pixels: &mut [u8]) {
+	for p in pixels.chunks_mut(4) {
+		let alpha = p[3] as u32;
+		for c in &mut p[..3] {
+			*c = ((*c as u32 * alpha + 127) / 255) as u8;
+		}
+	}
+}
+
+/// Divide the color components of the pixels by their alpha, undoing
+/// `premultiply_alpha` up to rounding. Fully transparent pixels become
+/// transparent black.
+pub fn unpremultiply_alpha(pixels: &mut [u8]) {
+	for p in pixels.chunks_mut(4) {
+		let alpha = p[3] as u32;
+		for c in &mut p[..3] {
+			*c = if alpha == 0 { 0 } else { ((*c as u32 * 255 + alpha / 2) / alpha).min(255) as u8 };
+		}
+	}
+}
+
+#[cfg(test)]
+fn gray(values: &[u8]) -> Vec<u8> {
+	values.iter().flat_map(|&v| vec![v, v, v, 255]).collect()
+}
+
+#[test]
+fn image_ops_resize() {
+	let pixels = gray(&[0, 100, 200, 50]);
+	assert_eq!(resize(&pixels, 2, 2, 4, 4, Filter::Nearest),
+	           gray(&[0, 0, 100, 100, 0, 0, 100, 100, 200, 200, 50, 50, 200, 200, 50, 50]));
+	for &filter in &[Filter::Nearest, Filter::Bilinear, Filter::Bicubic] {
+		assert_eq!(resize(&pixels, 2, 2, 2, 2, filter), pixels);
+	}
+	assert_eq!(resize(&gray(&[0, 200]), 2, 1, 4, 1, Filter::Bilinear), gray(&[0, 50, 150, 200]));
+	assert_eq!(resize(&gray(&[0, 200]), 2, 1, 4, 1, Filter::Bicubic), gray(&[0, 41, 159, 214]));
+	assert_eq!(resize(&pixels, 2, 2, 1, 1, Filter::Bilinear), gray(&[88]));
+	// Transparent pixels do not darken their neighbours
+	let clear = vec![255, 0, 0, 255, 0, 0, 0, 0];
+	assert_eq!(resize(&clear, 2, 1, 1, 1, Filter::Bilinear), vec![255, 0, 0, 128]);
+}
+
+#[test]
+fn image_ops_crop_rotate() {
+	let pixels = gray(&[1, 2, 3, 4, 5, 6]);
+	assert_eq!(crop(&pixels, 3, 2, IntRect::new(1, 0, 5, 1)), (gray(&[2, 3]), 2, 1));
+	assert_eq!(crop(&pixels, 3, 2, IntRect::new(4, 0, 2, 2)).1, 0);
+	assert_eq!(crop(&pixels, 3, 2, IntRect::new(2, 1, i32::MAX, i32::MAX)), (gray(&[6]), 1, 1));
+	assert_eq!(crop(&pixels, 3, 2, IntRect::new(i32::MIN, i32::MIN, -1, -1)).1, 0);
+	assert_eq!(rotate90(&pixels, 3, 2, 1), (gray(&[4, 1, 5, 2, 6, 3]), 2, 3));
+	assert_eq!(rotate90(&pixels, 3, 2, 2), (gray(&[6, 5, 4, 3, 2, 1]), 3, 2));
+	assert_eq!(rotate90(&pixels, 3, 2, -1), (gray(&[3, 6, 2, 5, 1, 4]), 2, 3));
+	assert_eq!(rotate90(&pixels, 3, 2, 4), (pixels, 3, 2));
+}
+
+#[test]
+fn image_ops_blur() {
+	let mut flat = gray(&[90; 9]);
+	blur(&mut flat, 3, 3, 2.);
+	assert_eq!(flat, gray(&[90; 9]));
+	let mut dot = gray(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+	blur(&mut dot, 5, 5, 3.);
+	let at = |x: usize, y: usize| dot[(y * 5 + x) * 4];
+	assert!(at(2, 2) < 255 && at(2, 2) > at(1, 2) && at(1, 2) > at(0, 2) && at(0, 2) > 0);
+	assert_eq!(at(1, 2), at(3, 2));
+	assert_eq!(at(1, 2), at(2, 1));
+	assert_eq!(at(0, 0), at(4, 4));
+}
+
+#[test]
+fn image_ops_colors() {
+	let mut pixels = vec![100, 150, 200, 255];
+	adjust_brightness(&mut pixels, 0.2);
+	assert_eq!(pixels, vec![151, 201, 251, 255]);
+	adjust_contrast(&mut pixels, 0.);
+	assert_eq!(pixels, vec![128, 128, 128, 255]);
+	let mut pixels = vec![255, 0, 0, 128];
+	adjust_saturation(&mut pixels, 0.);
+	assert_eq!(pixels, vec![76, 76, 76, 128]);
+	let mut pixels = vec![200, 100, 50, 255];
+	tint(&mut pixels, Color::new_rgba(255, 128, 0, 128));
+	assert_eq!(pixels, vec![200, 50, 0, 128]);
+	let mut pixels = vec![200, 100, 50, 128, 10, 20, 30, 0];
+	premultiply_alpha(&mut pixels);
+	assert_eq!(pixels, vec![100, 50, 25, 128, 0, 0, 0, 0]);
+	unpremultiply_alpha(&mut pixels);
+	assert_eq!(pixels, vec![199, 100, 50, 128, 0, 0, 0, 0]);
+}
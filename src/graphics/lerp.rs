@@ -0,0 +1,77 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use graphics::Color;
+use system::{Vector2f, Vector3f};
+
+/// Types that can be linearly interpolated.
+///
+/// `a.lerp(b, 0.)` is `a`, `a.lerp(b, 1.)` is `b`, and values in between
+/// blend the two. Factors outside `[0, 1]` extrapolate where the type allows
+/// it; `Color` components saturate instead.
+pub trait Lerp {
+	/// Interpolate between `self` and `other` by `factor`.
+	fn lerp(&self, other: &Self, factor: f32) -> Self;
+}
+
+impl Lerp for f32 {
+	fn lerp(&self, other: &f32, factor: f32) -> f32 {
+		self + (other - self) * factor
+	}
+}
+
+impl Lerp for Vector2f {
+	fn lerp(&self, other: &Vector2f, factor: f32) -> Vector2f {
+		Vector2f::new(self.x.lerp(&other.x, factor), self.y.lerp(&other.y, factor))
+	}
+}
+
+impl Lerp for Vector3f {
+	fn lerp(&self, other: &Vector3f, factor: f32) -> Vector3f {
+		Vector3f::new(self.x.lerp(&other.x, factor),
+		              self.y.lerp(&other.y, factor),
+		              self.z.lerp(&other.z, factor))
+	}
+}
+
+impl Lerp for Color {
+	fn lerp(&self, other: &Color, factor: f32) -> Color {
+		fn channel(a: u8, b: u8, factor: f32) -> u8 {
+			let value = (a as f32).lerp(&(b as f32), factor).round();
+			if value < 0. { 0 } else if value > 255. { 255 } else { value as u8 }
+		}
+		Color::new_rgba(channel(self.red, other.red, factor),
+		                channel(self.green, other.green, factor),
+		                channel(self.blue, other.blue, factor),
+		                channel(self.alpha, other.alpha, factor))
+	}
+}
+
+#[test]
+fn lerp_values() {
+	assert_eq!(2f32.lerp(&4., 0.25), 2.5);
+	assert_eq!(Vector2f::new(0., 10.).lerp(&Vector2f::new(10., 0.), 0.5), Vector2f::new(5., 5.));
+	assert_eq!(Color::black().lerp(&Color::white(), 0.5), Color::new_rgb(128, 128, 128));
+	assert_eq!(Color::black().lerp(&Color::white(), 2.), Color::white());
+}
@@ -0,0 +1,462 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Dynamic 2D point lights with hard and soft shadows.
+//!
+//! Lights are rendered into a `LightMap`: a render texture cleared to the
+//! ambient color, into which every light adds its contribution, clipped to
+//! the area it can see. Drawing the light map over the scene multiplies it,
+//! darkening everything that is not lit.
+//!
+//! Shadows come from `Occluder` polygons. The part of the world a light can
+//! see is computed on the CPU by `visibility_polygon`, which is independent
+//! of rendering.
+//!
+//! ```ignore
+//! let mut lights = LightMap::new(800, 600).unwrap();
+//! lights.set_ambient(Color::new_rgb(30, 30, 50));
+//! let walls: Vec<Occluder> = shapes.iter().map(Occluder::from_shape).collect();
+//! let mut torch = Light::new(player.get_position(), 300., Color::new_rgb(255, 200, 120));
+//! torch.softness = 6.;
+//! // Each frame, after drawing the scene:
+//! lights.render(&[torch], &walls, &window.get_view());
+//! window.draw(&lights);
+//! ```
+
+use std::cmp::Ordering;
+
+use graphics::{BlendMode, Color, Drawable, FloatRect, Image, PrimitiveType, RenderStates,
+               RenderTarget, RenderTexture, Shape, Texture, Vertex, VertexArray, View};
+use graphics::post_process::draw_fullscreen_with;
+use system::{Vector2f, Vector2u};
+use window::Event;
+
+use ffi::Ref;
+
+/// A polygon that blocks light.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Occluder {
+	/// Vertices of the polygon, in world coordinates. The polygon is closed
+	/// and may be concave.
+	pub points: Vec<Vector2f>
+}
+
+impl Occluder {
+	/// Create an occluder from the vertices of a polygon.
+	pub fn new(points: Vec<Vector2f>) -> Occluder {
+		Occluder {
+			points: points
+		}
+	}
+
+	/// Create an occluder from the outline of a shape, with its transform
+	/// applied.
+	pub fn from_shape<S: Shape>(shape: &S) -> Occluder {
+		let transform = shape.get_transform();
+		Occluder {
+			points: (0..shape.get_point_count())
+				.map(|i| transform.transform_point(shape.get_point(i)))
+				.collect()
+		}
+	}
+
+	/// Create an occluder from a rectangle.
+	pub fn from_rect(rect: FloatRect) -> Occluder {
+		Occluder {
+			points: rect_corners(rect).to_vec()
+		}
+	}
+}
+
+fn rect_corners(rect: FloatRect) -> [Vector2f; 4] {
+	[Vector2f::new(rect.left, rect.top),
+	 Vector2f::new(rect.left + rect.width, rect.top),
+	 Vector2f::new(rect.left + rect.width, rect.top + rect.height),
+	 Vector2f::new(rect.left, rect.top + rect.height)]
+}
+
+fn cross(a: Vector2f, b: Vector2f) -> f32 {
+	a.x * b.y - a.y * b.x
+}
+
+// Distance along a ray to a segment, if they intersect.
+fn ray_hit(origin: Vector2f, direction: Vector2f, a: Vector2f, b: Vector2f) -> Option<f32> {
+	let edge = b - a;
+	let denominator = cross(direction, edge);
+	if denominator.abs() < 1e-9 {
+		return None
+	}
+	let to_start = a - origin;
+	let t = cross(to_start, edge) / denominator;
+	let u = cross(to_start, direction) / denominator;
+	if t >= 0. && u >= -1e-6 && u <= 1. + 1e-6 {
+		Some(t)
+	} else {
+		None
+	}
+}
+
+/// Compute the region visible from `origin`, as a polygon sorted by angle
+/// around it.
+///
+/// Rays are cast toward every occluder vertex (and slightly to each side of
+/// it, to see past corners); `bounds` closes the region where nothing blocks
+/// the view. The polygon is suitable for drawing as a triangle fan around
+/// `origin`.
+pub fn visibility_polygon(origin: Vector2f, occluders: &[Occluder], bounds: FloatRect) -> Vec<Vector2f> {
+	let mut segments = Vec::new();
+	let corners = rect_corners(bounds);
+	for i in 0..4 {
+		segments.push((corners[i], corners[(i + 1) % 4]));
+	}
+	for occluder in occluders {
+		let count = occluder.points.len();
+		if count < 2 {
+			continue
+		}
+		for i in 0..count {
+			segments.push((occluder.points[i], occluder.points[(i + 1) % count]));
+		}
+	}
+
+	let mut angles = Vec::with_capacity(segments.len() * 3);
+	for &(point, _) in &segments {
+		let angle = (point.y - origin.y).atan2(point.x - origin.x);
+		angles.push(angle - 1e-4);
+		angles.push(angle);
+		angles.push(angle + 1e-4);
+	}
+
+	let mut hits: Vec<(f32, Vector2f)> = Vec::with_capacity(angles.len());
+	for angle in angles {
+		let direction = Vector2f::new(angle.cos(), angle.sin());
+		let nearest = segments.iter()
+			.filter_map(|&(a, b)| ray_hit(origin, direction, a, b))
+			.fold(None, |best: Option<f32>, t| Some(best.map_or(t, |b| b.min(t))));
+		if let Some(t) = nearest {
+			hits.push((angle, origin + direction * t));
+		}
+	}
+	hits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+	let mut polygon: Vec<Vector2f> = Vec::with_capacity(hits.len());
+	for (_, point) in hits {
+		let duplicate = polygon.last().map_or(false, |last| {
+			(last.x - point.x).abs() < 1e-3 && (last.y - point.y).abs() < 1e-3
+		});
+		if !duplicate {
+			polygon.push(point);
+		}
+	}
+	polygon
+}
+
+/// Tell whether a point lies inside a polygon (even-odd rule).
+pub fn polygon_contains(polygon: &[Vector2f], point: Vector2f) -> bool {
+	let mut inside = false;
+	let count = polygon.len();
+	for i in 0..count {
+		let (a, b) = (polygon[i], polygon[(i + count - 1) % count]);
+		if (a.y > point.y) != (b.y > point.y) &&
+		   point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x {
+			inside = !inside;
+		}
+	}
+	inside
+}
+
+/// A point light.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Light {
+	/// Position, in world coordinates.
+	pub position: Vector2f,
+	/// Distance at which the light fades out completely.
+	pub radius: f32,
+	/// Color of the light.
+	pub color: Color,
+	/// Brightness multiplier, in `[0, 1]`. The default is 1.
+	pub intensity: f32,
+	/// Exponent of the falloff curve: 1 fades linearly from the center to
+	/// the radius, higher values concentrate the light near the center. The
+	/// default is 2.
+	pub falloff: f32,
+	/// Size of the light source. 0 casts hard shadows; larger values blur
+	/// the shadow edges. The default is 0.
+	pub softness: f32,
+	/// Whether occluders block this light. The default is true.
+	pub casts_shadows: bool
+}
+
+impl Light {
+	/// Create a light with hard shadows and quadratic falloff.
+	pub fn new(position: Vector2f, radius: f32, color: Color) -> Light {
+		Light {
+			position: position,
+			radius: radius,
+			color: color,
+			intensity: 1.,
+			falloff: 2.,
+			softness: 0.,
+			casts_shadows: true
+		}
+	}
+
+	/// Get the area the light can reach.
+	pub fn get_bounds(&self) -> FloatRect {
+		FloatRect::new(self.position.x - self.radius, self.position.y - self.radius,
+		               self.radius * 2., self.radius * 2.)
+	}
+}
+
+const GRADIENT_SIZE: u32 = 256;
+
+/// Generate the RGBA pixels of a radial gradient of `size` x `size` pixels,
+/// white at the center and fading to black at the edge of the inscribed
+/// circle as `(1 - distance) ^ falloff`.
+pub fn falloff_pixels(size: u32, falloff: f32) -> Vec<u8> {
+	let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+	let half = size as f32 / 2.;
+	for y in 0..size {
+		for x in 0..size {
+			let dx = (x as f32 + 0.5 - half) / half;
+			let dy = (y as f32 + 0.5 - half) / half;
+			let distance = (dx * dx + dy * dy).sqrt().min(1.);
+			let value = ((1. - distance).powf(falloff) * 255.).round() as u8;
+			pixels.push(value);
+			pixels.push(value);
+			pixels.push(value);
+			pixels.push(255);
+		}
+	}
+	pixels
+}
+
+/// Render texture accumulating lights, drawn over a scene to light it.
+pub struct LightMap {
+	target: RenderTexture,
+	ambient: Color,
+	soft_samples: u32,
+	gradients: Vec<(f32, Texture)>,
+	vertices: VertexArray
+}
+
+impl LightMap {
+	/// Create a light map of `width` x `height` pixels, usually the size of
+	/// the window.
+	///
+	/// Returns None if the render texture could not be created.
+	pub fn new(width: u32, height: u32) -> Option<LightMap> {
+		RenderTexture::new(width, height, false).map(|target| LightMap {
+			target: target,
+			ambient: Color::black(),
+			soft_samples: 8,
+			gradients: Vec::new(),
+			vertices: VertexArray::new(PrimitiveType::TrianglesFan)
+		})
+	}
+
+	/// Set the light of areas no light reaches. The default is black.
+	pub fn set_ambient(&mut self, ambient: Color) {
+		self.ambient = ambient;
+	}
+
+	/// Get the light of areas no light reaches.
+	pub fn get_ambient(&self) -> Color {
+		self.ambient
+	}
+
+	/// Set how many times soft lights are drawn, from positions spread over
+	/// their source. More samples give smoother penumbras. The default is 8.
+	pub fn set_soft_shadow_samples(&mut self, samples: u32) {
+		self.soft_samples = samples.max(1);
+	}
+
+	/// Get how many times soft lights are drawn.
+	pub fn get_soft_shadow_samples(&self) -> u32 {
+		self.soft_samples
+	}
+
+	/// Get the size of the light map, in pixels.
+	pub fn get_size(&self) -> Vector2u {
+		self.target.get_size()
+	}
+
+	/// Recreate the light map with a new size.
+	///
+	/// Returns false, keeping the previous texture, on failure.
+	pub fn resize(&mut self, width: u32, height: u32) -> bool {
+		if self.get_size() == Vector2u::new(width, height) {
+			return true
+		}
+		match RenderTexture::new(width, height, false) {
+			Some(target) => {
+				self.target = target;
+				true
+			}
+			None => false
+		}
+	}
+
+	/// Follow the window size on `Event::Resized`. Other events are ignored.
+	pub fn handle_event(&mut self, event: &Event) {
+		if let Event::Resized { width, height } = *event {
+			self.resize(width, height);
+		}
+	}
+
+	/// Get the rendered light map.
+	pub fn get_texture<'a>(&'a self) -> Option<Ref<'a, Texture>> {
+		self.target.get_texture()
+	}
+
+	/// Render the lights, as seen through `view`, which should be the view
+	/// the scene was drawn with.
+	///
+	/// Returns false if the gradient texture of a light could not be
+	/// created; such lights are left out.
+	pub fn render(&mut self, lights: &[Light], occluders: &[Occluder], view: &View) -> bool {
+		let mut complete = true;
+		self.target.set_view(view);
+		self.target.clear(self.ambient);
+		for light in lights {
+			if light.radius <= 0. || light.intensity <= 0. {
+				continue
+			}
+			let index = match self.gradient(light.falloff) {
+				Some(index) => index,
+				None => {
+					complete = false;
+					continue
+				}
+			};
+			let samples = if light.softness > 0. && light.casts_shadows { self.soft_samples } else { 1 };
+			let color = scale_color(light.color, light.intensity / samples as f32);
+			for sample in 0..samples {
+				let origin = if samples > 1 {
+					// Spread the samples over a disc with a golden-angle spiral.
+					let angle = sample as f32 * 2.3999632;
+					let distance = light.softness * ((sample as f32 + 0.5) / samples as f32).sqrt();
+					light.position + Vector2f::new(angle.cos(), angle.sin()) * distance
+				} else {
+					light.position
+				};
+				let polygon = if light.casts_shadows {
+					visibility_polygon(origin, occluders, light.get_bounds())
+				} else {
+					rect_corners(light.get_bounds()).to_vec()
+				};
+				self.build_fan(light, origin, &polygon, color);
+				let states = RenderStates {
+					blend_mode: BlendMode::add(),
+					texture: Some(&self.gradients[index].1),
+					.. RenderStates::default()
+				};
+				self.vertices.draw(&mut self.target, &states);
+			}
+		}
+		self.target.display();
+		complete
+	}
+
+	// Build a triangle fan covering `polygon`, textured so that the gradient
+	// is centered on the light.
+	fn build_fan(&mut self, light: &Light, origin: Vector2f, polygon: &[Vector2f], color: Color) {
+		let scale = GRADIENT_SIZE as f32 / (light.radius * 2.);
+		let corner = light.position - Vector2f::new(light.radius, light.radius);
+		let vertex = |point: Vector2f| Vertex::new(point, color, (point - corner) * scale);
+		self.vertices.clear();
+		self.vertices.push(vertex(origin));
+		for &point in polygon {
+			self.vertices.push(vertex(point));
+		}
+		if let Some(&first) = polygon.first() {
+			self.vertices.push(vertex(first));
+		}
+	}
+
+	fn gradient(&mut self, falloff: f32) -> Option<usize> {
+		if let Some(index) = self.gradients.iter().position(|g| (g.0 - falloff).abs() < 1e-3) {
+			return Some(index)
+		}
+		let pixels = falloff_pixels(GRADIENT_SIZE, falloff);
+		let texture = Image::create_from_pixels(GRADIENT_SIZE, GRADIENT_SIZE, &pixels)
+			.and_then(|image| Texture::new_from_image(&image, None));
+		texture.map(|mut texture| {
+			texture.set_smooth(true);
+			self.gradients.push((falloff, texture));
+			self.gradients.len() - 1
+		})
+	}
+}
+
+fn scale_color(color: Color, factor: f32) -> Color {
+	let channel = |c: u8| (c as f32 * factor).round().max(0.).min(255.) as u8;
+	Color::new_rgba(channel(color.red), channel(color.green), channel(color.blue), color.alpha)
+}
+
+impl Drawable for LightMap {
+	/// Multiply the light map over the whole target.
+	///
+	/// The blend mode of `states` is replaced by multiplication; see
+	/// `draw_fullscreen_with` for the other states.
+	fn draw(&self, target: &mut RenderTarget, states: &RenderStates) {
+		if let Some(texture) = self.target.get_texture() {
+			draw_fullscreen_with(&texture, target, &RenderStates {
+				blend_mode: BlendMode::multiply(),
+				.. *states
+			});
+		}
+	}
+}
+
+#[test]
+fn visibility_with_occluder() {
+	let bounds = FloatRect::new(0., 0., 100., 100.);
+	let origin = Vector2f::new(10., 50.);
+
+	let open = visibility_polygon(origin, &[], bounds);
+	assert!(polygon_contains(&open, Vector2f::new(90., 90.)));
+	assert!(polygon_contains(&open, Vector2f::new(99., 1.)));
+
+	let wall = Occluder::from_rect(FloatRect::new(40., 40., 20., 20.));
+	let polygon = visibility_polygon(origin, &[wall], bounds);
+	assert!(polygon.iter().any(|p| (p.x - 40.).abs() < 1e-3 && (p.y - 40.).abs() < 1e-3));
+	assert!(polygon.iter().all(|p| !(p.x > 40.01 && p.x < 60. && p.y > 40.01 && p.y < 59.99)));
+	assert!(!polygon_contains(&polygon, Vector2f::new(80., 50.)));
+	assert!(!polygon_contains(&polygon, Vector2f::new(50., 50.)));
+	assert!(polygon_contains(&polygon, Vector2f::new(80., 10.)));
+	assert!(polygon_contains(&polygon, Vector2f::new(30., 50.)));
+	let angles: Vec<f32> = polygon.iter().map(|p| (p.y - origin.y).atan2(p.x - origin.x)).collect();
+	assert!(angles.windows(2).all(|w| w[0] <= w[1]));
+}
+
+#[test]
+fn falloff_gradient() {
+	let pixels = falloff_pixels(4, 1.);
+	assert_eq!(pixels.len(), 64);
+	// The center of pixel (1, 1) is at a distance of sqrt(0.125) radius.
+	assert_eq!(pixels[(4 + 1) * 4], ((1. - 0.125f32.sqrt()) * 255.).round() as u8);
+	assert_eq!(pixels[0], 0);
+	assert_eq!(pixels[3], 255);
+}
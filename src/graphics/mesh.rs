@@ -0,0 +1,299 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use graphics::{Vertex, VertexArray, FloatRect, PrimitiveType, RenderTarget, RenderStates, Drawable, Texture, Color};
+use system::Vector2f;
+
+/// Indices into the vertices of a `Mesh`.
+///
+/// Small meshes can use 16-bit indices to save memory; pushing an index that
+/// does not fit in 16 bits switches the list to 32-bit indices.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Indices {
+	/// 16-bit indices.
+	U16(Vec<u16>),
+	/// 32-bit indices.
+	U32(Vec<u32>)
+}
+
+impl Indices {
+	/// Get the number of indices.
+	pub fn len(&self) -> usize {
+		match *self {
+			Indices::U16(ref v) => v.len(),
+			Indices::U32(ref v) => v.len()
+		}
+	}
+
+	/// Tell whether there are no indices.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Get the index at position `i`, if any.
+	pub fn get(&self, i: usize) -> Option<u32> {
+		match *self {
+			Indices::U16(ref v) => v.get(i).map(|&x| x as u32),
+			Indices::U32(ref v) => v.get(i).cloned()
+		}
+	}
+
+	/// Append an index, widening the list to 32 bits if needed.
+	pub fn push(&mut self, index: u32) {
+		if index > 0xFFFF {
+			self.widen();
+		}
+		match *self {
+			Indices::U16(ref mut v) => v.push(index as u16),
+			Indices::U32(ref mut v) => v.push(index)
+		}
+	}
+
+	/// Remove all the indices, keeping the index width.
+	pub fn clear(&mut self) {
+		match *self {
+			Indices::U16(ref mut v) => v.clear(),
+			Indices::U32(ref mut v) => v.clear()
+		}
+	}
+
+	fn widen(&mut self) {
+		let wide = match *self {
+			Indices::U16(ref v) => v.iter().map(|&x| x as u32).collect(),
+			Indices::U32(_) => return
+		};
+		*self = Indices::U32(wide);
+	}
+}
+
+/// A set of vertices shared between primitives through an index list.
+///
+/// Where a `VertexArray` repeats every corner of every primitive, a mesh
+/// stores each vertex once and describes primitives with indices into it, so
+/// a grid of quads needs one vertex per grid point rather than six per cell.
+///
+/// If the index list is empty, the vertices are drawn in order, like a
+/// `VertexArray`. Indices that are out of range are skipped when drawing.
+#[derive(Clone)]
+pub struct Mesh<'s> {
+	primitive_type: PrimitiveType,
+	vertices: Vec<Vertex>,
+	indices: Indices,
+	texture: Option<&'s Texture>
+}
+
+impl<'s> Mesh<'s> {
+	/// Create an empty mesh with the given primitive type and 16-bit indices.
+	pub fn new(primitive_type: PrimitiveType) -> Mesh<'s> {
+		Mesh::from_parts(primitive_type, Vec::new(), Indices::U16(Vec::new()))
+	}
+
+	/// Create a mesh from existing vertices and indices.
+	pub fn from_parts(primitive_type: PrimitiveType, vertices: Vec<Vertex>, indices: Indices) -> Mesh<'s> {
+		Mesh {
+			primitive_type: primitive_type,
+			vertices: vertices,
+			indices: indices,
+			texture: None
+		}
+	}
+
+	/// Create a textured quad made of two triangles.
+	///
+	/// `tex_rect` is the part of the texture, in pixels, mapped onto `rect`.
+	pub fn quad(rect: FloatRect, tex_rect: FloatRect) -> Mesh<'s> {
+		Mesh::grid(rect, tex_rect, 1, 1)
+	}
+
+	/// Create a textured grid of `columns` by `rows` cells, as triangles.
+	///
+	/// Neighbouring cells share their vertices, so individual grid points
+	/// can later be moved to warp the texture.
+	pub fn grid(rect: FloatRect, tex_rect: FloatRect, columns: u32, rows: u32) -> Mesh<'s> {
+		let (columns, rows) = (columns.max(1), rows.max(1));
+		let mut mesh = Mesh::new(PrimitiveType::Triangles);
+		for y in 0..rows + 1 {
+			for x in 0..columns + 1 {
+				let (u, v) = (x as f32 / columns as f32, y as f32 / rows as f32);
+				mesh.add_vertex(Vertex::new_with_pos_coords(
+					Vector2f::new(rect.left + rect.width * u, rect.top + rect.height * v),
+					Vector2f::new(tex_rect.left + tex_rect.width * u, tex_rect.top + tex_rect.height * v)
+				));
+			}
+		}
+		let stride = columns + 1;
+		for y in 0..rows {
+			for x in 0..columns {
+				let i = y * stride + x;
+				mesh.add_triangle(i, i + 1, i + stride + 1);
+				mesh.add_triangle(i, i + stride + 1, i + stride);
+			}
+		}
+		mesh
+	}
+
+	/// Create a filled polygon as a fan of triangles around its first point.
+	///
+	/// This is only correct for convex polygons.
+	pub fn fan(points: &[Vector2f], color: Color) -> Mesh<'s> {
+		let mut mesh = Mesh::new(PrimitiveType::Triangles);
+		for &point in points {
+			mesh.add_vertex(Vertex::new_with_pos_color(point, color));
+		}
+		for i in 1..(points.len().max(2) - 1) as u32 {
+			mesh.add_triangle(0, i, i + 1);
+		}
+		mesh
+	}
+
+	/// Append a vertex and return its index.
+	pub fn add_vertex(&mut self, vertex: Vertex) -> u32 {
+		self.vertices.push(vertex);
+		self.vertices.len() as u32 - 1
+	}
+
+	/// Append the indices of a triangle.
+	pub fn add_triangle(&mut self, a: u32, b: u32, c: u32) {
+		self.indices.push(a);
+		self.indices.push(b);
+		self.indices.push(c);
+	}
+
+	/// Get the vertices of the mesh.
+	pub fn get_vertices(&self) -> &Vec<Vertex> {
+		&self.vertices
+	}
+
+	/// Get the vertices of the mesh for modification.
+	pub fn get_vertices_mut(&mut self) -> &mut Vec<Vertex> {
+		&mut self.vertices
+	}
+
+	/// Get the indices of the mesh.
+	pub fn get_indices(&self) -> &Indices {
+		&self.indices
+	}
+
+	/// Get the indices of the mesh for modification.
+	pub fn get_indices_mut(&mut self) -> &mut Indices {
+		&mut self.indices
+	}
+
+	/// Remove all vertices and indices.
+	pub fn clear(&mut self) {
+		self.vertices.clear();
+		self.indices.clear();
+	}
+
+	/// Get the type of the primitives drawn by the mesh.
+	pub fn get_primitive_type(&self) -> PrimitiveType {
+		self.primitive_type
+	}
+
+	/// Set the type of the primitives drawn by the mesh.
+	pub fn set_primitive_type(&mut self, primitive_type: PrimitiveType) {
+		self.primitive_type = primitive_type;
+	}
+
+	/// Set the texture of the mesh.
+	pub fn set_texture(&mut self, texture: &'s Texture) {
+		self.texture = Some(texture);
+	}
+
+	/// Remove the texture of the mesh.
+	pub fn disable_texture(&mut self) {
+		self.texture = None;
+	}
+
+	/// Get the texture of the mesh, if any.
+	pub fn get_texture(&self) -> Option<&'s Texture> {
+		self.texture
+	}
+
+	/// Compute the bounding rectangle of the mesh's vertices.
+	pub fn get_bounds(&self) -> FloatRect {
+		let first = match self.vertices.first() {
+			Some(vertex) => vertex.position,
+			None => return FloatRect::new(0., 0., 0., 0.)
+		};
+		let (mut min, mut max) = (first, first);
+		for &Vertex { position, .. } in &self.vertices[1..] {
+			min.x = min.x.min(position.x);
+			min.y = min.y.min(position.y);
+			max.x = max.x.max(position.x);
+			max.y = max.y.max(position.y);
+		}
+		FloatRect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+	}
+
+	/// Expand the indices into a flat list of vertices, as drawn.
+	pub fn to_vertex_array(&self) -> VertexArray {
+		if self.indices.is_empty() {
+			return VertexArray::from_vec(self.primitive_type, self.vertices.clone())
+		}
+		let mut array = VertexArray::with_capacity(self.primitive_type, self.indices.len());
+		for i in 0..self.indices.len() {
+			let index = self.indices.get(i).unwrap() as usize;
+			if let Some(&vertex) = self.vertices.get(index) {
+				array.push(vertex);
+			}
+		}
+		array
+	}
+}
+
+impl<'s> Drawable for Mesh<'s> {
+	fn draw(&self, target: &mut RenderTarget, states: &RenderStates) {
+		let states = RenderStates {
+			texture: self.texture.or(states.texture),
+			.. *states
+		};
+		if self.indices.is_empty() {
+			target.draw_primitives_rs(&self.vertices, self.primitive_type, &states);
+		} else {
+			self.to_vertex_array().draw(target, &states);
+		}
+	}
+}
+
+#[test]
+fn mesh_builders() {
+	let grid = Mesh::grid(FloatRect::new(0., 0., 30., 20.), FloatRect::new(0., 0., 3., 2.), 3, 2);
+	assert_eq!(grid.get_vertices().len(), 12);
+	assert_eq!(grid.get_indices().len(), 36);
+	assert_eq!(grid.get_bounds(), FloatRect::new(0., 0., 30., 20.));
+	assert_eq!(grid.get_vertices()[5].tex_coords, Vector2f::new(1., 1.));
+
+	let square = [Vector2f::new(0., 0.), Vector2f::new(1., 0.), Vector2f::new(1., 1.), Vector2f::new(0., 1.)];
+	let fan = Mesh::fan(&square, Color::white());
+	assert_eq!(fan.get_indices(), &Indices::U16(vec![0, 1, 2, 0, 2, 3]));
+	assert_eq!(fan.to_vertex_array().len(), 6);
+}
+
+#[test]
+fn indices_widen() {
+	let mut indices = Indices::U16(vec![1, 2]);
+	indices.push(70000);
+	assert_eq!(indices, Indices::U32(vec![1, 2, 70000]));
+}
@@ -29,18 +29,24 @@ pub use graphics::render_states::RenderStates;
 pub use graphics::render_window::RenderWindow;
 pub use graphics::rect::{Rect, FloatRect, IntRect};
 pub use graphics::texture::Texture;
-pub use graphics::blend_mode::{BlendMode, BlendFactor, BlendEquation};
+pub use graphics::blend_mode::{BlendMode, BlendFactor, BlendEquation, BlendModeError};
 pub use graphics::transform::Transform;
 pub use graphics::text::Text;
-pub use graphics::shader::Shader;
+pub use graphics::shader::{Shader, ShaderParameter, ShaderError, Uniform, UniformGroup,
+                           CurrentTexture};
 pub use graphics::color::Color;
 pub use graphics::font::{Font, FontInfo};
+pub use graphics::font_family::FontFamily;
 pub use graphics::view::View;
 pub use graphics::image::Image;
+pub use graphics::image_format::{ImageFormat, ImageSaveError};
+pub use graphics::animated_image::{AnimatedImage, AnimatedFrame, ImageInfo, ImageFileFormat, ImageDecodeError};
+pub use graphics::capture::{FrameCapture, CapturedFrame};
 pub use graphics::sprite::Sprite;
 pub use graphics::circle_shape::CircleShape;
 pub use graphics::rectangle_shape::RectangleShape;
 pub use graphics::convex_shape::ConvexShape;
+pub use graphics::polygon_shape::{PolygonShape, triangulate};
 pub use graphics::primitive_type::PrimitiveType;
 pub use graphics::vertex::Vertex;
 pub use graphics::transformable::{Transformable, BasicTransformable};
@@ -48,8 +54,19 @@ pub use graphics::glyph::Glyph;
 pub use graphics::render_texture::RenderTexture;
 pub use graphics::base_shape::BaseShape;
 pub use graphics::vertex_array::VertexArray;
+pub use graphics::vertex_buffer::{VertexBuffer, VertexBufferUsage};
+pub use graphics::mesh::{Mesh, Indices};
+pub use graphics::bitmap_font::{BitmapFont, BitmapFontData, BitmapFontError, BitmapChar, BitmapText};
+pub use graphics::sdf_font::{SdfFont, SdfText, distance_field, pack_rects};
 pub use graphics::text_style::TextStyle;
 pub use graphics::traits::{Drawable, ShapeImpl, Shape};
+pub use graphics::lerp::Lerp;
+pub use graphics::stencil::{StencilMode, StencilComparison, StencilOperation, StencilError};
+pub use graphics::shader_builder::{ShaderBuilder, ShaderBuildError, PreprocessedSource,
+                                   HotShader};
+pub use graphics::camera::{Camera, letterbox_viewport};
+pub use graphics::animation::{Animation, AnimatedSprite, AnimationPlayer,
+                              AnimationEvent, Frame, PlayMode};
 
 mod render_target;
 mod render_states;
@@ -65,15 +82,37 @@ mod color;
 mod font;
 mod view;
 mod image;
+mod image_format;
+mod animated_image;
+mod capture;
 mod sprite;
 mod circle_shape;
 mod rectangle_shape;
 mod convex_shape;
+mod polygon_shape;
 mod primitive_type;
 mod vertex;
 mod vertex_array;
+mod vertex_buffer;
+mod mesh;
+mod bitmap_font;
+mod sdf_font;
+mod font_family;
 mod transformable;
 mod glyph;
 mod render_texture;
 mod base_shape;
 mod traits;
+mod animation;
+pub mod tilemap;
+pub mod particles;
+pub mod tween;
+pub mod post_process;
+pub mod lighting;
+pub mod path;
+pub mod text_layout;
+pub mod image_ops;
+mod lerp;
+mod camera;
+mod shader_builder;
+mod stencil;
@@ -0,0 +1,644 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Particle systems: emitters, affectors, and batched rendering.
+//!
+//! A `ParticleSystem` owns a pool of `Particle`s. Every `update`, it ages and
+//! moves the living particles, lets its `Affector`s change them (gravity,
+//! drag, color and scale curves, ...), spawns new ones from its `Emitter`s,
+//! and rebuilds a single `VertexArray` of quads that is drawn in one call with
+//! the system's texture and `BlendMode`.
+//!
+//! All randomness comes from a seeded generator, so a system created with the
+//! same seed and updated with the same time steps always produces the same
+//! particles.
+//!
+//! ```ignore
+//! let mut fire = ParticleSystem::new(42, 1000);
+//! fire.set_texture(&spark, IntRect::new(0, 0, 8, 8));
+//! fire.set_blend_mode(BlendMode::add());
+//! let mut emitter = Emitter::new(Vector2f::new(400., 500.));
+//! emitter.rate = 200.;
+//! emitter.direction = (250., 290.);
+//! fire.add_emitter(emitter);
+//! fire.add_affector(Gravity { acceleration: Vector2f::new(0., -50.) });
+//! fire.add_affector(ColorOverLifetime {
+//!     curve: Curve::new(Color::yellow(), Color::new_rgba(255, 0, 0, 0))
+//! });
+//! // Each frame:
+//! fire.update(clock.restart());
+//! window.draw(&fire);
+//! ```
+
+use std::cmp::Ordering;
+
+use graphics::{BlendMode, Color, Drawable, IntRect, Lerp, PrimitiveType, RenderStates,
+               RenderTarget, Texture, Vertex, VertexArray};
+use system::{Time, Vector2f};
+
+/// Seeded pseudo-random number generator (xorshift64*).
+///
+/// Not suitable for cryptography, but fast and reproducible across platforms.
+#[derive(Clone, Debug)]
+pub struct Random {
+	state: u64
+}
+
+impl Random {
+	/// Create a generator from a seed.
+	pub fn new(seed: u64) -> Random {
+		Random {
+			state: if seed == 0 { 0x9e3779b97f4a7c15 } else { seed }
+		}
+	}
+
+	/// Generate the next 32 random bits.
+	pub fn next_u32(&mut self) -> u32 {
+		self.state ^= self.state >> 12;
+		self.state ^= self.state << 25;
+		self.state ^= self.state >> 27;
+		(self.state.wrapping_mul(0x2545f4914f6cdd1d) >> 32) as u32
+	}
+
+	/// Generate a float in `[0, 1)`.
+	pub fn next_f32(&mut self) -> f32 {
+		(self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+	}
+
+	/// Generate a float in `[min, max)`, or `min` if the range is empty.
+	pub fn range(&mut self, min: f32, max: f32) -> f32 {
+		min + (max - min) * self.next_f32()
+	}
+}
+
+/// A piecewise-linear curve over the normalized range `[0, 1]`.
+///
+/// Used by the lifetime affectors, where the input is the age of a particle
+/// divided by its lifetime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve<T> {
+	keys: Vec<(f32, T)>
+}
+
+impl<T: Lerp + Clone> Curve<T> {
+	/// Create a curve going linearly from `start` to `end`.
+	pub fn new(start: T, end: T) -> Curve<T> {
+		Curve {
+			keys: vec![(0., start), (1., end)]
+		}
+	}
+
+	/// Create a curve from `(position, value)` keys.
+	///
+	/// Keys are sorted by position. Panics if `keys` is empty.
+	pub fn from_keys(mut keys: Vec<(f32, T)>) -> Curve<T> {
+		assert!(!keys.is_empty(), "a curve needs at least one key");
+		keys.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+		Curve {
+			keys: keys
+		}
+	}
+
+	/// Add a key to the curve.
+	pub fn add_key(&mut self, position: f32, value: T) {
+		let index = self.keys.iter().position(|k| k.0 > position).unwrap_or(self.keys.len());
+		self.keys.insert(index, (position, value));
+	}
+
+	/// Get the value of the curve at `position`.
+	///
+	/// Positions before the first key or after the last key take the value of
+	/// that key.
+	pub fn sample(&self, position: f32) -> T {
+		let first = &self.keys[0];
+		if position <= first.0 {
+			return first.1.clone()
+		}
+		for pair in self.keys.windows(2) {
+			let (ref a, ref b) = (&pair[0], &pair[1]);
+			if position <= b.0 {
+				let span = b.0 - a.0;
+				let factor = if span > 0. { (position - a.0) / span } else { 1. };
+				return a.1.lerp(&b.1, factor)
+			}
+		}
+		self.keys[self.keys.len() - 1].1.clone()
+	}
+}
+
+/// A single particle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Particle {
+	/// Position, in the system's local coordinates.
+	pub position: Vector2f,
+	/// Velocity, in units per second.
+	pub velocity: Vector2f,
+	/// Rotation, in degrees.
+	pub rotation: f32,
+	/// Rotation speed, in degrees per second.
+	pub angular_velocity: f32,
+	/// Current scale factor applied to the system's particle size.
+	pub scale: f32,
+	/// Scale factor the particle was emitted with.
+	pub base_scale: f32,
+	/// Color of the particle, modulated with the texture.
+	pub color: Color,
+	/// Time since the particle was emitted.
+	pub age: Time,
+	/// Age at which the particle dies.
+	pub lifetime: Time
+}
+
+impl Particle {
+	/// Get the age of the particle as a fraction of its lifetime, in `[0, 1]`.
+	pub fn get_life_ratio(&self) -> f32 {
+		if self.lifetime <= Time::with_microseconds(0) {
+			1.
+		} else {
+			(self.age / self.lifetime).min(1.)
+		}
+	}
+}
+
+/// The area in which an `Emitter` spawns particles, centered on its position.
+#[derive(Clone, Debug, PartialEq, Copy)]
+pub enum EmitterShape {
+	/// Spawn every particle at the emitter's position.
+	Point,
+	/// Spawn particles uniformly inside a circle of the given radius.
+	Circle(f32),
+	/// Spawn particles uniformly inside a rectangle of the given size.
+	Rect(Vector2f)
+}
+
+/// A source of particles.
+///
+/// The public fields describe the particles that are spawned; each range is
+/// given as `(min, max)` and sampled uniformly for every particle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Emitter {
+	/// Center of the emission area.
+	pub position: Vector2f,
+	/// Shape of the emission area.
+	pub shape: EmitterShape,
+	/// Number of particles spawned per second. Use 0 to only spawn bursts.
+	pub rate: f32,
+	/// Lifetime of the particles.
+	pub lifetime: (Time, Time),
+	/// Initial speed of the particles, in units per second.
+	pub speed: (f32, f32),
+	/// Direction of the initial velocity, in degrees (0 points right, 90
+	/// points down).
+	pub direction: (f32, f32),
+	/// Initial scale factor of the particles.
+	pub scale: (f32, f32),
+	/// Initial rotation of the particles, in degrees.
+	pub rotation: (f32, f32),
+	/// Initial rotation speed of the particles, in degrees per second.
+	pub angular_velocity: (f32, f32),
+	/// Initial color of the particles.
+	pub color: Color,
+	accumulator: f32
+}
+
+impl Emitter {
+	/// Create an emitter at a position, spawning 10 white particles per
+	/// second in all directions at 50 units per second, living 1 second.
+	pub fn new(position: Vector2f) -> Emitter {
+		Emitter {
+			position: position,
+			shape: EmitterShape::Point,
+			rate: 10.,
+			lifetime: (Time::with_seconds(1.), Time::with_seconds(1.)),
+			speed: (50., 50.),
+			direction: (0., 360.),
+			scale: (1., 1.),
+			rotation: (0., 0.),
+			angular_velocity: (0., 0.),
+			color: Color::white(),
+			accumulator: 0.
+		}
+	}
+
+	fn spawn(&self, random: &mut Random) -> Particle {
+		let offset = match self.shape {
+			EmitterShape::Point => Vector2f::new(0., 0.),
+			EmitterShape::Circle(radius) => {
+				let distance = radius * random.next_f32().sqrt();
+				let angle = random.range(0., 360.).to_radians();
+				Vector2f::new(angle.cos() * distance, angle.sin() * distance)
+			}
+			EmitterShape::Rect(size) => {
+				Vector2f::new(random.range(-size.x / 2., size.x / 2.),
+				              random.range(-size.y / 2., size.y / 2.))
+			}
+		};
+		let lifetime = random.range(self.lifetime.0.as_seconds(), self.lifetime.1.as_seconds());
+		let speed = random.range(self.speed.0, self.speed.1);
+		let direction = random.range(self.direction.0, self.direction.1).to_radians();
+		let scale = random.range(self.scale.0, self.scale.1);
+		Particle {
+			position: self.position + offset,
+			velocity: Vector2f::new(direction.cos() * speed, direction.sin() * speed),
+			rotation: random.range(self.rotation.0, self.rotation.1),
+			angular_velocity: random.range(self.angular_velocity.0, self.angular_velocity.1),
+			scale: scale,
+			base_scale: scale,
+			color: self.color,
+			age: Time::with_microseconds(0),
+			lifetime: Time::with_seconds(lifetime)
+		}
+	}
+}
+
+/// Modifies living particles every update.
+///
+/// Affectors run before the particles are moved by their velocity.
+pub trait Affector {
+	/// Apply the affector to one particle, for a time step of `delta`.
+	fn affect(&self, particle: &mut Particle, delta: Time);
+}
+
+/// Accelerates particles in a constant direction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gravity {
+	/// Acceleration, in units per second squared.
+	pub acceleration: Vector2f
+}
+
+impl Affector for Gravity {
+	fn affect(&self, particle: &mut Particle, delta: Time) {
+		particle.velocity = particle.velocity + self.acceleration * delta.as_seconds();
+	}
+}
+
+/// Slows particles down proportionally to their speed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Drag {
+	/// Fraction of the velocity lost per second.
+	pub coefficient: f32
+}
+
+impl Affector for Drag {
+	fn affect(&self, particle: &mut Particle, delta: Time) {
+		let factor = (1. - self.coefficient * delta.as_seconds()).max(0.);
+		particle.velocity = particle.velocity * factor;
+	}
+}
+
+/// Sets the color of particles from a curve over their lifetime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorOverLifetime {
+	/// Color as a function of the life ratio.
+	pub curve: Curve<Color>
+}
+
+impl Affector for ColorOverLifetime {
+	fn affect(&self, particle: &mut Particle, _: Time) {
+		particle.color = self.curve.sample(particle.get_life_ratio());
+	}
+}
+
+/// Scales particles from a curve over their lifetime.
+///
+/// The curve value multiplies the scale each particle was emitted with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScaleOverLifetime {
+	/// Scale multiplier as a function of the life ratio.
+	pub curve: Curve<f32>
+}
+
+impl Affector for ScaleOverLifetime {
+	fn affect(&self, particle: &mut Particle, _: Time) {
+		particle.scale = particle.base_scale * self.curve.sample(particle.get_life_ratio());
+	}
+}
+
+/// Changes the rotation speed of particles at a constant rate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rotation {
+	/// Angular acceleration, in degrees per second squared.
+	pub acceleration: f32
+}
+
+impl Affector for Rotation {
+	fn affect(&self, particle: &mut Particle, delta: Time) {
+		particle.angular_velocity += self.acceleration * delta.as_seconds();
+	}
+}
+
+/// A pool of particles, with the emitters and affectors that drive them.
+///
+/// See the module documentation for an overview.
+pub struct ParticleSystem<'s> {
+	particles: Vec<Particle>,
+	alive: usize,
+	max_particles: usize,
+	emitters: Vec<Emitter>,
+	affectors: Vec<Box<Affector>>,
+	random: Random,
+	vertices: VertexArray,
+	texture: Option<&'s Texture>,
+	texture_rect: IntRect,
+	particle_size: Vector2f,
+	blend_mode: BlendMode
+}
+
+impl<'s> ParticleSystem<'s> {
+	/// Create an empty particle system that holds at most `max_particles`
+	/// particles, using `seed` for all random values.
+	pub fn new(seed: u64, max_particles: usize) -> ParticleSystem<'s> {
+		ParticleSystem {
+			particles: Vec::with_capacity(max_particles),
+			alive: 0,
+			max_particles: max_particles,
+			emitters: Vec::new(),
+			affectors: Vec::new(),
+			random: Random::new(seed),
+			vertices: VertexArray::with_capacity(PrimitiveType::Quads, max_particles * 4),
+			texture: None,
+			texture_rect: IntRect::new(0, 0, 0, 0),
+			particle_size: Vector2f::new(4., 4.),
+			blend_mode: BlendMode::alpha()
+		}
+	}
+
+	/// Set the texture of the particles and the part of it each particle
+	/// displays.
+	pub fn set_texture(&mut self, texture: &'s Texture, rect: IntRect) {
+		self.texture = Some(texture);
+		self.texture_rect = rect;
+	}
+
+	/// Draw particles as plain colored quads.
+	pub fn disable_texture(&mut self) {
+		self.texture = None;
+		self.texture_rect = IntRect::new(0, 0, 0, 0);
+	}
+
+	/// Get the texture of the particles, if any.
+	pub fn get_texture(&self) -> Option<&'s Texture> {
+		self.texture
+	}
+
+	/// Set the blend mode used to draw the particles. The default is
+	/// `BlendMode::alpha()`.
+	pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+		self.blend_mode = blend_mode;
+	}
+
+	/// Get the blend mode used to draw the particles.
+	pub fn get_blend_mode(&self) -> BlendMode {
+		self.blend_mode
+	}
+
+	/// Set the size of a particle quad at scale 1. The default is (4, 4).
+	pub fn set_particle_size(&mut self, size: Vector2f) {
+		self.particle_size = size;
+	}
+
+	/// Get the size of a particle quad at scale 1.
+	pub fn get_particle_size(&self) -> Vector2f {
+		self.particle_size
+	}
+
+	/// Add an emitter, returning its index.
+	pub fn add_emitter(&mut self, emitter: Emitter) -> usize {
+		self.emitters.push(emitter);
+		self.emitters.len() - 1
+	}
+
+	/// Get an emitter by index.
+	pub fn get_emitter(&self, index: usize) -> Option<&Emitter> {
+		self.emitters.get(index)
+	}
+
+	/// Mutably access an emitter by index, for example to move it.
+	pub fn get_emitter_mut(&mut self, index: usize) -> Option<&mut Emitter> {
+		self.emitters.get_mut(index)
+	}
+
+	/// Remove every emitter. Living particles are kept.
+	pub fn clear_emitters(&mut self) {
+		self.emitters.clear();
+	}
+
+	/// Add an affector. Affectors are applied in the order they were added.
+	pub fn add_affector<A: Affector + 'static>(&mut self, affector: A) {
+		self.affectors.push(Box::new(affector));
+	}
+
+	/// Remove every affector.
+	pub fn clear_affectors(&mut self) {
+		self.affectors.clear();
+	}
+
+	/// Spawn `count` particles at once from an emitter, regardless of its
+	/// rate.
+	pub fn burst(&mut self, emitter: usize, count: u32) {
+		if emitter < self.emitters.len() {
+			for _ in 0..count {
+				let particle = self.emitters[emitter].spawn(&mut self.random);
+				self.push(particle);
+			}
+			self.rebuild();
+		}
+	}
+
+	/// Get the living particles.
+	pub fn particles(&self) -> &[Particle] {
+		&self.particles[..self.alive]
+	}
+
+	/// Get the number of living particles.
+	pub fn get_particle_count(&self) -> usize {
+		self.alive
+	}
+
+	/// Kill every particle.
+	pub fn clear(&mut self) {
+		self.alive = 0;
+		self.vertices.clear();
+	}
+
+	fn push(&mut self, particle: Particle) {
+		if self.alive == self.max_particles {
+			return
+		}
+		if self.alive < self.particles.len() {
+			self.particles[self.alive] = particle;
+		} else {
+			self.particles.push(particle);
+		}
+		self.alive += 1;
+	}
+
+	/// Advance the simulation by `delta`.
+	///
+	/// Existing particles are aged, affected and moved, then the emitters
+	/// spawn new particles, and finally the vertex array is rebuilt.
+	pub fn update(&mut self, delta: Time) {
+		let seconds = delta.as_seconds();
+		let mut i = 0;
+		while i < self.alive {
+			let dead = {
+				let particle = &mut self.particles[i];
+				particle.age = particle.age + delta;
+				if particle.age >= particle.lifetime {
+					true
+				} else {
+					for affector in &self.affectors {
+						affector.affect(particle, delta);
+					}
+					particle.position = particle.position + particle.velocity * seconds;
+					particle.rotation += particle.angular_velocity * seconds;
+					false
+				}
+			};
+			if dead {
+				// Dead particles are swapped past the end of the living range,
+				// where their slot is reused by the next emission.
+				self.alive -= 1;
+				self.particles.swap(i, self.alive);
+			} else {
+				i += 1;
+			}
+		}
+
+		for index in 0..self.emitters.len() {
+			let count = {
+				let emitter = &mut self.emitters[index];
+				emitter.accumulator += emitter.rate * seconds;
+				let count = emitter.accumulator.floor();
+				emitter.accumulator -= count;
+				count as u32
+			};
+			for _ in 0..count {
+				let particle = self.emitters[index].spawn(&mut self.random);
+				self.push(particle);
+			}
+		}
+
+		self.rebuild();
+	}
+
+	fn rebuild(&mut self) {
+		let rect = self.texture_rect;
+		let tex_coords = [Vector2f::new(rect.left as f32, rect.top as f32),
+		                  Vector2f::new((rect.left + rect.width) as f32, rect.top as f32),
+		                  Vector2f::new((rect.left + rect.width) as f32, (rect.top + rect.height) as f32),
+		                  Vector2f::new(rect.left as f32, (rect.top + rect.height) as f32)];
+		let corners = [Vector2f::new(-0.5, -0.5), Vector2f::new(0.5, -0.5),
+		               Vector2f::new(0.5, 0.5), Vector2f::new(-0.5, 0.5)];
+
+		self.vertices.clear();
+		for particle in &self.particles[..self.alive] {
+			let size = self.particle_size * particle.scale;
+			let angle = particle.rotation.to_radians();
+			let (sin, cos) = (angle.sin(), angle.cos());
+			for i in 0..4 {
+				let corner = corners[i] * size;
+				let rotated = Vector2f::new(corner.x * cos - corner.y * sin, corner.x * sin + corner.y * cos);
+				self.vertices.push(Vertex::new(particle.position + rotated, particle.color, tex_coords[i]));
+			}
+		}
+	}
+
+	/// Get the vertices of the particle quads, as of the last update.
+	pub fn get_vertices(&self) -> &VertexArray {
+		&self.vertices
+	}
+}
+
+impl<'s> Drawable for ParticleSystem<'s> {
+	fn draw(&self, target: &mut RenderTarget, states: &RenderStates) {
+		self.vertices.draw(target, &RenderStates {
+			blend_mode: self.blend_mode,
+			texture: self.texture,
+			.. *states
+		});
+	}
+}
+
+#[test]
+fn particle_emission_is_deterministic() {
+	let make = || {
+		let mut system = ParticleSystem::new(7, 100);
+		let mut emitter = Emitter::new(Vector2f::new(10., 10.));
+		emitter.rate = 20.;
+		emitter.shape = EmitterShape::Circle(5.);
+		emitter.speed = (10., 30.);
+		system.add_emitter(emitter);
+		system.add_affector(Drag { coefficient: 0.5 });
+		system
+	};
+	let (mut a, mut b) = (make(), make());
+	a.update(Time::with_seconds(0.5));
+	assert_eq!(a.get_particle_count(), 10);
+	assert_eq!(a.get_vertices().len(), 40);
+	for _ in 0..3 {
+		a.update(Time::with_seconds(0.25));
+	}
+	b.update(Time::with_seconds(0.5));
+	for _ in 0..3 {
+		b.update(Time::with_seconds(0.25));
+	}
+	assert_eq!(a.particles(), b.particles());
+	assert!(a.particles().iter().all(|p| (p.position - Vector2f::new(10., 10.)).x.abs() < 40.));
+}
+
+#[test]
+fn particle_pool_and_lifetime() {
+	let mut system = ParticleSystem::new(1, 8);
+	let mut emitter = Emitter::new(Vector2f::new(0., 0.));
+	emitter.rate = 0.;
+	emitter.speed = (0., 0.);
+	let index = system.add_emitter(emitter);
+	system.add_affector(Gravity { acceleration: Vector2f::new(0., 10.) });
+	system.add_affector(ColorOverLifetime { curve: Curve::new(Color::white(), Color::transparent()) });
+
+	system.burst(index, 20);
+	assert_eq!(system.get_particle_count(), 8);
+	for _ in 0..5 {
+		system.update(Time::with_seconds(0.1));
+	}
+	let particle = &system.particles()[0];
+	assert!((particle.velocity.y - 5.).abs() < 1e-4);
+	assert_eq!(particle.color, Color::new_rgba(128, 128, 128, 128));
+
+	system.update(Time::with_seconds(0.6));
+	assert_eq!(system.get_particle_count(), 0);
+	system.burst(index, 4);
+	assert_eq!(system.get_particle_count(), 4);
+	assert_eq!(system.particles.len(), 8);
+}
+
+#[test]
+fn curve_sampling() {
+	let curve = Curve::from_keys(vec![(1., 0.), (0., 2.), (0.5, 4.)]);
+	assert_eq!(curve.sample(-1.), 2.);
+	assert_eq!(curve.sample(0.25), 3.);
+	assert_eq!(curve.sample(0.75), 2.);
+	assert_eq!(curve.sample(2.), 0.);
+}
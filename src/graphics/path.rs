@@ -0,0 +1,889 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Vector paths: lines, curves and arcs tessellated into fills and strokes.
+//!
+//! A `Path` is built from drawing commands in the style of a 2D canvas or an
+//! SVG `<path>` element. Curves are flattened into polylines as they are
+//! added, within the path's tolerance. The path can then be turned into a
+//! filled `Mesh` with `fill`, or into a stroked outline `VertexArray` with
+//! `stroke`; both are drawables which can be kept and drawn every frame.
+//!
+//! ```ignore
+//! let mut path = Path::new();
+//! path.move_to(Vector2f::new(10., 80.))
+//!     .cubic_to(Vector2f::new(40., 10.), Vector2f::new(65., 10.), Vector2f::new(95., 80.))
+//!     .line_to(Vector2f::new(180., 80.));
+//! let mut style = StrokeStyle::new(4.);
+//! style.join = LineJoin::Round;
+//! style.dash = vec![12., 6.];
+//! let curve = path.stroke(&style, Color::white());
+//!
+//! let heart = Path::from_svg("M 10,30 A 20,20 0,0,1 50,30 A 20,20 0,0,1 90,30 \
+//!                             Q 90,60 50,90 Q 10,60 10,30 z").unwrap();
+//! let filled = heart.fill(Color::red());
+//! ```
+
+use std::error::Error;
+use std::f32::consts::PI;
+use std::fmt;
+
+use graphics::{Color, FloatRect, Mesh, PrimitiveType, Vertex, VertexArray, triangulate};
+use graphics::lighting::polygon_contains;
+use system::Vector2f;
+
+/// Shape drawn where two segments of a stroke meet.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum LineJoin {
+	/// Extend the outer edges until they meet, up to the miter limit.
+	Miter,
+	/// Round off the corner.
+	Round,
+	/// Cut the corner off.
+	Bevel
+}
+
+/// Shape drawn at the ends of an open stroke.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum LineCap {
+	/// Stop exactly at the end point.
+	Butt,
+	/// Add a half circle around the end point.
+	Round,
+	/// Extend the stroke by half its width past the end point.
+	Square
+}
+
+/// How the outline of a path is stroked.
+#[derive(Clone, PartialEq, Debug)]
+pub struct StrokeStyle {
+	/// Width of the stroke, centered on the path.
+	pub width: f32,
+	/// Shape of the corners.
+	pub join: LineJoin,
+	/// Shape of the ends of open subpaths and dashes.
+	pub cap: LineCap,
+	/// Ratio of miter length to stroke width above which miter joins are
+	/// beveled instead.
+	pub miter_limit: f32,
+	/// Alternating lengths of dashes and gaps. Empty for a solid stroke. A
+	/// list with an odd number of lengths is repeated to make it even. The
+	/// stroke is solid if a length is negative or not finite, or if they are
+	/// all 0.
+	pub dash: Vec<f32>,
+	/// Distance into the dash pattern at which the stroke starts.
+	pub dash_offset: f32
+}
+
+impl StrokeStyle {
+	/// Create a solid style of the given width, with miter joins and butt
+	/// caps.
+	pub fn new(width: f32) -> StrokeStyle {
+		StrokeStyle {
+			width: width,
+			join: LineJoin::Miter,
+			cap: LineCap::Butt,
+			miter_limit: 4.,
+			dash: Vec::new(),
+			dash_offset: 0.
+		}
+	}
+}
+
+/// A flattened subpath.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Polyline {
+	/// The points of the subpath, in order.
+	pub points: Vec<Vector2f>,
+	/// Whether the last point connects back to the first.
+	pub closed: bool
+}
+
+/// Error returned when parsing SVG path data fails.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PathError {
+	/// A character that is not valid at this point, with its byte offset.
+	UnexpectedCharacter(usize, char),
+	/// A command is missing a number, at the given byte offset.
+	MissingNumber(usize),
+	/// The path data does not start with a move command.
+	MissingMoveTo
+}
+
+impl fmt::Display for PathError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			PathError::UnexpectedCharacter(position, character) =>
+				write!(f, "unexpected character {:?} at offset {}", character, position),
+			PathError::MissingNumber(position) =>
+				write!(f, "expected a number at offset {}", position),
+			PathError::MissingMoveTo =>
+				write!(f, "path data must start with a move command")
+		}
+	}
+}
+
+impl Error for PathError {
+	fn description(&self) -> &str {
+		match *self {
+			PathError::UnexpectedCharacter(..) => "unexpected character in path data",
+			PathError::MissingNumber(_) => "missing number in path data",
+			PathError::MissingMoveTo => "path data does not start with a move command"
+		}
+	}
+}
+
+/// A sequence of subpaths made of lines, curves and arcs.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Path {
+	polylines: Vec<Polyline>,
+	tolerance: f32
+}
+
+fn length(v: Vector2f) -> f32 {
+	(v.x * v.x + v.y * v.y).sqrt()
+}
+
+fn unit(v: Vector2f) -> Vector2f {
+	let l = length(v);
+	if l > 0. { Vector2f::new(v.x / l, v.y / l) } else { Vector2f::new(0., 0.) }
+}
+
+fn perp(v: Vector2f) -> Vector2f {
+	Vector2f::new(-v.y, v.x)
+}
+
+fn cross(a: Vector2f, b: Vector2f) -> f32 {
+	a.x * b.y - a.y * b.x
+}
+
+fn dot(a: Vector2f, b: Vector2f) -> f32 {
+	a.x * b.x + a.y * b.y
+}
+
+// Number of segments needed to keep a curve within `tolerance`, given the
+// largest second difference of its control points (Wang's formula).
+fn curve_segments(degree: f32, difference: f32, tolerance: f32) -> u32 {
+	let n = (degree * (degree - 1.) / 8. * difference / tolerance).sqrt().ceil();
+	(n as u32).max(1).min(1000)
+}
+
+fn arc_segments(radius: f32, sweep: f32, tolerance: f32) -> u32 {
+	let step = if tolerance < radius { 2. * (1. - tolerance / radius).acos() } else { PI / 2. };
+	((sweep.abs() / step).ceil() as u32).max(1).min(1000)
+}
+
+impl Path {
+	/// Create an empty path with a tolerance of a quarter pixel.
+	pub fn new() -> Path {
+		Path {
+			polylines: Vec::new(),
+			tolerance: 0.25
+		}
+	}
+
+	/// Set the maximum distance between curves and their flattened
+	/// polylines. It applies to the curves added afterwards.
+	pub fn set_tolerance(&mut self, tolerance: f32) {
+		self.tolerance = tolerance.max(0.001);
+	}
+
+	/// Get the maximum distance between curves and their flattened polylines.
+	pub fn get_tolerance(&self) -> f32 {
+		self.tolerance
+	}
+
+	/// Get the flattened subpaths.
+	pub fn get_polylines(&self) -> &[Polyline] {
+		&self.polylines
+	}
+
+	/// Tell whether the path has no subpaths.
+	pub fn is_empty(&self) -> bool {
+		self.polylines.is_empty()
+	}
+
+	/// Remove all subpaths.
+	pub fn clear(&mut self) {
+		self.polylines.clear();
+	}
+
+	/// Get the current point: the end of the last command, or the start of
+	/// the last subpath if it was closed.
+	pub fn get_current_point(&self) -> Vector2f {
+		match self.polylines.last() {
+			Some(&Polyline { ref points, closed: true }) => points[0],
+			Some(&Polyline { ref points, closed: false }) => *points.last().unwrap(),
+			None => Vector2f::new(0., 0.)
+		}
+	}
+
+	fn push(&mut self, point: Vector2f) {
+		let start = self.get_current_point();
+		match self.polylines.last_mut() {
+			Some(polyline) if !polyline.closed => {
+				polyline.points.push(point);
+				return
+			}
+			_ => {}
+		}
+		self.polylines.push(Polyline { points: vec![start, point], closed: false });
+	}
+
+	/// Start a new subpath at `point`.
+	pub fn move_to(&mut self, point: Vector2f) -> &mut Path {
+		self.polylines.push(Polyline { points: vec![point], closed: false });
+		self
+	}
+
+	/// Add a straight line to `point`.
+	pub fn line_to(&mut self, point: Vector2f) -> &mut Path {
+		self.push(point);
+		self
+	}
+
+	/// Add a quadratic bézier curve to `point`.
+	pub fn quad_to(&mut self, control: Vector2f, point: Vector2f) -> &mut Path {
+		let start = self.get_current_point();
+		let n = curve_segments(2., length(start - control * 2. + point), self.tolerance);
+		for i in 1..n + 1 {
+			let t = i as f32 / n as f32;
+			let u = 1. - t;
+			self.push(start * (u * u) + control * (2. * u * t) + point * (t * t));
+		}
+		self
+	}
+
+	/// Add a cubic bézier curve to `point`.
+	pub fn cubic_to(&mut self, control1: Vector2f, control2: Vector2f, point: Vector2f) -> &mut Path {
+		let start = self.get_current_point();
+		let difference = length(start - control1 * 2. + control2).max(length(control1 - control2 * 2. + point));
+		let n = curve_segments(3., difference, self.tolerance);
+		for i in 1..n + 1 {
+			let t = i as f32 / n as f32;
+			let u = 1. - t;
+			self.push(start * (u * u * u) + control1 * (3. * u * u * t) +
+			          control2 * (3. * u * t * t) + point * (t * t * t));
+		}
+		self
+	}
+
+	/// Add a circular arc.
+	///
+	/// Angles are in degrees; positive sweeps go clockwise on screen, like
+	/// rotations. The arc is joined to the current subpath by a straight
+	/// line, or starts a new subpath if there is none.
+	pub fn arc(&mut self, center: Vector2f, radius: f32, start_angle: f32, sweep_angle: f32) -> &mut Path {
+		let start = start_angle.to_radians();
+		let first = center + Vector2f::new(start.cos(), start.sin()) * radius;
+		match self.polylines.last() {
+			Some(polyline) if !polyline.closed => { self.push(first); }
+			_ => { self.move_to(first); }
+		}
+		self.ellipse(center, Vector2f::new(radius, radius), 0., start, sweep_angle.to_radians());
+		self
+	}
+
+	/// Add an elliptical arc to `point`, as described by SVG's `A` command.
+	///
+	/// `radii` are scaled up if they are too small to reach `point`,
+	/// `rotation` is the rotation of the ellipse in degrees, and the flags
+	/// choose which of the four possible arcs is drawn.
+	pub fn arc_to(&mut self, radii: Vector2f, rotation: f32, large_arc: bool, sweep: bool, point: Vector2f) -> &mut Path {
+		let start = self.get_current_point();
+		let (mut rx, mut ry) = (radii.x.abs(), radii.y.abs());
+		if start == point {
+			return self
+		}
+		if rx == 0. || ry == 0. {
+			return self.line_to(point)
+		}
+		// Endpoint to center parameterization, from the SVG specification
+		let phi = rotation.to_radians();
+		let (sin, cos) = phi.sin_cos();
+		let half = (start - point) / 2.;
+		let x1 = cos * half.x + sin * half.y;
+		let y1 = -sin * half.x + cos * half.y;
+		let lambda = x1 * x1 / (rx * rx) + y1 * y1 / (ry * ry);
+		if lambda > 1. {
+			rx *= lambda.sqrt();
+			ry *= lambda.sqrt();
+		}
+		let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+		let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+		let mut coefficient = (numerator / denominator).max(0.).sqrt();
+		if large_arc == sweep {
+			coefficient = -coefficient;
+		}
+		let (cx, cy) = (coefficient * rx * y1 / ry, -coefficient * ry * x1 / rx);
+		let center = Vector2f::new(cos * cx - sin * cy + (start.x + point.x) / 2.,
+		                           sin * cx + cos * cy + (start.y + point.y) / 2.);
+		let angle = |u: Vector2f, v: Vector2f| cross(u, v).atan2(dot(u, v));
+		let u = Vector2f::new((x1 - cx) / rx, (y1 - cy) / ry);
+		let v = Vector2f::new((-x1 - cx) / rx, (-y1 - cy) / ry);
+		let theta = angle(Vector2f::new(1., 0.), u);
+		let mut delta = angle(u, v);
+		if !sweep && delta > 0. {
+			delta -= 2. * PI;
+		} else if sweep && delta < 0. {
+			delta += 2. * PI;
+		}
+		self.ellipse(center, Vector2f::new(rx, ry), phi, theta, delta);
+		// Land exactly on the end point despite rounding
+		if let Some(polyline) = self.polylines.last_mut() {
+			*polyline.points.last_mut().unwrap() = point;
+		}
+		self
+	}
+
+	fn ellipse(&mut self, center: Vector2f, radii: Vector2f, rotation: f32, start: f32, sweep: f32) {
+		let (sin, cos) = rotation.sin_cos();
+		let n = arc_segments(radii.x.max(radii.y), sweep, self.tolerance);
+		for i in 1..n + 1 {
+			let (s, c) = (start + sweep * i as f32 / n as f32).sin_cos();
+			let (x, y) = (radii.x * c, radii.y * s);
+			self.push(Vector2f::new(center.x + x * cos - y * sin, center.y + x * sin + y * cos));
+		}
+	}
+
+	/// Close the current subpath with a line back to its start.
+	pub fn close(&mut self) -> &mut Path {
+		if let Some(polyline) = self.polylines.last_mut() {
+			polyline.closed = true;
+		}
+		self
+	}
+
+	/// Add a closed rectangle as a new subpath.
+	pub fn rectangle(&mut self, rect: FloatRect) -> &mut Path {
+		self.move_to(Vector2f::new(rect.left, rect.top))
+		    .line_to(Vector2f::new(rect.left + rect.width, rect.top))
+		    .line_to(Vector2f::new(rect.left + rect.width, rect.top + rect.height))
+		    .line_to(Vector2f::new(rect.left, rect.top + rect.height))
+		    .close()
+	}
+
+	/// Add a closed circle as a new subpath.
+	pub fn circle(&mut self, center: Vector2f, radius: f32) -> &mut Path {
+		self.move_to(center + Vector2f::new(radius, 0.));
+		self.ellipse(center, Vector2f::new(radius, radius), 0., 0., 2. * PI);
+		self.polylines.last_mut().unwrap().points.pop();
+		self.close()
+	}
+
+	// Subpaths with repeated points removed, and without a duplicate closing
+	// point.
+	fn cleaned(&self) -> Vec<Polyline> {
+		self.polylines.iter().map(|polyline| {
+			let mut points: Vec<Vector2f> = Vec::with_capacity(polyline.points.len());
+			for &point in &polyline.points {
+				if points.last() != Some(&point) {
+					points.push(point);
+				}
+			}
+			if polyline.closed && points.len() > 1 && points[0] == points[points.len() - 1] {
+				points.pop();
+			}
+			Polyline { points: points, closed: polyline.closed }
+		}).collect()
+	}
+
+	/// Tessellate the inside of the path into a mesh of the given color.
+	///
+	/// Every subpath is implicitly closed, and the inside is decided by the
+	/// even-odd rule: a subpath inside another one cuts a hole in it. The
+	/// subpaths must not intersect themselves or each other.
+	pub fn fill<'s>(&self, color: Color) -> Mesh<'s> {
+		let rings: Vec<Vec<Vector2f>> = self.cleaned().into_iter()
+			.map(|polyline| polyline.points)
+			.filter(|points| points.len() >= 3)
+			.collect();
+		let depths: Vec<usize> = rings.iter().enumerate().map(|(i, ring)| {
+			rings.iter().enumerate().filter(|&(j, other)| j != i && polygon_contains(other, ring[0])).count()
+		}).collect();
+
+		let mut mesh = Mesh::new(PrimitiveType::Triangles);
+		for (i, ring) in rings.iter().enumerate().filter(|&(i, _)| depths[i] % 2 == 0) {
+			let holes: Vec<Vec<Vector2f>> = rings.iter().enumerate()
+				.filter(|&(j, hole)| depths[j] == depths[i] + 1 && polygon_contains(ring, hole[0]))
+				.map(|(_, hole)| hole.clone())
+				.collect();
+			let base = mesh.get_vertices().len() as u32;
+			for &point in ring.iter().chain(holes.iter().flat_map(|hole| hole.iter())) {
+				mesh.add_vertex(Vertex::new_with_pos_color(point, color));
+			}
+			for triangle in triangulate(ring, &holes).chunks(3) {
+				mesh.add_triangle(base + triangle[0], base + triangle[1], base + triangle[2]);
+			}
+		}
+		mesh
+	}
+
+	/// Tessellate the outline of the path into triangles of the given color.
+	pub fn stroke(&self, style: &StrokeStyle, color: Color) -> VertexArray {
+		let mut array = VertexArray::new(PrimitiveType::Triangles);
+		if style.width <= 0. {
+			return array
+		}
+		let mut pattern = style.dash.clone();
+		if pattern.len() % 2 == 1 {
+			pattern.extend_from_slice(&style.dash);
+		}
+		let dashed = pattern.iter().all(|&length| length >= 0. && length.is_finite()) &&
+			pattern.iter().fold(0., |a, &b| a + b) > 0.;
+		let mut stroker = Stroker {
+			array: &mut array,
+			style: style,
+			color: color,
+			tolerance: self.tolerance
+		};
+		for polyline in self.cleaned() {
+			if dashed {
+				for dash in split_dashes(&polyline, &pattern, style.dash_offset) {
+					stroker.polyline(&dash, false);
+				}
+			} else {
+				stroker.polyline(&polyline.points, polyline.closed);
+			}
+		}
+		array
+	}
+
+	/// Parse SVG path data, as found in the `d` attribute of a `<path>`.
+	///
+	/// All commands are supported, in both absolute and relative forms.
+	pub fn from_svg(data: &str) -> Result<Path, PathError> {
+		let mut parser = SvgParser { data: data.as_bytes(), position: 0 };
+		let mut path = Path::new();
+		let mut command = None;
+		// Control point of the previous curve, for the smooth curve commands
+		let (mut last_cubic, mut last_quad) = (None, None);
+
+		loop {
+			parser.skip_separators();
+			let c = match parser.peek() {
+				Some(c) => c,
+				None => break
+			};
+			if c.is_ascii_alphabetic() {
+				parser.position += 1;
+				if command.is_none() && c != b'M' && c != b'm' {
+					return Err(PathError::MissingMoveTo)
+				}
+				command = Some(c);
+			} else if command.is_none() {
+				return Err(PathError::MissingMoveTo)
+			} else if !parser.at_number() || command == Some(b'Z') || command == Some(b'z') {
+				return Err(PathError::UnexpectedCharacter(parser.position, parser.current_char()))
+			}
+			let c = command.unwrap();
+			let relative = c.is_ascii_lowercase();
+			let current = path.get_current_point();
+			let offset = if relative { current } else { Vector2f::new(0., 0.) };
+			let (cubic, quad) = (last_cubic.take(), last_quad.take());
+
+			match c.to_ascii_uppercase() {
+				b'M' => {
+					let point = try!(parser.point()) + offset;
+					path.move_to(point);
+					// Further coordinate pairs are implicit line commands
+					command = Some(if relative { b'l' } else { b'L' });
+				}
+				b'L' => {
+					let point = try!(parser.point()) + offset;
+					path.line_to(point);
+				}
+				b'H' => {
+					let x = try!(parser.number()) + offset.x;
+					path.line_to(Vector2f::new(x, current.y));
+				}
+				b'V' => {
+					let y = try!(parser.number()) + offset.y;
+					path.line_to(Vector2f::new(current.x, y));
+				}
+				b'C' | b'S' => {
+					let control1 = if c.to_ascii_uppercase() == b'C' {
+						try!(parser.point()) + offset
+					} else {
+						cubic.map_or(current, |control: Vector2f| current * 2. - control)
+					};
+					let control2 = try!(parser.point()) + offset;
+					let point = try!(parser.point()) + offset;
+					path.cubic_to(control1, control2, point);
+					last_cubic = Some(control2);
+				}
+				b'Q' | b'T' => {
+					let control = if c.to_ascii_uppercase() == b'Q' {
+						try!(parser.point()) + offset
+					} else {
+						quad.map_or(current, |control: Vector2f| current * 2. - control)
+					};
+					let point = try!(parser.point()) + offset;
+					path.quad_to(control, point);
+					last_quad = Some(control);
+				}
+				b'A' => {
+					let radii = try!(parser.point());
+					let rotation = try!(parser.number());
+					let large_arc = try!(parser.flag());
+					let sweep = try!(parser.flag());
+					let point = try!(parser.point()) + offset;
+					path.arc_to(radii, rotation, large_arc, sweep, point);
+				}
+				b'Z' => {
+					path.close();
+				}
+				_ => return Err(PathError::UnexpectedCharacter(parser.position - 1, c as char))
+			}
+		}
+		Ok(path)
+	}
+}
+
+struct SvgParser<'a> {
+	data: &'a [u8],
+	position: usize
+}
+
+impl<'a> SvgParser<'a> {
+	fn peek(&self) -> Option<u8> {
+		self.data.get(self.position).cloned()
+	}
+
+	fn current_char(&self) -> char {
+		self.data.get(self.position).map_or('\0', |&c| c as char)
+	}
+
+	fn skip_separators(&mut self) {
+		while let Some(c) = self.peek() {
+			if c == b',' || c.is_ascii_whitespace() {
+				self.position += 1;
+			} else {
+				break
+			}
+		}
+	}
+
+	fn at_number(&self) -> bool {
+		match self.peek() {
+			Some(c) => c.is_ascii_digit() || c == b'.' || c == b'-' || c == b'+',
+			None => false
+		}
+	}
+
+	fn number(&mut self) -> Result<f32, PathError> {
+		self.skip_separators();
+		let begin = self.position;
+		let digits = |parser: &mut SvgParser| {
+			let from = parser.position;
+			while parser.peek().map_or(false, |c| c.is_ascii_digit()) {
+				parser.position += 1;
+			}
+			parser.position > from
+		};
+		if self.peek() == Some(b'-') || self.peek() == Some(b'+') {
+			self.position += 1;
+		}
+		let mut valid = digits(self);
+		if self.peek() == Some(b'.') {
+			self.position += 1;
+			valid = digits(self) || valid;
+		}
+		if !valid {
+			self.position = begin;
+			return Err(PathError::MissingNumber(begin))
+		}
+		if self.peek() == Some(b'e') || self.peek() == Some(b'E') {
+			let mark = self.position;
+			self.position += 1;
+			if self.peek() == Some(b'-') || self.peek() == Some(b'+') {
+				self.position += 1;
+			}
+			if !digits(self) {
+				self.position = mark;
+			}
+		}
+		let text = ::std::str::from_utf8(&self.data[begin..self.position]).unwrap();
+		text.parse().map_err(|_| PathError::MissingNumber(begin))
+	}
+
+	fn point(&mut self) -> Result<Vector2f, PathError> {
+		let x = try!(self.number());
+		let y = try!(self.number());
+		Ok(Vector2f::new(x, y))
+	}
+
+	// Arc flags may be written without separators, as in "a1 1 0 011 1".
+	fn flag(&mut self) -> Result<bool, PathError> {
+		self.skip_separators();
+		match self.peek() {
+			Some(b'0') => { self.position += 1; Ok(false) }
+			Some(b'1') => { self.position += 1; Ok(true) }
+			_ => Err(PathError::MissingNumber(self.position))
+		}
+	}
+}
+
+// Tell whether stepping `length` along the pattern from `distance` moves at
+// all. Steps too small for the precision of `distance` do not, and are
+// treated as reaching the end instead, so that tiny lengths cannot loop
+// forever.
+fn makes_progress(distance: f32, length: f32) -> bool {
+	length == 0. || distance - length < distance
+}
+
+// Split a polyline along a dash pattern of finite, non-negative lengths with
+// a positive total.
+fn split_dashes(polyline: &Polyline, pattern: &[f32], offset: f32) -> Vec<Vec<Vector2f>> {
+	let mut points = polyline.points.clone();
+	if polyline.closed && !points.is_empty() {
+		let first = points[0];
+		points.push(first);
+	}
+	let total = pattern.iter().fold(0., |a, &b| a + b);
+	let mut offset = offset % total;
+	if offset < 0. {
+		offset += total;
+	}
+	let (mut index, mut remaining) = (0, pattern[0]);
+	while offset > 0. {
+		if offset >= remaining && makes_progress(offset, remaining) {
+			offset -= remaining;
+			index = (index + 1) % pattern.len();
+			remaining = pattern[index];
+		} else {
+			remaining = (remaining - offset).max(0.);
+			offset = 0.;
+		}
+	}
+
+	let mut dashes = Vec::new();
+	let mut current = Vec::new();
+	if index % 2 == 0 && !points.is_empty() {
+		current.push(points[0]);
+	}
+	for i in 1..points.len() {
+		let (mut from, to) = (points[i - 1], points[i]);
+		let direction = unit(to - from);
+		let mut left = length(to - from);
+		while left > 0. {
+			if remaining > left || !makes_progress(left, remaining) {
+				remaining = (remaining - left).max(0.);
+				left = 0.;
+				if index % 2 == 0 {
+					current.push(to);
+				}
+			} else {
+				from = from + direction * remaining;
+				left -= remaining;
+				if index % 2 == 0 {
+					current.push(from);
+					dashes.push(::std::mem::replace(&mut current, Vec::new()));
+				} else {
+					current = vec![from];
+				}
+				index = (index + 1) % pattern.len();
+				remaining = pattern[index];
+			}
+		}
+	}
+	if index % 2 == 0 && current.len() >= 2 {
+		dashes.push(current);
+	}
+	dashes
+}
+
+struct Stroker<'a> {
+	array: &'a mut VertexArray,
+	style: &'a StrokeStyle,
+	color: Color,
+	tolerance: f32
+}
+
+impl<'a> Stroker<'a> {
+	fn triangle(&mut self, a: Vector2f, b: Vector2f, c: Vector2f) {
+		for &position in &[a, b, c] {
+			self.array.push(Vertex::new_with_pos_color(position, self.color));
+		}
+	}
+
+	// Triangles around `center`, sweeping `from` by `sweep` radians.
+	fn fan(&mut self, center: Vector2f, from: Vector2f, sweep: f32) {
+		let n = arc_segments(length(from), sweep, self.tolerance);
+		let mut previous = center + from;
+		for i in 1..n + 1 {
+			let (sin, cos) = (sweep * i as f32 / n as f32).sin_cos();
+			let next = center + Vector2f::new(from.x * cos - from.y * sin, from.x * sin + from.y * cos);
+			self.triangle(center, previous, next);
+			previous = next;
+		}
+	}
+
+	fn polyline(&mut self, points: &[Vector2f], closed: bool) {
+		let mut points: Vec<Vector2f> = points.to_vec();
+		points.dedup();
+		let n = points.len();
+		if n < 2 {
+			return
+		}
+		let half = self.style.width / 2.;
+		if !closed && self.style.cap == LineCap::Square {
+			let (d0, d1) = (unit(points[1] - points[0]), unit(points[n - 1] - points[n - 2]));
+			points[0] = points[0] - d0 * half;
+			points[n - 1] = points[n - 1] + d1 * half;
+		}
+
+		let segments = if closed { n } else { n - 1 };
+		for i in 0..segments {
+			let (a, b) = (points[i], points[(i + 1) % n]);
+			let normal = perp(unit(b - a)) * half;
+			self.triangle(a + normal, b + normal, b - normal);
+			self.triangle(a + normal, b - normal, a - normal);
+		}
+
+		let joins = if closed { 0..n } else { 1..n - 1 };
+		for i in joins {
+			let (previous, point, next) = (points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+			self.join(previous, point, next);
+		}
+
+		if !closed && self.style.cap == LineCap::Round {
+			let start = perp(unit(points[1] - points[0])) * half;
+			let end = perp(unit(points[n - 1] - points[n - 2])) * half;
+			self.fan(points[0], start, PI);
+			self.fan(points[n - 1], end, -PI);
+		}
+	}
+
+	fn join(&mut self, previous: Vector2f, point: Vector2f, next: Vector2f) {
+		let half = self.style.width / 2.;
+		let (d0, d1) = (unit(point - previous), unit(next - point));
+		let turn = cross(d0, d1);
+		if turn.abs() < 1e-6 && dot(d0, d1) > 0. {
+			return
+		}
+		// The gap to fill is on the outer side of the turn
+		let side = if turn > 0. { -half } else { half };
+		let (n0, n1) = (perp(d0) * side, perp(d1) * side);
+		match self.style.join {
+			LineJoin::Round => {
+				let sweep = cross(n0, n1).atan2(dot(n0, n1));
+				self.fan(point, n0, sweep);
+			}
+			LineJoin::Miter => {
+				let direction = unit(n0 + n1);
+				let cos = dot(direction, unit(n0));
+				if cos > 0. && 1. / cos <= self.style.miter_limit {
+					let tip = point + direction * (half / cos);
+					self.triangle(point, point + n0, tip);
+					self.triangle(point, tip, point + n1);
+				} else {
+					self.triangle(point, point + n0, point + n1);
+				}
+			}
+			LineJoin::Bevel => self.triangle(point, point + n0, point + n1)
+		}
+	}
+}
+
+#[cfg(test)]
+fn mesh_area(mesh: &Mesh) -> f32 {
+	let vertices = mesh.to_vertex_array();
+	vertices.chunks(3).map(|t| cross(t[1].position - t[0].position, t[2].position - t[0].position).abs() / 2.).sum()
+}
+
+#[test]
+fn path_svg_commands() {
+	let path = Path::from_svg("M10 10 h 20 v20 H10 z").unwrap();
+	assert_eq!(path.get_polylines(), &[Polyline {
+		points: vec![Vector2f::new(10., 10.), Vector2f::new(30., 10.), Vector2f::new(30., 30.), Vector2f::new(10., 30.)],
+		closed: true
+	}][..]);
+
+	let path = Path::from_svg("M0,0L10-5.5e1.5.5").unwrap();
+	assert_eq!(path.get_polylines()[0].points,
+	           vec![Vector2f::new(0., 0.), Vector2f::new(10., -55.), Vector2f::new(0.5, 0.5)]);
+
+	let path = Path::from_svg("m1 1 2 2 c0 10 10 10 10 0 s10 -10 10 0").unwrap();
+	let points = &path.get_polylines()[0].points;
+	assert_eq!(points[1], Vector2f::new(3., 3.));
+	assert_eq!(*points.last().unwrap(), Vector2f::new(23., 3.));
+
+	let path = Path::from_svg("M0 0 A10 10 0 0 1 20 0").unwrap();
+	let points = &path.get_polylines()[0].points;
+	assert_eq!(*points.last().unwrap(), Vector2f::new(20., 0.));
+	let depth = points.iter().fold(0., |a: f32, p| a.max(p.y.abs()));
+	assert!((depth - 10.).abs() < 0.25);
+
+	assert_eq!(Path::from_svg("L 10 10"), Err(PathError::MissingMoveTo));
+	assert_eq!(Path::from_svg("M 10"), Err(PathError::MissingNumber(4)));
+	assert_eq!(Path::from_svg("M 1 1 X"), Err(PathError::UnexpectedCharacter(6, 'X')));
+	assert_eq!(Path::from_svg("M 1 1 z 2"), Err(PathError::UnexpectedCharacter(8, '2')));
+}
+
+#[test]
+fn path_fill_with_hole() {
+	let path = Path::from_svg("M0 0 H10 V10 H0 Z M2 2 H8 V8 H2 Z").unwrap();
+	assert!((mesh_area(&path.fill(Color::white())) - 64.).abs() < 1e-3);
+
+	let mut circle = Path::new();
+	circle.set_tolerance(0.01);
+	circle.circle(Vector2f::new(0., 0.), 10.);
+	assert!((mesh_area(&circle.fill(Color::white())) - 100. * PI).abs() < 2.);
+}
+
+#[test]
+fn path_stroke() {
+	let mut path = Path::new();
+	path.move_to(Vector2f::new(0., 0.)).line_to(Vector2f::new(10., 0.));
+	let mut style = StrokeStyle::new(2.);
+	let stroke = path.stroke(&style, Color::white());
+	assert_eq!(stroke.len(), 6);
+	assert_eq!(stroke.get_bounds(), FloatRect::new(0., -1., 10., 2.));
+
+	style.cap = LineCap::Square;
+	assert_eq!(path.stroke(&style, Color::white()).get_bounds(), FloatRect::new(-1., -1., 12., 2.));
+
+	style.cap = LineCap::Butt;
+	style.dash = vec![2., 2.];
+	assert_eq!(path.stroke(&style, Color::white()).len(), 3 * 6);
+	style.dash_offset = 1.;
+	assert_eq!(path.stroke(&style, Color::white()).len(), 3 * 6);
+
+	// Lengths too small to move along the path end the current dash
+	let mut long = Path::new();
+	long.move_to(Vector2f::new(0., 0.)).line_to(Vector2f::new(1e8, 0.));
+	style.dash = vec![1e-30, 1e-30];
+	style.dash_offset = 0.;
+	assert_eq!(long.stroke(&style, Color::white()).len(), 6);
+	style.dash = vec![1., -1.];
+	assert_eq!(path.stroke(&style, Color::white()).len(), 6);
+	style.dash = vec![1., ::std::f32::INFINITY];
+	assert_eq!(path.stroke(&style, Color::white()).len(), 6);
+
+	let mut corner = Path::new();
+	corner.move_to(Vector2f::new(0., 0.)).line_to(Vector2f::new(10., 0.)).line_to(Vector2f::new(10., 10.));
+	let style = StrokeStyle::new(2.);
+	assert_eq!(corner.stroke(&style, Color::white()).get_bounds(), FloatRect::new(0., -1., 11., 11.));
+}
@@ -0,0 +1,505 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use std::cmp::Ordering;
+
+use graphics::{BasicTransformable, Color, Drawable, FloatRect, IntRect, PrimitiveType, RenderStates,
+               RenderTarget, Shape, Texture, Transform, Transformable, Vertex, VertexArray};
+use system::Vector2f;
+
+fn cross(o: Vector2f, a: Vector2f, b: Vector2f) -> f32 {
+	(a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+fn signed_area(points: &[Vector2f]) -> f32 {
+	let mut area = 0.;
+	for i in 0..points.len() {
+		let (a, b) = (points[i], points[(i + 1) % points.len()]);
+		area += a.x * b.y - b.x * a.y;
+	}
+	area / 2.
+}
+
+// Whether the segments ab and cd cross at a point inside both of them.
+fn segments_cross(a: Vector2f, b: Vector2f, c: Vector2f, d: Vector2f) -> bool {
+	let (d1, d2) = (cross(a, b, c), cross(a, b, d));
+	let (d3, d4) = (cross(c, d, a), cross(c, d, b));
+	((d1 > 0. && d2 < 0.) || (d1 < 0. && d2 > 0.)) && ((d3 > 0. && d4 < 0.) || (d3 < 0. && d4 > 0.))
+}
+
+fn contour_crossed(points: &[Vector2f], contour: &[usize], a: Vector2f, b: Vector2f) -> bool {
+	(0..contour.len()).any(|i| {
+		let (c, d) = (points[contour[i]], points[contour[(i + 1) % contour.len()]]);
+		segments_cross(a, b, c, d)
+	})
+}
+
+// Whether `p` lies inside the interior angle of a counter-clockwise contour
+// at vertex `i`.
+fn in_wedge(points: &[Vector2f], contour: &[usize], i: usize, p: Vector2f) -> bool {
+	let n = contour.len();
+	let (prev, v, next) = (points[contour[(i + n - 1) % n]], points[contour[i]], points[contour[(i + 1) % n]]);
+	if cross(prev, v, next) >= 0. {
+		cross(prev, v, p) > 0. && cross(v, next, p) > 0.
+	} else {
+		cross(prev, v, p) > 0. || cross(v, next, p) > 0.
+	}
+}
+
+fn is_ear(points: &[Vector2f], ring: &[usize], i: usize) -> bool {
+	let n = ring.len();
+	let (a, b, c) = (points[ring[(i + n - 1) % n]], points[ring[i]], points[ring[(i + 1) % n]]);
+	let turn = cross(a, b, c);
+	if turn == 0. {
+		// Collinear vertices are clipped without producing a triangle
+		return true
+	}
+	turn > 0. && !ring.iter().any(|&j| {
+		let p = points[j];
+		p != a && p != b && p != c && cross(a, b, p) >= 0. && cross(b, c, p) >= 0. && cross(c, a, p) >= 0.
+	})
+}
+
+/// Split a simple polygon, optionally with holes, into triangles.
+///
+/// The outline and holes may be given in either winding order, but must not
+/// intersect themselves or each other, and the holes must lie inside the
+/// outline. The result holds three indices per triangle, referring to the
+/// points of the outline followed by the points of each hole in turn.
+///
+/// Holes are joined to the outline by bridge edges and the resulting polygon
+/// is cut by ear clipping. Holes that can not be joined are ignored.
+pub fn triangulate(outline: &[Vector2f], holes: &[Vec<Vector2f>]) -> Vec<u32> {
+	if outline.len() < 3 {
+		return Vec::new()
+	}
+	let mut points = outline.to_vec();
+	let mut contour: Vec<usize> = (0..outline.len()).collect();
+	if signed_area(outline) < 0. {
+		contour.reverse();
+	}
+
+	// Holes are wound the opposite way and bridged rightmost first
+	let mut hole_contours = Vec::new();
+	for hole in holes {
+		let mut indices: Vec<usize> = (points.len()..points.len() + hole.len()).collect();
+		points.extend_from_slice(hole);
+		if hole.len() < 3 {
+			continue
+		}
+		if signed_area(hole) > 0. {
+			indices.reverse();
+		}
+		hole_contours.push(indices);
+	}
+	let max_x = |contour: &Vec<usize>| contour.iter().map(|&i| points[i].x).fold(::std::f32::MIN, f32::max);
+	hole_contours.sort_by(|a, b| max_x(b).partial_cmp(&max_x(a)).unwrap_or(Ordering::Equal));
+
+	for h in 0..hole_contours.len() {
+		let hole = &hole_contours[h];
+		let m = (0..hole.len()).max_by(|&a, &b| {
+			points[hole[a]].x.partial_cmp(&points[hole[b]].x).unwrap_or(Ordering::Equal)
+		}).unwrap();
+		let origin = points[hole[m]];
+		let distance = |i: usize| {
+			let p = points[contour[i]];
+			(p.x - origin.x) * (p.x - origin.x) + (p.y - origin.y) * (p.y - origin.y)
+		};
+		let mut candidates: Vec<usize> = (0..contour.len()).filter(|&i| points[contour[i]].x >= origin.x).collect();
+		candidates.sort_by(|&a, &b| distance(a).partial_cmp(&distance(b)).unwrap_or(Ordering::Equal));
+		let bridge = candidates.into_iter().find(|&i| {
+			let target = points[contour[i]];
+			target != origin
+				&& in_wedge(&points, &contour, i, origin)
+				&& !contour_crossed(&points, &contour, origin, target)
+				&& !hole_contours[h + 1..].iter().any(|other| contour_crossed(&points, other, origin, target))
+		});
+		if let Some(i) = bridge {
+			let mut merged = Vec::with_capacity(contour.len() + hole.len() + 2);
+			merged.extend_from_slice(&contour[..i + 1]);
+			merged.extend_from_slice(&hole[m..]);
+			merged.extend_from_slice(&hole[..m + 1]);
+			merged.extend_from_slice(&contour[i..]);
+			contour = merged;
+		}
+	}
+
+	let mut triangles = Vec::with_capacity((contour.len() - 2) * 3);
+	let mut start = 0;
+	while contour.len() >= 3 {
+		let n = contour.len();
+		// Fall back to clipping anything if the input was not simple
+		let i = (0..n).map(|k| (start + k) % n).find(|&i| is_ear(&points, &contour, i)).unwrap_or(start % n);
+		let (a, b, c) = (contour[(i + n - 1) % n], contour[i], contour[(i + 1) % n]);
+		if cross(points[a], points[b], points[c]) != 0. {
+			triangles.push(a as u32);
+			triangles.push(b as u32);
+			triangles.push(c as u32);
+		}
+		contour.remove(i);
+		start = i;
+	}
+	triangles
+}
+
+/// Specialized shape representing any simple polygon, with optional holes.
+///
+/// Unlike `ConvexShape`, the outline of a `PolygonShape` may be concave, and
+/// holes can be cut out of it. The polygon is triangulated whenever its
+/// points change, so it is best suited to shapes that are built once and then
+/// moved around with the `Transformable` methods.
+///
+/// The outline and holes must not intersect themselves or each other.
+/// `get_point_count` and `get_point` only describe the outline.
+#[derive(Clone)]
+pub struct PolygonShape<'s> {
+	transformable: BasicTransformable,
+	points: Vec<Vector2f>,
+	holes: Vec<Vec<Vector2f>>,
+	texture: Option<&'s Texture>,
+	texture_rect: IntRect,
+	fill_color: Color,
+	outline_color: Color,
+	outline_thickness: f32,
+	fill: VertexArray,
+	outline: VertexArray,
+	bounds: FloatRect
+}
+
+impl<'s> PolygonShape<'s> {
+	/// Create a new polygon shape with no points.
+	///
+	/// Returns Some(PolygonShape) or None on failure.
+	pub fn new() -> Option<PolygonShape<'s>> {
+		PolygonShape::from_vec(Vec::new())
+	}
+
+	/// Create a new polygon shape from the specified outline.
+	///
+	/// Returns Some(PolygonShape) or None on failure.
+	pub fn from_vec(points: Vec<Vector2f>) -> Option<PolygonShape<'s>> {
+		BasicTransformable::new().map(|transformable| {
+			let mut shape = PolygonShape {
+				transformable: transformable,
+				points: points,
+				holes: Vec::new(),
+				texture: None,
+				texture_rect: IntRect::new(0, 0, 0, 0),
+				fill_color: Color::white(),
+				outline_color: Color::white(),
+				outline_thickness: 0.,
+				fill: VertexArray::new(PrimitiveType::Triangles),
+				outline: VertexArray::new(PrimitiveType::Triangles),
+				bounds: FloatRect::new(0., 0., 0., 0.)
+			};
+			shape.update();
+			shape
+		})
+	}
+
+	/// Get the outline of the polygon.
+	pub fn get_points(&self) -> &[Vector2f] {
+		&self.points
+	}
+
+	/// Replace the outline of the polygon.
+	pub fn set_points(&mut self, points: Vec<Vector2f>) {
+		self.points = points;
+		self.update();
+	}
+
+	/// Get the holes cut out of the polygon.
+	pub fn get_holes(&self) -> &[Vec<Vector2f>] {
+		&self.holes
+	}
+
+	/// Cut a hole out of the polygon.
+	///
+	/// The hole must lie inside the outline and not touch the other holes.
+	pub fn add_hole(&mut self, points: Vec<Vector2f>) {
+		self.holes.push(points);
+		self.update();
+	}
+
+	/// Remove all the holes of the polygon.
+	pub fn clear_holes(&mut self) {
+		self.holes.clear();
+		self.update();
+	}
+
+	/// Change the source texture of the shape.
+	///
+	/// If `reset_rect` is true, the texture rect is set to the full texture.
+	pub fn set_texture(&mut self, texture: &'s Texture, reset_rect: bool) {
+		self.texture = Some(texture);
+		if reset_rect {
+			let size = texture.get_size();
+			self.set_texture_rect(IntRect::new(0, 0, size.x as i32, size.y as i32));
+		}
+	}
+
+	/// Disable texturing for the shape.
+	pub fn disable_texture(&mut self) {
+		self.texture = None;
+	}
+
+	/// Get the source texture of the shape, if any.
+	pub fn get_texture(&self) -> Option<&'s Texture> {
+		self.texture
+	}
+
+	/// Set the part of the texture mapped onto the bounds of the polygon.
+	pub fn set_texture_rect(&mut self, rect: IntRect) {
+		self.texture_rect = rect;
+		self.update_fill();
+	}
+
+	/// Get the part of the texture mapped onto the bounds of the polygon.
+	pub fn get_texture_rect(&self) -> IntRect {
+		self.texture_rect
+	}
+
+	/// Get the local bounding rectangle of the shape, outline included.
+	pub fn get_local_bounds(&self) -> FloatRect {
+		self.bounds
+	}
+
+	/// Get the global bounding rectangle of the shape, outline included.
+	pub fn get_global_bounds(&self) -> FloatRect {
+		self.get_transform().transform_rect(self.bounds)
+	}
+
+	/// Get the triangles the polygon is filled with.
+	pub fn get_fill_vertices(&self) -> &VertexArray {
+		&self.fill
+	}
+
+	fn update(&mut self) {
+		let triangles = triangulate(&self.points, &self.holes);
+		let all: Vec<Vector2f> = self.points.iter().chain(self.holes.iter().flat_map(|h| h.iter())).cloned().collect();
+		self.fill.clear();
+		for index in triangles {
+			self.fill.push(Vertex::new_with_pos(all[index as usize]));
+		}
+		self.update_fill();
+		self.update_outline();
+	}
+
+	fn update_fill(&mut self) {
+		let bounds = self.fill.get_bounds();
+		let rect = self.texture_rect;
+		for vertex in self.fill.iter_mut() {
+			let u = if bounds.width > 0. { (vertex.position.x - bounds.left) / bounds.width } else { 0. };
+			let v = if bounds.height > 0. { (vertex.position.y - bounds.top) / bounds.height } else { 0. };
+			vertex.color = self.fill_color;
+			vertex.tex_coords = Vector2f::new(rect.left as f32 + rect.width as f32 * u,
+			                                  rect.top as f32 + rect.height as f32 * v);
+		}
+	}
+
+	fn update_outline(&mut self) {
+		self.outline.clear();
+		if self.outline_thickness != 0. && self.points.len() >= 3 {
+			let outer = outward(&self.points, true);
+			push_outline(&mut self.outline, &outer, self.outline_thickness, self.outline_color);
+			for hole in self.holes.iter().filter(|hole| hole.len() >= 3) {
+				push_outline(&mut self.outline, &outward(hole, false), self.outline_thickness, self.outline_color);
+			}
+		}
+		self.bounds = if self.outline.is_empty() { self.fill.get_bounds() } else {
+			let (a, b) = (self.fill.get_bounds(), self.outline.get_bounds());
+			let (left, top) = (a.left.min(b.left), a.top.min(b.top));
+			let right = (a.left + a.width).max(b.left + b.width);
+			let bottom = (a.top + a.height).max(b.top + b.height);
+			FloatRect::new(left, top, right - left, bottom - top)
+		};
+	}
+}
+
+// Wind a contour so that the left hand normal of its edges points away from
+// the filled area.
+fn outward(points: &[Vector2f], outer: bool) -> Vec<Vector2f> {
+	let mut points = points.to_vec();
+	if (signed_area(&points) < 0.) == outer {
+		points.reverse();
+	}
+	points
+}
+
+fn push_outline(array: &mut VertexArray, points: &[Vector2f], thickness: f32, color: Color) {
+	let n = points.len();
+	let normal = |a: Vector2f, b: Vector2f| {
+		let (x, y) = (b.y - a.y, a.x - b.x);
+		let length = (x * x + y * y).sqrt();
+		if length > 0. { Vector2f::new(x / length, y / length) } else { Vector2f::new(0., 0.) }
+	};
+	let offsets: Vec<Vector2f> = (0..n).map(|i| {
+		let (p0, p1, p2) = (points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+		let (n1, n2) = (normal(p0, p1), normal(p1, p2));
+		// Same miter as SFML's shapes
+		let factor = 1. + (n1.x * n2.x + n1.y * n2.y);
+		let factor = if factor > 0.01 { factor } else { 0.01 };
+		Vector2f::new((n1.x + n2.x) / factor * thickness, (n1.y + n2.y) / factor * thickness)
+	}).collect();
+	for i in 0..n {
+		let j = (i + 1) % n;
+		let (inner_i, inner_j) = (points[i], points[j]);
+		let (outer_i, outer_j) = (points[i] + offsets[i], points[j] + offsets[j]);
+		for &position in &[inner_i, outer_i, inner_j, inner_j, outer_i, outer_j] {
+			array.push(Vertex::new_with_pos_color(position, color));
+		}
+	}
+}
+
+impl<'s> Shape for PolygonShape<'s> {
+	fn set_fill_color(&mut self, color: Color) {
+		self.fill_color = color;
+		self.update_fill();
+	}
+
+	fn set_outline_color(&mut self, color: Color) {
+		self.outline_color = color;
+		for vertex in self.outline.iter_mut() {
+			vertex.color = color;
+		}
+	}
+
+	fn set_outline_thickness(&mut self, thickness: f32) {
+		self.outline_thickness = thickness;
+		self.update_outline();
+	}
+
+	fn get_fill_color(&self) -> Color {
+		self.fill_color
+	}
+
+	fn get_outline_color(&self) -> Color {
+		self.outline_color
+	}
+
+	fn get_outline_thickness(&self) -> f32 {
+		self.outline_thickness
+	}
+
+	fn get_point_count(&self) -> u32 {
+		self.points.len() as u32
+	}
+
+	fn get_point(&self, point: u32) -> Vector2f {
+		self.points[point as usize]
+	}
+}
+
+impl<'s> Transformable for PolygonShape<'s> {
+	fn set_position(&mut self, position: Vector2f) { self.transformable.set_position(position) }
+	fn set_rotation(&mut self, angle: f32) { self.transformable.set_rotation(angle) }
+	fn set_scale(&mut self, scale: Vector2f) { self.transformable.set_scale(scale) }
+	fn set_origin(&mut self, origin: Vector2f) { self.transformable.set_origin(origin) }
+	fn get_position(&self) -> Vector2f { self.transformable.get_position() }
+	fn get_rotation(&self) -> f32 { self.transformable.get_rotation() }
+	fn get_scale(&self) -> Vector2f { self.transformable.get_scale() }
+	fn get_origin(&self) -> Vector2f { self.transformable.get_origin() }
+	fn move_(&mut self, offset: Vector2f) { self.transformable.move_(offset) }
+	fn rotate(&mut self, angle: f32) { self.transformable.rotate(angle) }
+	fn scale(&mut self, factors: Vector2f) { self.transformable.scale(factors) }
+	fn get_transform(&self) -> Transform { self.transformable.get_transform() }
+	fn get_inverse_transform(&self) -> Transform { self.transformable.get_inverse_transform() }
+}
+
+impl<'s> Drawable for PolygonShape<'s> {
+	fn draw(&self, target: &mut RenderTarget, states: &RenderStates) {
+		let mut transform = states.transform;
+		transform.combine(&self.get_transform());
+		target.draw_primitives_rs(&self.fill, PrimitiveType::Triangles, &RenderStates {
+			transform: transform,
+			texture: self.texture,
+			.. *states
+		});
+		if !self.outline.is_empty() {
+			target.draw_primitives_rs(&self.outline, PrimitiveType::Triangles, &RenderStates {
+				transform: transform,
+				texture: None,
+				.. *states
+			});
+		}
+	}
+}
+
+#[cfg(test)]
+fn triangles_area(points: &[Vector2f], triangles: &[u32]) -> f32 {
+	triangles.chunks(3).map(|t| {
+		cross(points[t[0] as usize], points[t[1] as usize], points[t[2] as usize]).abs() / 2.
+	}).sum()
+}
+
+#[cfg(test)]
+fn random_star(random: &mut ::graphics::particles::Random, center: Vector2f, radius: f32, count: usize) -> Vec<Vector2f> {
+	(0..count).map(|i| {
+		let angle = i as f32 / count as f32 * 2. * ::std::f32::consts::PI;
+		let r = random.range(radius * 0.3, radius);
+		Vector2f::new(center.x + angle.cos() * r, center.y + angle.sin() * r)
+	}).collect()
+}
+
+#[test]
+fn triangulate_concave() {
+	// An L shape, clockwise
+	let outline = [Vector2f::new(0., 0.), Vector2f::new(0., 2.), Vector2f::new(2., 2.),
+	               Vector2f::new(2., 1.), Vector2f::new(1., 1.), Vector2f::new(1., 0.)];
+	let triangles = triangulate(&outline, &[]);
+	assert_eq!(triangles.len(), 4 * 3);
+	assert!((triangles_area(&outline, &triangles) - 3.).abs() < 1e-5);
+}
+
+#[test]
+fn triangulate_random_polygons() {
+	let mut random = ::graphics::particles::Random::new(42);
+	for round in 0..200 {
+		let count = 3 + round % 40;
+		let outline = random_star(&mut random, Vector2f::new(0., 0.), 100., count);
+		let triangles = triangulate(&outline, &[]);
+		assert_eq!(triangles.len(), (count - 2) * 3);
+		assert!((triangles_area(&outline, &triangles) - signed_area(&outline).abs()).abs() < 1e-2);
+	}
+}
+
+#[test]
+fn triangulate_random_polygons_with_holes() {
+	let mut random = ::graphics::particles::Random::new(7);
+	for round in 0..200 {
+		let count = 8 + round % 30;
+		// Outer radius at least 60, holes at most 20 wide around two points
+		let outline: Vec<Vector2f> = random_star(&mut random, Vector2f::new(0., 0.), 200., count).into_iter()
+			.map(|p| { let l = (p.x * p.x + p.y * p.y).sqrt(); p * ((l.max(60.) + 40.) / l) }).collect();
+		let holes = vec![
+			random_star(&mut random, Vector2f::new(-40., 0.), 20., 3 + round % 7),
+			random_star(&mut random, Vector2f::new(40., 10.), 20., 3 + round % 5)
+		];
+		let all: Vec<Vector2f> = outline.iter().chain(holes[0].iter()).chain(holes[1].iter()).cloned().collect();
+		let triangles = triangulate(&outline, &holes);
+		assert_eq!(triangles.len() / 3, all.len() + 2 * holes.len() - 2);
+		let area = signed_area(&outline).abs() - signed_area(&holes[0]).abs() - signed_area(&holes[1]).abs();
+		assert!((triangles_area(&all, &triangles) - area).abs() < area * 1e-4);
+	}
+}
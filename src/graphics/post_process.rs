@@ -0,0 +1,640 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Post-processing: fullscreen shader effects applied to a rendered scene.
+//!
+//! A `PostProcessor` owns an off-screen scene texture and two ping-pong
+//! buffers. Draw the scene into `get_scene_mut()`, then call `apply` with an
+//! ordered list of `PostEffect`s: each effect reads the output of the
+//! previous one, and the result is drawn over the whole target.
+//!
+//! Built-in effects are `Blur`, `Bloom`, `Vignette`, `ColorGrading` and
+//! `Crt`; `ShaderPass` runs any fragment shader reading the `texture`
+//! sampler. Effects keep their settings in public fields, so they can be
+//! tweaked between frames.
+//!
+//! ```ignore
+//! let mut post = PostProcessor::new(800, 600).unwrap();
+//! let mut bloom = Bloom::new().unwrap();
+//! let mut vignette = Vignette::new().unwrap();
+//! loop {
+//!     for event in window.events() {
+//!         post.handle_event(&event);
+//!     }
+//!     post.get_scene_mut().clear(Color::black());
+//!     post.get_scene_mut().draw(&world);
+//!     post.apply(&mut [&mut bloom, &mut vignette], &mut window);
+//!     window.display();
+//! }
+//! ```
+//!
+//! All effects require shader support, see `Shader::is_available()`.
+
+use graphics::{BlendMode, Color, Drawable, RenderStates, RenderTarget, RenderTexture, Shader,
+               Sprite, Texture, Transformable, Uniform, CurrentTexture};
+use system::{Vector2f, Vector2u};
+use window::Event;
+
+/// A fullscreen effect in a post-processing chain.
+pub trait PostEffect {
+	/// Draw `input` into `output` with the effect applied.
+	///
+	/// `output` has been cleared, and is displayed by the caller afterwards.
+	fn apply(&mut self, input: &Texture, output: &mut RenderTexture);
+}
+
+/// Draw a texture stretched over the whole target, ignoring its current
+/// view.
+///
+/// This is the building block of post effects: pass a shader to process
+/// every pixel of `input`.
+pub fn draw_fullscreen(input: &Texture, output: &mut RenderTarget, shader: Option<&Shader>,
+                       blend_mode: BlendMode) {
+	draw_fullscreen_with(input, output, &RenderStates {
+		blend_mode: blend_mode,
+		shader: shader,
+		.. RenderStates::default()
+	});
+}
+
+/// Draw a texture stretched over the whole target with render states,
+/// ignoring the current view of the target.
+///
+/// The transform of `states` is applied in the coordinates of the default
+/// view; its texture is replaced by `input`.
+pub fn draw_fullscreen_with(input: &Texture, output: &mut RenderTarget, states: &RenderStates) {
+	let mut sprite = match Sprite::new_with_texture(input) {
+		Some(sprite) => sprite,
+		None => return
+	};
+	let (from, to) = (input.get_size(), output.get_size());
+	sprite.set_scale(Vector2f::new(to.x as f32 / from.x as f32, to.y as f32 / from.y as f32));
+
+	let previous = (*output.get_view()).clone();
+	let default = (*output.get_default_view()).clone();
+	output.set_view(&default);
+	sprite.draw(output, states);
+	output.set_view(&previous);
+}
+
+/// Scene buffer and ping-pong buffers for a chain of `PostEffect`s.
+pub struct PostProcessor {
+	// The scene, then the two ping-pong buffers.
+	targets: Vec<RenderTexture>
+}
+
+impl PostProcessor {
+	/// Create a post-processor rendering at `width` x `height` pixels.
+	///
+	/// Returns None if the render textures could not be created.
+	pub fn new(width: u32, height: u32) -> Option<PostProcessor> {
+		create_targets(width, height).map(|targets| PostProcessor { targets: targets })
+	}
+
+	/// Get the size of the buffers.
+	pub fn get_size(&self) -> Vector2u {
+		self.targets[0].get_size()
+	}
+
+	/// Recreate the buffers with a new size. Their contents are lost.
+	///
+	/// Returns false, keeping the previous buffers, if the new ones could not
+	/// be created.
+	pub fn resize(&mut self, width: u32, height: u32) -> bool {
+		if self.get_size() == Vector2u::new(width, height) {
+			return true
+		}
+		match create_targets(width, height) {
+			Some(targets) => {
+				self.targets = targets;
+				true
+			}
+			None => false
+		}
+	}
+
+	/// Resize the buffers to follow the window on `Event::Resized`. Other
+	/// events are ignored.
+	pub fn handle_event(&mut self, event: &Event) {
+		if let Event::Resized { width, height } = *event {
+			self.resize(width, height);
+		}
+	}
+
+	/// Get the texture the scene is drawn into.
+	pub fn get_scene(&self) -> &RenderTexture {
+		&self.targets[0]
+	}
+
+	/// Mutably access the texture the scene is drawn into.
+	pub fn get_scene_mut(&mut self) -> &mut RenderTexture {
+		&mut self.targets[0]
+	}
+
+	/// Run the effects in order on the scene, and draw the result over the
+	/// whole `target`.
+	///
+	/// With no effect, the scene is drawn as is.
+	pub fn apply(&mut self, effects: &mut [&mut PostEffect], target: &mut RenderTarget) {
+		self.targets[0].display();
+		let mut input = 0;
+		for effect in effects.iter_mut() {
+			let output = if input == 1 { 2 } else { 1 };
+			{
+				let (source, destination) = split(&mut self.targets, input, output);
+				let texture = match source.get_texture() {
+					Some(texture) => texture,
+					None => continue
+				};
+				destination.clear(Color::transparent());
+				effect.apply(&texture, destination);
+				destination.display();
+			}
+			input = output;
+		}
+		if let Some(texture) = self.targets[input].get_texture() {
+			draw_fullscreen(&texture, target, None, BlendMode::alpha());
+		}
+	}
+}
+
+fn create_targets(width: u32, height: u32) -> Option<Vec<RenderTexture>> {
+	let mut targets = Vec::with_capacity(3);
+	for _ in 0..3 {
+		let mut target = match RenderTexture::new(width, height, false) {
+			Some(target) => target,
+			None => return None
+		};
+		target.set_smooth(true);
+		targets.push(target);
+	}
+	Some(targets)
+}
+
+fn split(targets: &mut [RenderTexture], input: usize, output: usize) -> (&RenderTexture, &mut RenderTexture) {
+	if input < output {
+		let (left, right) = targets.split_at_mut(output);
+		(&left[input], &mut right[0])
+	} else {
+		let (left, right) = targets.split_at_mut(input);
+		(&right[0], &mut left[output])
+	}
+}
+
+// Make sure an intermediate buffer exists with the given size.
+fn ensure_buffer(buffer: &mut Option<RenderTexture>, size: Vector2u) -> bool {
+	let matches = buffer.as_ref().map_or(false, |b| b.get_size() == size);
+	if !matches {
+		*buffer = RenderTexture::new(size.x, size.y, false);
+		if let Some(ref mut buffer) = *buffer {
+			buffer.set_smooth(true);
+		}
+	}
+	buffer.is_some()
+}
+
+// Compile a fragment shader whose input is the `texture` sampler, and look
+// up its other uniforms.
+fn load<'s>(source: &str, names: &[&str]) -> Option<(Shader<'s>, Vec<Uniform>)> {
+	let mut shader = match Shader::new_from_memory(None, Some(source)) {
+		Some(shader) => shader,
+		None => return None
+	};
+	if shader.set_parameter("texture", CurrentTexture).is_err() {
+		return None
+	}
+	let mut uniforms = Vec::with_capacity(names.len());
+	for name in names {
+		match shader.uniform(name) {
+			Ok(uniform) => uniforms.push(uniform),
+			Err(_) => return None
+		}
+	}
+	Some((shader, uniforms))
+}
+
+/// Runs a custom fragment shader over the image.
+///
+/// The shader reads the image from a `sampler2D` uniform named `texture`.
+pub struct ShaderPass<'s> {
+	shader: Shader<'s>
+}
+
+impl<'s> ShaderPass<'s> {
+	/// Compile a pass from fragment shader source code.
+	///
+	/// Returns None if the shader does not compile or has no `texture`
+	/// uniform.
+	pub fn new(fragment_shader: &str) -> Option<ShaderPass<'s>> {
+		load(fragment_shader, &[]).map(|(shader, _)| ShaderPass { shader: shader })
+	}
+
+	/// Get the shader, to set its parameters.
+	pub fn get_shader_mut(&mut self) -> &mut Shader<'s> {
+		&mut self.shader
+	}
+}
+
+impl<'s> PostEffect for ShaderPass<'s> {
+	fn apply(&mut self, input: &Texture, output: &mut RenderTexture) {
+		draw_fullscreen(input, output, Some(&self.shader), BlendMode::none());
+	}
+}
+
+static BLUR_SOURCE: &'static str = "
+uniform sampler2D texture;
+uniform vec2 direction;
+
+void main() {
+	vec2 uv = gl_TexCoord[0].xy;
+	vec4 color = texture2D(texture, uv) * 0.2270270270;
+	color += texture2D(texture, uv + direction * 1.3846153846) * 0.3162162162;
+	color += texture2D(texture, uv - direction * 1.3846153846) * 0.3162162162;
+	color += texture2D(texture, uv + direction * 3.2307692308) * 0.0702702703;
+	color += texture2D(texture, uv - direction * 3.2307692308) * 0.0702702703;
+	gl_FragColor = color * gl_Color;
+}";
+
+/// Separable Gaussian blur.
+pub struct Blur {
+	/// Spacing of the samples, in pixels. Larger values blur more but
+	/// eventually show banding. The default is 1.
+	pub radius: f32,
+	/// Number of times the blur is applied. The default is 2.
+	pub iterations: u32,
+	shader: Shader<'static>,
+	direction: Uniform,
+	temp: Option<RenderTexture>
+}
+
+impl Blur {
+	/// Create a blur effect.
+	///
+	/// Returns None if the shader could not be compiled.
+	pub fn new() -> Option<Blur> {
+		load(BLUR_SOURCE, &["direction"]).map(|(shader, mut uniforms)| Blur {
+			radius: 1.,
+			iterations: 2,
+			shader: shader,
+			direction: uniforms.remove(0),
+			temp: None
+		})
+	}
+}
+
+impl PostEffect for Blur {
+	fn apply(&mut self, input: &Texture, output: &mut RenderTexture) {
+		let size = input.get_size();
+		if !ensure_buffer(&mut self.temp, size) {
+			draw_fullscreen(input, output, None, BlendMode::none());
+			return
+		}
+		let horizontal = Vector2f::new(self.radius / size.x as f32, 0.);
+		let vertical = Vector2f::new(0., self.radius / size.y as f32);
+		let temp = self.temp.as_mut().unwrap();
+		for i in 0..self.iterations.max(1) {
+			self.shader.set_uniform(&self.direction, horizontal);
+			temp.clear(Color::transparent());
+			if i == 0 {
+				draw_fullscreen(input, temp, Some(&self.shader), BlendMode::none());
+			} else if let Some(previous) = output.get_texture() {
+				draw_fullscreen(&previous, temp, Some(&self.shader), BlendMode::none());
+			}
+			temp.display();
+
+			self.shader.set_uniform(&self.direction, vertical);
+			output.clear(Color::transparent());
+			if let Some(horizontal) = temp.get_texture() {
+				draw_fullscreen(&horizontal, output, Some(&self.shader), BlendMode::none());
+			}
+			output.display();
+		}
+	}
+}
+
+static BRIGHT_PASS_SOURCE: &'static str = "
+uniform sampler2D texture;
+uniform float threshold;
+
+void main() {
+	vec4 color = texture2D(texture, gl_TexCoord[0].xy);
+	float luma = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+	gl_FragColor = vec4(color.rgb * max(luma - threshold, 0.0) / max(1.0 - threshold, 0.0001), 1.0);
+}";
+
+static INTENSITY_SOURCE: &'static str = "
+uniform sampler2D texture;
+uniform float intensity;
+
+void main() {
+	gl_FragColor = texture2D(texture, gl_TexCoord[0].xy) * gl_Color * intensity;
+}";
+
+/// Makes bright areas glow.
+///
+/// Pixels brighter than the threshold are extracted, blurred, and added back
+/// on top of the image.
+pub struct Bloom {
+	/// Luminance, in `[0, 1]`, above which pixels glow. The default is 0.6.
+	pub threshold: f32,
+	/// Strength of the glow. The default is 1.
+	pub intensity: f32,
+	/// Blur applied to the bright pixels.
+	pub blur: Blur,
+	extract: Shader<'static>,
+	threshold_uniform: Uniform,
+	combine: Shader<'static>,
+	intensity_uniform: Uniform,
+	bright: Option<RenderTexture>,
+	blurred: Option<RenderTexture>
+}
+
+impl Bloom {
+	/// Create a bloom effect.
+	///
+	/// Returns None if the shaders could not be compiled.
+	pub fn new() -> Option<Bloom> {
+		let (extract, mut extract_uniforms) = match load(BRIGHT_PASS_SOURCE, &["threshold"]) {
+			Some(loaded) => loaded,
+			None => return None
+		};
+		let (combine, mut combine_uniforms) = match load(INTENSITY_SOURCE, &["intensity"]) {
+			Some(loaded) => loaded,
+			None => return None
+		};
+		let mut blur = match Blur::new() {
+			Some(blur) => blur,
+			None => return None
+		};
+		blur.radius = 2.;
+		blur.iterations = 3;
+		Some(Bloom {
+			threshold: 0.6,
+			intensity: 1.,
+			blur: blur,
+			extract: extract,
+			threshold_uniform: extract_uniforms.remove(0),
+			combine: combine,
+			intensity_uniform: combine_uniforms.remove(0),
+			bright: None,
+			blurred: None
+		})
+	}
+}
+
+impl PostEffect for Bloom {
+	fn apply(&mut self, input: &Texture, output: &mut RenderTexture) {
+		let size = input.get_size();
+		if !ensure_buffer(&mut self.bright, size) || !ensure_buffer(&mut self.blurred, size) {
+			draw_fullscreen(input, output, None, BlendMode::none());
+			return
+		}
+		let bright = self.bright.as_mut().unwrap();
+		let blurred = self.blurred.as_mut().unwrap();
+
+		self.extract.set_uniform(&self.threshold_uniform, self.threshold);
+		bright.clear(Color::black());
+		draw_fullscreen(input, bright, Some(&self.extract), BlendMode::none());
+		bright.display();
+
+		blurred.clear(Color::black());
+		if let Some(texture) = bright.get_texture() {
+			self.blur.apply(&texture, blurred);
+		}
+		blurred.display();
+
+		draw_fullscreen(input, output, None, BlendMode::none());
+		self.combine.set_uniform(&self.intensity_uniform, self.intensity);
+		if let Some(texture) = blurred.get_texture() {
+			draw_fullscreen(&texture, output, Some(&self.combine), BlendMode::add());
+		}
+	}
+}
+
+static VIGNETTE_SOURCE: &'static str = "
+uniform sampler2D texture;
+uniform float radius;
+uniform float softness;
+uniform vec4 color;
+
+void main() {
+	vec4 pixel = texture2D(texture, gl_TexCoord[0].xy);
+	float distance = length(gl_TexCoord[0].xy - vec2(0.5)) * 1.41421356;
+	float shade = smoothstep(radius - softness, radius, distance) * color.a;
+	gl_FragColor = vec4(mix(pixel.rgb, color.rgb, shade), pixel.a) * gl_Color;
+}";
+
+/// Darkens (or tints) the edges of the image.
+pub struct Vignette {
+	/// Distance from the center, as a fraction of the distance to the
+	/// corners, at which the effect is full. The default is 1.
+	pub radius: f32,
+	/// Width of the transition from no effect to full effect, in the same
+	/// unit as `radius`. The default is 0.6.
+	pub softness: f32,
+	/// Color of the edges; its alpha is the strength of the effect. The
+	/// default is black.
+	pub color: Color,
+	shader: Shader<'static>,
+	uniforms: Vec<Uniform>
+}
+
+impl Vignette {
+	/// Create a vignette effect.
+	///
+	/// Returns None if the shader could not be compiled.
+	pub fn new() -> Option<Vignette> {
+		load(VIGNETTE_SOURCE, &["radius", "softness", "color"]).map(|(shader, uniforms)| Vignette {
+			radius: 1.,
+			softness: 0.6,
+			color: Color::black(),
+			shader: shader,
+			uniforms: uniforms
+		})
+	}
+}
+
+impl PostEffect for Vignette {
+	fn apply(&mut self, input: &Texture, output: &mut RenderTexture) {
+		self.shader.set_uniform(&self.uniforms[0], self.radius);
+		self.shader.set_uniform(&self.uniforms[1], self.softness);
+		self.shader.set_uniform(&self.uniforms[2], self.color);
+		draw_fullscreen(input, output, Some(&self.shader), BlendMode::none());
+	}
+}
+
+static COLOR_GRADING_SOURCE: &'static str = "
+uniform sampler2D texture;
+uniform sampler2D lut;
+uniform float size;
+uniform float strength;
+
+void main() {
+	vec4 pixel = texture2D(texture, gl_TexCoord[0].xy);
+	vec3 cell = clamp(pixel.rgb, 0.0, 1.0) * (size - 1.0);
+	float blue = floor(cell.b);
+	float next = min(blue + 1.0, size - 1.0);
+	vec2 uv = vec2((cell.r + 0.5) / (size * size), (cell.g + 0.5) / size);
+	vec3 low = texture2D(lut, uv + vec2(blue / size, 0.0)).rgb;
+	vec3 high = texture2D(lut, uv + vec2(next / size, 0.0)).rgb;
+	vec3 graded = mix(low, high, cell.b - blue);
+	gl_FragColor = vec4(mix(pixel.rgb, graded, strength), pixel.a) * gl_Color;
+}";
+
+/// Remaps colors through a 3D lookup table.
+///
+/// The table is stored in a texture as a horizontal strip of N slices of
+/// N x N pixels (for example 256 x 16 for N = 16): red increases left to
+/// right within a slice, green top to bottom, and blue from one slice to
+/// the next. `identity_lut` generates a neutral table, which can be edited
+/// in an image editor alongside a screenshot to design a grade.
+///
+/// The texture should be smooth (see `Texture::set_smooth`), so that colors
+/// between table entries are interpolated.
+pub struct ColorGrading<'s> {
+	/// How much of the graded color replaces the original, in `[0, 1]`. The
+	/// default is 1.
+	pub strength: f32,
+	shader: Shader<'s>,
+	uniforms: Vec<Uniform>
+}
+
+impl<'s> ColorGrading<'s> {
+	/// Create a color grading effect using a lookup table texture.
+	///
+	/// Returns None if the shader could not be compiled.
+	pub fn new(lut: &'s Texture) -> Option<ColorGrading<'s>> {
+		let (mut shader, uniforms) = match load(COLOR_GRADING_SOURCE, &["size", "strength"]) {
+			Some(loaded) => loaded,
+			None => return None
+		};
+		if shader.set_parameter("lut", lut).is_err() {
+			return None
+		}
+		shader.set_uniform(&uniforms[0], lut.get_size().y as f32);
+		Some(ColorGrading {
+			strength: 1.,
+			shader: shader,
+			uniforms: uniforms
+		})
+	}
+}
+
+impl<'s> PostEffect for ColorGrading<'s> {
+	fn apply(&mut self, input: &Texture, output: &mut RenderTexture) {
+		self.shader.set_uniform(&self.uniforms[1], self.strength);
+		draw_fullscreen(input, output, Some(&self.shader), BlendMode::none());
+	}
+}
+
+/// Generate the RGBA pixels of a neutral lookup table for `ColorGrading`,
+/// of `size * size` x `size` pixels.
+pub fn identity_lut(size: u32) -> Vec<u8> {
+	let scale = 255. / (size.max(2) - 1) as f32;
+	let mut pixels = Vec::with_capacity((size * size * size * 4) as usize);
+	for green in 0..size {
+		for x in 0..size * size {
+			let (red, blue) = (x % size, x / size);
+			pixels.push((red as f32 * scale).round() as u8);
+			pixels.push((green as f32 * scale).round() as u8);
+			pixels.push((blue as f32 * scale).round() as u8);
+			pixels.push(255);
+		}
+	}
+	pixels
+}
+
+static CRT_SOURCE: &'static str = "
+uniform sampler2D texture;
+uniform vec2 resolution;
+uniform float curvature;
+uniform float scanlines;
+uniform float aberration;
+
+void main() {
+	vec2 uv = gl_TexCoord[0].xy * 2.0 - 1.0;
+	uv += uv * uv.yx * uv.yx * curvature;
+	uv = uv * 0.5 + 0.5;
+	if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+		gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+		return;
+	}
+	vec2 shift = vec2(aberration / resolution.x, 0.0);
+	vec4 center = texture2D(texture, uv);
+	vec4 color = vec4(texture2D(texture, uv + shift).r, center.g, texture2D(texture, uv - shift).b, center.a);
+	float line = 0.5 + 0.5 * sin(uv.y * resolution.y * 3.14159265);
+	color.rgb *= mix(1.0, line, scanlines);
+	gl_FragColor = color * gl_Color;
+}";
+
+/// Imitates an old CRT monitor: curved screen, scanlines and chromatic
+/// aberration.
+pub struct Crt {
+	/// Bulge of the screen; 0 keeps it flat. The default is 0.1.
+	pub curvature: f32,
+	/// Darkness of the scanlines, in `[0, 1]`. The default is 0.3.
+	pub scanlines: f32,
+	/// Horizontal offset of the red and blue channels, in pixels. The
+	/// default is 1.
+	pub aberration: f32,
+	shader: Shader<'static>,
+	uniforms: Vec<Uniform>
+}
+
+impl Crt {
+	/// Create a CRT effect.
+	///
+	/// Returns None if the shader could not be compiled.
+	pub fn new() -> Option<Crt> {
+		load(CRT_SOURCE, &["resolution", "curvature", "scanlines", "aberration"]).map(|(shader, uniforms)| Crt {
+			curvature: 0.1,
+			scanlines: 0.3,
+			aberration: 1.,
+			shader: shader,
+			uniforms: uniforms
+		})
+	}
+}
+
+impl PostEffect for Crt {
+	fn apply(&mut self, input: &Texture, output: &mut RenderTexture) {
+		let size = input.get_size();
+		self.shader.set_uniform(&self.uniforms[0], Vector2f::new(size.x as f32, size.y as f32));
+		self.shader.set_uniform(&self.uniforms[1], self.curvature);
+		self.shader.set_uniform(&self.uniforms[2], self.scanlines);
+		self.shader.set_uniform(&self.uniforms[3], self.aberration);
+		draw_fullscreen(input, output, Some(&self.shader), BlendMode::none());
+	}
+}
+
+#[test]
+fn identity_lut_pixels() {
+	let pixels = identity_lut(4);
+	assert_eq!(pixels.len(), 16 * 4 * 4);
+	let pixel = |x: usize, y: usize| &pixels[(y * 16 + x) * 4..(y * 16 + x) * 4 + 4];
+	assert_eq!(pixel(0, 0), &[0, 0, 0, 255]);
+	assert_eq!(pixel(3, 0), &[255, 0, 0, 255]);
+	assert_eq!(pixel(5, 2), &[85, 170, 85, 255]);
+	assert_eq!(pixel(15, 3), &[255, 255, 255, 255]);
+}
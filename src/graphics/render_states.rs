@@ -24,7 +24,7 @@
 
 use std::ptr;
 
-use graphics::{BlendMode, Shader, Texture, Transform};
+use graphics::{BlendMode, Color, IntRect, Shader, Texture, Transform, StencilMode};
 
 use ffi::graphics as ffi;
 
@@ -62,16 +62,23 @@ use ffi::graphics as ffi;
 ///     self.child.draw(target, &RenderStates { transform: transform, .. *states });
 /// }
 /// ```
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct RenderStates<'s> {
     /// How pixels of objects are blended with the background.
     pub blend_mode: BlendMode,
+    /// Color used by the `Constant*` blend factors of the blend mode.
+    pub blend_color: Color,
     /// How objects are positioned/rotated/scaled.
     pub transform: Transform,
     /// What texture, if any, is applied to objects.
     pub texture: Option<&'s Texture>,
     /// What custom effect, if any, is applied to objects.
-    pub shader: Option<&'s Shader<'s>>
+    pub shader: Option<&'s Shader<'s>>,
+    /// How the stencil buffer of the target masks and records the drawing.
+    /// Targets without a stencil buffer draw nothing with one.
+    pub stencil: Option<StencilMode>,
+    /// Rectangle, in target pixels, outside of which nothing is drawn.
+    pub scissor: Option<IntRect>
 }
 
 impl<'s> RenderStates<'s> {
@@ -85,19 +92,32 @@ impl<'s> RenderStates<'s> {
                shader: Option<&'s Shader<'s>>) -> RenderStates<'s> {
         RenderStates {
             blend_mode: blend_mode,
+            blend_color: Color::transparent(),
             transform: transform,
             texture: texture,
-            shader: shader
+            shader: shader,
+            stencil: None,
+            scissor: None
         }
     }
 
+    // Blend modes CSFML cannot apply are replaced by alpha blending, which
+    // render targets override through OpenGL.
     #[doc(hidden)]
     pub fn unwrap(&self) -> ffi::sfRenderStates {
 		ffi::sfRenderStates {
-			blendMode: self.blend_mode,
+			blendMode: if self.blend_mode.is_native() { self.blend_mode } else { BlendMode::alpha() },
 			transform: self.transform,
 			texture: self.texture.map_or(ptr::null(), |x| x.unwrap()),
 			shader: self.shader.map_or(ptr::null(), |x| x.unwrap())
 		}
     }
 }
+
+impl<'s> Default for RenderStates<'s> {
+	/// Alpha blending with a transparent blend color, identity transform,
+	/// no texture and no shader.
+	fn default() -> RenderStates<'s> {
+		RenderStates::new(BlendMode::default(), Transform::default(), None, None)
+	}
+}
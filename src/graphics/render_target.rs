@@ -25,7 +25,7 @@
 //Authored on 2014-08-30 by Brandon Sanderson
 
 use graphics::{Color, View, RenderStates, CircleShape, RectangleShape, Text, Sprite,
-               IntRect, Vertex, PrimitiveType, BaseShape, Drawable};
+               IntRect, Vertex, VertexBuffer, PrimitiveType, BaseShape, Drawable, Image, StencilError};
 use system::{Vector2f, Vector2i, Vector2u};
 use ffi::Ref;
 
@@ -156,6 +156,37 @@ pub trait RenderTarget {
     /// Get the size of the rendering region of the target, in pixels.
     fn get_size(&self) -> Vector2u;
 
+	/// Copy the current contents of the target to an image.
+	///
+	/// For a window, call this after drawing and before `display()`. For a
+	/// render texture, the contents are those of the last `display()`.
+	///
+	/// This is a slow operation, whose main purpose is to make screenshots
+	/// of the application. Returns None on failure.
+	///
+	/// The default implementation returns None, for targets that can not
+	/// be read back.
+    fn capture(&self) -> Option<Image> {
+        None
+    }
+
+	/// Tell whether the target has a stencil buffer, which drawing with a
+	/// `StencilMode` needs.
+	///
+	/// The default implementation returns false.
+	fn has_stencil_buffer(&self) -> bool {
+		false
+	}
+
+	/// Clear the stencil buffer of the target to `value`.
+	///
+	/// Returns an error if the target has no stencil buffer, which the
+	/// default implementation assumes.
+	fn clear_stencil(&mut self, value: u32) -> Result<(), StencilError> {
+		let _ = value;
+		Err(StencilError::NoStencilBuffer)
+	}
+
 
     /// Save the current OpenGL render states and matrices.
     ///
@@ -205,4 +236,15 @@ pub trait RenderTarget {
 
 	#[doc(hidden)]
     fn draw_primitives_rs(&mut self, vertices: &[Vertex], ty: PrimitiveType, rs: &RenderStates);
+
+	/// Draw `count` vertices of a vertex buffer, starting at `first`. The
+	/// range is clamped to the buffer.
+	///
+	/// The default implementation draws the vertices from system memory.
+	#[doc(hidden)]
+    fn draw_vertex_buffer_rs(&mut self, buffer: &VertexBuffer, first: u32, count: u32, rs: &RenderStates) {
+        let (first, count) = buffer.clamp_range(first, count);
+        let vertices = &buffer.get_vertices()[first as usize..(first + count) as usize];
+        self.draw_primitives_rs(vertices, buffer.get_primitive_type(), rs);
+    }
 }
@@ -28,8 +28,10 @@ use libc::c_uint;
 
 use system::{Vector2f, Vector2i, Vector2u};
 use graphics::{View, Color, IntRect, CircleShape, RectangleShape, Text,
-               RenderStates, Sprite, Texture,
-               RenderTarget, Vertex, PrimitiveType, BaseShape};
+               RenderStates, Sprite, Texture, Image,
+               RenderTarget, Vertex, VertexBuffer, PrimitiveType, BaseShape};
+
+use graphics::stencil::{self, GlStatesTarget};
 
 use ffi::{SfBool, Foreign, Ref};
 use ffi::graphics as ffi;
@@ -134,11 +136,25 @@ impl RenderTexture {
     }
 }
 
+impl GlStatesTarget for RenderTexture {
+	fn activate_gl_states(&mut self, reset: bool) {
+		self.set_active(true);
+		if reset {
+			unsafe { ffi::sfRenderTexture_resetGLStates(self.raw_mut()) }
+		}
+	}
+
+}
+
 impl RenderTarget for RenderTexture {
     fn get_size(&self) -> Vector2u {
         unsafe { ffi::sfRenderTexture_getSize(self.raw()) }
     }
 
+    fn capture(&self) -> Option<Image> {
+        self.get_texture().and_then(|texture| texture.copy_to_image())
+    }
+
     fn clear(&mut self, color: Color) {
         unsafe { ffi::sfRenderTexture_clear(self.raw_mut(), color) }
     }
@@ -176,43 +192,55 @@ impl RenderTarget for RenderTexture {
     }
 
     fn draw_text_rs(&mut self, text: &Text, rs: &RenderStates) {
-        unsafe {
-            ffi::sfRenderTexture_drawText(self.raw_mut(), text.unwrap(), &rs.unwrap())
-        }
+        stencil::draw_with_gl_states(self, rs, |target, states| unsafe {
+            ffi::sfRenderTexture_drawText(target.raw_mut(), text.unwrap(), states)
+        })
     }
 
     fn draw_shape_rs(&mut self, shape: &BaseShape, rs: &RenderStates) {
-        unsafe {
-            ffi::sfRenderTexture_drawShape(self.raw_mut(), shape.unwrap(), &rs.unwrap())
-        }
+        stencil::draw_with_gl_states(self, rs, |target, states| unsafe {
+            ffi::sfRenderTexture_drawShape(target.raw_mut(), shape.unwrap(), states)
+        })
     }
 
     fn draw_sprite_rs(&mut self, sprite: &Sprite, rs: &RenderStates) {
-        unsafe {
-            ffi::sfRenderTexture_drawSprite(self.raw_mut(), sprite.unwrap(), &rs.unwrap())
-        }
+        stencil::draw_with_gl_states(self, rs, |target, states| unsafe {
+            ffi::sfRenderTexture_drawSprite(target.raw_mut(), sprite.unwrap(), states)
+        })
     }
 
     fn draw_circle_shape_rs(&mut self, circle: &CircleShape, rs: &RenderStates) {
-        unsafe {
-            ffi::sfRenderTexture_drawCircleShape(self.raw_mut(), circle.unwrap(), &rs.unwrap())
-        }
+        stencil::draw_with_gl_states(self, rs, |target, states| unsafe {
+            ffi::sfRenderTexture_drawCircleShape(target.raw_mut(), circle.unwrap(), states)
+        })
     }
 
     fn draw_rectangle_shape_rs(&mut self, rect: &RectangleShape, rs: &RenderStates) {
-        unsafe {
-            ffi::sfRenderTexture_drawRectangleShape(self.raw_mut(), rect.unwrap(), &rs.unwrap())
-        }
+        stencil::draw_with_gl_states(self, rs, |target, states| unsafe {
+            ffi::sfRenderTexture_drawRectangleShape(target.raw_mut(), rect.unwrap(), states)
+        })
     }
 
     fn draw_primitives_rs(&mut self, vertices: &[Vertex], ty: PrimitiveType, rs: &RenderStates) {
-        unsafe {
-            ffi::sfRenderTexture_drawPrimitives(self.raw_mut(),
+        stencil::draw_with_gl_states(self, rs, |target, states| unsafe {
+            ffi::sfRenderTexture_drawPrimitives(target.raw_mut(),
                                                 vertices.as_ptr(),
                                                 vertices.len() as u32,
                                                 ty,
-                                                &rs.unwrap());
-        }
+                                                states)
+        })
+    }
+
+    fn draw_vertex_buffer_rs(&mut self, buffer: &VertexBuffer, first: u32, count: u32, rs: &RenderStates) {
+        stencil::draw_with_gl_states(self, rs, |target, states| {
+            buffer.draw_with(first, count, states, |vertices, ty| unsafe {
+                ffi::sfRenderTexture_drawPrimitives(target.raw_mut(),
+                                                    vertices.as_ptr(),
+                                                    vertices.len() as u32,
+                                                    ty,
+                                                    states)
+            })
+        })
     }
 
     fn push_gl_states(&mut self) {
@@ -28,8 +28,10 @@ use window::{ContextSettings, VideoMode, Event, WindowStyle};
 use window::raw_event::sfEvent;
 use system::{Vector2f, Vector2i, Vector2u};
 use graphics::{Color, CircleShape, RectangleShape, Text, Sprite,
-               RenderStates, View, Image, IntRect, RenderTarget,
-               Vertex, PrimitiveType, BaseShape};
+               RenderStates, View, Image, IntRect, RenderTarget, StencilError,
+               Vertex, VertexBuffer, PrimitiveType, BaseShape};
+
+use graphics::stencil::{self, GlStatesTarget};
 
 use ffi::{SfBool, Foreign, Ref};
 use ffi::graphics as ffi;
@@ -370,6 +372,16 @@ impl RenderWindow {
     }
 }
 
+impl GlStatesTarget for RenderWindow {
+	fn activate_gl_states(&mut self, reset: bool) {
+		self.set_active(true);
+		if reset {
+			unsafe { ffi::sfRenderWindow_resetGLStates(self.raw_mut()) }
+		}
+	}
+
+}
+
 impl RenderTarget for RenderWindow {
     fn push_gl_states(&mut self) {
         unsafe { ffi::sfRenderWindow_pushGLStates(self.raw_mut()) }
@@ -419,44 +431,73 @@ impl RenderTarget for RenderWindow {
         unsafe { ffi::sfRenderWindow_getSize(self.raw()) }
     }
 
+    fn capture(&self) -> Option<Image> {
+        RenderWindow::capture(self)
+    }
+
+	fn has_stencil_buffer(&self) -> bool {
+		self.get_settings().stencil_bits > 0
+	}
+
+	fn clear_stencil(&mut self, value: u32) -> Result<(), StencilError> {
+		if !self.has_stencil_buffer() {
+			return Err(StencilError::NoStencilBuffer)
+		}
+		self.set_active(true);
+		stencil::clear_stencil(value);
+		Ok(())
+	}
+
     fn draw_text_rs(&mut self, text: &Text, rs: &RenderStates) {
-        unsafe {
-            ffi::sfRenderWindow_drawText(self.raw_mut(), text.unwrap(), &rs.unwrap())
-        }
+        stencil::draw_with_gl_states(self, rs, |target, states| unsafe {
+            ffi::sfRenderWindow_drawText(target.raw_mut(), text.unwrap(), states)
+        })
     }
 
     fn draw_shape_rs(&mut self, shape: &BaseShape, rs: &RenderStates) {
-        unsafe {
-            ffi::sfRenderWindow_drawShape(self.raw_mut(), shape.unwrap(), &rs.unwrap())
-        }
+        stencil::draw_with_gl_states(self, rs, |target, states| unsafe {
+            ffi::sfRenderWindow_drawShape(target.raw_mut(), shape.unwrap(), states)
+        })
     }
 
     fn draw_sprite_rs(&mut self, sprite: &Sprite, rs: &RenderStates) {
-        unsafe {
-            ffi::sfRenderWindow_drawSprite(self.raw_mut(), sprite.unwrap(), &rs.unwrap())
-        }
+        stencil::draw_with_gl_states(self, rs, |target, states| unsafe {
+            ffi::sfRenderWindow_drawSprite(target.raw_mut(), sprite.unwrap(), states)
+        })
     }
 
     fn draw_circle_shape_rs(&mut self, circle: &CircleShape, rs: &RenderStates) {
-        unsafe {
-            ffi::sfRenderWindow_drawCircleShape(self.raw_mut(), circle.unwrap(), &rs.unwrap())
-        }
+        stencil::draw_with_gl_states(self, rs, |target, states| unsafe {
+            ffi::sfRenderWindow_drawCircleShape(target.raw_mut(), circle.unwrap(), states)
+        })
     }
 
     fn draw_rectangle_shape_rs(&mut self, rect: &RectangleShape, rs: &RenderStates) {
-        unsafe {
-            ffi::sfRenderWindow_drawRectangleShape(self.raw_mut(), rect.unwrap(), &rs.unwrap())
-        }
+        stencil::draw_with_gl_states(self, rs, |target, states| unsafe {
+            ffi::sfRenderWindow_drawRectangleShape(target.raw_mut(), rect.unwrap(), states)
+        })
     }
 
     fn draw_primitives_rs(&mut self, vertices: &[Vertex], ty: PrimitiveType, rs: &RenderStates) {
-        unsafe {
-            ffi::sfRenderWindow_drawPrimitives(self.raw_mut(),
+        stencil::draw_with_gl_states(self, rs, |target, states| unsafe {
+            ffi::sfRenderWindow_drawPrimitives(target.raw_mut(),
                                                vertices.as_ptr(),
                                                vertices.len() as u32,
                                                ty,
-                                               &rs.unwrap());
-        }
+                                               states)
+        })
+    }
+
+    fn draw_vertex_buffer_rs(&mut self, buffer: &VertexBuffer, first: u32, count: u32, rs: &RenderStates) {
+        stencil::draw_with_gl_states(self, rs, |target, states| {
+            buffer.draw_with(first, count, states, |vertices, ty| unsafe {
+                ffi::sfRenderWindow_drawPrimitives(target.raw_mut(),
+                                                   vertices.as_ptr(),
+                                                   vertices.len() as u32,
+                                                   ty,
+                                                   states)
+            })
+        })
     }
 
     fn clear(&mut self, color: Color) {
@@ -0,0 +1,665 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use std::collections::HashMap;
+
+use graphics::{BitmapFont, BitmapFontError, Color, CurrentTexture, Drawable, FloatRect, Font, Glyph, Image,
+               IntRect, RenderStates, RenderTarget, Shader, Texture, Transform, Transformable, Uniform};
+use graphics::text_layout::{GlyphSource, LayoutOptions, RichText, Span, SpanStyle, TextLayout};
+use system::Vector2f;
+use ffi::Ref;
+
+static SDF_SHADER: &'static str = r#"
+uniform sampler2D texture;
+uniform vec4 outline_color;
+uniform float outline_width;
+uniform vec4 glow_color;
+uniform float glow_width;
+uniform vec4 shadow_color;
+uniform vec2 shadow_offset;
+uniform float shadow_softness;
+
+// Composite premultiplied colors
+vec4 over(vec4 top, vec4 bottom) {
+	return top + bottom * (1.0 - top.a);
+}
+
+vec4 layer(vec4 color, float coverage) {
+	return vec4(color.rgb, 1.0) * color.a * coverage;
+}
+
+void main() {
+	vec2 uv = gl_TexCoord[0].xy;
+	float distance = texture2D(texture, uv).a;
+	float width = max(fwidth(distance), 0.0001);
+	float edge = 0.5 - outline_width;
+	float fill = smoothstep(0.5 - width, 0.5 + width, distance);
+	float outline = smoothstep(edge - width, edge + width, distance);
+	float glow = glow_width > 0.0 ? smoothstep(edge - glow_width, edge, distance) : 0.0;
+	float shadow_distance = texture2D(texture, uv - shadow_offset).a;
+	float shadow = smoothstep(edge - shadow_softness - width, edge + shadow_softness + width, shadow_distance);
+
+	vec4 color = layer(shadow_color, shadow);
+	color = over(layer(glow_color, glow), color);
+	color = over(layer(outline_color, outline), color);
+	color = over(layer(gl_Color, fill), color);
+	gl_FragColor = color.a > 0.0 ? vec4(color.rgb / color.a, color.a) : vec4(0.0);
+}
+"#;
+
+// Squared distance transform of a sampled function, in one dimension
+// (Felzenszwalb and Huttenlocher).
+fn transform_line(f: &[f32], d: &mut [f32], v: &mut [usize], z: &mut [f32]) {
+	let n = f.len();
+	let mut k = 0;
+	v[0] = 0;
+	z[0] = ::std::f32::NEG_INFINITY;
+	z[1] = ::std::f32::INFINITY;
+	for q in 1..n {
+		loop {
+			let p = v[k];
+			let s = ((f[q] + (q * q) as f32) - (f[p] + (p * p) as f32)) / (2. * q as f32 - 2. * p as f32);
+			if s <= z[k] && k > 0 {
+				k -= 1;
+			} else if s <= z[k] {
+				// Only reachable with infinite values; keep the current parabola
+				break
+			} else {
+				k += 1;
+				v[k] = q;
+				z[k] = s;
+				z[k + 1] = ::std::f32::INFINITY;
+				break
+			}
+		}
+	}
+	k = 0;
+	for q in 0..n {
+		while z[k + 1] < q as f32 {
+			k += 1;
+		}
+		let p = v[k];
+		d[q] = (q as f32 - p as f32) * (q as f32 - p as f32) + f[p];
+	}
+}
+
+// Squared Euclidean distance from each pixel to the nearest pixel for which
+// `feature` is true.
+fn distance_transform(features: &[bool], width: usize, height: usize) -> Vec<f32> {
+	let big = ((width * width + height * height) as f32) * 4. + 1.;
+	let mut grid: Vec<f32> = features.iter().map(|&f| if f { 0. } else { big }).collect();
+	let n = width.max(height);
+	let (mut f, mut d) = (vec![0.; n], vec![0.; n]);
+	let (mut v, mut z) = (vec![0; n], vec![0.; n + 1]);
+	for x in 0..width {
+		for y in 0..height {
+			f[y] = grid[y * width + x];
+		}
+		transform_line(&f[..height], &mut d[..height], &mut v, &mut z);
+		for y in 0..height {
+			grid[y * width + x] = d[y];
+		}
+	}
+	for y in 0..height {
+		f[..width].copy_from_slice(&grid[y * width..(y + 1) * width]);
+		transform_line(&f[..width], &mut d[..width], &mut v, &mut z);
+		grid[y * width..(y + 1) * width].copy_from_slice(&d[..width]);
+	}
+	grid
+}
+
+/// Compute a signed distance field from a coverage bitmap.
+///
+/// Pixels of `coverage` of 128 or more are inside the shape. In the result,
+/// the edge of the shape is at 128, values increase inside and decrease
+/// outside, reaching 255 and 0 at `spread` pixels from the edge.
+pub fn distance_field(coverage: &[u8], width: usize, height: usize, spread: f32) -> Vec<u8> {
+	let inside: Vec<bool> = coverage.iter().map(|&c| c >= 128).collect();
+	let outside: Vec<bool> = inside.iter().map(|&i| !i).collect();
+	let to_inside = distance_transform(&inside, width, height);
+	let to_outside = distance_transform(&outside, width, height);
+	(0..width * height).map(|i| {
+		let distance = if inside[i] { -(to_outside[i].sqrt() - 0.5) } else { to_inside[i].sqrt() - 0.5 };
+		let value = 0.5 - distance / (2. * spread);
+		(value.max(0.).min(1.) * 255. + 0.5) as u8
+	}).collect()
+}
+
+/// Place rectangles of the given sizes in rows no wider than `width`,
+/// tallest first.
+///
+/// Returns the position of each rectangle, in the order given, and the
+/// total height used, or None if a rectangle is wider than `width`.
+pub fn pack_rects(sizes: &[(u32, u32)], width: u32) -> Option<(Vec<(u32, u32)>, u32)> {
+	let mut order: Vec<usize> = (0..sizes.len()).collect();
+	order.sort_by(|&a, &b| sizes[b].1.cmp(&sizes[a].1));
+	let mut positions = vec![(0, 0); sizes.len()];
+	let (mut x, mut y, mut row) = (0, 0, 0);
+	for i in order {
+		let (w, h) = sizes[i];
+		if w > width {
+			return None
+		}
+		if x + w > width {
+			x = 0;
+			y += row;
+			row = 0;
+		}
+		positions[i] = (x, y);
+		x += w;
+		row = row.max(h);
+	}
+	Some((positions, y + row))
+}
+
+/// A font whose glyphs are stored as signed distance fields.
+///
+/// Distance field glyphs stay sharp at any scale, and allow cheap outlines,
+/// glows and shadows; they are drawn by `SdfText`. An `SdfFont` is either
+/// generated from a `Font` once at load time, or loaded from a distance
+/// field atlas in the BMFont format, as produced by tools such as Hiero.
+pub struct SdfFont {
+	glyphs: HashMap<char, (Glyph, u32)>,
+	kernings: HashMap<(char, char), f32>,
+	size: u32,
+	line_spacing: f32,
+	underline_position: f32,
+	underline_thickness: f32,
+	spread: f32,
+	pages: Vec<Texture>
+}
+
+impl SdfFont {
+	/// Generate distance field glyphs for `characters` from a font.
+	///
+	/// The glyphs are rendered at `base_size`, large enough to hold the
+	/// details of the font (48 to 64 pixels is typical), and their distance
+	/// fields extend `spread` pixels around them, which limits the width of
+	/// outlines, glows and shadows.
+	///
+	/// Returns None if the atlas could not be created.
+	pub fn from_font(font: &Font, characters: &str, base_size: u32, spread: u32) -> Option<SdfFont> {
+		let mut chars: Vec<char> = characters.chars().filter(|c| !c.is_control()).collect();
+		chars.push(' ');
+		chars.sort();
+		chars.dedup();
+
+		let glyphs: Vec<Glyph> = chars.iter().map(|&c| font.get_glyph(c, base_size, false)).collect();
+		let source = match font.get_shared_texture(base_size).and_then(|texture| texture.copy_to_image()) {
+			Some(image) => image,
+			None => return None
+		};
+		let (source_width, pixels) = (source.get_size().x as i32, source.get_pixels());
+
+		// A solid block for underlines comes first, then padded glyphs
+		let pad = spread as i32;
+		let mut sizes = vec![(4, 4)];
+		sizes.extend(glyphs.iter().map(|g| {
+			if g.texture_rect.width > 0 { ((g.texture_rect.width + 2 * pad) as u32, (g.texture_rect.height + 2 * pad) as u32) }
+			else { (0, 0) }
+		}));
+		let area: u32 = sizes.iter().map(|&(w, h)| w * h).sum();
+		let widest = sizes.iter().map(|&(w, _)| w).max().unwrap_or(0);
+		let width = ((area as f32 * 1.2).sqrt() as u32).max(widest).max(64).next_power_of_two();
+		let (positions, height) = match pack_rects(&sizes, width) {
+			Some(packed) => packed,
+			None => return None
+		};
+
+		let mut atlas = vec![255u8; (width * height.max(1) * 4) as usize];
+		for alpha in atlas.chunks_mut(4) {
+			alpha[3] = 0;
+		}
+		for y in 0..4 {
+			for x in 0..4 {
+				atlas[((y * width + x) * 4 + 3) as usize] = 255;
+			}
+		}
+
+		let mut table = HashMap::new();
+		for (i, (&c, glyph)) in chars.iter().zip(glyphs.iter()).enumerate() {
+			let (w, h) = sizes[i + 1];
+			let (ax, ay) = positions[i + 1];
+			let rect = glyph.texture_rect;
+			if w > 0 {
+				let mut coverage = vec![0u8; (w * h) as usize];
+				for y in 0..rect.height {
+					for x in 0..rect.width {
+						let (sx, sy) = (rect.left + x, rect.top + y);
+						coverage[((y + pad) as u32 * w + (x + pad) as u32) as usize] =
+							pixels[((sy * source_width + sx) * 4 + 3) as usize];
+					}
+				}
+				let field = distance_field(&coverage, w as usize, h as usize, spread.max(1) as f32);
+				for y in 0..h {
+					for x in 0..w {
+						atlas[(((ay + y) * width + ax + x) * 4 + 3) as usize] = field[(y * w + x) as usize];
+					}
+				}
+			}
+			let b = glyph.bounds;
+			table.insert(c, (Glyph {
+				advance: glyph.advance,
+				bounds: if w > 0 {
+					FloatRect::new(b.left - pad as f32, b.top - pad as f32, b.width + 2. * pad as f32, b.height + 2. * pad as f32)
+				} else {
+					b
+				},
+				texture_rect: IntRect::new(ax as i32, ay as i32, w as i32, h as i32)
+			}, 0));
+		}
+
+		let mut kernings = HashMap::new();
+		for &first in &chars {
+			for &second in &chars {
+				let kerning = font.get_kerning(first, second, base_size);
+				if kerning != 0. {
+					kernings.insert((first, second), kerning);
+				}
+			}
+		}
+
+		let image = match Image::create_from_pixels(width, height.max(1), &atlas) {
+			Some(image) => image,
+			None => return None
+		};
+		let mut texture = match Texture::new_from_image(&image, None) {
+			Some(texture) => texture,
+			None => return None
+		};
+		texture.set_smooth(true);
+		Some(SdfFont {
+			glyphs: table,
+			kernings: kernings,
+			size: base_size,
+			line_spacing: font.get_line_spacing(base_size),
+			underline_position: font.get_underline_position(base_size),
+			underline_thickness: font.get_underline_thickness(base_size),
+			spread: spread as f32,
+			pages: vec![texture]
+		})
+	}
+
+	/// Load a prebuilt distance field font from a BMFont descriptor.
+	///
+	/// `spread` is the distance, in atlas pixels, over which the fields go
+	/// from the edge to fully inside or outside.
+	pub fn new_from_file(filename: &str, spread: f32) -> Result<SdfFont, BitmapFontError> {
+		BitmapFont::new_from_file(filename).map(|font| SdfFont::from_bitmap_font(font, spread))
+	}
+
+	/// Use a loaded bitmap font, whose pages hold distance fields in their
+	/// alpha channel, as a distance field font.
+	pub fn from_bitmap_font(font: BitmapFont, spread: f32) -> SdfFont {
+		let (data, mut pages) = font.into_parts();
+		for page in &mut pages {
+			page.set_smooth(true);
+		}
+		let glyphs = data.chars.iter().map(|(&c, info)| (c, (data.get_glyph(c, data.size), info.page))).collect();
+		let kernings = data.kernings.iter().map(|(&pair, &amount)| (pair, amount as f32)).collect();
+		let descent = data.line_height.saturating_sub(data.base).max(2);
+		SdfFont {
+			glyphs: glyphs,
+			kernings: kernings,
+			size: data.size.max(1),
+			line_spacing: data.line_height as f32,
+			underline_position: (descent / 2) as f32,
+			underline_thickness: (data.size as f32 / 14.).max(1.),
+			spread: spread,
+			pages: pages
+		}
+	}
+
+	/// Get the size the glyphs were rendered at.
+	pub fn get_size(&self) -> u32 {
+		self.size
+	}
+
+	/// Get the distance, in atlas pixels, covered by the distance fields on
+	/// each side of the edges.
+	pub fn get_spread(&self) -> f32 {
+		self.spread
+	}
+
+	/// Get an atlas page.
+	pub fn get_page(&self, index: u32) -> Option<&Texture> {
+		self.pages.get(index as usize)
+	}
+
+	/// Tell whether the font has a glyph for a character.
+	pub fn has_glyph(&self, codepoint: char) -> bool {
+		self.glyphs.contains_key(&codepoint)
+	}
+
+	fn scale(&self, character_size: u32) -> f32 {
+		if character_size == 0 { 1. } else { character_size as f32 / self.size as f32 }
+	}
+}
+
+impl GlyphSource for SdfFont {
+	fn get_glyph(&self, codepoint: char, character_size: u32, _bold: bool) -> Glyph {
+		let scale = self.scale(character_size);
+		match self.glyphs.get(&codepoint) {
+			Some(&(glyph, _)) => Glyph {
+				advance: glyph.advance * scale,
+				bounds: FloatRect::new(glyph.bounds.left * scale, glyph.bounds.top * scale,
+				                       glyph.bounds.width * scale, glyph.bounds.height * scale),
+				texture_rect: glyph.texture_rect
+			},
+			None => Glyph {
+				advance: 0.,
+				bounds: FloatRect::new(0., 0., 0., 0.),
+				texture_rect: IntRect::new(0, 0, 0, 0)
+			}
+		}
+	}
+
+	fn get_kerning(&self, first: char, second: char, character_size: u32) -> f32 {
+		self.kernings.get(&(first, second)).map_or(0., |&kerning| kerning * self.scale(character_size))
+	}
+
+	fn get_line_spacing(&self, character_size: u32) -> f32 {
+		self.line_spacing * self.scale(character_size)
+	}
+
+	fn get_underline_position(&self, character_size: u32) -> f32 {
+		self.underline_position * self.scale(character_size)
+	}
+
+	fn get_underline_thickness(&self, character_size: u32) -> f32 {
+		self.underline_thickness * self.scale(character_size)
+	}
+
+	fn get_texture<'a>(&'a self, codepoint: char, _character_size: u32, _bold: bool) -> Option<Ref<'a, Texture>> {
+		self.pages.get(self.get_texture_index(codepoint)).and_then(|texture| unsafe { Ref::new(texture.unwrap()) })
+	}
+
+	fn get_texture_index(&self, codepoint: char) -> usize {
+		self.glyphs.get(&codepoint).map_or(0, |&(_, page)| page as usize)
+	}
+}
+
+/// Text drawn with a distance field font, sharp at any scale.
+///
+/// `SdfText` has the interface of `Text`, plus an outline, a glow and a drop
+/// shadow, all rendered by a bundled shader in a single pass. Their sizes
+/// are in pixels at the text's character size, and can not exceed the
+/// font's spread, scaled to that size.
+pub struct SdfText<'s> {
+	text: RichText<'s>,
+	font: &'s SdfFont,
+	string: String,
+	style: SpanStyle,
+	shader: Shader<'s>,
+	uniforms: Vec<Uniform>,
+	outline: (Color, f32),
+	glow: (Color, f32),
+	shadow: (Color, Vector2f, f32)
+}
+
+impl<'s> SdfText<'s> {
+	/// Create a new empty text drawn with `font` at its base size.
+	///
+	/// Returns None if shaders are not available, see
+	/// `Shader::is_available()`.
+	pub fn new(font: &'s SdfFont) -> Option<SdfText<'s>> {
+		let mut shader = match Shader::new_from_memory(None, Some(SDF_SHADER)) {
+			Some(shader) => shader,
+			None => return None
+		};
+		if shader.set_parameter("texture", CurrentTexture).is_err() {
+			return None
+		}
+		let mut uniforms = Vec::new();
+		for name in &["outline_color", "outline_width", "glow_color", "glow_width",
+		              "shadow_color", "shadow_offset", "shadow_softness"] {
+			match shader.uniform(name) {
+				Ok(uniform) => uniforms.push(uniform),
+				Err(_) => return None
+			}
+		}
+		RichText::new(font, LayoutOptions::new()).map(|text| {
+			let mut text = SdfText {
+				text: text,
+				font: font,
+				string: String::new(),
+				style: SpanStyle::new(Color::white(), font.get_size()),
+				shader: shader,
+				uniforms: uniforms,
+				outline: (Color::transparent(), 0.),
+				glow: (Color::transparent(), 0.),
+				shadow: (Color::transparent(), Vector2f::new(0., 0.), 0.)
+			};
+			text.update_shader();
+			text
+		})
+	}
+
+	/// Create a new text from a string, font, and size.
+	///
+	/// Returns None if shaders are not available.
+	pub fn new_init(string: &str, font: &'s SdfFont, character_size: u32) -> Option<SdfText<'s>> {
+		SdfText::new(font).map(|mut text| {
+			text.set_character_size(character_size);
+			text.set_string(string);
+			text
+		})
+	}
+
+	fn update(&mut self) {
+		self.text.set_spans(vec![Span::new(&self.string, self.style)]);
+	}
+
+	// Convert sizes in pixels at the character size to distance field units.
+	fn update_shader(&mut self) {
+		let pixel = 1. / (self.font.scale(self.style.character_size) * 2. * self.font.spread.max(1.));
+		let page = self.font.get_page(0).map_or(Vector2f::new(1., 1.), |page| {
+			let size = page.get_size();
+			Vector2f::new(size.x as f32, size.y as f32)
+		});
+		let texel = self.font.scale(self.style.character_size);
+		let offset = Vector2f::new(self.shadow.1.x / texel / page.x, self.shadow.1.y / texel / page.y);
+		let shader = &mut self.shader;
+		let uniforms = &self.uniforms;
+		shader.set_uniform(&uniforms[0], self.outline.0);
+		shader.set_uniform(&uniforms[1], (self.outline.1 * pixel).min(0.5));
+		shader.set_uniform(&uniforms[2], self.glow.0);
+		shader.set_uniform(&uniforms[3], self.glow.1 * pixel);
+		shader.set_uniform(&uniforms[4], self.shadow.0);
+		shader.set_uniform(&uniforms[5], offset);
+		shader.set_uniform(&uniforms[6], self.shadow.2 * pixel);
+	}
+
+	/// Set the text's string.
+	pub fn set_string(&mut self, string: &str) {
+		self.string = string.to_owned();
+		self.update();
+	}
+
+	/// Get the text's string.
+	pub fn get_string(&self) -> &str {
+		&self.string
+	}
+
+	/// Set the text's font.
+	pub fn set_font(&mut self, font: &'s SdfFont) {
+		self.font = font;
+		self.text.set_source(font);
+		self.update_shader();
+	}
+
+	/// Get the text's font.
+	pub fn get_font(&self) -> &'s SdfFont {
+		self.font
+	}
+
+	/// Set the character size in pixels.
+	pub fn set_character_size(&mut self, size: u32) {
+		self.style.character_size = size;
+		self.update();
+		self.update_shader();
+	}
+
+	/// Get the character size, in pixels.
+	pub fn get_character_size(&self) -> u32 {
+		self.style.character_size
+	}
+
+	/// Set the text's style. Bold is not available for distance field
+	/// fonts.
+	pub fn set_style(&mut self, style: ::graphics::TextStyle) {
+		self.style.style = style;
+		self.update();
+	}
+
+	/// Get the text's style.
+	pub fn get_style(&self) -> ::graphics::TextStyle {
+		self.style.style
+	}
+
+	/// Set the fill color of the text.
+	pub fn set_color(&mut self, color: Color) {
+		self.style.color = color;
+		self.update();
+	}
+
+	/// Get the fill color of the text.
+	pub fn get_color(&self) -> Color {
+		self.style.color
+	}
+
+	/// Set the color and thickness of the outline. A thickness of zero
+	/// disables it.
+	pub fn set_outline(&mut self, color: Color, thickness: f32) {
+		self.outline = (color, thickness.max(0.));
+		self.update_shader();
+	}
+
+	/// Get the color and thickness of the outline.
+	pub fn get_outline(&self) -> (Color, f32) {
+		self.outline
+	}
+
+	/// Set the color and width of the glow, which fades out around the
+	/// outline. A width of zero disables it.
+	pub fn set_glow(&mut self, color: Color, width: f32) {
+		self.glow = (color, width.max(0.));
+		self.update_shader();
+	}
+
+	/// Get the color and width of the glow.
+	pub fn get_glow(&self) -> (Color, f32) {
+		self.glow
+	}
+
+	/// Set the color, offset and blur radius of the drop shadow. A
+	/// transparent color disables it.
+	pub fn set_shadow(&mut self, color: Color, offset: Vector2f, softness: f32) {
+		self.shadow = (color, offset, softness.max(0.));
+		self.update_shader();
+	}
+
+	/// Get the color, offset and blur radius of the drop shadow.
+	pub fn get_shadow(&self) -> (Color, Vector2f, f32) {
+		self.shadow
+	}
+
+	/// Get the layout of the text, in local coordinates.
+	pub fn get_layout(&self) -> &TextLayout {
+		self.text.get_layout()
+	}
+
+	/// Return the position of the `index`-th character, in global
+	/// coordinates.
+	pub fn find_character_pos(&self, index: usize) -> Vector2f {
+		self.get_transform().transform_point(self.get_layout().find_character_pos(index))
+	}
+
+	/// Get the local bounding rectangle of the text.
+	///
+	/// The bounds include the padding of the distance fields around the
+	/// glyphs, where outlines and shadows are drawn.
+	pub fn get_local_bounds(&self) -> FloatRect {
+		self.text.get_local_bounds()
+	}
+
+	/// Get the global bounding rectangle of the text.
+	pub fn get_global_bounds(&self) -> FloatRect {
+		self.text.get_global_bounds()
+	}
+}
+
+impl<'s> Transformable for SdfText<'s> {
+	fn set_position(&mut self, position: Vector2f) { self.text.set_position(position) }
+	fn set_rotation(&mut self, angle: f32) { self.text.set_rotation(angle) }
+	fn set_scale(&mut self, scale: Vector2f) { self.text.set_scale(scale) }
+	fn set_origin(&mut self, origin: Vector2f) { self.text.set_origin(origin) }
+	fn get_position(&self) -> Vector2f { self.text.get_position() }
+	fn get_rotation(&self) -> f32 { self.text.get_rotation() }
+	fn get_scale(&self) -> Vector2f { self.text.get_scale() }
+	fn get_origin(&self) -> Vector2f { self.text.get_origin() }
+	fn move_(&mut self, offset: Vector2f) { self.text.move_(offset) }
+	fn rotate(&mut self, angle: f32) { self.text.rotate(angle) }
+	fn scale(&mut self, factors: Vector2f) { self.text.scale(factors) }
+	fn get_transform(&self) -> Transform { self.text.get_transform() }
+	fn get_inverse_transform(&self) -> Transform { self.text.get_inverse_transform() }
+}
+
+impl<'s> Drawable for SdfText<'s> {
+	fn draw(&self, target: &mut RenderTarget, states: &RenderStates) {
+		self.text.draw(target, &RenderStates {
+			shader: Some(&self.shader),
+			.. *states
+		});
+	}
+}
+
+#[test]
+fn sdf_distance_field() {
+	// A 4x4 square in the middle of a 12x12 bitmap
+	let mut coverage = vec![0u8; 144];
+	for y in 4..8 {
+		for x in 4..8 {
+			coverage[y * 12 + x] = 255;
+		}
+	}
+	let field = distance_field(&coverage, 12, 12, 4.);
+	let at = |x: usize, y: usize| field[y * 12 + x];
+	// Half a pixel from the edge, on either side
+	assert_eq!(at(4, 5), 143);
+	assert_eq!(at(3, 5), 112);
+	// Deeper inside, and further out along a diagonal
+	assert_eq!(at(5, 5), 175);
+	assert!(at(1, 1) < at(2, 2) && at(0, 0) == 0);
+	assert!((0..12).all(|i| at(i, 5) == at(11 - i, 5)));
+}
+
+#[test]
+fn sdf_pack_rects() {
+	let sizes = [(10, 5), (20, 10), (15, 8), (40, 3)];
+	let (positions, height) = pack_rects(&sizes, 45).unwrap();
+	assert_eq!(positions, vec![(35, 0), (0, 0), (20, 0), (0, 10)]);
+	assert_eq!(height, 13);
+	assert!(pack_rects(&sizes, 30).is_none());
+}
@@ -26,6 +26,9 @@ use std::ptr;
 use std::ffi::CString;
 use std::marker::PhantomData;
 use std::io::{Read, Seek};
+use std::fs::File;
+use std::error::Error;
+use std::fmt;
 
 use graphics::{Texture, Color, Transform};
 use system::{Vector2f, Vector3f, InputStream};
@@ -84,10 +87,15 @@ use ffi::graphics as ffi;
 ///
 /// Like `Texture` that can be used as a raw OpenGL texture, `Shader` can also
 /// be used for custom OpenGL geometry by calling `bind()`.
-pub struct Shader<'s>(Foreign<ffi::sfShader>, PhantomData<&'s Texture>);
+///
+/// Instead of the `set_X_parameter()` family, variables can be set with the
+/// generic `set_parameter()`, which accepts any `ShaderParameter` and reports
+/// unknown names, or through a cached `Uniform` handle with `set_uniform()`.
+pub struct Shader<'s>(Foreign<ffi::sfShader>, PhantomData<&'s Texture>, Option<Vec<String>>);
 
 // The PhantomData represents arguments to set_texture_parameter which must
-// outlive the Shader.
+// outlive the Shader. The vector holds the uniform names declared in the
+// sources, when they are known.
 
 macro_rules! try_string {
 	($val:expr, $fallback:expr) => ({
@@ -131,9 +139,10 @@ impl<'s> Shader<'s> {
 			}
 		};
 
+		let uniforms = uniforms_from_files(&[vertex_shader_filename, fragment_shader_filename]);
         unsafe {
             Foreign::new(ffi::sfShader_createFromFile(vertex_ptr, fragment_ptr))
-        }.map(|shader| Shader(shader, PhantomData))
+        }.map(|shader| Shader(shader, PhantomData, uniforms))
     }
 
     /// Load a vertex and/or fragment shader from source code in memory.
@@ -168,9 +177,13 @@ impl<'s> Shader<'s> {
 			}
 		};
 
+		let mut uniforms = Vec::new();
+		for source in vertex_shader.iter().chain(fragment_shader.iter()) {
+			parse_uniform_names(source, &mut uniforms);
+		}
         unsafe {
             Foreign::new(ffi::sfShader_createFromMemory(vertex_ptr, fragment_ptr))
-        }.map(|shader| Shader(shader, PhantomData))
+        }.map(|shader| Shader(shader, PhantomData, Some(uniforms)))
     }
 
     /// Load a vertex and/or fragment shader from source code in streams.
@@ -205,7 +218,7 @@ impl<'s> Shader<'s> {
 
 		unsafe {
 			Foreign::new(ffi::sfShader_createFromStream(v_stream_ptr, f_stream_ptr))
-		}.map(|shader| Shader(shader, PhantomData))
+		}.map(|shader| Shader(shader, PhantomData, None))
 	}
 
 	fn raw(&self) -> &ffi::sfShader { self.0.as_ref() }
@@ -329,6 +342,62 @@ impl<'s> Shader<'s> {
 		}
 	}
 
+	/// Look up a uniform by name, returning a handle that can be reused with
+	/// `set_uniform()` without allocating.
+	///
+	/// Array elements (`lights[2]`) and struct members (`light.color`) are
+	/// accepted; only the part before the first `[` or `.` is checked.
+	///
+	/// Returns an error if the name contains a nul byte, or if the shader
+	/// was loaded from files or memory and its sources declare no uniform of
+	/// that name. Shaders loaded from streams accept any name.
+	pub fn uniform(&self, name: &str) -> Result<Uniform, ShaderError> {
+		let uniform = match Uniform::new(name) {
+			Some(uniform) => uniform,
+			None => return Err(ShaderError::InvalidName(name.to_string()))
+		};
+		if let Some(ref names) = self.2 {
+			let base = name.split(|c| c == '[' || c == '.').next().unwrap_or(name);
+			if !names.iter().any(|n| n == base) {
+				return Err(ShaderError::UnknownUniform(name.to_string()))
+			}
+		}
+		Ok(uniform)
+	}
+
+	/// Get the names of the uniforms declared in the shader sources.
+	///
+	/// Returns None if the shader was loaded from streams.
+	pub fn get_uniform_names(&self) -> Option<&[String]> {
+		self.2.as_ref().map(|names| &names[..])
+	}
+
+	/// Change a parameter of the shader, of any supported type.
+	///
+	/// See `uniform()` for the errors this can return.
+	pub fn set_parameter<T: ShaderParameter<'s>>(&mut self, name: &str, value: T) -> Result<(), ShaderError> {
+		let uniform = try!(self.uniform(name));
+		value.apply(self, &uniform);
+		Ok(())
+	}
+
+	/// Change a parameter of the shader through a handle obtained from
+	/// `uniform()`.
+	pub fn set_uniform<T: ShaderParameter<'s>>(&mut self, uniform: &Uniform, value: T) {
+		value.apply(self, uniform);
+	}
+
+	/// Change a group of parameters at once.
+	///
+	/// Nothing is changed if one of the names reported by the group is
+	/// invalid or unknown; otherwise stops at the first error.
+	pub fn set_uniforms<G: UniformGroup<'s>>(&mut self, group: &G) -> Result<(), ShaderError> {
+		for name in group.get_uniform_names() {
+			try!(self.uniform(name));
+		}
+		group.apply(self)
+	}
+
     /// Bind a shader for rendering.
     ///
     /// This function is not part of the graphics API, and mustn't be
@@ -351,3 +420,326 @@ impl<'s> Shader<'s> {
         unsafe { ffi::sfShader_isAvailable() }.to_bool()
     }
 }
+
+/// Handle to a shader variable, which avoids converting its name on every
+/// call.
+///
+/// Handles are obtained with `Shader::uniform()`, or with `Uniform::new()`
+/// when no validation is wanted.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Uniform {
+	name: CString
+}
+
+impl Uniform {
+	/// Create a handle from a name, without checking it against a shader.
+	///
+	/// Returns None if the name contains a nul byte.
+	pub fn new(name: &str) -> Option<Uniform> {
+		CString::new(name).ok().map(|name| Uniform { name: name })
+	}
+
+	/// Get the name of the variable.
+	pub fn get_name(&self) -> &str {
+		self.name.to_str().unwrap_or("")
+	}
+
+	/// Get a handle to the element `index` of an array variable.
+	pub fn index(&self, index: usize) -> Uniform {
+		Uniform::new(&format!("{}[{}]", self.get_name(), index)).unwrap()
+	}
+
+	/// Get a handle to a member of a struct variable.
+	///
+	/// Returns None if the member name contains a nul byte.
+	pub fn member(&self, member: &str) -> Option<Uniform> {
+		Uniform::new(&format!("{}.{}", self.get_name(), member))
+	}
+
+	fn as_ptr(&self) -> *const ::libc::c_char {
+		self.name.as_ptr()
+	}
+}
+
+/// Error returned when setting a shader variable fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShaderError {
+	/// The name contains a nul byte.
+	InvalidName(String),
+	/// The shader sources declare no uniform with that name.
+	UnknownUniform(String)
+}
+
+impl fmt::Display for ShaderError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			ShaderError::InvalidName(ref name) => write!(f, "invalid uniform name {:?}", name),
+			ShaderError::UnknownUniform(ref name) => write!(f, "unknown uniform {:?}", name)
+		}
+	}
+}
+
+impl Error for ShaderError {
+	fn description(&self) -> &str {
+		match *self {
+			ShaderError::InvalidName(_) => "invalid uniform name",
+			ShaderError::UnknownUniform(_) => "unknown uniform"
+		}
+	}
+}
+
+/// A value that can be assigned to a shader variable.
+///
+/// The GLSL type of the variable must match the value:
+///
+/// | Rust type | GLSL type |
+/// |-----------|-----------|
+/// | `f32` | `float` |
+/// | `(f32, f32)`, `Vector2f` | `vec2` |
+/// | `(f32, f32, f32)`, `Vector3f` | `vec3` |
+/// | `(f32, f32, f32, f32)`, `Color` | `vec4` |
+/// | `Transform` | `mat4` |
+/// | `&Texture`, `CurrentTexture` | `sampler2D` |
+/// | `&[T]` | array of the type of `T` |
+///
+/// Implement it for your own types by setting each component with
+/// `Shader::set_uniform()`, for example on the handles returned by
+/// `Uniform::member()`.
+pub trait ShaderParameter<'s> {
+	/// Assign the value to the variable designated by `uniform`.
+	fn apply(&self, shader: &mut Shader<'s>, uniform: &Uniform);
+}
+
+/// Maps a texture variable to the texture of the object being drawn.
+///
+/// See `Shader::set_current_texture_parameter()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CurrentTexture;
+
+impl<'s> ShaderParameter<'s> for f32 {
+	fn apply(&self, shader: &mut Shader<'s>, uniform: &Uniform) {
+		unsafe { ffi::sfShader_setFloatParameter(shader.raw_mut(), uniform.as_ptr(), *self) }
+	}
+}
+
+impl<'s> ShaderParameter<'s> for (f32, f32) {
+	fn apply(&self, shader: &mut Shader<'s>, uniform: &Uniform) {
+		unsafe { ffi::sfShader_setFloat2Parameter(shader.raw_mut(), uniform.as_ptr(), self.0, self.1) }
+	}
+}
+
+impl<'s> ShaderParameter<'s> for (f32, f32, f32) {
+	fn apply(&self, shader: &mut Shader<'s>, uniform: &Uniform) {
+		unsafe {
+			ffi::sfShader_setFloat3Parameter(shader.raw_mut(), uniform.as_ptr(), self.0, self.1, self.2)
+		}
+	}
+}
+
+impl<'s> ShaderParameter<'s> for (f32, f32, f32, f32) {
+	fn apply(&self, shader: &mut Shader<'s>, uniform: &Uniform) {
+		unsafe {
+			ffi::sfShader_setFloat4Parameter(shader.raw_mut(), uniform.as_ptr(), self.0, self.1, self.2, self.3)
+		}
+	}
+}
+
+impl<'s> ShaderParameter<'s> for Vector2f {
+	fn apply(&self, shader: &mut Shader<'s>, uniform: &Uniform) {
+		unsafe { ffi::sfShader_setVector2Parameter(shader.raw_mut(), uniform.as_ptr(), *self) }
+	}
+}
+
+impl<'s> ShaderParameter<'s> for Vector3f {
+	fn apply(&self, shader: &mut Shader<'s>, uniform: &Uniform) {
+		unsafe { ffi::sfShader_setVector3Parameter(shader.raw_mut(), uniform.as_ptr(), *self) }
+	}
+}
+
+impl<'s> ShaderParameter<'s> for Color {
+	fn apply(&self, shader: &mut Shader<'s>, uniform: &Uniform) {
+		unsafe { ffi::sfShader_setColorParameter(shader.raw_mut(), uniform.as_ptr(), *self) }
+	}
+}
+
+impl<'s> ShaderParameter<'s> for Transform {
+	fn apply(&self, shader: &mut Shader<'s>, uniform: &Uniform) {
+		unsafe { ffi::sfShader_setTransformParameter(shader.raw_mut(), uniform.as_ptr(), *self) }
+	}
+}
+
+impl<'s> ShaderParameter<'s> for &'s Texture {
+	fn apply(&self, shader: &mut Shader<'s>, uniform: &Uniform) {
+		unsafe {
+			ffi::sfShader_setTextureParameter(shader.raw_mut(), uniform.as_ptr(), self.unwrap())
+		}
+	}
+}
+
+impl<'s> ShaderParameter<'s> for CurrentTexture {
+	fn apply(&self, shader: &mut Shader<'s>, uniform: &Uniform) {
+		unsafe { ffi::sfShader_setCurrentTextureParameter(shader.raw_mut(), uniform.as_ptr()) }
+	}
+}
+
+impl<'a, 's, T: ShaderParameter<'s>> ShaderParameter<'s> for &'a [T] {
+	fn apply(&self, shader: &mut Shader<'s>, uniform: &Uniform) {
+		for (i, value) in self.iter().enumerate() {
+			value.apply(shader, &uniform.index(i));
+		}
+	}
+}
+
+/// A set of shader parameters that are usually changed together.
+///
+/// Use the `uniform_group!` macro to declare a struct whose fields map to
+/// uniforms of the same names, or implement it by hand.
+pub trait UniformGroup<'s> {
+	/// Assign every parameter of the group.
+	fn apply(&self, shader: &mut Shader<'s>) -> Result<(), ShaderError>;
+
+	/// Get the names of the uniforms assigned by `apply`.
+	///
+	/// `Shader::set_uniforms` checks them all before assigning anything.
+	/// The default implementation returns no names, skipping the check.
+	fn get_uniform_names(&self) -> Vec<&'static str> {
+		Vec::new()
+	}
+}
+
+/// Declare a struct implementing `UniformGroup`, where each field sets the
+/// shader uniform of the same name.
+///
+/// ```ignore
+/// uniform_group! {
+///     /// Parameters of the lighting shader.
+///     pub struct Lighting {
+///         pub ambient: Color,
+///         pub light_position: Vector2f,
+///         pub radius: f32
+///     }
+/// }
+///
+/// try!(shader.set_uniforms(&Lighting { ambient: Color::black(), light_position: pos, radius: 200. }));
+/// ```
+#[macro_export]
+macro_rules! uniform_group {
+	($(#[$attr:meta])* pub struct $name:ident {
+		$($(#[$field_attr:meta])* pub $field:ident : $ty:ty),* $(,)*
+	}) => {
+		$(#[$attr])*
+		pub struct $name {
+			$($(#[$field_attr])* pub $field: $ty),*
+		}
+
+		impl<'s> $crate::graphics::UniformGroup<'s> for $name {
+			fn apply(&self, shader: &mut $crate::graphics::Shader<'s>)
+			         -> Result<(), $crate::graphics::ShaderError> {
+				$(try!(shader.set_parameter(stringify!($field), self.$field.clone()));)*
+				Ok(())
+			}
+
+			fn get_uniform_names(&self) -> Vec<&'static str> {
+				vec![$(stringify!($field)),*]
+			}
+		}
+	}
+}
+
+fn uniforms_from_files(files: &[Option<&str>]) -> Option<Vec<String>> {
+	let mut names = Vec::new();
+	for file in files.iter().filter_map(|f| *f) {
+		let mut source = String::new();
+		match File::open(file).and_then(|mut f| f.read_to_string(&mut source)) {
+			Ok(_) => parse_uniform_names(&source, &mut names),
+			Err(_) => return None
+		}
+	}
+	Some(names)
+}
+
+/// Collect the names of the uniforms declared in GLSL source code.
+fn parse_uniform_names(source: &str, names: &mut Vec<String>) {
+	let mut code = String::with_capacity(source.len());
+	let mut rest = source;
+	let mut line_start = true;
+	while !rest.is_empty() {
+		if rest.starts_with("//") || line_start && rest.starts_with('#') {
+			rest = &rest[rest.find('\n').unwrap_or(rest.len())..];
+		} else if rest.starts_with("/*") {
+			rest = match rest[2..].find("*/") {
+				Some(end) => &rest[end + 4..],
+				None => ""
+			};
+			code.push(' ');
+		} else {
+			let c = rest.chars().next().unwrap();
+			if c == '\n' {
+				line_start = true;
+			} else if !c.is_whitespace() {
+				line_start = false;
+			}
+			code.push(c);
+			rest = &rest[c.len_utf8()..];
+		}
+	}
+
+	for statement in code.split(';') {
+		let tokens: Vec<&str> = statement.split(|c: char| {
+			c.is_whitespace() || c == ',' || c == '{' || c == '}' || c == '(' || c == ')'
+		}).filter(|t| !t.is_empty()).collect();
+		let start = match tokens.iter().position(|t| *t == "uniform") {
+			Some(index) => index + 1,
+			None => continue
+		};
+		let mut tokens = tokens[start..].iter()
+			.skip_while(|t| **t == "lowp" || **t == "mediump" || **t == "highp");
+		// Skip the type.
+		tokens.next();
+		for token in tokens {
+			if token.starts_with('=') || token.starts_with('[') {
+				break
+			}
+			let name = token.split(|c| c == '[' || c == '=').next().unwrap_or(token);
+			if !name.is_empty() && !names.iter().any(|n| n == name) {
+				names.push(name.to_string());
+			}
+			if token.contains('=') {
+				break
+			}
+		}
+	}
+}
+
+#[test]
+fn uniform_declarations() {
+	let source = "#version 120\n\
+	              // uniform float commented;\n\
+	              uniform sampler2D texture;\n\
+	              uniform lowp vec2 offset, scale;\n\
+	              /* uniform vec4 hidden; */\n\
+	              uniform vec4 lights[8];\n\
+	              uniform float gamma = 2.2;\n\
+	              void main() { gl_FragColor = texture2D(texture, gl_TexCoord[0].xy); }\n\
+	              uniform mat4 late;";
+	let mut names = Vec::new();
+	parse_uniform_names(source, &mut names);
+	assert_eq!(names, vec!["texture", "offset", "scale", "lights", "gamma", "late"]);
+
+	let uniform = Uniform::new("lights").unwrap();
+	assert_eq!(uniform.index(3).get_name(), "lights[3]");
+	assert_eq!(uniform.member("color").unwrap().get_name(), "lights.color");
+	assert!(Uniform::new("bad\0name").is_none());
+
+	uniform_group! {
+		/// Test group.
+		pub struct Group {
+			/// A color.
+			pub tint: Color,
+			/// A float.
+			pub gamma: f32,
+		}
+	}
+	let group = Group { tint: Color::white(), gamma: 2.2 };
+	assert_eq!(UniformGroup::get_uniform_names(&group), vec!["tint", "gamma"]);
+}
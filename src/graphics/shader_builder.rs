@@ -0,0 +1,641 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::SystemTime;
+
+use graphics::Shader;
+
+/// Error produced when building a shader fails.
+#[derive(Debug)]
+pub enum ShaderBuildError {
+	/// A source file could not be read.
+	Io(String, io::Error),
+	/// An `#include` names a file that is neither a virtual file nor found
+	/// next to the including file or in the search path.
+	IncludeNotFound {
+		/// File containing the directive.
+		file: String,
+		/// Line of the directive.
+		line: u32,
+		/// Name of the missing file.
+		include: String
+	},
+	/// A file includes itself, directly or indirectly.
+	RecursiveInclude {
+		/// File containing the directive.
+		file: String,
+		/// Line of the directive.
+		line: u32,
+		/// Name of the file included again.
+		include: String
+	},
+	/// A directive is malformed.
+	Syntax {
+		/// File containing the directive.
+		file: String,
+		/// Line of the directive.
+		line: u32,
+		/// What is wrong.
+		message: String
+	},
+	/// The preprocessed sources failed to compile or link.
+	///
+	/// SFML writes the driver's log to the standard error output; the file
+	/// numbers it contains can be turned into names with the `map_log`
+	/// method of the source of the stage that failed.
+	Compile {
+		/// The preprocessed vertex shader, if any.
+		vertex: Option<PreprocessedSource>,
+		/// The preprocessed fragment shader, if any.
+		fragment: Option<PreprocessedSource>
+	}
+}
+
+impl fmt::Display for ShaderBuildError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			ShaderBuildError::Io(ref file, ref err) => write!(f, "{}: {}", file, err),
+			ShaderBuildError::IncludeNotFound { ref file, line, ref include } =>
+				write!(f, "{}:{}: included file {:?} not found", file, line, include),
+			ShaderBuildError::RecursiveInclude { ref file, line, ref include } =>
+				write!(f, "{}:{}: {:?} includes itself", file, line, include),
+			ShaderBuildError::Syntax { ref file, line, ref message } =>
+				write!(f, "{}:{}: {}", file, line, message),
+			ShaderBuildError::Compile { ref vertex, ref fragment } => {
+				let files: Vec<&str> = vertex.iter().chain(fragment.iter())
+					.filter_map(|s| s.files.first()).map(|f| &f[..]).collect();
+				write!(f, "shader compilation failed: {}", files.join(", "))
+			}
+		}
+	}
+}
+
+impl Error for ShaderBuildError {
+	fn description(&self) -> &str {
+		match *self {
+			ShaderBuildError::Io(..) => "I/O error",
+			ShaderBuildError::IncludeNotFound { .. } => "included file not found",
+			ShaderBuildError::RecursiveInclude { .. } => "recursive include",
+			ShaderBuildError::Syntax { .. } => "malformed directive",
+			ShaderBuildError::Compile { .. } => "shader compilation failed"
+		}
+	}
+
+	fn cause(&self) -> Option<&Error> {
+		match *self {
+			ShaderBuildError::Io(_, ref err) => Some(err),
+			_ => None
+		}
+	}
+}
+
+/// GLSL source produced by a `ShaderBuilder`.
+///
+/// Every included file is given a number, and `#line` directives are
+/// inserted so that the driver reports errors with the line numbers of the
+/// original files. Use `map_line` or `map_log` to get the file names back.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreprocessedSource {
+	/// The complete source, ready to be compiled.
+	pub source: String,
+	files: Vec<String>,
+	origins: Vec<Option<(usize, u32)>>
+}
+
+impl PreprocessedSource {
+	/// Get the names of the files that make up the source, indexed by the
+	/// file number used in `#line` directives. The first one is the root
+	/// file.
+	pub fn get_files(&self) -> &[String] {
+		&self.files
+	}
+
+	/// Get the file and line a line of `source` (starting at 1) comes from.
+	///
+	/// Returns None for lines added by the builder.
+	pub fn map_line(&self, line: u32) -> Option<(&str, u32)> {
+		if line == 0 {
+			return None
+		}
+		match self.origins.get(line as usize - 1) {
+			Some(&Some((file, line))) => Some((&self.files[file], line)),
+			_ => None
+		}
+	}
+
+	/// Replace file numbers by file names in a compiler log.
+	///
+	/// Both the `0(12)` and the `0:12` location formats are recognized; the
+	/// first location on each line of the log is rewritten to `name:12`.
+	pub fn map_log(&self, log: &str) -> String {
+		let mut result = String::with_capacity(log.len());
+		for line in log.lines() {
+			result.push_str(&self.map_log_line(line));
+			result.push('\n');
+		}
+		result
+	}
+
+	fn map_log_line(&self, line: &str) -> String {
+		let bytes = line.as_bytes();
+		let mut i = 0;
+		while i < bytes.len() {
+			let preceded = i > 0 && (bytes[i - 1] as char).is_alphanumeric();
+			if preceded || !(bytes[i] as char).is_digit(10) {
+				i += 1;
+				continue
+			}
+			let file_end = i + bytes[i..].iter().take_while(|b| (**b as char).is_digit(10)).count();
+			if file_end + 1 < bytes.len() && (bytes[file_end] == b':' || bytes[file_end] == b'(') {
+				let line_start = file_end + 1;
+				let line_end = line_start + bytes[line_start..].iter().take_while(|b| (**b as char).is_digit(10)).count();
+				let closed = bytes[file_end] == b':' || bytes.get(line_end) == Some(&b')');
+				if line_end > line_start && closed {
+					let file = line[i..file_end].parse::<usize>().ok();
+					if let Some(name) = file.and_then(|f| self.files.get(f)) {
+						let rest_start = if bytes[file_end] == b'(' { line_end + 1 } else { line_end };
+						return format!("{}{}:{}{}", &line[..i], name, &line[line_start..line_end], &line[rest_start..])
+					}
+				}
+			}
+			i = file_end;
+		}
+		line.to_string()
+	}
+
+	fn push(&mut self, line: String, origin: Option<(usize, u32)>) {
+		self.source.push_str(&line);
+		self.source.push('\n');
+		self.origins.push(origin);
+	}
+}
+
+/// Builds shaders from GLSL sources split across several files.
+///
+/// The builder understands the following directives:
+///
+/// * `#include "file"` and `#include <file>` paste another file. Quoted
+///   names are first looked up next to the including file; then both forms
+///   are looked up among the virtual files and in the search path, in order.
+/// * `#pragma once` prevents a file from being included twice.
+/// * `#version` in the root file is moved to the top; in included files it
+///   is dropped. A version set with `set_version` replaces it.
+///
+/// Defines added with `define` are inserted right after the version.
+///
+/// ```ignore
+/// let mut builder = ShaderBuilder::new();
+/// builder.add_search_path("resources/shaders/common");
+/// builder.set_version(Some("120"));
+/// builder.define("MAX_LIGHTS", "8");
+/// let shader = try!(builder.build(None, Some("resources/shaders/light.frag")));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ShaderBuilder {
+	search_path: Vec<String>,
+	virtual_files: HashMap<String, String>,
+	defines: Vec<(String, String)>,
+	version: Option<String>
+}
+
+impl ShaderBuilder {
+	/// Create a builder with an empty search path and no defines.
+	pub fn new() -> ShaderBuilder {
+		ShaderBuilder::default()
+	}
+
+	/// Add a directory where included files are looked up.
+	pub fn add_search_path(&mut self, directory: &str) {
+		self.search_path.push(directory.to_string());
+	}
+
+	/// Register source code that can be included by name without touching
+	/// the file system, for example a file embedded with `include_str!`.
+	pub fn add_virtual_file(&mut self, name: &str, source: &str) {
+		self.virtual_files.insert(name.to_string(), source.to_string());
+	}
+
+	/// Add `#define name value` to the preprocessed sources. An existing
+	/// define of the same name is replaced.
+	pub fn define(&mut self, name: &str, value: &str) {
+		match self.defines.iter().position(|d| d.0 == name) {
+			Some(index) => self.defines[index].1 = value.to_string(),
+			None => self.defines.push((name.to_string(), value.to_string()))
+		}
+	}
+
+	/// Remove a define.
+	pub fn undefine(&mut self, name: &str) {
+		self.defines.retain(|d| d.0 != name);
+	}
+
+	/// Set the `#version` of the preprocessed sources, such as `"120"` or
+	/// `"330 core"`, or None to keep the version of the root file.
+	pub fn set_version(&mut self, version: Option<&str>) {
+		self.version = version.map(|v| v.to_string());
+	}
+
+	/// Preprocess a file, looking it up in the virtual files and the search
+	/// path if it does not exist.
+	pub fn preprocess_file(&self, filename: &str) -> Result<PreprocessedSource, ShaderBuildError> {
+		let (name, source) = match self.find(None, filename) {
+			Some(found) => try!(self.load(found)),
+			None => {
+				let mut source = String::new();
+				match File::open(filename).and_then(|mut f| f.read_to_string(&mut source)) {
+					Ok(_) => (filename.to_string(), source),
+					Err(err) => return Err(ShaderBuildError::Io(filename.to_string(), err))
+				}
+			}
+		};
+		self.preprocess(&name, &source)
+	}
+
+	/// Preprocess source code in memory. `name` is used for error messages
+	/// and to resolve quoted includes relative to its directory.
+	pub fn preprocess(&self, name: &str, source: &str) -> Result<PreprocessedSource, ShaderBuildError> {
+		let mut state = State {
+			files: Vec::new(),
+			stack: Vec::new(),
+			once: Vec::new(),
+			version: None,
+			lines: Vec::new()
+		};
+		try!(self.process(&mut state, name, source));
+
+		let version = self.version.clone().or(state.version);
+		// Before GLSL 3.30, `#line n` sets the number of the line after the
+		// directive to n + 1 rather than n.
+		let offset = match version.as_ref().and_then(|v| parse_version_number(v)) {
+			Some(number) if number >= 330 => 0,
+			_ => 1
+		};
+
+		let mut result = PreprocessedSource {
+			source: String::new(),
+			files: state.files,
+			origins: Vec::new()
+		};
+		if let Some(version) = version {
+			result.push(format!("#version {}", version), None);
+		}
+		for &(ref name, ref value) in &self.defines {
+			result.push(format!("#define {} {}", name, value), None);
+		}
+		for line in state.lines {
+			match line {
+				Line::Code(text, origin) => result.push(text, Some(origin)),
+				Line::Marker(file, line) => {
+					result.push(format!("#line {} {}", line - offset, file), None)
+				}
+			}
+		}
+		Ok(result)
+	}
+
+	/// Preprocess and compile a vertex and/or fragment shader from files.
+	///
+	/// When compilation fails, the previous shader (if any) should be kept;
+	/// see `HotShader` for a wrapper that does so.
+	pub fn build<'s>(&self, vertex_shader_filename: Option<&str>,
+	                 fragment_shader_filename: Option<&str>)
+	                 -> Result<Shader<'s>, ShaderBuildError> {
+		let vertex = match vertex_shader_filename {
+			Some(file) => Some(try!(self.preprocess_file(file))),
+			None => None
+		};
+		let fragment = match fragment_shader_filename {
+			Some(file) => Some(try!(self.preprocess_file(file))),
+			None => None
+		};
+		match Shader::new_from_memory(vertex.as_ref().map(|s| &s.source[..]),
+		                              fragment.as_ref().map(|s| &s.source[..])) {
+			Some(shader) => Ok(shader),
+			None => Err(ShaderBuildError::Compile {
+				vertex: vertex,
+				fragment: fragment
+			})
+		}
+	}
+
+	fn find(&self, directory: Option<&str>, name: &str) -> Option<Found> {
+		if let Some(directory) = directory {
+			let path = Path::new(directory).join(name);
+			if path.is_file() {
+				return Some(Found::File(path.to_string_lossy().into_owned()))
+			}
+		}
+		if self.virtual_files.contains_key(name) {
+			return Some(Found::Virtual(name.to_string()))
+		}
+		for directory in &self.search_path {
+			let path = Path::new(directory).join(name);
+			if path.is_file() {
+				return Some(Found::File(path.to_string_lossy().into_owned()))
+			}
+		}
+		None
+	}
+
+	fn load(&self, found: Found) -> Result<(String, String), ShaderBuildError> {
+		match found {
+			Found::Virtual(name) => {
+				let source = self.virtual_files[&name].clone();
+				Ok((name, source))
+			}
+			Found::File(path) => {
+				let mut source = String::new();
+				match File::open(&path).and_then(|mut f| f.read_to_string(&mut source)) {
+					Ok(_) => Ok((path, source)),
+					Err(err) => Err(ShaderBuildError::Io(path, err))
+				}
+			}
+		}
+	}
+
+	fn process(&self, state: &mut State, name: &str, source: &str) -> Result<(), ShaderBuildError> {
+		let index = state.files.len();
+		state.files.push(name.to_string());
+		state.stack.push(name.to_string());
+		state.lines.push(Line::Marker(index, 1));
+		let directory = if self.virtual_files.contains_key(name) {
+			None
+		} else {
+			Path::new(name).parent().map(|p| p.to_string_lossy().into_owned())
+		};
+
+		for (number, text) in source.lines().enumerate() {
+			let number = number as u32 + 1;
+			let trimmed = text.trim();
+			if !trimmed.starts_with('#') {
+				state.lines.push(Line::Code(text.to_string(), (index, number)));
+				continue
+			}
+			let directive = trimmed[1..].trim_left();
+			if directive.starts_with("version") {
+				if index == 0 && state.version.is_none() {
+					state.version = Some(directive["version".len()..].trim().to_string());
+				}
+				state.lines.push(Line::Code(String::new(), (index, number)));
+			} else if directive.starts_with("pragma") && directive["pragma".len()..].trim() == "once" {
+				state.once.push(name.to_string());
+				state.lines.push(Line::Code(String::new(), (index, number)));
+			} else if directive.starts_with("include") {
+				let include = match parse_include(&directive["include".len()..]) {
+					Some(include) => include,
+					None => return Err(ShaderBuildError::Syntax {
+						file: name.to_string(),
+						line: number,
+						message: format!("malformed include: {}", trimmed)
+					})
+				};
+				let found = match self.find(if include.1 { directory.as_ref().map(|d| &d[..]) } else { None },
+				                            include.0) {
+					Some(found) => found,
+					None => return Err(ShaderBuildError::IncludeNotFound {
+						file: name.to_string(),
+						line: number,
+						include: include.0.to_string()
+					})
+				};
+				let (included_name, included_source) = try!(self.load(found));
+				if state.stack.contains(&included_name) {
+					return Err(ShaderBuildError::RecursiveInclude {
+						file: name.to_string(),
+						line: number,
+						include: include.0.to_string()
+					})
+				}
+				if !state.once.contains(&included_name) {
+					try!(self.process(state, &included_name, &included_source));
+				}
+				state.lines.push(Line::Marker(index, number + 1));
+			} else {
+				state.lines.push(Line::Code(text.to_string(), (index, number)));
+			}
+		}
+		state.stack.pop();
+		Ok(())
+	}
+}
+
+enum Found {
+	File(String),
+	Virtual(String)
+}
+
+enum Line {
+	Code(String, (usize, u32)),
+	Marker(usize, u32)
+}
+
+struct State {
+	files: Vec<String>,
+	stack: Vec<String>,
+	once: Vec<String>,
+	version: Option<String>,
+	lines: Vec<Line>
+}
+
+// Parse the argument of an include directive, returning the name and whether
+// it was quoted.
+fn parse_include(argument: &str) -> Option<(&str, bool)> {
+	let argument = argument.trim();
+	let (close, quoted) = match argument.chars().next() {
+		Some('"') => ('"', true),
+		Some('<') => ('>', false),
+		_ => return None
+	};
+	let end = match argument[1..].find(close) {
+		Some(end) => end + 1,
+		None => return None
+	};
+	let rest = argument[end + 1..].trim();
+	if end == 1 || !(rest.is_empty() || rest.starts_with("//")) {
+		return None
+	}
+	Some((&argument[1..end], quoted))
+}
+
+fn parse_version_number(version: &str) -> Option<u32> {
+	version.split_whitespace().next().and_then(|v| v.parse().ok())
+}
+
+/// A shader rebuilt automatically when its source files change.
+///
+/// Call `update` regularly (for example once per frame or once per second).
+/// When any file that went into the current shader has been modified, the
+/// shader is rebuilt; if that fails, the last good shader stays in use and
+/// the error is returned.
+///
+/// Parameters are not carried over to a rebuilt shader: set them again after
+/// `update` returns `Ok(true)`.
+pub struct HotShader<'s> {
+	builder: ShaderBuilder,
+	vertex: Option<String>,
+	fragment: Option<String>,
+	shader: Shader<'s>,
+	watched: Vec<(String, Option<SystemTime>)>
+}
+
+impl<'s> HotShader<'s> {
+	/// Build a shader from files and start watching them.
+	pub fn new(builder: ShaderBuilder, vertex_shader_filename: Option<&str>,
+	           fragment_shader_filename: Option<&str>)
+	           -> Result<HotShader<'s>, ShaderBuildError> {
+		let shader = try!(builder.build(vertex_shader_filename, fragment_shader_filename));
+		let mut hot = HotShader {
+			builder: builder,
+			vertex: vertex_shader_filename.map(|f| f.to_string()),
+			fragment: fragment_shader_filename.map(|f| f.to_string()),
+			shader: shader,
+			watched: Vec::new()
+		};
+		hot.watch();
+		Ok(hot)
+	}
+
+	/// Get the current shader.
+	pub fn get_shader(&self) -> &Shader<'s> {
+		&self.shader
+	}
+
+	/// Mutably access the current shader, for example to set parameters.
+	pub fn get_shader_mut(&mut self) -> &mut Shader<'s> {
+		&mut self.shader
+	}
+
+	/// Get the builder used for rebuilding.
+	///
+	/// Changes take effect on the next rebuild; call `reload` to apply them
+	/// immediately.
+	pub fn get_builder_mut(&mut self) -> &mut ShaderBuilder {
+		&mut self.builder
+	}
+
+	/// Tell whether a watched file was modified since the last build.
+	pub fn has_changed(&self) -> bool {
+		self.watched.iter().any(|&(ref file, time)| modified(file) != time)
+	}
+
+	/// Rebuild the shader if a watched file changed.
+	///
+	/// Returns `Ok(true)` if the shader was rebuilt, `Ok(false)` if nothing
+	/// changed. After a failure, the shader is not rebuilt again until a
+	/// file changes again.
+	pub fn update(&mut self) -> Result<bool, ShaderBuildError> {
+		if !self.has_changed() {
+			return Ok(false)
+		}
+		self.reload().map(|_| true)
+	}
+
+	/// Rebuild the shader now, keeping the current one on failure.
+	pub fn reload(&mut self) -> Result<(), ShaderBuildError> {
+		let result = self.builder.build(self.vertex.as_ref().map(|f| &f[..]),
+		                                self.fragment.as_ref().map(|f| &f[..]));
+		self.watch();
+		result.map(|shader| self.shader = shader)
+	}
+
+	fn watch(&mut self) {
+		let mut files = Vec::new();
+		for root in self.vertex.iter().chain(self.fragment.iter()) {
+			match self.builder.preprocess_file(root) {
+				Ok(source) => files.extend(source.get_files().iter().cloned()),
+				Err(_) => files.push(root.clone())
+			}
+		}
+		files.retain(|f| !self.builder.virtual_files.contains_key(f));
+		self.watched = files.into_iter().map(|f| {
+			let time = modified(&f);
+			(f, time)
+		}).collect();
+	}
+}
+
+fn modified(file: &str) -> Option<SystemTime> {
+	fs::metadata(file).and_then(|m| m.modified()).ok()
+}
+
+#[test]
+fn preprocess_includes() {
+	let mut builder = ShaderBuilder::new();
+	builder.add_virtual_file("common.glsl", "#pragma once\nfloat luma(vec3 c) {\n\treturn dot(c, vec3(0.3, 0.6, 0.1));\n}");
+	builder.add_virtual_file("noise.glsl", "#include \"common.glsl\"\nfloat noise(vec2 p);");
+	builder.define("STRENGTH", "0.5");
+	let result = builder.preprocess("main.frag", "#version 120\n#include <common.glsl>\n#include <noise.glsl>\nvoid main() {}").unwrap();
+	assert_eq!(result.source,
+	           "#version 120\n#define STRENGTH 0.5\n#line 0 0\n\n\
+	            #line 0 1\n\nfloat luma(vec3 c) {\n\treturn dot(c, vec3(0.3, 0.6, 0.1));\n}\n#line 2 0\n\
+	            #line 0 2\n#line 1 2\nfloat noise(vec2 p);\n#line 3 0\nvoid main() {}\n");
+	assert_eq!(result.get_files(), &["main.frag".to_string(), "common.glsl".to_string(), "noise.glsl".to_string()][..]);
+	assert_eq!(result.map_line(7), Some(("common.glsl", 2)));
+	assert_eq!(result.map_line(13), Some(("noise.glsl", 2)));
+	assert_eq!(result.map_line(15), Some(("main.frag", 4)));
+	assert_eq!(result.map_line(1), None);
+	assert_eq!(result.map_log("ERROR: 2:2: 'p' : undeclared\n1(3) : error C0000: syntax error"),
+	           "ERROR: noise.glsl:2: 'p' : undeclared\ncommon.glsl:3 : error C0000: syntax error\n");
+
+	builder.set_version(Some("330 core"));
+	let result = builder.preprocess("main.frag", "#version 120\nvoid main() {}").unwrap();
+	assert_eq!(result.source, "#version 330 core\n#define STRENGTH 0.5\n#line 1 0\n\nvoid main() {}\n");
+}
+
+#[test]
+fn preprocess_errors() {
+	let mut builder = ShaderBuilder::new();
+	builder.add_virtual_file("a.glsl", "#include <b.glsl>");
+	builder.add_virtual_file("b.glsl", "\n#include <a.glsl>");
+	match builder.preprocess("main.frag", "#include <a.glsl>") {
+		Err(ShaderBuildError::RecursiveInclude { ref file, line: 2, .. }) if file == "b.glsl" => (),
+		other => panic!("unexpected result {:?}", other)
+	}
+	match builder.preprocess("main.frag", "\n\n#include \"missing.glsl\"") {
+		Err(ShaderBuildError::IncludeNotFound { line: 3, ref include, .. }) if include == "missing.glsl" => (),
+		other => panic!("unexpected result {:?}", other)
+	}
+	match builder.preprocess("main.frag", "#include missing.glsl") {
+		Err(ShaderBuildError::Syntax { line: 1, .. }) => (),
+		other => panic!("unexpected result {:?}", other)
+	}
+
+	// Compile errors keep the sources to map the driver's log
+	let error = ShaderBuildError::Compile {
+		vertex: None,
+		fragment: Some(builder.preprocess("main.frag", "void main() {}").unwrap())
+	};
+	assert_eq!(error.to_string(), "shader compilation failed: main.frag");
+	match error {
+		ShaderBuildError::Compile { fragment: Some(ref source), .. } =>
+			assert_eq!(source.map_log("0(1) : error"), "main.frag:1 : error\n"),
+		_ => unreachable!()
+	}
+}
@@ -0,0 +1,357 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+
+use graphics::{BlendEquation, BlendFactor, BlendMode, Color, IntRect, RenderStates, RenderTarget};
+
+use ffi::gl;
+use ffi::graphics as ffi;
+
+/// Test deciding whether a pixel passes the stencil test.
+///
+/// The stored stencil value and the reference are both masked with
+/// `StencilMode::mask` before the comparison.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum StencilComparison {
+	/// The test never passes.
+	Never,
+	/// Passes if the reference is less than the stored value.
+	Less,
+	/// Passes if the reference is less than or equal to the stored value.
+	LessEqual,
+	/// Passes if the reference is greater than the stored value.
+	Greater,
+	/// Passes if the reference is greater than or equal to the stored
+	/// value.
+	GreaterEqual,
+	/// Passes if the reference is equal to the stored value.
+	Equal,
+	/// Passes if the reference is not equal to the stored value.
+	NotEqual,
+	/// The test always passes.
+	Always
+}
+
+/// Change applied to the stored stencil value.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum StencilOperation {
+	/// Keep the stored value.
+	Keep,
+	/// Set the stored value to 0.
+	Zero,
+	/// Set the stored value to the reference.
+	Replace,
+	/// Increment the stored value, stopping at the maximum.
+	Increment,
+	/// Decrement the stored value, stopping at 0.
+	Decrement,
+	/// Increment the stored value, wrapping to 0 after the maximum.
+	IncrementWrap,
+	/// Decrement the stored value, wrapping to the maximum below 0.
+	DecrementWrap,
+	/// Invert the bits of the stored value.
+	Invert
+}
+
+/// Stencil test and update applied when drawing.
+///
+/// Only windows can have a stencil buffer, requested through
+/// `ContextSettings::stencil_bits`. Drawing with a stencil mode to render
+/// textures and to windows without one draws nothing and prints an error to
+/// the standard error output; see `RenderTarget::has_stencil_buffer`.
+///
+/// The usual masking pattern is to draw the mask shape with
+/// `StencilMode::write(1)`, then the masked content with
+/// `StencilMode::equal(1)`:
+///
+/// ```ignore
+/// window.clear_stencil(0).unwrap();
+/// window.draw_rs(&panel_shape, &RenderStates { stencil: Some(StencilMode::write(1)), .. Default::default() });
+/// window.draw_rs(&contents, &RenderStates { stencil: Some(StencilMode::equal(1)), .. Default::default() });
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub struct StencilMode {
+	/// Test deciding whether pixels are drawn.
+	pub comparison: StencilComparison,
+	/// Value compared with, and possibly written to, the stencil buffer.
+	pub reference: u32,
+	/// Bits of the stencil buffer that are compared and written.
+	pub mask: u32,
+	/// Operation applied where the test passes.
+	pub pass_operation: StencilOperation,
+	/// Operation applied where the test fails.
+	pub fail_operation: StencilOperation,
+	/// Whether only the stencil buffer is written, leaving the colors
+	/// untouched.
+	pub stencil_only: bool
+}
+
+impl StencilMode {
+	/// Create a stencil mode using all bits, keeping the stored value where
+	/// the test fails and writing colors.
+	pub fn new(comparison: StencilComparison, reference: u32, pass_operation: StencilOperation) -> StencilMode {
+		StencilMode {
+			comparison: comparison,
+			reference: reference,
+			mask: !0,
+			pass_operation: pass_operation,
+			fail_operation: StencilOperation::Keep,
+			stencil_only: false
+		}
+	}
+
+	/// Write `value` into the stencil buffer wherever the object covers,
+	/// without drawing colors.
+	pub fn write(value: u32) -> StencilMode {
+		StencilMode {
+			stencil_only: true,
+			.. StencilMode::new(StencilComparison::Always, value, StencilOperation::Replace)
+		}
+	}
+
+	/// Draw only where the stencil buffer contains `value`.
+	pub fn equal(value: u32) -> StencilMode {
+		StencilMode::new(StencilComparison::Equal, value, StencilOperation::Keep)
+	}
+
+	/// Draw only where the stencil buffer does not contain `value`.
+	pub fn not_equal(value: u32) -> StencilMode {
+		StencilMode::new(StencilComparison::NotEqual, value, StencilOperation::Keep)
+	}
+}
+
+/// Error returned by `RenderTarget::clear_stencil`.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum StencilError {
+	/// The render target has no stencil buffer.
+	NoStencilBuffer
+}
+
+impl fmt::Display for StencilError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			StencilError::NoStencilBuffer => write!(f, "the render target has no stencil buffer")
+		}
+	}
+}
+
+impl Error for StencilError {
+	fn description(&self) -> &str {
+		match *self {
+			StencilError::NoStencilBuffer => "no stencil buffer"
+		}
+	}
+}
+
+fn comparison_to_gl(comparison: StencilComparison) -> gl::GLenum {
+	match comparison {
+		StencilComparison::Never => gl::GL_NEVER,
+		StencilComparison::Less => gl::GL_LESS,
+		StencilComparison::LessEqual => gl::GL_LEQUAL,
+		StencilComparison::Greater => gl::GL_GREATER,
+		StencilComparison::GreaterEqual => gl::GL_GEQUAL,
+		StencilComparison::Equal => gl::GL_EQUAL,
+		StencilComparison::NotEqual => gl::GL_NOTEQUAL,
+		StencilComparison::Always => gl::GL_ALWAYS
+	}
+}
+
+fn operation_to_gl(operation: StencilOperation) -> gl::GLenum {
+	match operation {
+		StencilOperation::Keep => gl::GL_KEEP,
+		StencilOperation::Zero => gl::GL_ZERO,
+		StencilOperation::Replace => gl::GL_REPLACE,
+		StencilOperation::Increment => gl::GL_INCR,
+		StencilOperation::Decrement => gl::GL_DECR,
+		StencilOperation::IncrementWrap => gl::GL_INCR_WRAP,
+		StencilOperation::DecrementWrap => gl::GL_DECR_WRAP,
+		StencilOperation::Invert => gl::GL_INVERT
+	}
+}
+
+fn factor_to_gl(factor: BlendFactor) -> gl::GLenum {
+	match factor {
+		BlendFactor::Zero => gl::GL_ZERO,
+		BlendFactor::One => gl::GL_ONE,
+		BlendFactor::SrcColor => gl::GL_SRC_COLOR,
+		BlendFactor::OneMinusSrcColor => gl::GL_ONE_MINUS_SRC_COLOR,
+		BlendFactor::DstColor => gl::GL_DST_COLOR,
+		BlendFactor::OneMinusDstColor => gl::GL_ONE_MINUS_DST_COLOR,
+		BlendFactor::SrcAlpha => gl::GL_SRC_ALPHA,
+		BlendFactor::OneMinusSrcAlpha => gl::GL_ONE_MINUS_SRC_ALPHA,
+		BlendFactor::DstAlpha => gl::GL_DST_ALPHA,
+		BlendFactor::OneMinusDstAlpha => gl::GL_ONE_MINUS_DST_ALPHA,
+		BlendFactor::ConstantColor => gl::GL_CONSTANT_COLOR,
+		BlendFactor::OneMinusConstantColor => gl::GL_ONE_MINUS_CONSTANT_COLOR,
+		BlendFactor::ConstantAlpha => gl::GL_CONSTANT_ALPHA,
+		BlendFactor::OneMinusConstantAlpha => gl::GL_ONE_MINUS_CONSTANT_ALPHA
+	}
+}
+
+fn equation_to_gl(equation: BlendEquation) -> gl::GLenum {
+	match equation {
+		BlendEquation::Add => gl::GL_FUNC_ADD,
+		BlendEquation::Subtract => gl::GL_FUNC_SUBTRACT,
+		BlendEquation::ReverseSubtract => gl::GL_FUNC_REVERSE_SUBTRACT,
+		BlendEquation::Min => gl::GL_MIN,
+		BlendEquation::Max => gl::GL_MAX
+	}
+}
+
+fn apply_blend_mode(mode: BlendMode) {
+	unsafe {
+		gl::glBlendFuncSeparate(factor_to_gl(mode.color_src_factor), factor_to_gl(mode.color_dst_factor),
+		                        factor_to_gl(mode.alpha_src_factor), factor_to_gl(mode.alpha_dst_factor));
+		gl::glBlendEquationSeparate(equation_to_gl(mode.color_equation), equation_to_gl(mode.alpha_equation));
+	}
+}
+
+// Convert a scissor rectangle from target coordinates (origin at the top)
+// to OpenGL coordinates (origin at the bottom).
+fn scissor_to_gl(rect: IntRect, target_height: u32) -> (i32, i32, i32, i32) {
+	(rect.left, target_height as i32 - rect.top - rect.height, rect.width.max(0), rect.height.max(0))
+}
+
+/// Render targets drawn to through `draw_with_gl_states`.
+pub trait GlStatesTarget: RenderTarget {
+	/// Activate the context of the target, and if `reset` is true, reset
+	/// CSFML's cached states so that its blend mode is alpha blending.
+	fn activate_gl_states(&mut self, reset: bool);
+}
+
+/// Tell whether drawing with `states` needs OpenGL state that CSFML does not
+/// manage.
+pub fn needs_gl_states(states: &RenderStates) -> bool {
+	states.stencil.is_some() || states.scissor.is_some() || !states.blend_mode.is_native()
+}
+
+/// Call `draw` with the CSFML version of `states`, setting up the blend
+/// mode, blend color, stencil and scissor state CSFML does not manage around
+/// it.
+///
+/// A blend mode CSFML cannot apply is replaced by alpha blending in the
+/// states it is given, so CSFML's cached blend mode is reset to alpha first;
+/// otherwise it would overwrite the one set here. If the context cannot
+/// apply the blend mode either, nothing is drawn and the error is printed to
+/// the standard error output, like CSFML's own errors. So does a stencil
+/// mode when the target has no stencil buffer.
+pub fn draw_with_gl_states<T, F>(target: &mut T, states: &RenderStates, draw: F)
+	where T: GlStatesTarget, F: FnOnce(&mut T, &ffi::sfRenderStates) {
+	if !needs_gl_states(states) {
+		return draw(target, &states.unwrap())
+	}
+	if states.stencil.is_some() && !target.has_stencil_buffer() {
+		let _ = writeln!(io::stderr(), "Failed to draw with a stencil mode: {}", StencilError::NoStencilBuffer);
+		return
+	}
+	let blend = !states.blend_mode.is_native();
+	target.activate_gl_states(blend);
+	if let Err(err) = states.blend_mode.check() {
+		let _ = writeln!(io::stderr(), "Failed to draw with blend mode {:?}: {}", states.blend_mode, err);
+		return
+	}
+	let constant = blend && states.blend_mode.uses_constant();
+	let height = target.get_size().y;
+	unsafe {
+		if blend {
+			apply_blend_mode(states.blend_mode);
+		}
+		if constant {
+			apply_blend_color(states.blend_color);
+		}
+		if let Some(stencil) = states.stencil {
+			gl::glEnable(gl::GL_STENCIL_TEST);
+			gl::glStencilFunc(comparison_to_gl(stencil.comparison), stencil.reference as gl::GLint, stencil.mask);
+			let (fail, pass) = (operation_to_gl(stencil.fail_operation), operation_to_gl(stencil.pass_operation));
+			gl::glStencilOp(fail, pass, pass);
+			gl::glStencilMask(stencil.mask);
+			if stencil.stencil_only {
+				gl::glColorMask(gl::GL_FALSE, gl::GL_FALSE, gl::GL_FALSE, gl::GL_FALSE);
+			}
+		}
+		if let Some(rect) = states.scissor {
+			let (x, y, width, height) = scissor_to_gl(rect, height);
+			gl::glEnable(gl::GL_SCISSOR_TEST);
+			gl::glScissor(x, y, width, height);
+		}
+	}
+
+	draw(target, &states.unwrap());
+
+	// Leave the states as CSFML expects them
+	if blend {
+		apply_blend_mode(BlendMode::alpha());
+	}
+	if constant {
+		apply_blend_color(Color::transparent());
+	}
+	unsafe {
+		gl::glDisable(gl::GL_STENCIL_TEST);
+		gl::glDisable(gl::GL_SCISSOR_TEST);
+		gl::glStencilMask(!0);
+		gl::glColorMask(gl::GL_TRUE, gl::GL_TRUE, gl::GL_TRUE, gl::GL_TRUE);
+	}
+}
+
+fn apply_blend_color(color: Color) {
+	unsafe {
+		gl::glBlendColor(color.red as f32 / 255., color.green as f32 / 255., color.blue as f32 / 255.,
+		                 color.alpha as f32 / 255.);
+	}
+}
+
+/// Clear the stencil buffer of the active context to `value`.
+pub fn clear_stencil(value: u32) {
+	unsafe {
+		gl::glStencilMask(!0);
+		gl::glClearStencil(value as gl::GLint);
+		gl::glClear(gl::GL_STENCIL_BUFFER_BIT);
+	}
+}
+
+#[test]
+fn stencil_conversions() {
+	assert_eq!(comparison_to_gl(StencilComparison::GreaterEqual), gl::GL_GEQUAL);
+	assert_eq!(operation_to_gl(StencilOperation::DecrementWrap), gl::GL_DECR_WRAP);
+	assert_eq!(scissor_to_gl(IntRect::new(10, 20, 100, 50), 600), (10, 530, 100, 50));
+
+	let write = StencilMode::write(3);
+	assert_eq!(write.comparison, StencilComparison::Always);
+	assert_eq!(write.pass_operation, StencilOperation::Replace);
+	assert!(write.stencil_only);
+	assert!(!StencilMode::equal(3).stencil_only);
+
+	assert_eq!(factor_to_gl(BlendFactor::OneMinusConstantAlpha), gl::GL_ONE_MINUS_CONSTANT_ALPHA);
+	assert_eq!(equation_to_gl(BlendEquation::ReverseSubtract), gl::GL_FUNC_REVERSE_SUBTRACT);
+	let lighten = RenderStates { blend_mode: BlendMode::lighten(), .. RenderStates::default() };
+	assert!(needs_gl_states(&lighten));
+	assert_eq!(lighten.unwrap().blendMode, BlendMode::alpha());
+	assert!(!needs_gl_states(&RenderStates::default()));
+	let masked = RenderStates { stencil: Some(StencilMode::equal(1)), .. RenderStates::default() };
+	assert!(needs_gl_states(&masked));
+	assert_eq!(StencilError::NoStencilBuffer.to_string(), "the render target has no stencil buffer");
+}
@@ -23,9 +23,15 @@
 */
 
 use libc::{c_float, c_uint, size_t};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
 
 use graphics::{RenderTarget, Font, FloatRect, Drawable, Transformable,
-               Color, Transform, RenderStates, TextStyle};
+               Color, Transform, RenderStates, TextStyle, RenderTexture,
+               BlendMode, Sprite};
+use graphics::text_layout::{TextLayout, LayoutOptions, Span, SpanStyle};
+use std::f32::consts::PI;
 use system::Vector2f;
 
 use ffi::Foreign;
@@ -48,11 +54,46 @@ use ffi::graphics as ffi;
 /// which can combine the glyphs data and metrics of a `Font` to display any
 /// text on a render target.
 ///
+/// Text can also have an outline and a drop shadow, which CSFML does not
+/// support: the text and its outline are rendered into a `RenderTexture`
+/// the first time they are drawn, which is then tinted with the outline and
+/// shadow colors under the text. The texture is shared by the texts of the
+/// thread with the same string, font, style, character size and outline
+/// thickness, and rendered again when one of them changes. If it can not be
+/// created, the text is drawn without its effects.
+///
 /// See also the note on coordinated and undistorted rendering in
 /// `Transformable`.
 pub struct Text<'s> {
     text: Foreign<ffi::sfText>,
-    font: Option<&'s Font>
+    font: Option<&'s Font>,
+	outline_color: Color,
+	outline_thickness: f32,
+	shadow_color: Color,
+	shadow_offset: Vector2f,
+	effect: RefCell<Option<Rc<EffectMask>>>
+}
+
+// The shape of a text and its outline, in white.
+struct EffectMask {
+	texture: RenderTexture,
+	// Position of the texture's top left corner, in local coordinates
+	origin: Vector2f
+}
+
+// What the effect mask of a text depends on.
+#[derive(PartialEq, Eq, Hash)]
+struct EffectKey {
+	font: usize,
+	string: String,
+	character_size: u32,
+	style: u32,
+	thickness: u32
+}
+
+thread_local! {
+	// Effect masks in use, shared by identical texts.
+	static EFFECT_MASKS: RefCell<HashMap<EffectKey, Weak<EffectMask>>> = RefCell::new(HashMap::new())
 }
 
 impl<'s> Text<'s> {
@@ -64,7 +105,12 @@ impl<'s> Text<'s> {
 			Foreign::new(ffi::sfText_create())
 		}.map(|text| Text {
 			text: text,
-			font: None
+			font: None,
+			outline_color: Color::black(),
+			outline_thickness: 0.,
+			shadow_color: Color::transparent(),
+			shadow_offset: Vector2f::new(0., 0.),
+			effect: RefCell::new(None)
 		})
     }
 
@@ -103,7 +149,12 @@ impl<'s> Text<'s> {
 			Foreign::new(ffi::sfText_copy(self.raw()))
 		}.map(|text| Text {
 			text: text,
-			font: self.font
+			font: self.font,
+			outline_color: self.outline_color,
+			outline_thickness: self.outline_thickness,
+			shadow_color: self.shadow_color,
+			shadow_offset: self.shadow_offset,
+			effect: RefCell::new(self.effect.borrow().clone())
 		})
     }
 
@@ -115,6 +166,7 @@ impl<'s> Text<'s> {
         unsafe {
             ffi::sfText_setUnicodeString(self.raw_mut(), vec.as_ptr())
         }
+		self.effect = RefCell::new(None);
     }
 
     /// Get the text's string.
@@ -138,6 +190,7 @@ impl<'s> Text<'s> {
     pub fn set_font(&mut self, font: &'s Font) {
         self.font = Some(font);
         unsafe { ffi::sfText_setFont(self.raw_mut(), font.unwrap()) }
+		self.effect = RefCell::new(None);
     }
 
     /// Set the text's style.
@@ -146,6 +199,7 @@ impl<'s> Text<'s> {
     /// example `BOLD | ITALIC`. The default style is `REGULAR`.
     pub fn set_style(&mut self, style: TextStyle) {
         unsafe { ffi::sfText_setStyle(self.raw_mut(), style.bits()) }
+		self.effect = RefCell::new(None);
     }
 
     /// Set the character size in pixels.
@@ -159,6 +213,7 @@ impl<'s> Text<'s> {
 	/// that supports that size is used.
     pub fn set_character_size(&mut self, size: u32) {
         unsafe { ffi::sfText_setCharacterSize(self.raw_mut(), size as c_uint) }
+		self.effect = RefCell::new(None);
     }
 
     /// Get the text's style.
@@ -187,6 +242,56 @@ impl<'s> Text<'s> {
         unsafe { ffi::sfText_getColor(self.raw()) }
     }
 
+    /// Set the color of the text's outline.
+    ///
+    /// By default, the outline color is opaque black.
+    pub fn set_outline_color(&mut self, color: Color) {
+		self.outline_color = color;
+    }
+
+    /// Get the color of the text's outline.
+    pub fn get_outline_color(&self) -> Color {
+		self.outline_color
+    }
+
+    /// Set the thickness of the text's outline, in pixels.
+    ///
+    /// By default, the outline thickness is 0, which disables the outline.
+    /// Negative values are treated as 0.
+    pub fn set_outline_thickness(&mut self, thickness: f32) {
+		self.outline_thickness = thickness.max(0.);
+		self.effect = RefCell::new(None);
+    }
+
+    /// Get the thickness of the text's outline, in pixels.
+    pub fn get_outline_thickness(&self) -> f32 {
+		self.outline_thickness
+    }
+
+    /// Set the color of the text's drop shadow.
+    ///
+    /// By default, the shadow is transparent, which disables it.
+    pub fn set_shadow_color(&mut self, color: Color) {
+		self.shadow_color = color;
+    }
+
+    /// Get the color of the text's drop shadow.
+    pub fn get_shadow_color(&self) -> Color {
+		self.shadow_color
+    }
+
+    /// Set the offset of the text's drop shadow, in local coordinates.
+    ///
+    /// The shadow has the shape of the text and its outline.
+    pub fn set_shadow_offset(&mut self, offset: Vector2f) {
+		self.shadow_offset = offset;
+    }
+
+    /// Get the offset of the text's drop shadow.
+    pub fn get_shadow_offset(&self) -> Vector2f {
+		self.shadow_offset
+    }
+
     /// Return the position of the `index`-th character.
     ///
     /// This function computes the visual position of a character
@@ -199,6 +304,42 @@ impl<'s> Text<'s> {
         unsafe { ffi::sfText_findCharacterPos(self.raw(), index as size_t) }
     }
 
+    /// Lay the text out with the layout engine, giving access to the
+    /// position of every character.
+    ///
+    /// Returns None if the text has no font.
+    pub fn get_layout(&self) -> Option<TextLayout> {
+		self.font.map(|font| self.layout_with_color(font, self.get_color()))
+    }
+
+    /// Return the index of the caret position closest to a point.
+    ///
+    /// This is the inverse of `find_character_pos`: the point is in global
+    /// coordinates, and the result is between 0 and the length of the string,
+    /// both included. Returns 0 if the text has no font.
+    pub fn find_character_index(&self, point: Vector2f) -> usize {
+		let local = self.get_inverse_transform().transform_point(point);
+		self.get_layout().map_or(0, |layout| layout.find_character_index(local))
+    }
+
+    /// Get the rectangle of a caret `width` pixels wide before the
+    /// `index`-th character, in global coordinates.
+    pub fn get_caret_rect(&self, index: usize, width: f32) -> FloatRect {
+		let rect = self.get_layout().map_or(FloatRect::new(0., 0., width, 0.),
+		                                    |layout| layout.get_caret_rect(index, width));
+		self.get_transform().transform_rect(rect)
+    }
+
+    /// Get the rectangles covering the characters from `start` to `end`, one
+    /// per line, in global coordinates.
+    ///
+    /// The bounds can be given in any order.
+    pub fn get_selection_rects(&self, start: usize, end: usize) -> Vec<FloatRect> {
+		let transform = self.get_transform();
+		self.get_layout().map_or(Vec::new(), |layout| layout.get_selection_rects(start, end))
+			.into_iter().map(|rect| transform.transform_rect(rect)).collect()
+    }
+
     /// Get the local bounding rectangle of the text.
     ///
     /// The returned rectangle is in local coordinates, which means
@@ -206,8 +347,13 @@ impl<'s> Text<'s> {
     /// scale, ...) that are applied to the entity.
     /// In other words, this function returns the bounds of the
     /// entity in the entity's coordinate system.
+    ///
+    /// The bounds include the outline and the drop shadow, if they are
+    /// visible.
     pub fn get_local_bounds(&self) -> FloatRect {
-        unsafe { ffi::sfText_getLocalBounds(self.raw()) }
+		let bounds = unsafe { ffi::sfText_getLocalBounds(self.raw()) };
+		let shadow = if self.shadow_color.alpha > 0 { Some(self.shadow_offset) } else { None };
+		effect_bounds(bounds, self.outline_thickness, self.outline_color.alpha > 0, shadow)
     }
 
     /// Get the global bounding rectangle of the text.
@@ -218,8 +364,119 @@ impl<'s> Text<'s> {
     /// In other words, this function returns the bounds of the
     /// text in the global 2D world's coordinate system.
     pub fn get_global_bounds(&self) -> FloatRect {
-        unsafe { ffi::sfText_getGlobalBounds(self.raw()) }
+		self.get_transform().transform_rect(self.get_local_bounds())
     }
+
+	// Get the effect mask of the text, from the ones in use if an identical
+	// text has one.
+	fn get_effect(&self, font: &Font) -> Option<Rc<EffectMask>> {
+		let key = EffectKey {
+			font: font as *const Font as usize,
+			string: self.get_string(),
+			character_size: self.get_character_size(),
+			style: self.get_style().bits(),
+			thickness: self.outline_thickness.to_bits()
+		};
+		EFFECT_MASKS.with(|masks| {
+			let mut masks = masks.borrow_mut();
+			if let Some(mask) = masks.get(&key).and_then(|mask| mask.upgrade()) {
+				return Some(mask)
+			}
+			let mask = match self.render_effect(font) {
+				Some(mask) => Rc::new(mask),
+				None => return None
+			};
+			masks.retain(|_, mask| mask.upgrade().is_some());
+			masks.insert(key, Rc::downgrade(&mask));
+			Some(mask)
+		})
+	}
+
+	// Render the text and its outline into a texture, stamping the glyphs
+	// around their position. The white glyphs are blended as premultiplied
+	// colors, which keeps the colors white and overlapping stamps from
+	// adding up past full coverage.
+	fn render_effect(&self, font: &Font) -> Option<EffectMask> {
+		let layout = self.layout_with_color(font, Color::white());
+		let bounds = layout.get_bounds();
+		let thickness = self.outline_thickness;
+		let origin = Vector2f::new((bounds.left - thickness).floor() - 1., (bounds.top - thickness).floor() - 1.);
+		let width = (bounds.left + bounds.width + thickness - origin.x).ceil() as u32 + 1;
+		let height = (bounds.top + bounds.height + thickness - origin.y).ceil() as u32 + 1;
+		let mut texture = match RenderTexture::new(width, height, false) {
+			Some(texture) => texture,
+			None => return None
+		};
+
+		let mut offsets = vec![Vector2f::new(0., 0.)];
+		if thickness > 0. {
+			let steps = ((2. * PI * thickness).ceil() as usize).max(8);
+			offsets.extend((0..steps).map(|i| {
+				let angle = 2. * PI * i as f32 / steps as f32;
+				Vector2f::new(angle.cos() * thickness, angle.sin() * thickness)
+			}));
+			if thickness > 1.5 {
+				// Fill the inside of thick outlines
+				offsets.extend((0..8).map(|i| {
+					let angle = PI * i as f32 / 4.;
+					Vector2f::new(angle.cos() * thickness / 2., angle.sin() * thickness / 2.)
+				}));
+			}
+		}
+		texture.clear(Color::transparent());
+		for offset in offsets {
+			let mut transform = Transform::new_identity();
+			transform.translate(offset.x - origin.x, offset.y - origin.y);
+			let states = RenderStates {
+				blend_mode: BlendMode::premultiplied_alpha(),
+				transform: transform,
+				.. Default::default()
+			};
+			layout.draw_with(font, &mut texture, &states);
+		}
+		texture.display();
+		Some(EffectMask { texture: texture, origin: origin })
+	}
+
+	// Draw the effect mask in one color, moved by `offset`.
+	fn draw_effect(&self, mask: &EffectMask, color: Color, offset: Vector2f,
+	               target: &mut RenderTarget, states: &RenderStates) {
+		let texture = match mask.texture.get_texture() {
+			Some(texture) => texture,
+			None => return
+		};
+		if let Some(mut sprite) = Sprite::new_with_texture(&texture) {
+			sprite.set_color(color);
+			sprite.set_position(Vector2f::new(mask.origin.x + offset.x, mask.origin.y + offset.y));
+			let mut transform = states.transform;
+			transform.combine(&self.get_transform());
+			sprite.draw(target, &RenderStates { transform: transform, .. *states });
+		}
+	}
+
+	fn layout_with_color(&self, font: &Font, color: Color) -> TextLayout {
+		let mut style = SpanStyle::new(color, self.get_character_size());
+		style.style = self.get_style();
+		TextLayout::new(font, &[Span::new(&self.get_string(), style)], &LayoutOptions::new())
+	}
+}
+
+// Grow text bounds by a visible outline, then cover a shadow of the text and
+// its outline, visible or not, at `shadow`.
+fn effect_bounds(bounds: FloatRect, thickness: f32, outline: bool, shadow: Option<Vector2f>) -> FloatRect {
+	if bounds.width <= 0. && bounds.height <= 0. {
+		return bounds
+	}
+	let grow = if outline { thickness } else { 0. };
+	let (mut left, mut top) = (bounds.left - grow, bounds.top - grow);
+	let (mut right, mut bottom) = (bounds.left + bounds.width + grow, bounds.top + bounds.height + grow);
+	if let Some(shadow) = shadow {
+		left = left.min(bounds.left - thickness + shadow.x);
+		top = top.min(bounds.top - thickness + shadow.y);
+		right = right.max(bounds.left + bounds.width + thickness + shadow.x);
+		bottom = bottom.max(bounds.top + bounds.height + thickness + shadow.y);
+	}
+	FloatRect::new(left, top, right - left, bottom - top)
 }
 
 impl<'s> Transformable for Text<'s> {
@@ -272,6 +529,34 @@ impl<'s> Clone for Text<'s> {
 
 impl<'s> Drawable for Text<'s> {
     fn draw(&self, target: &mut RenderTarget, states: &RenderStates) {
+		let shadow = self.shadow_color.alpha > 0;
+		let outline = self.outline_thickness > 0. && self.outline_color.alpha > 0;
+		if let (Some(font), true) = (self.font, shadow || outline) {
+			let mut effect = self.effect.borrow_mut();
+			if effect.is_none() {
+				*effect = self.get_effect(font);
+			}
+			if let Some(ref mask) = *effect {
+				if shadow {
+					self.draw_effect(mask, self.shadow_color, self.shadow_offset, target, states);
+				}
+				if outline {
+					self.draw_effect(mask, self.outline_color, Vector2f::new(0., 0.), target, states);
+				}
+			}
+		}
         target.draw_text_rs(self, states)
     }
 }
+
+#[test]
+fn text_effect_bounds() {
+	let bounds = FloatRect::new(2., 10., 40., 20.);
+	assert_eq!(effect_bounds(bounds, 0., true, None), bounds);
+	assert_eq!(effect_bounds(bounds, 2., true, None), FloatRect::new(0., 8., 44., 24.));
+	assert_eq!(effect_bounds(bounds, 2., false, None), bounds);
+	assert_eq!(effect_bounds(bounds, 2., true, Some(Vector2f::new(3., -1.))), FloatRect::new(0., 7., 47., 25.));
+	assert_eq!(effect_bounds(bounds, 2., false, Some(Vector2f::new(3., -1.))), FloatRect::new(2., 7., 45., 24.));
+	let empty = FloatRect::new(0., 0., 0., 0.);
+	assert_eq!(effect_bounds(empty, 2., true, Some(Vector2f::new(3., 3.))), empty);
+}
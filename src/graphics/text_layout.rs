@@ -0,0 +1,998 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Rich text layout: word wrapping, alignment and spans of mixed styles.
+//!
+//! `Text` draws a single string in a single style. The layout engine in this
+//! module instead takes a list of `Span`s, each with its own color, character
+//! size and `TextStyle`, and arranges them into lines: words are wrapped at a
+//! maximum width, lines are aligned, and their spacing can be scaled. The
+//! result, a `TextLayout`, can be measured and queried, and holds the glyph
+//! quads ready to be drawn with the font's textures. `RichText` packages all
+//! of this as a transformable drawable.
+//!
+//! Spans can be built by hand or parsed from a small markup language with
+//! `parse_markup`:
+//!
+//! ```ignore
+//! let mut options = LayoutOptions::new();
+//! options.max_width = Some(400.);
+//! options.alignment = Alignment::Justify;
+//! let mut dialogue = RichText::new(&font, options).unwrap();
+//! dialogue.set_markup("You found the [color=yellow][b]golden key[/b][/color]! \
+//!                      [size=16][i]It hums quietly.[/i][/size]",
+//!                     SpanStyle::new(Color::white(), 24));
+//! window.draw(&dialogue);
+//! ```
+//!
+//! Layout is not tied to TrueType fonts: anything implementing `GlyphSource`
+//! can be laid out.
+
+use graphics::{BasicTransformable, Color, Drawable, FloatRect, Font, Glyph, PrimitiveType, RenderStates,
+               RenderTarget, Texture, TextStyle, Transform, Transformable, Vertex, VertexArray};
+use graphics::text_style::{BOLD, ITALIC, UNDERLINED, STRIKETHROUGH};
+use system::Vector2f;
+use ffi::Ref;
+
+/// Provider of glyphs and metrics for text layout.
+///
+/// Implemented by `Font`; implement it to lay out text with other kinds of
+/// fonts. All metrics are in pixels, for the given character size.
+pub trait GlyphSource {
+	/// Get the glyph of a character.
+	fn get_glyph(&self, codepoint: char, character_size: u32, bold: bool) -> Glyph;
+
+	/// Get the kerning offset to apply between two characters.
+	fn get_kerning(&self, first: char, second: char, character_size: u32) -> f32;
+
+	/// Get the vertical offset between two consecutive lines.
+	fn get_line_spacing(&self, character_size: u32) -> f32;
+
+	/// Get the vertical offset between the baseline and the underline.
+	fn get_underline_position(&self, character_size: u32) -> f32;
+
+	/// Get the thickness of the underline.
+	fn get_underline_thickness(&self, character_size: u32) -> f32;
+
+	/// Get the texture holding the glyph of a character.
+	///
+	/// Underlines and strikethroughs are drawn with the pixel at (1, 1) of
+	/// this texture, which should be opaque white.
+	///
+	/// Layouts only request a texture right before drawing with it, once per
+	/// batch, so the texture may be refilled by the next call, as fonts do.
+	fn get_texture<'a>(&'a self, codepoint: char, character_size: u32, bold: bool) -> Option<Ref<'a, Texture>>;
+
+	/// Get the index of the font or page holding the glyph of a character,
+	/// for sources made of several.
+	///
+	/// Layouts batch characters with the same index, character size and
+	/// bold status, which must share a texture. The default returns 0.
+	fn get_texture_index(&self, _codepoint: char) -> usize {
+		0
+	}
+}
+
+impl GlyphSource for Font {
+	fn get_glyph(&self, codepoint: char, character_size: u32, bold: bool) -> Glyph {
+		Font::get_glyph(self, codepoint, character_size, bold)
+	}
+
+	fn get_kerning(&self, first: char, second: char, character_size: u32) -> f32 {
+		Font::get_kerning(self, first, second, character_size)
+	}
+
+	fn get_line_spacing(&self, character_size: u32) -> f32 {
+		Font::get_line_spacing(self, character_size)
+	}
+
+	fn get_underline_position(&self, character_size: u32) -> f32 {
+		Font::get_underline_position(self, character_size)
+	}
+
+	fn get_underline_thickness(&self, character_size: u32) -> f32 {
+		Font::get_underline_thickness(self, character_size)
+	}
+
+	fn get_texture<'a>(&'a self, _codepoint: char, character_size: u32, _bold: bool) -> Option<Ref<'a, Texture>> {
+		self.get_shared_texture(character_size)
+	}
+}
+
+/// Horizontal alignment of the lines of a layout.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum Alignment {
+	/// Lines start at the left edge.
+	Left,
+	/// Lines are centered.
+	Center,
+	/// Lines end at the right edge.
+	Right,
+	/// Wrapped lines are stretched to fill the width, by widening their
+	/// spaces. The last line of each paragraph is aligned left.
+	Justify
+}
+
+/// Appearance of the characters of a span.
+#[derive(Clone, PartialEq, Debug, Copy)]
+pub struct SpanStyle {
+	/// Fill color of the characters.
+	pub color: Color,
+	/// Character size, in pixels.
+	pub character_size: u32,
+	/// Bold, italic, underlined and strikethrough flags.
+	pub style: TextStyle
+}
+
+impl SpanStyle {
+	/// Create a regular style with the given color and character size.
+	pub fn new(color: Color, character_size: u32) -> SpanStyle {
+		SpanStyle {
+			color: color,
+			character_size: character_size,
+			style: TextStyle::empty()
+		}
+	}
+}
+
+/// A run of text sharing one style.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Span {
+	/// The text of the span.
+	pub text: String,
+	/// The style of the text.
+	pub style: SpanStyle
+}
+
+impl Span {
+	/// Create a span.
+	pub fn new(text: &str, style: SpanStyle) -> Span {
+		Span {
+			text: text.to_owned(),
+			style: style
+		}
+	}
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+	match value {
+		"black" => return Some(Color::black()),
+		"white" => return Some(Color::white()),
+		"red" => return Some(Color::red()),
+		"green" => return Some(Color::green()),
+		"blue" => return Some(Color::blue()),
+		"yellow" => return Some(Color::yellow()),
+		"magenta" => return Some(Color::magenta()),
+		"cyan" => return Some(Color::cyan()),
+		"transparent" => return Some(Color::transparent()),
+		_ => {}
+	}
+	if !value.starts_with('#') || (value.len() != 7 && value.len() != 9) {
+		return None
+	}
+	let channel = |i: usize| value.get(i..i + 2).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+	let alpha = if value.len() == 9 { channel(7) } else { Some(255) };
+	match (channel(1), channel(3), channel(5), alpha) {
+		(Some(r), Some(g), Some(b), Some(a)) => Some(Color::new_rgba(r, g, b, a)),
+		_ => None
+	}
+}
+
+/// Parse text with inline style tags into spans.
+///
+/// The supported tags are `[b]`, `[i]`, `[u]` and `[s]` for bold, italic,
+/// underlined and strikethrough text, `[color=...]` with a color name or a
+/// `#rrggbb` or `#rrggbbaa` value, and `[size=...]` with a character size in
+/// pixels. Each is closed by the same tag with a slash, such as `[/color]`,
+/// and tags can be nested. Write `[[` for a literal `[`. Text in brackets
+/// that is not a known tag is kept as it is.
+pub fn parse_markup(markup: &str, base: SpanStyle) -> Vec<Span> {
+	let mut spans = Vec::new();
+	let mut colors = vec![base.color];
+	let mut sizes = vec![base.character_size];
+	let mut counts = [0u32; 4];
+	let mut current = base;
+	let mut text = String::new();
+	let mut rest = markup;
+
+	loop {
+		let open = match rest.find('[') {
+			Some(open) => open,
+			None => {
+				text.push_str(rest);
+				break
+			}
+		};
+		text.push_str(&rest[..open]);
+		rest = &rest[open..];
+		if rest.starts_with("[[") {
+			text.push('[');
+			rest = &rest[2..];
+			continue
+		}
+		let close = match rest.find(']') {
+			Some(close) => close,
+			None => {
+				text.push_str(rest);
+				break
+			}
+		};
+		let tag = &rest[1..close];
+		let (name, value) = match tag.find('=') {
+			Some(equals) => (&tag[..equals], Some(&tag[equals + 1..])),
+			None => (tag, None)
+		};
+		let flag = ["b", "i", "u", "s"].iter().position(|&f| f == name.trim_left_matches('/'));
+		let known = match (name, value, flag) {
+			("color", Some(value), _) => parse_color(value).map(|color| colors.push(color)).is_some(),
+			("size", Some(value), _) => value.parse().ok().map(|size| sizes.push(size)).is_some(),
+			("/color", None, _) if colors.len() > 1 => { colors.pop(); true }
+			("/size", None, _) if sizes.len() > 1 => { sizes.pop(); true }
+			(_, None, Some(flag)) if name.starts_with('/') => {
+				counts[flag] = counts[flag].saturating_sub(1);
+				true
+			}
+			(_, None, Some(flag)) => { counts[flag] += 1; true }
+			_ => false
+		};
+		if !known {
+			text.push('[');
+			rest = &rest[1..];
+			continue
+		}
+		rest = &rest[close + 1..];
+
+		let mut style = SpanStyle {
+			color: *colors.last().unwrap(),
+			character_size: *sizes.last().unwrap(),
+			style: base.style
+		};
+		for (&count, &flag) in counts.iter().zip([BOLD, ITALIC, UNDERLINED, STRIKETHROUGH].iter()) {
+			if count > 0 {
+				style.style = style.style | flag;
+			}
+		}
+		if style != current {
+			flush_span(&mut spans, &mut text, current);
+			current = style;
+		}
+	}
+	flush_span(&mut spans, &mut text, current);
+	spans
+}
+
+fn flush_span(spans: &mut Vec<Span>, text: &mut String, style: SpanStyle) {
+	if text.is_empty() {
+		return
+	}
+	match spans.last_mut() {
+		Some(span) if span.style == style => {
+			span.text.push_str(text);
+			text.clear();
+			return
+		}
+		_ => {}
+	}
+	spans.push(Span { text: text.clone(), style: style });
+	text.clear();
+}
+
+/// Settings of a text layout.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LayoutOptions {
+	/// Width at which lines are wrapped, or None to only break lines at
+	/// newlines. Words longer than the width are broken between characters.
+	pub max_width: Option<f32>,
+	/// Horizontal alignment of the lines, within `max_width` if set, or else
+	/// within the widest line.
+	pub alignment: Alignment,
+	/// Factor applied to the font's line spacing.
+	pub line_height: f32
+}
+
+impl LayoutOptions {
+	/// Create options for unwrapped, left aligned text with the font's line
+	/// spacing, which lays text out exactly like `Text`.
+	pub fn new() -> LayoutOptions {
+		LayoutOptions {
+			max_width: None,
+			alignment: Alignment::Left,
+			line_height: 1.
+		}
+	}
+}
+
+/// A character placed by a layout.
+#[derive(Clone, PartialEq, Debug, Copy)]
+pub struct PositionedGlyph {
+	/// The character.
+	pub character: char,
+	/// Index of the span the character belongs to.
+	pub span: usize,
+	/// Index of the line the character is on.
+	pub line: usize,
+	/// Position of the glyph's origin, on the baseline.
+	pub position: Vector2f,
+	/// Horizontal distance to the next character on the line, including
+	/// the space added by justification.
+	pub advance: f32,
+	/// The glyph of the character, as given by the font.
+	pub glyph: Glyph
+}
+
+/// A line of a layout.
+#[derive(Clone, PartialEq, Debug, Copy)]
+pub struct Line {
+	/// Index of the first character of the line.
+	pub start: usize,
+	/// Index one past the last character of the line, which includes its
+	/// trailing spaces and newline.
+	pub end: usize,
+	/// Horizontal position of the start of the line.
+	pub left: f32,
+	/// Width of the line, without trailing whitespace.
+	pub width: f32,
+	/// Vertical position of the top of the line.
+	pub top: f32,
+	/// Vertical position of the baseline.
+	pub baseline: f32,
+	/// Distance from the top of the line to the top of the next one.
+	pub height: f32
+}
+
+/// Glyph quads which are drawn with the same texture.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GlyphBatch {
+	/// The index of the font or page of the batch in its source.
+	pub texture_index: usize,
+	/// A character whose texture is used by the batch.
+	pub codepoint: char,
+	/// The character size of that character.
+	pub character_size: u32,
+	/// Whether that character is bold.
+	pub bold: bool,
+	/// The quads, as triangles.
+	pub vertices: VertexArray
+}
+
+impl GlyphBatch {
+	/// Get the texture to draw the batch with from the source it was laid
+	/// out with.
+	pub fn get_texture<'a>(&self, source: &'a GlyphSource) -> Option<Ref<'a, Texture>> {
+		source.get_texture(self.codepoint, self.character_size, self.bold)
+	}
+}
+
+/// Text arranged into lines, ready to be measured and drawn.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TextLayout {
+	glyphs: Vec<PositionedGlyph>,
+	lines: Vec<Line>,
+	batches: Vec<GlyphBatch>,
+	size: Vector2f,
+	bounds: FloatRect
+}
+
+impl TextLayout {
+	/// Lay out spans of text with glyphs from `source`.
+	pub fn new(source: &GlyphSource, spans: &[Span], options: &LayoutOptions) -> TextLayout {
+		let chars: Vec<(char, usize)> = spans.iter().enumerate()
+			.flat_map(|(i, span)| span.text.chars().map(move |c| (c, i)))
+			.collect();
+		let count = chars.len();
+		let style = |i: usize| spans[chars[i].1].style;
+
+		// Glyph, advance and kerning with the previous character
+		let metrics: Vec<(Glyph, f32, f32)> = (0..count).map(|i| {
+			let (c, s) = (chars[i].0, style(i));
+			let bold = s.style.contains(BOLD);
+			let glyph = source.get_glyph(c, s.character_size, bold);
+			let advance = match c {
+				'\n' => 0.,
+				'\t' => source.get_glyph(' ', s.character_size, bold).advance * 4.,
+				_ => glyph.advance
+			};
+			let kerning = if i > 0 && chars[i - 1].0 != '\n' && style(i - 1).character_size == s.character_size {
+				source.get_kerning(chars[i - 1].0, c, s.character_size)
+			} else {
+				0.
+			};
+			(glyph, advance, kerning)
+		}).collect();
+
+		// Break into lines of (start, end, ends a paragraph)
+		let mut breaks = Vec::new();
+		let mut start = 0;
+		while start < count {
+			let (mut x, mut last_break, mut end, mut hard) = (0., None, count, true);
+			for i in start..count {
+				let c = chars[i].0;
+				if c == '\n' {
+					end = i + 1;
+					break
+				}
+				let right = x + if i > start { metrics[i].2 } else { 0. } + metrics[i].1;
+				if let Some(max_width) = options.max_width {
+					if i > start && right > max_width && !c.is_whitespace() {
+						end = last_break.unwrap_or(i);
+						hard = false;
+						break
+					}
+				}
+				x = right;
+				if c == ' ' || c == '\t' {
+					last_break = Some(i + 1);
+				}
+			}
+			breaks.push((start, end, hard));
+			start = end;
+		}
+		if !spans.is_empty() && (count == 0 || chars[count - 1].0 == '\n') {
+			// An empty last line, for the caret to go to
+			breaks.push((count, count, true));
+		}
+
+		// Place characters along each line, starting at zero
+		let mut glyphs: Vec<PositionedGlyph> = Vec::with_capacity(count);
+		let mut widths = Vec::with_capacity(breaks.len());
+		for (line, &(start, end, _)) in breaks.iter().enumerate() {
+			let (mut x, mut width) = (0., 0.);
+			for i in start..end {
+				let (glyph, advance, kerning) = metrics[i];
+				x += if i > start { kerning } else { 0. };
+				glyphs.push(PositionedGlyph {
+					character: chars[i].0,
+					span: chars[i].1,
+					line: line,
+					position: Vector2f::new(x, 0.),
+					advance: advance,
+					glyph: glyph
+				});
+				x += advance;
+				if !chars[i].0.is_whitespace() {
+					width = x;
+				}
+			}
+			widths.push(width);
+		}
+		let box_width = options.max_width.unwrap_or_else(|| widths.iter().fold(0., |a: f32, &b| a.max(b)));
+
+		// Align and stack the lines
+		let mut lines = Vec::with_capacity(breaks.len());
+		let mut top = 0.;
+		for (line, &(start, end, hard)) in breaks.iter().enumerate() {
+			let styled: Vec<SpanStyle> = if start < end {
+				(start..end).map(|i| style(i)).collect()
+			} else {
+				vec![spans[chars.get(start.saturating_sub(1)).map_or(spans.len() - 1, |&(_, s)| s)].style]
+			};
+			let size = styled.iter().map(|s| s.character_size).max().unwrap_or(0);
+			let spacing = styled.iter().map(|s| source.get_line_spacing(s.character_size)).fold(0., f32::max);
+			let baseline = top + size as f32;
+			let mut width = widths[line];
+			let left = match options.alignment {
+				Alignment::Left | Alignment::Justify => 0.,
+				Alignment::Center => ((box_width - width) / 2.).floor(),
+				Alignment::Right => box_width - width
+			};
+
+			let visible = (start..end).filter(|&i| !chars[i].0.is_whitespace()).last().unwrap_or(start);
+			let spaces = (start..visible).filter(|&i| chars[i].0 == ' ').count();
+			let stretch = if options.alignment == Alignment::Justify && !hard && spaces > 0 && box_width > width {
+				(box_width - width) / spaces as f32
+			} else {
+				0.
+			};
+			let mut shift = left;
+			for i in start..end {
+				let glyph = &mut glyphs[i];
+				glyph.position = Vector2f::new(glyph.position.x + shift, baseline);
+				if stretch > 0. && i < visible && glyph.character == ' ' {
+					glyph.advance += stretch;
+					shift += stretch;
+				}
+			}
+			if stretch > 0. {
+				width = box_width;
+			}
+
+			lines.push(Line {
+				start: start,
+				end: end,
+				left: left,
+				width: width,
+				top: top,
+				baseline: baseline,
+				height: spacing * options.line_height
+			});
+			top += spacing * options.line_height;
+		}
+
+		let mut layout = TextLayout {
+			glyphs: glyphs,
+			lines: lines,
+			batches: Vec::new(),
+			size: Vector2f::new(0., top),
+			bounds: FloatRect::new(0., 0., 0., 0.)
+		};
+		layout.size.x = layout.lines.iter().fold(0., |a: f32, line| a.max(line.width));
+		layout.build_vertices(source, spans);
+		layout
+	}
+
+	fn build_vertices(&mut self, source: &GlyphSource, spans: &[Span]) {
+		let mut batches: Vec<GlyphBatch> = Vec::new();
+		let mut bounds: Option<FloatRect> = None;
+
+		for glyph in &self.glyphs {
+			let c = glyph.character;
+			if c == '\n' {
+				continue
+			}
+			let style = spans[glyph.span].style;
+			let (size, bold) = (style.character_size, style.style.contains(BOLD));
+			let texture_index = source.get_texture_index(c);
+			let index = match batches.iter().position(|batch| {
+				batch.texture_index == texture_index && batch.character_size == size && batch.bold == bold
+			}) {
+				Some(index) => index,
+				None => {
+					batches.push(GlyphBatch {
+						texture_index: texture_index,
+						codepoint: c,
+						character_size: size,
+						bold: bold,
+						vertices: VertexArray::new(PrimitiveType::Triangles)
+					});
+					batches.len() - 1
+				}
+			};
+			let vertices = &mut batches[index].vertices;
+			let Vector2f { x, y } = glyph.position;
+
+			if !c.is_whitespace() {
+				let shear = if style.style.contains(ITALIC) { 0.208 } else { 0. };
+				let (b, t) = (glyph.glyph.bounds, glyph.glyph.texture_rect);
+				let (top, bottom) = (b.top, b.top + b.height);
+				push_quad(vertices, style.color,
+				          [Vector2f::new(x + b.left - shear * top, y + top),
+				           Vector2f::new(x + b.left + b.width - shear * top, y + top),
+				           Vector2f::new(x + b.left - shear * bottom, y + bottom),
+				           Vector2f::new(x + b.left + b.width - shear * bottom, y + bottom)],
+				          [t.left as f32, t.top as f32, (t.left + t.width) as f32, (t.top + t.height) as f32]);
+			}
+
+			let thickness = source.get_underline_thickness(size);
+			let mut decorations = Vec::new();
+			if style.style.contains(UNDERLINED) {
+				decorations.push(source.get_underline_position(size));
+			}
+			if style.style.contains(STRIKETHROUGH) {
+				let x_bounds = source.get_glyph('x', size, bold).bounds;
+				decorations.push(x_bounds.top + x_bounds.height / 2.);
+			}
+			for offset in decorations {
+				let (top, bottom) = (y + offset - thickness / 2., y + offset + thickness / 2.);
+				let right = x + glyph.advance;
+				push_quad(vertices, style.color,
+				          [Vector2f::new(x, top), Vector2f::new(right, top),
+				           Vector2f::new(x, bottom), Vector2f::new(right, bottom)],
+				          [1., 1., 1., 1.]);
+			}
+		}
+
+		for batch in &batches {
+			let rect = batch.vertices.get_bounds();
+			bounds = Some(match bounds {
+				Some(b) => {
+					let (left, top) = (b.left.min(rect.left), b.top.min(rect.top));
+					let right = (b.left + b.width).max(rect.left + rect.width);
+					let bottom = (b.top + b.height).max(rect.top + rect.height);
+					FloatRect::new(left, top, right - left, bottom - top)
+				}
+				None => rect
+			});
+		}
+		self.batches = batches;
+		self.bounds = bounds.unwrap_or(FloatRect::new(0., 0., 0., 0.));
+	}
+
+	/// Get the characters of the layout, in order, with their positions.
+	pub fn get_glyphs(&self) -> &[PositionedGlyph] {
+		&self.glyphs
+	}
+
+	/// Get the lines of the layout.
+	pub fn get_lines(&self) -> &[Line] {
+		&self.lines
+	}
+
+	/// Get the glyph quads, grouped by texture.
+	pub fn get_batches(&self) -> &[GlyphBatch] {
+		&self.batches
+	}
+
+	/// Get the size of the layout: the width of its widest line and the sum
+	/// of the heights of its lines.
+	pub fn get_size(&self) -> Vector2f {
+		self.size
+	}
+
+	/// Get the rectangle covered by the glyphs and decorations.
+	pub fn get_bounds(&self) -> FloatRect {
+		self.bounds
+	}
+
+	// Line index and horizontal position of the caret before character
+	// `index`, or after the last character if it is out of range.
+	fn caret(&self, index: usize) -> Option<(usize, f32)> {
+		if let Some(glyph) = self.glyphs.get(index) {
+			return Some((glyph.line, glyph.position.x))
+		}
+		self.lines.last().map(|line| {
+			let x = self.glyphs[line.start..line.end].last()
+				.map_or(line.left, |glyph| glyph.position.x + glyph.advance);
+			(self.lines.len() - 1, x)
+		})
+	}
+
+	/// Get the position of the top left corner of the caret before
+	/// character `index`.
+	///
+	/// If `index` is out of range, the position after the last character is
+	/// returned. This is the layout's equivalent of
+	/// `Text::find_character_pos`.
+	pub fn find_character_pos(&self, index: usize) -> Vector2f {
+		match self.caret(index) {
+			Some((line, x)) => Vector2f::new(x, self.lines[line].top),
+			None => Vector2f::new(0., 0.)
+		}
+	}
+
+	/// Get the index of the caret position closest to `point`.
+	///
+	/// The result is between 0 and the number of characters, both included,
+	/// and is suitable for placing a caret or starting a selection. A point
+	/// past the end of a line gives the position before its newline or
+	/// trailing space, so that the caret stays on that line.
+	pub fn find_character_index(&self, point: Vector2f) -> usize {
+		let line = match self.lines.iter().position(|line| point.y < line.top + line.height) {
+			Some(line) => &self.lines[line],
+			None => match self.lines.last() {
+				Some(line) => line,
+				None => return 0
+			}
+		};
+		for i in line.start..line.end {
+			let glyph = &self.glyphs[i];
+			if glyph.character == '\n' || point.x < glyph.position.x + glyph.advance / 2. {
+				return i
+			}
+		}
+		if line.end < self.glyphs.len() && line.end > line.start {
+			line.end - 1
+		} else {
+			line.end
+		}
+	}
+
+	/// Get the rectangle of a caret before character `index`, as tall as
+	/// its line and `width` pixels wide.
+	pub fn get_caret_rect(&self, index: usize, width: f32) -> FloatRect {
+		match self.caret(index) {
+			Some((line, x)) => {
+				let line = &self.lines[line];
+				FloatRect::new(x, line.top, width, line.height)
+			}
+			None => FloatRect::new(0., 0., width, 0.)
+		}
+	}
+
+	/// Get the rectangles to highlight to show the selection of characters
+	/// `start` to `end`, with at most one rectangle per line.
+	///
+	/// The bounds can be given in any order.
+	pub fn get_selection_rects(&self, start: usize, end: usize) -> Vec<FloatRect> {
+		let (start, end) = (start.min(end), start.max(end).min(self.glyphs.len()));
+		let mut rects = Vec::new();
+		if start >= end {
+			return rects
+		}
+		let (first, _) = self.caret(start).unwrap();
+		let (last, _) = self.caret(end - 1).unwrap();
+		for line in &self.lines[first..last + 1] {
+			let from = start.max(line.start);
+			let to = end.min(line.end);
+			let left = self.glyphs[from].position.x;
+			let right = self.glyphs[to - 1].position.x + self.glyphs[to - 1].advance;
+			rects.push(FloatRect::new(left, line.top, right - left, line.height));
+		}
+		rects
+	}
+
+	/// Draw the layout with the textures of the source it was laid out with.
+	pub fn draw_with(&self, source: &GlyphSource, target: &mut RenderTarget, states: &RenderStates) {
+		for batch in &self.batches {
+			let texture = batch.get_texture(source);
+			target.draw_primitives_rs(&batch.vertices, PrimitiveType::Triangles, &RenderStates {
+				texture: texture.as_ref().map(|texture| &**texture),
+				.. *states
+			});
+		}
+	}
+}
+
+fn push_quad(vertices: &mut VertexArray, color: Color, corners: [Vector2f; 4], coords: [f32; 4]) {
+	let [u1, v1, u2, v2] = coords;
+	let quad = [
+		Vertex::new(corners[0], color, Vector2f::new(u1, v1)),
+		Vertex::new(corners[1], color, Vector2f::new(u2, v1)),
+		Vertex::new(corners[2], color, Vector2f::new(u1, v2)),
+		Vertex::new(corners[3], color, Vector2f::new(u2, v2))
+	];
+	for &i in &[0, 1, 2, 2, 1, 3] {
+		vertices.push(quad[i]);
+	}
+}
+
+/// Text made of styled spans, laid out and drawn as one object.
+///
+/// The layout is recomputed whenever the spans or options change.
+#[derive(Clone)]
+pub struct RichText<'s> {
+	source: &'s GlyphSource,
+	spans: Vec<Span>,
+	options: LayoutOptions,
+	layout: TextLayout,
+	transformable: BasicTransformable
+}
+
+impl<'s> RichText<'s> {
+	/// Create an empty rich text drawn with glyphs from `source`.
+	///
+	/// Returns Some(RichText) or None on failure.
+	pub fn new(source: &'s GlyphSource, options: LayoutOptions) -> Option<RichText<'s>> {
+		BasicTransformable::new().map(|transformable| RichText {
+			source: source,
+			layout: TextLayout::new(source, &[], &options),
+			spans: Vec::new(),
+			options: options,
+			transformable: transformable
+		})
+	}
+
+	/// Set the spans of text.
+	pub fn set_spans(&mut self, spans: Vec<Span>) {
+		self.spans = spans;
+		self.update();
+	}
+
+	/// Set the spans of text by parsing markup, see `parse_markup`.
+	pub fn set_markup(&mut self, markup: &str, base: SpanStyle) {
+		self.set_spans(parse_markup(markup, base));
+	}
+
+	/// Get the spans of text.
+	pub fn get_spans(&self) -> &[Span] {
+		&self.spans
+	}
+
+	/// Set the layout options.
+	pub fn set_options(&mut self, options: LayoutOptions) {
+		self.options = options;
+		self.update();
+	}
+
+	/// Get the layout options.
+	pub fn get_options(&self) -> &LayoutOptions {
+		&self.options
+	}
+
+	/// Set the source of the glyphs.
+	pub fn set_source(&mut self, source: &'s GlyphSource) {
+		self.source = source;
+		self.update();
+	}
+
+	/// Get the current layout of the text, in local coordinates.
+	pub fn get_layout(&self) -> &TextLayout {
+		&self.layout
+	}
+
+	/// Get the local bounding rectangle of the text.
+	pub fn get_local_bounds(&self) -> FloatRect {
+		self.layout.get_bounds()
+	}
+
+	/// Get the global bounding rectangle of the text.
+	pub fn get_global_bounds(&self) -> FloatRect {
+		self.get_transform().transform_rect(self.layout.get_bounds())
+	}
+
+	fn update(&mut self) {
+		self.layout = TextLayout::new(self.source, &self.spans, &self.options);
+	}
+}
+
+impl<'s> Transformable for RichText<'s> {
+	fn set_position(&mut self, position: Vector2f) { self.transformable.set_position(position) }
+	fn set_rotation(&mut self, angle: f32) { self.transformable.set_rotation(angle) }
+	fn set_scale(&mut self, scale: Vector2f) { self.transformable.set_scale(scale) }
+	fn set_origin(&mut self, origin: Vector2f) { self.transformable.set_origin(origin) }
+	fn get_position(&self) -> Vector2f { self.transformable.get_position() }
+	fn get_rotation(&self) -> f32 { self.transformable.get_rotation() }
+	fn get_scale(&self) -> Vector2f { self.transformable.get_scale() }
+	fn get_origin(&self) -> Vector2f { self.transformable.get_origin() }
+	fn move_(&mut self, offset: Vector2f) { self.transformable.move_(offset) }
+	fn rotate(&mut self, angle: f32) { self.transformable.rotate(angle) }
+	fn scale(&mut self, factors: Vector2f) { self.transformable.scale(factors) }
+	fn get_transform(&self) -> Transform { self.transformable.get_transform() }
+	fn get_inverse_transform(&self) -> Transform { self.transformable.get_inverse_transform() }
+}
+
+impl<'s> Drawable for RichText<'s> {
+	fn draw(&self, target: &mut RenderTarget, states: &RenderStates) {
+		let mut transform = states.transform;
+		transform.combine(&self.get_transform());
+		self.layout.draw_with(self.source, target, &RenderStates {
+			transform: transform,
+			.. *states
+		});
+	}
+}
+
+// A monospaced font with round metrics, for testing without FreeType.
+#[cfg(test)]
+struct TestFont;
+
+#[cfg(test)]
+impl GlyphSource for TestFont {
+	fn get_glyph(&self, codepoint: char, character_size: u32, _bold: bool) -> Glyph {
+		let size = character_size as f32;
+		Glyph {
+			advance: size / 2.,
+			bounds: if codepoint == ' ' { FloatRect::new(0., 0., 0., 0.) } else {
+				FloatRect::new(0., -size * 0.7, size / 2., size * 0.7)
+			},
+			texture_rect: ::graphics::IntRect::new(codepoint as i32, 0, 1, 1)
+		}
+	}
+
+	fn get_kerning(&self, first: char, second: char, _character_size: u32) -> f32 {
+		if first == 'A' && second == 'V' { -1. } else { 0. }
+	}
+
+	fn get_line_spacing(&self, character_size: u32) -> f32 {
+		character_size as f32 * 1.2
+	}
+
+	fn get_underline_position(&self, character_size: u32) -> f32 {
+		character_size as f32 / 10.
+	}
+
+	fn get_underline_thickness(&self, _character_size: u32) -> f32 {
+		1.
+	}
+
+	fn get_texture<'a>(&'a self, _codepoint: char, _character_size: u32, _bold: bool) -> Option<Ref<'a, Texture>> {
+		None
+	}
+}
+
+#[cfg(test)]
+fn line_strings(layout: &TextLayout) -> Vec<String> {
+	layout.get_lines().iter().map(|line| {
+		layout.get_glyphs()[line.start..line.end].iter().map(|glyph| glyph.character).collect()
+	}).collect()
+}
+
+#[test]
+fn text_layout_wraps_words() {
+	let spans = [Span::new("hello world foo\nbar", SpanStyle::new(Color::white(), 10))];
+	let mut options = LayoutOptions::new();
+	options.max_width = Some(55.);
+	let layout = TextLayout::new(&TestFont, &spans, &options);
+	assert_eq!(line_strings(&layout), vec!["hello world ", "foo\n", "bar"]);
+	let lines = layout.get_lines();
+	assert_eq!(lines[0].width, 55.);
+	assert_eq!(lines[1].baseline, 22.);
+	assert_eq!(layout.get_size(), Vector2f::new(55., 36.));
+
+	options.max_width = Some(12.);
+	let layout = TextLayout::new(&TestFont, &[Span::new("abcde", spans[0].style)], &options);
+	assert_eq!(line_strings(&layout), vec!["ab", "cd", "e"]);
+}
+
+#[test]
+fn text_layout_alignment() {
+	let spans = [Span::new("aa bb cc dd", SpanStyle::new(Color::white(), 10))];
+	let mut options = LayoutOptions::new();
+	options.max_width = Some(50.);
+	options.alignment = Alignment::Right;
+	let layout = TextLayout::new(&TestFont, &spans, &options);
+	assert_eq!(line_strings(&layout), vec!["aa bb cc ", "dd"]);
+	assert_eq!(layout.get_lines()[1].left, 40.);
+
+	options.alignment = Alignment::Center;
+	let layout = TextLayout::new(&TestFont, &spans, &options);
+	assert_eq!(layout.get_lines()[0].left, 5.);
+
+	options.alignment = Alignment::Justify;
+	let layout = TextLayout::new(&TestFont, &spans, &options);
+	let glyphs = layout.get_glyphs();
+	assert_eq!(glyphs[6].position.x, 40.);
+	assert_eq!(layout.get_lines()[0].width, 50.);
+	assert_eq!(glyphs[9].position.x, 0.);
+}
+
+#[test]
+fn text_layout_spans() {
+	let base = SpanStyle::new(Color::white(), 10);
+	let spans = parse_markup("A[color=red]V[size=20]x[[[/size][/color] [b][u]y[/u][/b][bad]", base);
+	let mut red = base;
+	red.color = Color::red();
+	let mut big = red;
+	big.character_size = 20;
+	let mut bold = base;
+	bold.style = BOLD | UNDERLINED;
+	assert_eq!(spans, vec![Span::new("A", base), Span::new("V", red), Span::new("x[", big),
+	                       Span::new(" ", base), Span::new("y", bold), Span::new("[bad]", base)]);
+
+	let layout = TextLayout::new(&TestFont, &spans, &LayoutOptions::new());
+	let glyphs = layout.get_glyphs();
+	assert_eq!(glyphs[1].position.x, 4.);
+	assert_eq!(glyphs[2].position.x, 9.);
+	assert_eq!(glyphs[2].position.y, 20.);
+	// One batch per character size and boldness; "y" is underlined
+	let batches: Vec<(u32, bool, usize)> = layout.get_batches().iter().map(|batch| {
+		(batch.character_size, batch.bold, batch.vertices.len() / 6)
+	}).collect();
+	assert_eq!(batches, vec![(10, false, 7), (20, false, 2), (10, true, 2)]);
+}
+
+#[test]
+fn text_layout_hit_testing() {
+	let spans = [Span::new("ab cd\nef", SpanStyle::new(Color::white(), 10))];
+	let mut options = LayoutOptions::new();
+	options.max_width = Some(16.);
+	let layout = TextLayout::new(&TestFont, &spans, &options);
+	assert_eq!(line_strings(&layout), vec!["ab ", "cd\n", "ef"]);
+
+	assert_eq!(layout.find_character_index(Vector2f::new(-5., -5.)), 0);
+	assert_eq!(layout.find_character_index(Vector2f::new(6., 5.)), 1);
+	assert_eq!(layout.find_character_index(Vector2f::new(40., 5.)), 2);
+	assert_eq!(layout.find_character_index(Vector2f::new(40., 15.)), 5);
+	assert_eq!(layout.find_character_index(Vector2f::new(8., 100.)), 8);
+
+	assert_eq!(layout.find_character_pos(4), Vector2f::new(5., 12.));
+	assert_eq!(layout.find_character_pos(100), Vector2f::new(10., 24.));
+	assert_eq!(layout.get_caret_rect(6, 1.), FloatRect::new(0., 24., 1., 12.));
+
+	assert_eq!(layout.get_selection_rects(7, 1), vec![
+		FloatRect::new(5., 0., 10., 12.),
+		FloatRect::new(0., 12., 10., 12.),
+		FloatRect::new(0., 24., 5., 12.)
+	]);
+	assert!(layout.get_selection_rects(3, 3).is_empty());
+}
@@ -0,0 +1,319 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Loader for Tiled's JSON map format.
+
+use std::collections::HashMap;
+
+use system::{Time, Vector2f};
+use graphics::tilemap::MapError;
+use graphics::tilemap::map::{self, Map, Tileset, TileAnimationFrame, Layer, TileLayer,
+                             ObjectLayer, MapObject, ObjectShape, Tile, Properties};
+use formats::json::{self, Json};
+
+fn number(value: &Json, key: &str, default: f64) -> Result<f64, MapError> {
+	match value.get(key) {
+		None | Some(&Json::Null) => Ok(default),
+		Some(&Json::Number(n)) => Ok(n),
+		Some(_) => Err(MapError::Parse(format!("`{}` is not a number", key)))
+	}
+}
+
+fn unsigned(value: &Json, key: &str, default: u32) -> Result<u32, MapError> {
+	number(value, key, default as f64).map(|n| n as u32)
+}
+
+fn float(value: &Json, key: &str, default: f32) -> Result<f32, MapError> {
+	number(value, key, default as f64).map(|n| n as f32)
+}
+
+fn string(value: &Json, key: &str) -> String {
+	value.get(key).and_then(Json::as_str).unwrap_or("").to_owned()
+}
+
+fn boolean(value: &Json, key: &str, default: bool) -> bool {
+	value.get(key).and_then(Json::as_bool).unwrap_or(default)
+}
+
+fn array<'a>(value: &'a Json, key: &str) -> &'a [Json] {
+	value.get(key).and_then(Json::as_array).unwrap_or(&[])
+}
+
+fn parse_document(text: &str, kind: &str) -> Result<Json, MapError> {
+	let value = try!(json::parse(text).map_err(MapError::Parse));
+	match value.get("type").and_then(Json::as_str) {
+		Some(found) if found != kind => {
+			Err(MapError::Parse(format!("expected a JSON {}, found a {}", kind, found)))
+		}
+		_ => Ok(value)
+	}
+}
+
+fn parse_properties(value: &Json) -> Result<Properties, MapError> {
+	let mut properties = HashMap::new();
+	for property in array(value, "properties") {
+		let text = match property.get("value") {
+			Some(&Json::Number(n)) => n.to_string(),
+			Some(&Json::Bool(b)) => b.to_string(),
+			Some(&Json::String(ref s)) => s.clone(),
+			_ => String::new()
+		};
+		let kind = property.get("type").and_then(Json::as_str).unwrap_or("string");
+		properties.insert(string(property, "name"), try!(map::make_property(kind, &text)));
+	}
+	Ok(properties)
+}
+
+pub fn parse_map(text: &str, dir: &str) -> Result<Map, MapError> {
+	let root = try!(parse_document(text, "map"));
+	if boolean(&root, "infinite", false) {
+		return Err(MapError::Unsupported("infinite maps".to_owned()))
+	}
+
+	let mut tilesets = Vec::new();
+	for value in array(&root, "tilesets") {
+		let first_gid = try!(unsigned(value, "firstgid", 1));
+		tilesets.push(match value.get("source").and_then(Json::as_str) {
+			Some(source) => try!(map::load_tileset(dir, source, first_gid)),
+			None => try!(parse_tileset(value, first_gid, dir))
+		});
+	}
+
+	let mut layers = Vec::new();
+	try!(parse_layers(array(&root, "layers"), &mut layers));
+
+	let orientation = root.get("orientation").and_then(Json::as_str).unwrap_or("orthogonal");
+	Ok(Map {
+		orientation: try!(map::parse_orientation(orientation)),
+		width: try!(unsigned(&root, "width", 0)),
+		height: try!(unsigned(&root, "height", 0)),
+		tile_width: try!(unsigned(&root, "tilewidth", 0)),
+		tile_height: try!(unsigned(&root, "tileheight", 0)),
+		background_color: root.get("backgroundcolor").and_then(Json::as_str).and_then(map::parse_color),
+		tilesets: tilesets,
+		layers: layers,
+		properties: try!(parse_properties(&root))
+	})
+}
+
+pub fn parse_tileset_file(text: &str, first_gid: u32, dir: &str) -> Result<Tileset, MapError> {
+	let root = try!(parse_document(text, "tileset"));
+	parse_tileset(&root, first_gid, dir)
+}
+
+fn parse_tileset(value: &Json, first_gid: u32, dir: &str) -> Result<Tileset, MapError> {
+	let image = match value.get("image").and_then(Json::as_str) {
+		Some(image) => map::resolve(dir, image),
+		None => return Err(MapError::Unsupported("image collection tilesets".to_owned()))
+	};
+	let mut tileset = Tileset {
+		first_gid: first_gid,
+		name: string(value, "name"),
+		tile_width: try!(unsigned(value, "tilewidth", 0)),
+		tile_height: try!(unsigned(value, "tileheight", 0)),
+		spacing: try!(unsigned(value, "spacing", 0)),
+		margin: try!(unsigned(value, "margin", 0)),
+		columns: try!(unsigned(value, "columns", 0)),
+		tile_count: try!(unsigned(value, "tilecount", 0)),
+		image: image,
+		image_width: try!(unsigned(value, "imagewidth", 0)),
+		image_height: try!(unsigned(value, "imageheight", 0)),
+		animations: HashMap::new(),
+		tile_properties: HashMap::new(),
+		properties: try!(parse_properties(value))
+	};
+	for tile in array(value, "tiles") {
+		let id = try!(unsigned(tile, "id", 0));
+		if tile.get("animation").is_some() {
+			let mut frames = Vec::new();
+			for frame in array(tile, "animation") {
+				frames.push(TileAnimationFrame {
+					tile_id: try!(unsigned(frame, "tileid", 0)),
+					duration: Time::with_milliseconds(try!(number(frame, "duration", 0.)) as i32)
+				});
+			}
+			tileset.animations.insert(id, frames);
+		}
+		let properties = try!(parse_properties(tile));
+		if !properties.is_empty() {
+			tileset.tile_properties.insert(id, properties);
+		}
+	}
+	try!(map::fix_up_tileset(&mut tileset));
+	Ok(tileset)
+}
+
+fn parse_layers(values: &[Json], layers: &mut Vec<Layer>) -> Result<(), MapError> {
+	for value in values {
+		let offset = Vector2f::new(try!(float(value, "offsetx", 0.)), try!(float(value, "offsety", 0.)));
+		let opacity = try!(float(value, "opacity", 1.));
+		let visible = boolean(value, "visible", true);
+		match value.get("type").and_then(Json::as_str).unwrap_or("") {
+			"tilelayer" => {
+				if value.get("chunks").is_some() {
+					return Err(MapError::Unsupported("infinite maps".to_owned()))
+				}
+				let width = try!(unsigned(value, "width", 0));
+				let height = try!(unsigned(value, "height", 0));
+				let count = try!(map::tile_count(width, height));
+				let tiles = match value.get("data") {
+					Some(&Json::String(ref data)) => {
+						let compression = value.get("compression").and_then(Json::as_str);
+						try!(map::decode_tiles(data, Some("base64"), compression, count))
+					}
+					Some(&Json::Array(ref data)) => {
+						let raw: Vec<u32> = data.iter().filter_map(Json::as_f64).map(|n| n as u32).collect();
+						if raw.len() != count {
+							return Err(MapError::Parse(format!("expected {} tiles, found {}", count, raw.len())))
+						}
+						raw.into_iter().map(Tile::from_raw).collect()
+					}
+					_ => return Err(MapError::Parse("tile layer without data".to_owned()))
+				};
+				layers.push(Layer::Tiles(TileLayer {
+					name: string(value, "name"),
+					width: width,
+					height: height,
+					tiles: tiles,
+					visible: visible,
+					opacity: opacity,
+					offset: offset,
+					properties: try!(parse_properties(value))
+				}));
+			}
+			"objectgroup" => {
+				let mut objects = Vec::new();
+				for object in array(value, "objects") {
+					objects.push(try!(parse_object(object)));
+				}
+				layers.push(Layer::Objects(ObjectLayer {
+					name: string(value, "name"),
+					objects: objects,
+					visible: visible,
+					opacity: opacity,
+					offset: offset,
+					properties: try!(parse_properties(value))
+				}));
+			}
+			"group" => {
+				let first = layers.len();
+				try!(parse_layers(array(value, "layers"), layers));
+				for layer in &mut layers[first..] {
+					map::apply_group(layer, offset, opacity, visible);
+				}
+			}
+			_ => {}
+		}
+	}
+	Ok(())
+}
+
+fn parse_points(values: &[Json]) -> Result<Vec<Vector2f>, MapError> {
+	let mut points = Vec::with_capacity(values.len());
+	for point in values {
+		points.push(Vector2f::new(try!(float(point, "x", 0.)), try!(float(point, "y", 0.))));
+	}
+	Ok(points)
+}
+
+fn parse_object(value: &Json) -> Result<MapObject, MapError> {
+	let shape = if boolean(value, "ellipse", false) {
+		ObjectShape::Ellipse
+	} else if boolean(value, "point", false) {
+		ObjectShape::Point
+	} else if value.get("polygon").is_some() {
+		ObjectShape::Polygon(try!(parse_points(array(value, "polygon"))))
+	} else if value.get("polyline").is_some() {
+		ObjectShape::Polyline(try!(parse_points(array(value, "polyline"))))
+	} else {
+		ObjectShape::Rectangle
+	};
+	let kind = match value.get("type").or(value.get("class")).and_then(Json::as_str) {
+		Some(kind) => kind.to_owned(),
+		None => String::new()
+	};
+	Ok(MapObject {
+		id: try!(unsigned(value, "id", 0)),
+		name: string(value, "name"),
+		kind: kind,
+		position: Vector2f::new(try!(float(value, "x", 0.)), try!(float(value, "y", 0.))),
+		size: Vector2f::new(try!(float(value, "width", 0.)), try!(float(value, "height", 0.))),
+		rotation: try!(float(value, "rotation", 0.)),
+		tile: match value.get("gid") {
+			Some(_) => Some(Tile::from_raw(try!(number(value, "gid", 0.)) as u32)),
+			None => None
+		},
+		visible: boolean(value, "visible", true),
+		shape: shape,
+		properties: try!(parse_properties(value))
+	})
+}
+
+#[test]
+fn json_parse() {
+	let text = r##"{
+		"type": "map", "orientation": "orthogonal", "width": 2, "height": 2,
+		"tilewidth": 8, "tileheight": 8, "infinite": false, "backgroundcolor": "#80ff0000",
+		"properties": [{"name": "lives", "type": "int", "value": 3}],
+		"tilesets": [{"firstgid": 1, "name": "t", "tilewidth": 8, "tileheight": 8,
+		              "image": "t.png", "imagewidth": 32, "imageheight": 16,
+		              "tiles": [{"id": 1, "animation": [{"tileid": 1, "duration": 50}, {"tileid": 2, "duration": 50}]}]}],
+		"layers": [
+			{"type": "tilelayer", "name": "a", "width": 2, "height": 2, "data": [1, 2, 0, 3],
+			 "opacity": 1, "visible": true, "x": 0, "y": 0},
+			{"type": "tilelayer", "name": "b", "width": 2, "height": 2, "encoding": "base64",
+			 "compression": "zlib", "data": "eJxjZGBgYGKAAGYgBgAARAAH"},
+			{"type": "objectgroup", "name": "o", "objects": [
+				{"id": 4, "name": "door", "type": "", "x": 1, "y": 2, "width": 3, "height": 4,
+				 "rotation": 0, "visible": true, "ellipse": true,
+				 "properties": [{"name": "locked", "type": "bool", "value": true}]}]}
+		]
+	}"##;
+	let map = Map::from_json(text, "").unwrap();
+	assert_eq!(map.background_color, Some(::graphics::Color::new_rgba(255, 0, 0, 128)));
+	assert_eq!(map.properties.get("lives"), Some(&::graphics::tilemap::Property::Int(3)));
+	assert_eq!(map.tilesets[0].columns, 4);
+	assert_eq!(map.tilesets[0].tile_count, 8);
+	assert_eq!(map.tilesets[0].animations[&1].len(), 2);
+	let (a, b) = match (&map.layers[0], &map.layers[1]) {
+		(&Layer::Tiles(ref a), &Layer::Tiles(ref b)) => (a, b),
+		_ => panic!("expected tile layers")
+	};
+	assert_eq!(a.tiles, b.tiles);
+	match map.layers[2] {
+		Layer::Objects(ref layer) => {
+			assert_eq!(layer.objects[0].shape, ObjectShape::Ellipse);
+			assert_eq!(layer.objects[0].size, Vector2f::new(3., 4.));
+			assert_eq!(layer.objects[0].properties.get("locked"),
+			           Some(&::graphics::tilemap::Property::Bool(true)));
+		}
+		_ => panic!("expected an object layer")
+	}
+
+	let huge = r#"{"type": "map", "layers": [{"type": "tilelayer", "width": 65536, "height": 65536, "data": [1]}]}"#;
+	match Map::from_json(huge, "") {
+		Err(MapError::Parse(ref msg)) if msg.contains("too large") => (),
+		other => panic!("expected a size error, got {:?}", other.err())
+	}
+}
@@ -0,0 +1,381 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use graphics::{Color, Drawable, FloatRect, IntRect, PrimitiveType, RenderStates, RenderTarget,
+               Texture, Vertex, VertexArray};
+use graphics::tilemap::{Map, Orientation, Layer, Tile};
+use system::{Time, Vector2f};
+
+/// A rectangular block of tiles of a `LayerMesh`, with one vertex array of
+/// quads per tileset used in the block.
+#[derive(Clone, Debug)]
+pub struct Chunk {
+	bounds: FloatRect,
+	meshes: Vec<(usize, VertexArray)>
+}
+
+impl Chunk {
+	/// Get the area covered by the chunk's vertices, in layer coordinates.
+	pub fn get_bounds(&self) -> FloatRect {
+		self.bounds
+	}
+
+	/// Get the vertex arrays of the chunk, paired with the index of the
+	/// tileset they use.
+	pub fn get_meshes(&self) -> &[(usize, VertexArray)] {
+		&self.meshes
+	}
+
+	/// Tell whether the chunk overlaps an area given in layer coordinates.
+	pub fn intersects(&self, area: &FloatRect) -> bool {
+		let b = &self.bounds;
+		b.left < area.left + area.width && area.left < b.left + b.width &&
+			b.top < area.top + area.height && area.top < b.top + b.height
+	}
+}
+
+// Texture rectangles and durations of one animated tile.
+#[derive(Clone, Debug)]
+struct TileAnimation {
+	frames: Vec<(IntRect, Time)>,
+	total: Time
+}
+
+impl TileAnimation {
+	fn frame_at(&self, elapsed: Time) -> usize {
+		if self.total <= Time::with_microseconds(0) {
+			return 0
+		}
+		let mut time = Time::with_microseconds(elapsed.as_microseconds() % self.total.as_microseconds());
+		for (i, &(_, duration)) in self.frames.iter().enumerate() {
+			if time < duration {
+				return i
+			}
+			time = time - duration;
+		}
+		self.frames.len() - 1
+	}
+}
+
+// Location in the chunk meshes of a quad showing an animated tile.
+#[derive(Clone, Debug)]
+struct AnimatedQuad {
+	chunk: usize,
+	mesh: usize,
+	vertex: usize,
+	animation: usize,
+	tile: Tile,
+	frame: usize
+}
+
+// Compute the texture coordinates of the four corners of a quad (top-left,
+// top-right, bottom-right, bottom-left), taking the tile's flags into account.
+fn quad_tex_coords(rect: IntRect, tile: Tile) -> [Vector2f; 4] {
+	let mut coords = [Vector2f::new(0., 0.); 4];
+	let corners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+	for (coord, &(mut x, mut y)) in coords.iter_mut().zip(corners.iter()) {
+		if tile.flip_vertically {
+			y = 1. - y;
+		}
+		if tile.flip_horizontally {
+			x = 1. - x;
+		}
+		if tile.flip_diagonally {
+			::std::mem::swap(&mut x, &mut y);
+		}
+		*coord = Vector2f::new(rect.left as f32 + x * rect.width as f32,
+		                       rect.top as f32 + y * rect.height as f32);
+	}
+	coords
+}
+
+/// Drawable geometry of one tile layer of a `Map`.
+///
+/// The layer is split into square chunks of tiles. When drawn, only the chunks
+/// that intersect the render target's current view are submitted, so large
+/// maps stay cheap to draw. Each tileset used by the layer needs a texture,
+/// given with `set_texture`; tiles of tilesets without one are not drawn.
+///
+/// Animated tiles are advanced with `update`.
+pub struct LayerMesh<'s> {
+	chunks: Vec<Chunk>,
+	textures: Vec<Option<&'s Texture>>,
+	animations: Vec<TileAnimation>,
+	animated: Vec<AnimatedQuad>,
+	elapsed: Time,
+	visible: bool
+}
+
+impl<'s> LayerMesh<'s> {
+	/// Build the geometry of a tile layer, using chunks of `chunk_size` by
+	/// `chunk_size` tiles.
+	///
+	/// Returns None if the layer does not exist or is not a tile layer, if the
+	/// map is not orthogonal, or if `chunk_size` is zero.
+	pub fn new(map: &Map, layer_index: usize, chunk_size: u32) -> Option<LayerMesh<'s>> {
+		let layer = match map.layers.get(layer_index) {
+			Some(&Layer::Tiles(ref layer)) => layer,
+			_ => return None
+		};
+		if map.orientation != Orientation::Orthogonal || chunk_size == 0 {
+			return None
+		}
+
+		// Collect the animations of every tileset up front.
+		let mut animations = Vec::new();
+		let mut animation_ids = Vec::new();
+		for (tileset_index, tileset) in map.tilesets.iter().enumerate() {
+			for (&local_id, frames) in &tileset.animations {
+				let frames: Vec<(IntRect, Time)> = frames.iter().map(|frame| {
+					(tileset.get_tile_rect(frame.tile_id), frame.duration)
+				}).collect();
+				let total = frames.iter().fold(Time::with_microseconds(0), |sum, f| sum + f.1);
+				if !frames.is_empty() {
+					animation_ids.push((tileset_index, local_id));
+					animations.push(TileAnimation { frames: frames, total: total });
+				}
+			}
+		}
+
+		let alpha = (layer.opacity.max(0.).min(1.) * 255.) as u8;
+		let color = Color::new_rgba(255, 255, 255, alpha);
+		let chunks_x = ((layer.width as u64 + chunk_size as u64 - 1) / chunk_size as u64) as u32;
+		let chunks_y = ((layer.height as u64 + chunk_size as u64 - 1) / chunk_size as u64) as u32;
+		let mut chunks = Vec::new();
+		let mut animated = Vec::new();
+
+		for chunk_y in 0..chunks_y {
+			for chunk_x in 0..chunks_x {
+				let mut meshes: Vec<(usize, VertexArray)> = Vec::new();
+				let end_y = (chunk_y + 1).saturating_mul(chunk_size).min(layer.height);
+				let end_x = (chunk_x + 1).saturating_mul(chunk_size).min(layer.width);
+				for y in chunk_y * chunk_size..end_y {
+					for x in chunk_x * chunk_size..end_x {
+						// Maps built by hand may have too few tiles
+						let tile = match layer.tiles.get(y as usize * layer.width as usize + x as usize) {
+							Some(&tile) => tile,
+							None => continue
+						};
+						let tileset_index = match map.get_tileset_index(tile.gid) {
+							Some(index) => index,
+							None => continue
+						};
+						let tileset = &map.tilesets[tileset_index];
+						let local_id = tile.gid - tileset.first_gid;
+
+						let mesh = match meshes.iter().position(|&(index, _)| index == tileset_index) {
+							Some(mesh) => mesh,
+							None => {
+								meshes.push((tileset_index, VertexArray::new(PrimitiveType::Quads)));
+								meshes.len() - 1
+							}
+						};
+						let animation = animation_ids.iter().position(|&id| id == (tileset_index, local_id));
+						let rect = match animation {
+							Some(animation) => {
+								animated.push(AnimatedQuad {
+									chunk: chunks.len(),
+									mesh: mesh,
+									vertex: meshes[mesh].1.len(),
+									animation: animation,
+									tile: tile,
+									frame: 0
+								});
+								animations[animation].frames[0].0
+							}
+							None => tileset.get_tile_rect(local_id)
+						};
+
+						// Tiles taller than the map grid extend upwards from
+						// the bottom of their cell, as in Tiled.
+						let (w, h) = (tileset.tile_width as f32, tileset.tile_height as f32);
+						let left = x as f32 * map.tile_width as f32 + layer.offset.x;
+						let top = (y as f32 + 1.) * map.tile_height as f32 - h + layer.offset.y;
+						let positions = [Vector2f::new(left, top), Vector2f::new(left + w, top),
+						                 Vector2f::new(left + w, top + h), Vector2f::new(left, top + h)];
+						let coords = quad_tex_coords(rect, tile);
+						for i in 0..4 {
+							meshes[mesh].1.push(Vertex::new(positions[i], color, coords[i]));
+						}
+					}
+				}
+
+				if !meshes.is_empty() {
+					let mut bounds = meshes[0].1.get_bounds();
+					for &(_, ref vertices) in &meshes[1..] {
+						bounds = union(bounds, vertices.get_bounds());
+					}
+					chunks.push(Chunk { bounds: bounds, meshes: meshes });
+				}
+			}
+		}
+
+		Some(LayerMesh {
+			chunks: chunks,
+			textures: vec![None; map.tilesets.len()],
+			animations: animations,
+			animated: animated,
+			elapsed: Time::with_microseconds(0),
+			visible: layer.visible
+		})
+	}
+
+	/// Set the texture used to draw the tiles of a tileset, by index in the
+	/// map's tilesets.
+	pub fn set_texture(&mut self, tileset_index: usize, texture: &'s Texture) {
+		if tileset_index >= self.textures.len() {
+			self.textures.resize(tileset_index + 1, None);
+		}
+		self.textures[tileset_index] = Some(texture);
+	}
+
+	/// Get the texture used for a tileset, if one was set.
+	pub fn get_texture(&self, tileset_index: usize) -> Option<&'s Texture> {
+		self.textures.get(tileset_index).and_then(|t| *t)
+	}
+
+	/// Get the chunks of the layer. Chunks without any tiles are omitted.
+	pub fn get_chunks(&self) -> &[Chunk] {
+		&self.chunks
+	}
+
+	/// Tell whether the layer is drawn. Initialized from the map.
+	pub fn is_visible(&self) -> bool {
+		self.visible
+	}
+
+	/// Show or hide the layer.
+	pub fn set_visible(&mut self, visible: bool) {
+		self.visible = visible;
+	}
+
+	/// Advance tile animations by `delta`.
+	pub fn update(&mut self, delta: Time) {
+		self.elapsed = self.elapsed + delta;
+		for quad in &mut self.animated {
+			let animation = &self.animations[quad.animation];
+			let frame = animation.frame_at(self.elapsed);
+			if frame != quad.frame {
+				quad.frame = frame;
+				let coords = quad_tex_coords(animation.frames[frame].0, quad.tile);
+				let vertices = &mut self.chunks[quad.chunk].meshes[quad.mesh].1;
+				for i in 0..4 {
+					vertices[quad.vertex + i].tex_coords = coords[i];
+				}
+			}
+		}
+	}
+
+	/// Draw only the chunks that intersect `area`, given in layer coordinates.
+	pub fn draw_area(&self, target: &mut RenderTarget, states: &RenderStates, area: &FloatRect) {
+		if !self.visible {
+			return
+		}
+		for chunk in self.chunks.iter().filter(|chunk| chunk.intersects(area)) {
+			for &(tileset, ref vertices) in &chunk.meshes {
+				if let Some(texture) = self.get_texture(tileset) {
+					vertices.draw(target, &RenderStates { texture: Some(texture), .. *states });
+				}
+			}
+		}
+	}
+}
+
+fn union(a: FloatRect, b: FloatRect) -> FloatRect {
+	let left = a.left.min(b.left);
+	let top = a.top.min(b.top);
+	let right = (a.left + a.width).max(b.left + b.width);
+	let bottom = (a.top + a.height).max(b.top + b.height);
+	FloatRect::new(left, top, right - left, bottom - top)
+}
+
+impl<'s> Drawable for LayerMesh<'s> {
+	fn draw(&self, target: &mut RenderTarget, states: &RenderStates) {
+		// Axis-aligned bounds of the view, brought into layer coordinates.
+		let view = target.get_view();
+		let (center, size) = (view.get_center(), view.get_size());
+		let angle = view.get_rotation().to_radians();
+		let (cos, sin) = (angle.cos().abs(), angle.sin().abs());
+		let half = Vector2f::new((size.x * cos + size.y * sin) / 2., (size.x * sin + size.y * cos) / 2.);
+		let area = FloatRect::new(center.x - half.x, center.y - half.y, half.x * 2., half.y * 2.);
+		let area = states.transform.get_inverse().transform_rect(area);
+		self.draw_area(target, states, &area);
+	}
+}
+
+#[cfg(test)]
+fn test_map() -> Map {
+	let text = r#"{"type": "map", "orientation": "orthogonal", "width": 5, "height": 3,
+		"tilewidth": 16, "tileheight": 16,
+		"tilesets": [{"firstgid": 1, "tilewidth": 16, "tileheight": 16, "columns": 4, "tilecount": 8,
+		              "image": "t.png", "imagewidth": 64, "imageheight": 32,
+		              "tiles": [{"id": 2, "animation": [{"tileid": 2, "duration": 100}, {"tileid": 6, "duration": 100}]}]}],
+		"layers": [{"type": "tilelayer", "width": 5, "height": 3, "opacity": 0.5,
+		            "data": [1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 2147483650, 0, 3, 0, 0]}]}"#;
+	Map::from_json(text, "").unwrap()
+}
+
+#[test]
+fn layer_mesh_chunks() {
+	let map = test_map();
+	let mesh = LayerMesh::new(&map, 0, 4).unwrap();
+	// Chunks (0, 0) and (1, 0) have tiles; no tiles fall in the second row of chunks.
+	let chunks = mesh.get_chunks();
+	assert_eq!(chunks.len(), 2);
+	assert_eq!(chunks[0].get_bounds(), FloatRect::new(0., 0., 48., 48.));
+	assert_eq!(chunks[1].get_bounds(), FloatRect::new(64., 0., 16., 16.));
+
+	let quads = &chunks[0].get_meshes()[0].1;
+	assert_eq!(quads.len(), 12);
+	assert_eq!(quads[0].color.alpha, 127);
+	// The horizontally flipped tile at (0, 2) swaps its left and right coordinates.
+	assert_eq!(quads[4].position, Vector2f::new(0., 32.));
+	assert_eq!(quads[4].tex_coords, Vector2f::new(32., 0.));
+	assert_eq!(quads[5].tex_coords, Vector2f::new(16., 0.));
+
+	assert!(chunks[0].intersects(&FloatRect::new(40., 40., 100., 100.)));
+	assert!(!chunks[1].intersects(&FloatRect::new(0., 20., 100., 100.)));
+	assert!(LayerMesh::new(&map, 1, 4).is_none());
+	assert!(LayerMesh::new(&map, 0, 0).is_none());
+
+	// Missing tiles of a hand-built layer are skipped
+	let mut map = test_map();
+	if let Layer::Tiles(ref mut layer) = map.layers[0] {
+		layer.tiles.truncate(5);
+	}
+	let mesh = LayerMesh::new(&map, 0, 0xffffffff).unwrap();
+	assert_eq!(mesh.get_chunks()[0].get_meshes()[0].1.len(), 8);
+}
+
+#[test]
+fn layer_mesh_animation() {
+	let map = test_map();
+	let mut mesh = LayerMesh::new(&map, 0, 4).unwrap();
+	let tex_left = |mesh: &LayerMesh| mesh.get_chunks()[0].get_meshes()[0].1[8].tex_coords;
+	assert_eq!(tex_left(&mesh), Vector2f::new(32., 0.));
+	mesh.update(Time::with_milliseconds(150));
+	assert_eq!(tex_left(&mesh), Vector2f::new(32., 16.));
+	mesh.update(Time::with_milliseconds(100));
+	assert_eq!(tex_left(&mesh), Vector2f::new(32., 0.));
+}
@@ -0,0 +1,571 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use graphics::{Color, IntRect};
+use system::{Time, Vector2f};
+use graphics::tilemap::{MapError, tmx, json};
+use formats::{base64, inflate};
+
+/// Bit set in a raw global tile ID when the tile is flipped horizontally.
+pub const FLIPPED_HORIZONTALLY: u32 = 0x80000000;
+/// Bit set in a raw global tile ID when the tile is flipped vertically.
+pub const FLIPPED_VERTICALLY: u32 = 0x40000000;
+/// Bit set in a raw global tile ID when the tile is flipped diagonally, that
+/// is, when its X and Y axes are swapped.
+pub const FLIPPED_DIAGONALLY: u32 = 0x20000000;
+
+/// A cell of a tile layer: a global tile ID and its flipping flags.
+///
+/// A global tile ID of 0 means that the cell is empty.
+#[derive(Clone, PartialEq, Eq, Debug, Copy, Default)]
+pub struct Tile {
+	/// Global tile ID, with the flipping flags removed.
+	pub gid: u32,
+	/// Whether the tile is mirrored left to right.
+	pub flip_horizontally: bool,
+	/// Whether the tile is mirrored top to bottom.
+	pub flip_vertically: bool,
+	/// Whether the tile's X and Y axes are swapped; applied before the
+	/// horizontal and vertical flips.
+	pub flip_diagonally: bool
+}
+
+impl Tile {
+	/// Decode a raw global tile ID as stored in map files.
+	pub fn from_raw(raw: u32) -> Tile {
+		Tile {
+			gid: raw & !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY),
+			flip_horizontally: raw & FLIPPED_HORIZONTALLY != 0,
+			flip_vertically: raw & FLIPPED_VERTICALLY != 0,
+			flip_diagonally: raw & FLIPPED_DIAGONALLY != 0
+		}
+	}
+
+	/// Tell whether the cell is empty.
+	pub fn is_empty(&self) -> bool {
+		self.gid == 0
+	}
+}
+
+/// A custom property attached to a map, layer, tileset, tile or object.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Property {
+	/// A `bool` property.
+	Bool(bool),
+	/// An `int` or `object` property.
+	Int(i64),
+	/// A `float` property.
+	Float(f64),
+	/// A `string` property.
+	String(String),
+	/// A `color` property.
+	Color(Color),
+	/// A `file` property, as written in the map.
+	File(String)
+}
+
+/// Custom properties, by name.
+pub type Properties = HashMap<String, Property>;
+
+/// Map orientation. Only orthogonal maps can be rendered by `LayerMesh`.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum Orientation {
+	/// Square or rectangular tiles on a regular grid.
+	Orthogonal,
+	/// Diamond-shaped isometric tiles.
+	Isometric,
+	/// Staggered isometric tiles.
+	Staggered,
+	/// Hexagonal tiles.
+	Hexagonal
+}
+
+/// One frame of an animated tile.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub struct TileAnimationFrame {
+	/// Local ID, within the same tileset, of the tile shown during the frame.
+	pub tile_id: u32,
+	/// How long the frame is shown.
+	pub duration: Time
+}
+
+/// A tileset: a texture containing a grid of tiles.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Tileset {
+	/// Global ID of the first tile of this tileset.
+	pub first_gid: u32,
+	/// Name of the tileset.
+	pub name: String,
+	/// Width of a tile, in pixels.
+	pub tile_width: u32,
+	/// Height of a tile, in pixels.
+	pub tile_height: u32,
+	/// Spacing between adjacent tiles in the image, in pixels.
+	pub spacing: u32,
+	/// Margin around the tiles in the image, in pixels.
+	pub margin: u32,
+	/// Number of tile columns in the image.
+	pub columns: u32,
+	/// Number of tiles in the tileset.
+	pub tile_count: u32,
+	/// Path of the image, relative to the working directory when the map was
+	/// loaded from a file.
+	pub image: String,
+	/// Width of the image, in pixels.
+	pub image_width: u32,
+	/// Height of the image, in pixels.
+	pub image_height: u32,
+	/// Animations of animated tiles, by local tile ID.
+	pub animations: HashMap<u32, Vec<TileAnimationFrame>>,
+	/// Custom properties of individual tiles, by local tile ID.
+	pub tile_properties: HashMap<u32, Properties>,
+	/// Custom properties of the tileset.
+	pub properties: Properties
+}
+
+impl Tileset {
+	/// Tell whether a global tile ID belongs to this tileset.
+	pub fn contains(&self, gid: u32) -> bool {
+		gid >= self.first_gid && gid - self.first_gid < self.tile_count
+	}
+
+	/// Get the texture rectangle of a tile, by local ID.
+	///
+	/// Coordinates too large for an `IntRect` are clamped; tilesets loaded
+	/// from files never have them.
+	pub fn get_tile_rect(&self, local_id: u32) -> IntRect {
+		let columns = if self.columns == 0 { 1 } else { self.columns };
+		let (column, row) = (local_id % columns, local_id / columns);
+		IntRect::new(tile_offset(self.margin, column, self.tile_width, self.spacing),
+		             tile_offset(self.margin, row, self.tile_height, self.spacing),
+		             self.tile_width.min(i32::MAX as u32) as i32,
+		             self.tile_height.min(i32::MAX as u32) as i32)
+	}
+
+}
+
+// Position of the tile at `index` in a row or column of tiles, clamped to
+// i32.
+fn tile_offset(margin: u32, index: u32, size: u32, spacing: u32) -> i32 {
+	(index as u64).checked_mul(size as u64 + spacing as u64)
+		.and_then(|offset| offset.checked_add(margin as u64))
+		.map_or(i32::MAX, |offset| offset.min(i32::MAX as u64) as i32)
+}
+
+/// A layer of tiles.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TileLayer {
+	/// Name of the layer.
+	pub name: String,
+	/// Width of the layer, in tiles.
+	pub width: u32,
+	/// Height of the layer, in tiles.
+	pub height: u32,
+	/// Tiles of the layer, row by row.
+	pub tiles: Vec<Tile>,
+	/// Whether the layer is shown.
+	pub visible: bool,
+	/// Opacity of the layer, between 0 and 1.
+	pub opacity: f32,
+	/// Rendering offset of the layer, in pixels.
+	pub offset: Vector2f,
+	/// Custom properties of the layer.
+	pub properties: Properties
+}
+
+impl TileLayer {
+	/// Get the tile at the given cell, or None if it is out of range.
+	pub fn get_tile(&self, x: u32, y: u32) -> Option<Tile> {
+		if x < self.width && y < self.height {
+			self.tiles.get(y as usize * self.width as usize + x as usize).cloned()
+		} else {
+			None
+		}
+	}
+}
+
+/// The geometric shape of a `MapObject`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ObjectShape {
+	/// A rectangle of the object's size.
+	Rectangle,
+	/// An ellipse inscribed in the object's size.
+	Ellipse,
+	/// A single point at the object's position.
+	Point,
+	/// A closed polygon, with points relative to the object's position.
+	Polygon(Vec<Vector2f>),
+	/// An open polyline, with points relative to the object's position.
+	Polyline(Vec<Vector2f>)
+}
+
+/// An object placed on an object layer.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MapObject {
+	/// Unique ID of the object.
+	pub id: u32,
+	/// Name of the object.
+	pub name: String,
+	/// Type (or class) of the object.
+	pub kind: String,
+	/// Position of the object, in pixels.
+	pub position: Vector2f,
+	/// Size of the object, in pixels.
+	pub size: Vector2f,
+	/// Rotation of the object around its position, in degrees.
+	pub rotation: f32,
+	/// Tile displayed by the object, for tile objects.
+	pub tile: Option<Tile>,
+	/// Whether the object is shown.
+	pub visible: bool,
+	/// Shape of the object.
+	pub shape: ObjectShape,
+	/// Custom properties of the object.
+	pub properties: Properties
+}
+
+/// A layer of free-form objects.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ObjectLayer {
+	/// Name of the layer.
+	pub name: String,
+	/// Objects of the layer.
+	pub objects: Vec<MapObject>,
+	/// Whether the layer is shown.
+	pub visible: bool,
+	/// Opacity of the layer, between 0 and 1.
+	pub opacity: f32,
+	/// Rendering offset of the layer, in pixels.
+	pub offset: Vector2f,
+	/// Custom properties of the layer.
+	pub properties: Properties
+}
+
+/// A layer of a map.
+///
+/// Group layers are flattened when a map is loaded: their children appear in
+/// their place, with the group's offset, opacity and visibility applied.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Layer {
+	/// A layer of tiles.
+	Tiles(TileLayer),
+	/// A layer of objects.
+	Objects(ObjectLayer)
+}
+
+impl Layer {
+	/// Get the name of the layer.
+	pub fn name(&self) -> &str {
+		match *self {
+			Layer::Tiles(ref layer) => &layer.name,
+			Layer::Objects(ref layer) => &layer.name
+		}
+	}
+}
+
+/// A tile map made with the Tiled map editor.
+///
+/// See the module documentation for an overview.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Map {
+	/// Orientation of the map.
+	pub orientation: Orientation,
+	/// Width of the map, in tiles.
+	pub width: u32,
+	/// Height of the map, in tiles.
+	pub height: u32,
+	/// Width of a tile, in pixels.
+	pub tile_width: u32,
+	/// Height of a tile, in pixels.
+	pub tile_height: u32,
+	/// Background color of the map, if any.
+	pub background_color: Option<Color>,
+	/// Tilesets used by the map, ordered by first global tile ID.
+	pub tilesets: Vec<Tileset>,
+	/// Layers of the map, from bottom to top.
+	pub layers: Vec<Layer>,
+	/// Custom properties of the map.
+	pub properties: Properties
+}
+
+impl Map {
+	/// Load a map from a file.
+	///
+	/// Files ending in `.json` are read as Tiled JSON maps, anything else as
+	/// TMX. External tilesets and tileset images are resolved relative to the
+	/// directory of the map.
+	pub fn new_from_file(filename: &str) -> Result<Map, MapError> {
+		let text = try!(read_file(filename));
+		let dir = directory_of(filename);
+		if is_json(filename) {
+			Map::from_json(&text, &dir)
+		} else {
+			Map::from_tmx(&text, &dir)
+		}
+	}
+
+	/// Parse a map in the TMX (XML) format.
+	///
+	/// External tilesets and tileset images are resolved relative to `dir`.
+	pub fn from_tmx(text: &str, dir: &str) -> Result<Map, MapError> {
+		tmx::parse_map(text, dir)
+	}
+
+	/// Parse a map in the Tiled JSON format.
+	///
+	/// External tilesets and tileset images are resolved relative to `dir`.
+	pub fn from_json(text: &str, dir: &str) -> Result<Map, MapError> {
+		json::parse_map(text, dir)
+	}
+
+	/// Get the index of the tileset containing a global tile ID.
+	pub fn get_tileset_index(&self, gid: u32) -> Option<usize> {
+		self.tilesets.iter().rposition(|tileset| tileset.first_gid <= gid)
+			.and_then(|i| if self.tilesets[i].contains(gid) { Some(i) } else { None })
+	}
+
+	/// Find a layer by name.
+	pub fn get_layer(&self, name: &str) -> Option<&Layer> {
+		self.layers.iter().find(|layer| layer.name() == name)
+	}
+}
+
+pub fn read_file(filename: &str) -> Result<String, MapError> {
+	let mut text = String::new();
+	try!(try!(File::open(filename)).read_to_string(&mut text));
+	Ok(text)
+}
+
+pub fn directory_of(filename: &str) -> String {
+	Path::new(filename).parent().map_or(String::new(), |p| p.to_string_lossy().into_owned())
+}
+
+pub fn resolve(dir: &str, path: &str) -> String {
+	if dir.is_empty() {
+		path.to_owned()
+	} else {
+		Path::new(dir).join(path).to_string_lossy().into_owned()
+	}
+}
+
+fn is_json(filename: &str) -> bool {
+	filename.to_lowercase().ends_with(".json")
+}
+
+// Load an external tileset referenced by a map.
+pub fn load_tileset(dir: &str, source: &str, first_gid: u32) -> Result<Tileset, MapError> {
+	let filename = resolve(dir, source);
+	let text = try!(read_file(&filename));
+	let dir = directory_of(&filename);
+	if is_json(&filename) {
+		json::parse_tileset_file(&text, first_gid, &dir)
+	} else {
+		tmx::parse_tileset_file(&text, first_gid, &dir)
+	}
+}
+
+// Number of tiles in a layer of `width` by `height` tiles, which must fit
+// in a u32.
+pub fn tile_count(width: u32, height: u32) -> Result<usize, MapError> {
+	width.checked_mul(height).map(|count| count as usize)
+		.ok_or_else(|| MapError::Parse(format!("a {}x{} tile layer is too large", width, height)))
+}
+
+// Decode the tiles of a layer. `data` is the text content of the layer data
+// for CSV and base64 encodings.
+pub fn decode_tiles(data: &str, encoding: Option<&str>, compression: Option<&str>,
+                    count: usize) -> Result<Vec<Tile>, MapError> {
+	let raw: Vec<u32> = match encoding {
+		Some("csv") => {
+			// Never trust the layer size to reserve memory
+			let mut raw = Vec::with_capacity(count.min(data.len() / 2 + 1));
+			for item in data.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+				match item.parse() {
+					Ok(gid) => raw.push(gid),
+					Err(_) => return Err(MapError::Parse(format!("invalid tile ID `{}`", item)))
+				}
+			}
+			raw
+		}
+		Some("base64") => {
+			let bytes = try!(base64::decode(data).map_err(|e| MapError::Parse(e.to_owned())));
+			let bytes = match compression {
+				None | Some("") => bytes,
+				Some("zlib") => try!(inflate::zlib_decompress(&bytes).map_err(|e| MapError::Parse(e.to_owned()))),
+				Some("gzip") => try!(inflate::gzip_decompress(&bytes).map_err(|e| MapError::Parse(e.to_owned()))),
+				Some(other) => return Err(MapError::Unsupported(format!("`{}` compression", other)))
+			};
+			bytes.chunks(4).filter(|c| c.len() == 4).map(|c| {
+				c[0] as u32 | (c[1] as u32) << 8 | (c[2] as u32) << 16 | (c[3] as u32) << 24
+			}).collect()
+		}
+		Some(other) => return Err(MapError::Unsupported(format!("`{}` layer encoding", other))),
+		None => return Err(MapError::Unsupported("XML tile elements".to_owned()))
+	};
+	if raw.len() != count {
+		return Err(MapError::Parse(format!("expected {} tiles, found {}", count, raw.len())))
+	}
+	Ok(raw.into_iter().map(Tile::from_raw).collect())
+}
+
+// Fill in the tileset fields that Tiled may omit from old files, and check
+// that the texture rectangles of its tiles fit in an `IntRect`.
+pub fn fix_up_tileset(tileset: &mut Tileset) -> Result<(), MapError> {
+	let too_large = MapError::Parse(format!("tileset `{}` is too large", tileset.name));
+	let (margin, spacing) = (tileset.margin as u64, tileset.spacing as u64);
+	let stride_x = tileset.tile_width as u64 + spacing;
+	let stride_y = tileset.tile_height as u64 + spacing;
+	if tileset.columns == 0 && tileset.tile_width > 0 {
+		let usable = (tileset.image_width as u64).saturating_sub(2 * margin) + spacing;
+		tileset.columns = (usable / stride_x) as u32;
+	}
+	if tileset.tile_count == 0 && tileset.tile_height > 0 {
+		let usable = (tileset.image_height as u64).saturating_sub(2 * margin) + spacing;
+		match tileset.columns.checked_mul((usable / stride_y) as u32) {
+			Some(count) => tileset.tile_count = count,
+			None => return Err(too_large)
+		}
+	}
+
+	let columns = tileset.columns.max(1) as u64;
+	let rows = (tileset.tile_count as u64 + columns - 1) / columns;
+	let right = margin + columns * stride_x;
+	let bottom = margin + rows * stride_y;
+	if right > i32::MAX as u64 || bottom > i32::MAX as u64 {
+		return Err(too_large)
+	}
+	Ok(())
+}
+
+// Apply the attributes of an enclosing group layer to one of its children.
+pub fn apply_group(layer: &mut Layer, offset: Vector2f, opacity: f32, visible: bool) {
+	let (layer_offset, layer_opacity, layer_visible) = match *layer {
+		Layer::Tiles(ref mut l) => (&mut l.offset, &mut l.opacity, &mut l.visible),
+		Layer::Objects(ref mut l) => (&mut l.offset, &mut l.opacity, &mut l.visible)
+	};
+	*layer_offset = *layer_offset + offset;
+	*layer_opacity *= opacity;
+	*layer_visible = *layer_visible && visible;
+}
+
+// Parse a color written as `#RRGGBB` or `#AARRGGBB`.
+pub fn parse_color(text: &str) -> Option<Color> {
+	let hex = text.trim_left_matches('#');
+	let value = match u32::from_str_radix(hex, 16) {
+		Ok(value) => value,
+		Err(_) => return None
+	};
+	match hex.len() {
+		6 => Some(Color::new_rgb((value >> 16) as u8, (value >> 8) as u8, value as u8)),
+		8 => Some(Color::new_rgba((value >> 16) as u8, (value >> 8) as u8, value as u8, (value >> 24) as u8)),
+		_ => None
+	}
+}
+
+// Build a property from its Tiled type name and textual value.
+pub fn make_property(kind: &str, value: &str) -> Result<Property, MapError> {
+	let invalid = || MapError::Parse(format!("invalid {} property `{}`", kind, value));
+	Ok(match kind {
+		"bool" => Property::Bool(value == "true"),
+		"int" | "object" => Property::Int(try!(value.parse().map_err(|_| invalid()))),
+		"float" => Property::Float(try!(value.parse().map_err(|_| invalid()))),
+		"color" => Property::Color(if value.is_empty() {
+			Color::transparent()
+		} else {
+			try!(parse_color(value).ok_or_else(invalid))
+		}),
+		"file" => Property::File(value.to_owned()),
+		_ => Property::String(value.to_owned())
+	})
+}
+
+pub fn parse_orientation(text: &str) -> Result<Orientation, MapError> {
+	match text {
+		"orthogonal" => Ok(Orientation::Orthogonal),
+		"isometric" => Ok(Orientation::Isometric),
+		"staggered" => Ok(Orientation::Staggered),
+		"hexagonal" => Ok(Orientation::Hexagonal),
+		_ => Err(MapError::Unsupported(format!("`{}` orientation", text)))
+	}
+}
+
+#[test]
+fn tile_flags() {
+	let tile = Tile::from_raw(FLIPPED_HORIZONTALLY | FLIPPED_DIAGONALLY | 7);
+	assert_eq!(tile.gid, 7);
+	assert!(tile.flip_horizontally && tile.flip_diagonally && !tile.flip_vertically);
+	assert!(Tile::from_raw(0).is_empty());
+}
+
+#[test]
+fn tile_layer_encodings() {
+	let csv = decode_tiles("1,2,\n0,2147483651", Some("csv"), None, 4).unwrap();
+	assert_eq!(csv[3], Tile::from_raw(FLIPPED_HORIZONTALLY | 3));
+	// [1, 2, 0, 3] as little-endian u32s, uncompressed and zlib-compressed.
+	let plain = decode_tiles("AQAAAAIAAAAAAAAAAwAAAA==", Some("base64"), None, 4).unwrap();
+	let zlib = decode_tiles("eJxjZGBgYGKAAGYgBgAARAAH", Some("base64"), Some("zlib"), 4).unwrap();
+	let gids: Vec<u32> = plain.iter().map(|t| t.gid).collect();
+	assert_eq!(gids, vec![1, 2, 0, 3]);
+	assert_eq!(plain, zlib);
+	assert!(decode_tiles("1,2", Some("csv"), None, 4).is_err());
+}
+
+#[test]
+fn tileset_sizes() {
+	let mut tileset = Tileset {
+		first_gid: 1, name: "huge".to_owned(), tile_width: 0x40000000, tile_height: 16, spacing: 0, margin: 0,
+		columns: 4, tile_count: 8, image: String::new(), image_width: 0, image_height: 0,
+		animations: HashMap::new(), tile_properties: HashMap::new(), properties: Properties::new()
+	};
+	assert!(fix_up_tileset(&mut tileset).is_err());
+	assert_eq!(tileset.get_tile_rect(3).left, i32::MAX);
+
+	// Old tilesets omit the columns and tile count
+	tileset.tile_width = 16;
+	tileset.columns = 0;
+	tileset.tile_count = 0;
+	tileset.spacing = 2;
+	tileset.margin = 1;
+	tileset.image_width = 72;
+	tileset.image_height = 36;
+	assert!(fix_up_tileset(&mut tileset).is_ok());
+	assert_eq!((tileset.columns, tileset.tile_count), (4, 8));
+	assert_eq!(tileset.get_tile_rect(5), IntRect::new(19, 19, 16, 16));
+
+	tileset.tile_width = 1;
+	tileset.tile_height = 1;
+	tileset.columns = 0;
+	tileset.tile_count = 0;
+	tileset.spacing = 0;
+	tileset.margin = 0;
+	tileset.image_width = 65536;
+	tileset.image_height = 65536;
+	assert!(fix_up_tileset(&mut tileset).is_err());
+	assert!(tile_count(65536, 65536).is_err());
+}
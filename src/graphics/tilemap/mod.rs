@@ -0,0 +1,104 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Tile maps: a Tiled (TMX/JSON) loader and a chunked renderer.
+//!
+//! A `Map` holds the data of a map made with the [Tiled](http://www.mapeditor.org/)
+//! editor: its tilesets, tile layers, object layers and custom properties. It
+//! can be loaded from either of Tiled's formats with `Map::new_from_file`,
+//! `Map::from_tmx` or `Map::from_json`. Tile layer data may be stored as CSV or
+//! as base64, optionally compressed with zlib or gzip.
+//!
+//! A `LayerMesh` turns one tile layer of a map into chunks of `VertexArray`
+//! quads, one vertex array per chunk and tileset. Only the chunks that
+//! intersect the current `View` are drawn. Flipped tiles and tile animations
+//! defined in the tilesets are supported.
+//!
+//! ```ignore
+//! let map = Map::new_from_file("level.tmx").unwrap();
+//! let tileset = Texture::new_from_file(&map.tilesets[0].image).unwrap();
+//! let mut ground = LayerMesh::new(&map, 0, 16).unwrap();
+//! ground.set_texture(0, &tileset);
+//! // Each frame:
+//! ground.update(clock.restart());
+//! window.draw(&ground);
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+pub use self::map::{Map, Orientation, Tileset, TileAnimationFrame, Layer, TileLayer,
+                    ObjectLayer, MapObject, ObjectShape, Tile, Property, Properties,
+                    FLIPPED_HORIZONTALLY, FLIPPED_VERTICALLY, FLIPPED_DIAGONALLY};
+pub use self::layer_mesh::{LayerMesh, Chunk};
+
+mod map;
+mod tmx;
+mod json;
+mod layer_mesh;
+
+/// Error produced when loading a map fails.
+#[derive(Debug)]
+pub enum MapError {
+	/// The map or one of its external tilesets could not be read.
+	Io(io::Error),
+	/// The map is malformed.
+	Parse(String),
+	/// The map uses a feature that the loader does not handle.
+	Unsupported(String)
+}
+
+impl fmt::Display for MapError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			MapError::Io(ref err) => write!(f, "I/O error: {}", err),
+			MapError::Parse(ref msg) => write!(f, "malformed map: {}", msg),
+			MapError::Unsupported(ref msg) => write!(f, "unsupported map feature: {}", msg)
+		}
+	}
+}
+
+impl Error for MapError {
+	fn description(&self) -> &str {
+		match *self {
+			MapError::Io(_) => "I/O error",
+			MapError::Parse(_) => "malformed map",
+			MapError::Unsupported(_) => "unsupported map feature"
+		}
+	}
+
+	fn cause(&self) -> Option<&Error> {
+		match *self {
+			MapError::Io(ref err) => Some(err),
+			_ => None
+		}
+	}
+}
+
+impl From<io::Error> for MapError {
+	fn from(err: io::Error) -> MapError {
+		MapError::Io(err)
+	}
+}
@@ -0,0 +1,327 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Loader for Tiled's TMX (XML) map format.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use system::{Time, Vector2f};
+use graphics::tilemap::MapError;
+use graphics::tilemap::map::{self, Map, Tileset, TileAnimationFrame, Layer, TileLayer,
+                             ObjectLayer, MapObject, ObjectShape, Tile, Properties};
+use formats::xml::{self, Element};
+
+fn attr<T: FromStr>(element: &Element, name: &str, default: T) -> Result<T, MapError> {
+	match element.attr(name) {
+		None => Ok(default),
+		Some(value) => value.trim().parse().map_err(|_| {
+			MapError::Parse(format!("invalid `{}` attribute on <{}>: `{}`", name, element.name, value))
+		})
+	}
+}
+
+fn attr_string(element: &Element, name: &str) -> String {
+	element.attr(name).unwrap_or("").to_owned()
+}
+
+fn parse_document(text: &str, root: &str) -> Result<Element, MapError> {
+	let element = try!(xml::parse(text).map_err(MapError::Parse));
+	if element.name != root {
+		return Err(MapError::Parse(format!("expected a <{}> root element, found <{}>", root, element.name)))
+	}
+	Ok(element)
+}
+
+fn parse_properties(element: &Element) -> Result<Properties, MapError> {
+	let mut properties = HashMap::new();
+	if let Some(list) = element.child("properties") {
+		for property in list.children_named("property") {
+			let value = property.attr("value").unwrap_or(&property.text);
+			properties.insert(attr_string(property, "name"),
+			                  try!(map::make_property(property.attr("type").unwrap_or("string"), value)));
+		}
+	}
+	Ok(properties)
+}
+
+pub fn parse_map(text: &str, dir: &str) -> Result<Map, MapError> {
+	let root = try!(parse_document(text, "map"));
+	if try!(attr(&root, "infinite", 0)) != 0 {
+		return Err(MapError::Unsupported("infinite maps".to_owned()))
+	}
+
+	let mut tilesets = Vec::new();
+	for element in root.children_named("tileset") {
+		let first_gid = try!(attr(element, "firstgid", 1));
+		tilesets.push(match element.attr("source") {
+			Some(source) => try!(map::load_tileset(dir, source, first_gid)),
+			None => try!(parse_tileset(element, first_gid, dir))
+		});
+	}
+
+	let mut layers = Vec::new();
+	try!(parse_layers(&root, &mut layers));
+
+	Ok(Map {
+		orientation: try!(map::parse_orientation(root.attr("orientation").unwrap_or("orthogonal"))),
+		width: try!(attr(&root, "width", 0)),
+		height: try!(attr(&root, "height", 0)),
+		tile_width: try!(attr(&root, "tilewidth", 0)),
+		tile_height: try!(attr(&root, "tileheight", 0)),
+		background_color: root.attr("backgroundcolor").and_then(map::parse_color),
+		tilesets: tilesets,
+		layers: layers,
+		properties: try!(parse_properties(&root))
+	})
+}
+
+pub fn parse_tileset_file(text: &str, first_gid: u32, dir: &str) -> Result<Tileset, MapError> {
+	let root = try!(parse_document(text, "tileset"));
+	parse_tileset(&root, first_gid, dir)
+}
+
+fn parse_tileset(element: &Element, first_gid: u32, dir: &str) -> Result<Tileset, MapError> {
+	let mut tileset = Tileset {
+		first_gid: first_gid,
+		name: attr_string(element, "name"),
+		tile_width: try!(attr(element, "tilewidth", 0)),
+		tile_height: try!(attr(element, "tileheight", 0)),
+		spacing: try!(attr(element, "spacing", 0)),
+		margin: try!(attr(element, "margin", 0)),
+		columns: try!(attr(element, "columns", 0)),
+		tile_count: try!(attr(element, "tilecount", 0)),
+		image: String::new(),
+		image_width: 0,
+		image_height: 0,
+		animations: HashMap::new(),
+		tile_properties: HashMap::new(),
+		properties: try!(parse_properties(element))
+	};
+	match element.child("image") {
+		Some(image) => {
+			tileset.image = map::resolve(dir, image.attr("source").unwrap_or(""));
+			tileset.image_width = try!(attr(image, "width", 0));
+			tileset.image_height = try!(attr(image, "height", 0));
+		}
+		None => return Err(MapError::Unsupported("image collection tilesets".to_owned()))
+	}
+
+	for tile in element.children_named("tile") {
+		let id = try!(attr(tile, "id", 0));
+		if let Some(animation) = tile.child("animation") {
+			let mut frames = Vec::new();
+			for frame in animation.children_named("frame") {
+				frames.push(TileAnimationFrame {
+					tile_id: try!(attr(frame, "tileid", 0)),
+					duration: Time::with_milliseconds(try!(attr(frame, "duration", 0)))
+				});
+			}
+			tileset.animations.insert(id, frames);
+		}
+		let properties = try!(parse_properties(tile));
+		if !properties.is_empty() {
+			tileset.tile_properties.insert(id, properties);
+		}
+	}
+	try!(map::fix_up_tileset(&mut tileset));
+	Ok(tileset)
+}
+
+fn parse_layers(parent: &Element, layers: &mut Vec<Layer>) -> Result<(), MapError> {
+	for element in &parent.children {
+		let offset = Vector2f::new(try!(attr(element, "offsetx", 0.)), try!(attr(element, "offsety", 0.)));
+		let opacity = try!(attr(element, "opacity", 1.));
+		let visible = try!(attr(element, "visible", 1)) != 0;
+		match &element.name[..] {
+			"layer" => {
+				let width = try!(attr(element, "width", 0));
+				let height = try!(attr(element, "height", 0));
+				let data = match element.child("data") {
+					Some(data) => data,
+					None => return Err(MapError::Parse("tile layer without <data>".to_owned()))
+				};
+				if data.child("chunk").is_some() {
+					return Err(MapError::Unsupported("infinite maps".to_owned()))
+				}
+				let count = try!(map::tile_count(width, height));
+				let tiles = if data.attr("encoding").is_none() {
+					let mut tiles = Vec::new();
+					for tile in data.children_named("tile") {
+						tiles.push(Tile::from_raw(try!(attr(tile, "gid", 0))));
+					}
+					tiles
+				} else {
+					try!(map::decode_tiles(&data.text, data.attr("encoding"), data.attr("compression"), count))
+				};
+				if tiles.len() != count {
+					return Err(MapError::Parse(format!("expected {} tiles, found {}", count, tiles.len())))
+				}
+				layers.push(Layer::Tiles(TileLayer {
+					name: attr_string(element, "name"),
+					width: width,
+					height: height,
+					tiles: tiles,
+					visible: visible,
+					opacity: opacity,
+					offset: offset,
+					properties: try!(parse_properties(element))
+				}));
+			}
+			"objectgroup" => {
+				let mut objects = Vec::new();
+				for object in element.children_named("object") {
+					objects.push(try!(parse_object(object)));
+				}
+				layers.push(Layer::Objects(ObjectLayer {
+					name: attr_string(element, "name"),
+					objects: objects,
+					visible: visible,
+					opacity: opacity,
+					offset: offset,
+					properties: try!(parse_properties(element))
+				}));
+			}
+			"group" => {
+				let first = layers.len();
+				try!(parse_layers(element, layers));
+				for layer in &mut layers[first..] {
+					map::apply_group(layer, offset, opacity, visible);
+				}
+			}
+			_ => {}
+		}
+	}
+	Ok(())
+}
+
+fn parse_points(text: &str) -> Result<Vec<Vector2f>, MapError> {
+	let mut points = Vec::new();
+	for pair in text.split_whitespace() {
+		let mut coords = pair.split(',').map(|c| c.parse::<f32>());
+		match (coords.next(), coords.next()) {
+			(Some(Ok(x)), Some(Ok(y))) => points.push(Vector2f::new(x, y)),
+			_ => return Err(MapError::Parse(format!("invalid point `{}`", pair)))
+		}
+	}
+	Ok(points)
+}
+
+fn parse_object(element: &Element) -> Result<MapObject, MapError> {
+	let shape = if element.child("ellipse").is_some() {
+		ObjectShape::Ellipse
+	} else if element.child("point").is_some() {
+		ObjectShape::Point
+	} else if let Some(polygon) = element.child("polygon") {
+		ObjectShape::Polygon(try!(parse_points(polygon.attr("points").unwrap_or(""))))
+	} else if let Some(polyline) = element.child("polyline") {
+		ObjectShape::Polyline(try!(parse_points(polyline.attr("points").unwrap_or(""))))
+	} else {
+		ObjectShape::Rectangle
+	};
+	let kind = element.attr("type").or(element.attr("class")).unwrap_or("");
+	Ok(MapObject {
+		id: try!(attr(element, "id", 0)),
+		name: attr_string(element, "name"),
+		kind: kind.to_owned(),
+		position: Vector2f::new(try!(attr(element, "x", 0.)), try!(attr(element, "y", 0.))),
+		size: Vector2f::new(try!(attr(element, "width", 0.)), try!(attr(element, "height", 0.))),
+		rotation: try!(attr(element, "rotation", 0.)),
+		tile: match element.attr("gid") {
+			Some(_) => Some(Tile::from_raw(try!(attr(element, "gid", 0)))),
+			None => None
+		},
+		visible: try!(attr(element, "visible", 1)) != 0,
+		shape: shape,
+		properties: try!(parse_properties(element))
+	})
+}
+
+#[test]
+fn tmx_parse() {
+	let text = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.2" orientation="orthogonal" width="3" height="2" tilewidth="16" tileheight="16" infinite="0">
+ <properties>
+  <property name="gravity" type="float" value="9.8"/>
+  <property name="title" value="Level 1"/>
+ </properties>
+ <tileset firstgid="1" name="ground" tilewidth="16" tileheight="16" spacing="1" margin="2" tilecount="8" columns="4">
+  <image source="ground.png" width="70" height="36"/>
+  <tile id="3">
+   <animation><frame tileid="3" duration="100"/><frame tileid="4" duration="150"/></animation>
+  </tile>
+ </tileset>
+ <layer id="1" name="ground" width="3" height="2" opacity="0.5">
+  <data encoding="csv">
+1,2,0,
+4,2147483649,3
+</data>
+ </layer>
+ <group name="entities" offsetx="10" visible="0">
+  <objectgroup id="2" name="spawns" offsetx="5">
+   <object id="1" name="player" type="spawn" x="32" y="8" width="0" height="0"><point/></object>
+   <object id="2" x="0" y="0"><polygon points="0,0 16,0 8,-12"/></object>
+  </objectgroup>
+ </group>
+</map>"##;
+	let map = Map::from_tmx(text, "maps").unwrap();
+	assert_eq!(map.orientation, ::graphics::tilemap::Orientation::Orthogonal);
+	assert_eq!((map.width, map.height, map.tile_width), (3, 2, 16));
+	assert_eq!(map.properties.get("gravity"), Some(&::graphics::tilemap::Property::Float(9.8)));
+
+	let tileset = &map.tilesets[0];
+	assert_eq!(tileset.image, ::std::path::Path::new("maps").join("ground.png").to_string_lossy());
+	assert_eq!(tileset.get_tile_rect(5), ::graphics::IntRect::new(19, 19, 16, 16));
+	assert_eq!(tileset.animations[&3][1].duration, Time::with_milliseconds(150));
+	assert_eq!(map.get_tileset_index(8), Some(0));
+	assert_eq!(map.get_tileset_index(9), None);
+
+	match map.layers[0] {
+		Layer::Tiles(ref layer) => {
+			assert_eq!(layer.opacity, 0.5);
+			assert_eq!(layer.get_tile(1, 1).map(|t| (t.gid, t.flip_horizontally)), Some((1, true)));
+		}
+		_ => panic!("expected a tile layer")
+	}
+	match map.layers[1] {
+		Layer::Objects(ref layer) => {
+			assert_eq!(layer.offset, Vector2f::new(15., 0.));
+			assert!(!layer.visible);
+			assert_eq!(layer.objects[0].shape, ObjectShape::Point);
+			assert_eq!(layer.objects[0].kind, "spawn");
+			assert_eq!(layer.objects[1].shape, ObjectShape::Polygon(vec![
+				Vector2f::new(0., 0.), Vector2f::new(16., 0.), Vector2f::new(8., -12.)]));
+		}
+		_ => panic!("expected an object layer")
+	}
+
+	assert!(Map::from_tmx("<map infinite=\"1\"/>", "").is_err());
+
+	// Huge layer sizes are parse errors, not overflows or huge allocations
+	let huge = "<map><layer width=\"65536\" height=\"65536\"><data encoding=\"csv\">1</data></layer></map>";
+	match Map::from_tmx(huge, "") {
+		Err(MapError::Parse(ref msg)) if msg.contains("too large") => (),
+		other => panic!("expected a size error, got {:?}", other.err())
+	}
+}
@@ -0,0 +1,807 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+//! Tweening: animating values over time with easing curves.
+//!
+//! A `Tween` interpolates between two values of any `Lerp` type and writes
+//! the result to a target object through a setter function. The functions in
+//! this module (`position`, `fill_color`, `view_center`, ...) are ready-made
+//! setters for `Transformable`s, sprites, shapes, texts and views.
+//!
+//! Tweens can be combined with `Sequence` (one after the other) and
+//! `Parallel` (all at once), and delayed with `Delay`. All of them implement
+//! `Tweenable` and are driven by calling `advance` with the frame time.
+//!
+//! ```ignore
+//! let mut intro = Sequence::new();
+//! intro.add(Tween::new(Vector2f::new(0., 0.), Vector2f::new(200., 100.),
+//!                      Time::with_seconds(1.), tween::position)
+//!     .with_easing(Easing::BackOut));
+//! intro.add(Delay::new(Time::with_seconds(0.5)));
+//! intro.add(Tween::new(0., 360., Time::with_seconds(2.), tween::rotation));
+//! // Each frame:
+//! intro.advance(clock.restart(), &mut sprite);
+//! ```
+
+use std::f32::consts::PI;
+
+use graphics::{Color, Lerp, Shape, Sprite, Text, Transformable, View};
+use system::{Time, Vector2f};
+
+/// An easing curve, mapping linear progress in `[0, 1]` to eased progress.
+///
+/// Every curve maps 0 to 0 and 1 to 1. `Back` and `Elastic` curves overshoot
+/// outside of `[0, 1]` in between.
+#[derive(Clone, Copy, Debug)]
+pub enum Easing {
+	/// Constant speed.
+	Linear,
+	/// Quadratic acceleration.
+	QuadIn,
+	/// Quadratic deceleration.
+	QuadOut,
+	/// Quadratic acceleration, then deceleration.
+	QuadInOut,
+	/// Cubic acceleration.
+	CubicIn,
+	/// Cubic deceleration.
+	CubicOut,
+	/// Cubic acceleration, then deceleration.
+	CubicInOut,
+	/// Quartic acceleration.
+	QuartIn,
+	/// Quartic deceleration.
+	QuartOut,
+	/// Quartic acceleration, then deceleration.
+	QuartInOut,
+	/// Quintic acceleration.
+	QuintIn,
+	/// Quintic deceleration.
+	QuintOut,
+	/// Quintic acceleration, then deceleration.
+	QuintInOut,
+	/// Sinusoidal acceleration.
+	SineIn,
+	/// Sinusoidal deceleration.
+	SineOut,
+	/// Sinusoidal acceleration, then deceleration.
+	SineInOut,
+	/// Exponential acceleration.
+	ExpoIn,
+	/// Exponential deceleration.
+	ExpoOut,
+	/// Exponential acceleration, then deceleration.
+	ExpoInOut,
+	/// Circular acceleration.
+	CircIn,
+	/// Circular deceleration.
+	CircOut,
+	/// Circular acceleration, then deceleration.
+	CircInOut,
+	/// Pulls back slightly before moving forward.
+	BackIn,
+	/// Overshoots the end slightly before settling.
+	BackOut,
+	/// Pulls back at the start and overshoots at the end.
+	BackInOut,
+	/// Oscillates with growing amplitude before snapping to the end.
+	ElasticIn,
+	/// Snaps to the end and oscillates around it.
+	ElasticOut,
+	/// Oscillates at both the start and the end.
+	ElasticInOut,
+	/// Bounces with growing height before reaching the end.
+	BounceIn,
+	/// Bounces on the end like a dropped ball.
+	BounceOut,
+	/// Bounces at both the start and the end.
+	BounceInOut,
+	/// A user-provided curve.
+	Custom(fn(f32) -> f32)
+}
+
+impl Easing {
+	/// Apply the curve to `t`, which is clamped to `[0, 1]`.
+	pub fn apply(&self, t: f32) -> f32 {
+		let t = t.max(0.).min(1.);
+		match *self {
+			Easing::Linear => t,
+			Easing::QuadIn => power_in(t, 2),
+			Easing::QuadOut => power_out(t, 2),
+			Easing::QuadInOut => power_in_out(t, 2),
+			Easing::CubicIn => power_in(t, 3),
+			Easing::CubicOut => power_out(t, 3),
+			Easing::CubicInOut => power_in_out(t, 3),
+			Easing::QuartIn => power_in(t, 4),
+			Easing::QuartOut => power_out(t, 4),
+			Easing::QuartInOut => power_in_out(t, 4),
+			Easing::QuintIn => power_in(t, 5),
+			Easing::QuintOut => power_out(t, 5),
+			Easing::QuintInOut => power_in_out(t, 5),
+			Easing::SineIn => 1. - (t * PI / 2.).cos(),
+			Easing::SineOut => (t * PI / 2.).sin(),
+			Easing::SineInOut => -((PI * t).cos() - 1.) / 2.,
+			Easing::ExpoIn => if t == 0. { 0. } else { 2f32.powf(10. * t - 10.) },
+			Easing::ExpoOut => if t == 1. { 1. } else { 1. - 2f32.powf(-10. * t) },
+			Easing::ExpoInOut => {
+				if t == 0. || t == 1. {
+					t
+				} else if t < 0.5 {
+					2f32.powf(20. * t - 10.) / 2.
+				} else {
+					(2. - 2f32.powf(-20. * t + 10.)) / 2.
+				}
+			}
+			Easing::CircIn => 1. - (1. - t * t).sqrt(),
+			Easing::CircOut => (1. - (t - 1.) * (t - 1.)).sqrt(),
+			Easing::CircInOut => {
+				if t < 0.5 {
+					(1. - (1. - 4. * t * t).sqrt()) / 2.
+				} else {
+					((1. - (-2. * t + 2.).powi(2)).sqrt() + 1.) / 2.
+				}
+			}
+			Easing::BackIn => BACK_C3 * t * t * t - BACK_C1 * t * t,
+			Easing::BackOut => 1. + BACK_C3 * (t - 1.).powi(3) + BACK_C1 * (t - 1.).powi(2),
+			Easing::BackInOut => {
+				let c2 = BACK_C1 * 1.525;
+				if t < 0.5 {
+					(2. * t).powi(2) * ((c2 + 1.) * 2. * t - c2) / 2.
+				} else {
+					((2. * t - 2.).powi(2) * ((c2 + 1.) * (t * 2. - 2.) + c2) + 2.) / 2.
+				}
+			}
+			Easing::ElasticIn => {
+				if t == 0. || t == 1. {
+					t
+				} else {
+					-2f32.powf(10. * t - 10.) * ((10. * t - 10.75) * ELASTIC_C4).sin()
+				}
+			}
+			Easing::ElasticOut => {
+				if t == 0. || t == 1. {
+					t
+				} else {
+					2f32.powf(-10. * t) * ((10. * t - 0.75) * ELASTIC_C4).sin() + 1.
+				}
+			}
+			Easing::ElasticInOut => {
+				let c5 = 2. * PI / 4.5;
+				if t == 0. || t == 1. {
+					t
+				} else if t < 0.5 {
+					-(2f32.powf(20. * t - 10.) * ((20. * t - 11.125) * c5).sin()) / 2.
+				} else {
+					2f32.powf(-20. * t + 10.) * ((20. * t - 11.125) * c5).sin() / 2. + 1.
+				}
+			}
+			Easing::BounceIn => 1. - bounce_out(1. - t),
+			Easing::BounceOut => bounce_out(t),
+			Easing::BounceInOut => {
+				if t < 0.5 {
+					(1. - bounce_out(1. - 2. * t)) / 2.
+				} else {
+					(1. + bounce_out(2. * t - 1.)) / 2.
+				}
+			}
+			Easing::Custom(function) => function(t)
+		}
+	}
+}
+
+const BACK_C1: f32 = 1.70158;
+const BACK_C3: f32 = BACK_C1 + 1.;
+const ELASTIC_C4: f32 = 2. * PI / 3.;
+
+fn power_in(t: f32, power: i32) -> f32 {
+	t.powi(power)
+}
+
+fn power_out(t: f32, power: i32) -> f32 {
+	1. - (1. - t).powi(power)
+}
+
+fn power_in_out(t: f32, power: i32) -> f32 {
+	if t < 0.5 {
+		2f32.powi(power - 1) * t.powi(power)
+	} else {
+		1. - (-2. * t + 2.).powi(power) / 2.
+	}
+}
+
+fn bounce_out(t: f32) -> f32 {
+	let (n1, d1) = (7.5625, 2.75);
+	if t < 1. / d1 {
+		n1 * t * t
+	} else if t < 2. / d1 {
+		let t = t - 1.5 / d1;
+		n1 * t * t + 0.75
+	} else if t < 2.5 / d1 {
+		let t = t - 2.25 / d1;
+		n1 * t * t + 0.9375
+	} else {
+		let t = t - 2.625 / d1;
+		n1 * t * t + 0.984375
+	}
+}
+
+/// How many times a tween or group plays after its first run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Repeat {
+	/// Play again this many times. `Count(0)` plays once.
+	Count(u32),
+	/// Play again forever.
+	Forever
+}
+
+impl Repeat {
+	fn is_done(&self, repeats: u32) -> bool {
+		match *self {
+			Repeat::Count(count) => repeats >= count,
+			Repeat::Forever => false
+		}
+	}
+}
+
+fn zero() -> Time {
+	Time::with_microseconds(0)
+}
+
+/// Something that animates an object of type `O` over time.
+pub trait Tweenable<O: ?Sized> {
+	/// Advance the animation by `delta` and update `target`.
+	///
+	/// Returns the part of `delta` left over after the animation finished,
+	/// which is zero while it is still running.
+	fn advance(&mut self, delta: Time, target: &mut O) -> Time;
+
+	/// Tell whether the animation has finished.
+	fn is_finished(&self) -> bool;
+
+	/// Rewind the animation to its start, without touching any target.
+	fn reset(&mut self);
+}
+
+/// Interpolates a value from `from` to `to` and applies it to a target.
+pub struct Tween<O: ?Sized, T> {
+	from: T,
+	to: T,
+	duration: Time,
+	delay: Time,
+	easing: Easing,
+	repeat: Repeat,
+	yoyo: bool,
+	setter: fn(&mut O, T),
+	elapsed: Time,
+	waited: Time,
+	repeats: u32,
+	finished: bool
+}
+
+impl<O: ?Sized, T: Lerp + Clone> Tween<O, T> {
+	/// Create a linear tween from `from` to `to` over `duration`, applied
+	/// to the target with `setter`.
+	pub fn new(from: T, to: T, duration: Time, setter: fn(&mut O, T)) -> Tween<O, T> {
+		Tween {
+			from: from,
+			to: to,
+			duration: duration,
+			delay: zero(),
+			easing: Easing::Linear,
+			repeat: Repeat::Count(0),
+			yoyo: false,
+			setter: setter,
+			elapsed: zero(),
+			waited: zero(),
+			repeats: 0,
+			finished: false
+		}
+	}
+
+	/// Set the easing curve and return the tween.
+	pub fn with_easing(mut self, easing: Easing) -> Tween<O, T> {
+		self.easing = easing;
+		self
+	}
+
+	/// Set the easing curve. The default is `Easing::Linear`.
+	pub fn set_easing(&mut self, easing: Easing) {
+		self.easing = easing;
+	}
+
+	/// Get the easing curve.
+	pub fn get_easing(&self) -> Easing {
+		self.easing
+	}
+
+	/// Set the time to wait before the first run starts. The default is 0.
+	///
+	/// The target is not touched while waiting.
+	pub fn set_delay(&mut self, delay: Time) {
+		self.delay = delay;
+	}
+
+	/// Get the time to wait before the first run starts.
+	pub fn get_delay(&self) -> Time {
+		self.delay
+	}
+
+	/// Set how many times the tween repeats. The default is `Count(0)`.
+	pub fn set_repeat(&mut self, repeat: Repeat) {
+		self.repeat = repeat;
+	}
+
+	/// Get how many times the tween repeats.
+	pub fn get_repeat(&self) -> Repeat {
+		self.repeat
+	}
+
+	/// Enable or disable yoyo mode, in which every other run goes from
+	/// `to` back to `from`.
+	pub fn set_yoyo(&mut self, yoyo: bool) {
+		self.yoyo = yoyo;
+	}
+
+	/// Tell whether yoyo mode is enabled.
+	pub fn is_yoyo(&self) -> bool {
+		self.yoyo
+	}
+
+	/// Get the duration of one run.
+	pub fn get_duration(&self) -> Time {
+		self.duration
+	}
+
+	/// Get the progress of the current run, in `[0, 1]`, before easing.
+	pub fn get_progress(&self) -> f32 {
+		if self.duration <= zero() {
+			1.
+		} else {
+			self.elapsed / self.duration
+		}
+	}
+
+	/// Get the current interpolated value.
+	pub fn get_value(&self) -> T {
+		let mut t = self.get_progress();
+		if self.yoyo && self.repeats % 2 == 1 {
+			t = 1. - t;
+		}
+		self.from.lerp(&self.to, self.easing.apply(t))
+	}
+
+	fn apply(&self, target: &mut O) {
+		(self.setter)(target, self.get_value());
+	}
+}
+
+impl<O: ?Sized, T: Lerp + Clone> Tweenable<O> for Tween<O, T> {
+	fn advance(&mut self, delta: Time, target: &mut O) -> Time {
+		if self.finished {
+			return delta
+		}
+		let mut remaining = delta;
+		if self.waited < self.delay {
+			let wait = self.delay - self.waited;
+			if remaining < wait {
+				self.waited = self.waited + remaining;
+				return zero()
+			}
+			self.waited = self.delay;
+			remaining = remaining - wait;
+		}
+		loop {
+			let left = self.duration - self.elapsed;
+			if remaining < left {
+				self.elapsed = self.elapsed + remaining;
+				self.apply(target);
+				return zero()
+			}
+			remaining = remaining - left;
+			self.elapsed = self.duration;
+			self.apply(target);
+			if self.repeat.is_done(self.repeats) {
+				self.finished = true;
+				return remaining
+			}
+			self.repeats += 1;
+			self.elapsed = zero();
+			if self.duration <= zero() && self.repeat == Repeat::Forever {
+				return zero()
+			}
+		}
+	}
+
+	fn is_finished(&self) -> bool {
+		self.finished
+	}
+
+	fn reset(&mut self) {
+		self.elapsed = zero();
+		self.waited = zero();
+		self.repeats = 0;
+		self.finished = false;
+	}
+}
+
+/// Waits for a duration without changing the target.
+///
+/// Mostly useful inside a `Sequence`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delay {
+	duration: Time,
+	elapsed: Time
+}
+
+impl Delay {
+	/// Create a delay of `duration`.
+	pub fn new(duration: Time) -> Delay {
+		Delay {
+			duration: duration,
+			elapsed: zero()
+		}
+	}
+}
+
+impl<O: ?Sized> Tweenable<O> for Delay {
+	fn advance(&mut self, delta: Time, _: &mut O) -> Time {
+		let left = self.duration - self.elapsed;
+		if delta < left {
+			self.elapsed = self.elapsed + delta;
+			zero()
+		} else {
+			self.elapsed = self.duration;
+			delta - left
+		}
+	}
+
+	fn is_finished(&self) -> bool {
+		self.elapsed >= self.duration
+	}
+
+	fn reset(&mut self) {
+		self.elapsed = zero();
+	}
+}
+
+/// Plays animations one after the other.
+///
+/// Time left over when an animation finishes is passed on to the next one,
+/// so a sequence stays in sync regardless of the frame rate.
+pub struct Sequence<'a, O: ?Sized> {
+	children: Vec<Box<Tweenable<O> + 'a>>,
+	current: usize,
+	repeat: Repeat,
+	repeats: u32,
+	finished: bool
+}
+
+impl<'a, O: ?Sized> Sequence<'a, O> {
+	/// Create an empty sequence.
+	pub fn new() -> Sequence<'a, O> {
+		Sequence {
+			children: Vec::new(),
+			current: 0,
+			repeat: Repeat::Count(0),
+			repeats: 0,
+			finished: false
+		}
+	}
+
+	/// Append an animation to the sequence.
+	pub fn add<T: Tweenable<O> + 'a>(&mut self, tween: T) {
+		self.children.push(Box::new(tween));
+	}
+
+	/// Set how many times the whole sequence repeats. The default is
+	/// `Count(0)`.
+	pub fn set_repeat(&mut self, repeat: Repeat) {
+		self.repeat = repeat;
+	}
+
+	/// Get the number of animations in the sequence.
+	pub fn len(&self) -> usize {
+		self.children.len()
+	}
+
+	/// Tell whether the sequence contains no animation.
+	pub fn is_empty(&self) -> bool {
+		self.children.is_empty()
+	}
+}
+
+impl<'a, O: ?Sized> Tweenable<O> for Sequence<'a, O> {
+	fn advance(&mut self, delta: Time, target: &mut O) -> Time {
+		let mut remaining = delta;
+		while !self.finished {
+			let before = remaining;
+			while self.current < self.children.len() {
+				remaining = self.children[self.current].advance(remaining, target);
+				if !self.children[self.current].is_finished() {
+					return zero()
+				}
+				self.current += 1;
+			}
+			if self.repeat.is_done(self.repeats) {
+				self.finished = true;
+			} else {
+				self.repeats += 1;
+				self.current = 0;
+				for child in &mut self.children {
+					child.reset();
+				}
+				if remaining == before && self.repeat == Repeat::Forever {
+					return zero()
+				}
+			}
+		}
+		remaining
+	}
+
+	fn is_finished(&self) -> bool {
+		self.finished
+	}
+
+	fn reset(&mut self) {
+		self.current = 0;
+		self.repeats = 0;
+		self.finished = false;
+		for child in &mut self.children {
+			child.reset();
+		}
+	}
+}
+
+/// Plays animations at the same time.
+///
+/// The group finishes when its longest animation finishes. Animations added
+/// later are applied after earlier ones, so they win if they drive the same
+/// property.
+pub struct Parallel<'a, O: ?Sized> {
+	children: Vec<Box<Tweenable<O> + 'a>>,
+	repeat: Repeat,
+	repeats: u32,
+	finished: bool
+}
+
+impl<'a, O: ?Sized> Parallel<'a, O> {
+	/// Create an empty group.
+	pub fn new() -> Parallel<'a, O> {
+		Parallel {
+			children: Vec::new(),
+			repeat: Repeat::Count(0),
+			repeats: 0,
+			finished: false
+		}
+	}
+
+	/// Add an animation to the group.
+	pub fn add<T: Tweenable<O> + 'a>(&mut self, tween: T) {
+		self.children.push(Box::new(tween));
+	}
+
+	/// Set how many times the whole group repeats. The default is
+	/// `Count(0)`.
+	pub fn set_repeat(&mut self, repeat: Repeat) {
+		self.repeat = repeat;
+	}
+
+	/// Get the number of animations in the group.
+	pub fn len(&self) -> usize {
+		self.children.len()
+	}
+
+	/// Tell whether the group contains no animation.
+	pub fn is_empty(&self) -> bool {
+		self.children.is_empty()
+	}
+}
+
+impl<'a, O: ?Sized> Tweenable<O> for Parallel<'a, O> {
+	fn advance(&mut self, delta: Time, target: &mut O) -> Time {
+		let mut remaining = delta;
+		while !self.finished {
+			let before = remaining;
+			let mut left_over = remaining;
+			let mut running = false;
+			for child in &mut self.children {
+				if child.is_finished() {
+					continue
+				}
+				let rest = child.advance(remaining, target);
+				if child.is_finished() {
+					left_over = if rest < left_over { rest } else { left_over };
+				} else {
+					running = true;
+				}
+			}
+			if running {
+				return zero()
+			}
+			remaining = left_over;
+			if self.repeat.is_done(self.repeats) {
+				self.finished = true;
+			} else {
+				self.repeats += 1;
+				for child in &mut self.children {
+					child.reset();
+				}
+				if remaining == before && self.repeat == Repeat::Forever {
+					return zero()
+				}
+			}
+		}
+		remaining
+	}
+
+	fn is_finished(&self) -> bool {
+		self.finished
+	}
+
+	fn reset(&mut self) {
+		self.repeats = 0;
+		self.finished = false;
+		for child in &mut self.children {
+			child.reset();
+		}
+	}
+}
+
+/// Setter for the position of a `Transformable`.
+pub fn position<O: Transformable + ?Sized>(object: &mut O, value: Vector2f) {
+	object.set_position(value);
+}
+
+/// Setter for the rotation of a `Transformable`, in degrees.
+pub fn rotation<O: Transformable + ?Sized>(object: &mut O, value: f32) {
+	object.set_rotation(value);
+}
+
+/// Setter for the scale of a `Transformable`.
+pub fn scale<O: Transformable + ?Sized>(object: &mut O, value: Vector2f) {
+	object.set_scale(value);
+}
+
+/// Setter for the origin of a `Transformable`.
+pub fn origin<O: Transformable + ?Sized>(object: &mut O, value: Vector2f) {
+	object.set_origin(value);
+}
+
+/// Setter for the color of a `Sprite`.
+pub fn sprite_color(sprite: &mut Sprite, value: Color) {
+	sprite.set_color(value);
+}
+
+/// Setter for the color of a `Text`.
+pub fn text_color(text: &mut Text, value: Color) {
+	text.set_color(value);
+}
+
+/// Setter for the fill color of a `Shape`.
+pub fn fill_color<O: Shape + ?Sized>(shape: &mut O, value: Color) {
+	shape.set_fill_color(value);
+}
+
+/// Setter for the outline color of a `Shape`.
+pub fn outline_color<O: Shape + ?Sized>(shape: &mut O, value: Color) {
+	shape.set_outline_color(value);
+}
+
+/// Setter for the outline thickness of a `Shape`.
+pub fn outline_thickness<O: Shape + ?Sized>(shape: &mut O, value: f32) {
+	shape.set_outline_thickness(value);
+}
+
+/// Setter for the center of a `View`.
+pub fn view_center(view: &mut View, value: Vector2f) {
+	view.set_center(value);
+}
+
+/// Setter for the size of a `View`. Tweening the size zooms the view.
+pub fn view_size(view: &mut View, value: Vector2f) {
+	view.set_size(value);
+}
+
+/// Setter for the rotation of a `View`, in degrees.
+pub fn view_rotation(view: &mut View, value: f32) {
+	view.set_rotation(value);
+}
+
+#[cfg(test)]
+fn set_first(target: &mut (f32, f32), value: f32) {
+	target.0 = value;
+}
+
+#[cfg(test)]
+fn set_second(target: &mut (f32, f32), value: f32) {
+	target.1 = value;
+}
+
+#[test]
+fn easing_endpoints() {
+	let all = [Easing::Linear, Easing::QuadIn, Easing::QuadOut, Easing::QuadInOut,
+	           Easing::CubicIn, Easing::CubicOut, Easing::CubicInOut, Easing::QuartIn,
+	           Easing::QuartOut, Easing::QuartInOut, Easing::QuintIn, Easing::QuintOut,
+	           Easing::QuintInOut, Easing::SineIn, Easing::SineOut, Easing::SineInOut,
+	           Easing::ExpoIn, Easing::ExpoOut, Easing::ExpoInOut, Easing::CircIn,
+	           Easing::CircOut, Easing::CircInOut, Easing::BackIn, Easing::BackOut,
+	           Easing::BackInOut, Easing::ElasticIn, Easing::ElasticOut,
+	           Easing::ElasticInOut, Easing::BounceIn, Easing::BounceOut,
+	           Easing::BounceInOut];
+	for easing in all.iter() {
+		assert!(easing.apply(0.).abs() < 1e-3, "{:?}", easing);
+		assert!((easing.apply(1.) - 1.).abs() < 1e-3, "{:?}", easing);
+	}
+	assert_eq!(Easing::QuadIn.apply(0.5), 0.25);
+	assert_eq!(Easing::QuadInOut.apply(0.25), 0.125);
+	assert!(Easing::BackIn.apply(0.2) < 0.);
+	assert!(Easing::ElasticOut.apply(0.2) > 1.);
+}
+
+#[test]
+fn tween_repeat_and_yoyo() {
+	let mut target = (0., 0.);
+	let mut tween = Tween::new(0., 10., Time::with_seconds(1.), set_first);
+	tween.set_delay(Time::with_seconds(0.5));
+	tween.set_repeat(Repeat::Count(1));
+	tween.set_yoyo(true);
+
+	assert_eq!(tween.advance(Time::with_seconds(0.25), &mut target), zero());
+	assert_eq!(target.0, 0.);
+	tween.advance(Time::with_seconds(0.5), &mut target);
+	assert_eq!(target.0, 2.5);
+	tween.advance(Time::with_seconds(1.), &mut target);
+	assert_eq!(target.0, 7.5);
+	let rest = tween.advance(Time::with_seconds(1.), &mut target);
+	assert!(tween.is_finished());
+	assert_eq!(target.0, 0.);
+	assert_eq!(rest, Time::with_seconds(0.25));
+}
+
+#[test]
+fn tween_groups() {
+	let mut target = (0., 0.);
+	let mut sequence = Sequence::new();
+	sequence.add(Tween::new(0., 1., Time::with_seconds(1.), set_first));
+	sequence.add(Delay::new(Time::with_seconds(1.)));
+	let mut parallel = Parallel::new();
+	parallel.add(Tween::new(1., 0., Time::with_seconds(1.), set_first));
+	parallel.add(Tween::new(0., 4., Time::with_seconds(2.), set_second));
+	sequence.add(parallel);
+
+	sequence.advance(Time::with_seconds(2.5), &mut target);
+	assert_eq!(target, (0.5, 1.));
+	sequence.advance(Time::with_seconds(1.), &mut target);
+	assert_eq!(target, (0., 3.));
+	assert!(!sequence.is_finished());
+	let rest = sequence.advance(Time::with_seconds(1.), &mut target);
+	assert_eq!(target, (0., 4.));
+	assert!(sequence.is_finished());
+	assert_eq!(rest, Time::with_seconds(0.5));
+
+	sequence.reset();
+	sequence.set_repeat(Repeat::Count(1));
+	sequence.advance(Time::with_seconds(4.5), &mut target);
+	assert_eq!(target.0, 0.5);
+}
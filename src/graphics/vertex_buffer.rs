@@ -0,0 +1,356 @@
+/*
+* Rust-SFML - Copyright (c) 2013 Letang Jeremy.
+*
+* The original software, SFML library, is provided by Laurent Gomila.
+*
+* This software is provided 'as-is', without any express or implied warranty.
+* In no event will the authors be held liable for any damages arising from
+* the use of this software.
+*
+* Permission is granted to anyone to use this software for any purpose,
+* including commercial applications, and to alter it and redistribute it
+* freely, subject to the following restrictions:
+*
+* 1. The origin of this software must not be misrepresented; you must not claim
+*    that you wrote the original software. If you use this software in a product,
+*    an acknowledgment in the product documentation would be appreciated but is
+*    not required.
+*
+* 2. Altered source versions must be plainly marked as such, and must not be
+*    misrepresented as being the original software.
+*
+* 3. This notice may not be removed or altered from any source distribution.
+*/
+
+use std::cell::{Cell, RefCell};
+use std::mem;
+use std::ptr;
+
+use graphics::{Vertex, PrimitiveType, RenderTarget, RenderStates, Drawable};
+
+use ffi::gl;
+use ffi::graphics as ffi;
+
+thread_local! {
+	// Buffers dropped since the last draw. Deleting them needs an active
+	// context, so it waits until the next vertex buffer draw on this thread.
+	static DROPPED_BUFFERS: RefCell<Vec<gl::GLuint>> = RefCell::new(Vec::new())
+}
+
+/// Usage hint for the storage of a `VertexBuffer`.
+///
+/// The hint tells the driver how often the vertices are expected to change,
+/// so that it can pick the most appropriate memory.
+#[derive(Clone, PartialEq, Eq, Debug, Copy)]
+pub enum VertexBufferUsage {
+	/// Vertices change every frame.
+	Stream,
+	/// Vertices change from time to time.
+	Dynamic,
+	/// Vertices rarely or never change.
+	Static
+}
+
+/// Vertex storage living in graphics memory.
+///
+/// Unlike a `VertexArray`, whose vertices are sent to the graphics card on
+/// every draw, a vertex buffer is filled through `update` and then drawn as
+/// many times as needed, which saves bandwidth for large static geometry
+/// such as level backgrounds.
+///
+/// The buffer keeps a copy of its vertices in system memory. Updates are
+/// sent to an OpenGL buffer object the next time the buffer is drawn, when
+/// a context is active; only the updated range is uploaded unless the
+/// buffer grew or its usage changed.
+///
+/// When OpenGL buffer objects are not available (see `is_available()`), the
+/// buffer draws its vertices from system memory like a `VertexArray`. The
+/// API behaves identically in both cases, so code does not need to check.
+///
+/// Buffer objects are released at the next vertex buffer draw made on the
+/// thread that dropped the `VertexBuffer`.
+#[derive(Debug)]
+pub struct VertexBuffer {
+	primitive_type: PrimitiveType,
+	usage: VertexBufferUsage,
+	vertices: Vec<Vertex>,
+	buffer: Cell<gl::GLuint>,
+	// Number of vertices the buffer object has room for
+	capacity: Cell<usize>,
+	// Range of vertices changed since the last upload
+	dirty: Cell<Option<(usize, usize)>>
+}
+
+impl VertexBuffer {
+	/// Create a buffer holding `vertex_count` default vertices.
+	///
+	/// The buffer object is created at the first draw, so this cannot fail.
+	pub fn new(primitive_type: PrimitiveType, vertex_count: u32, usage: VertexBufferUsage) -> VertexBuffer {
+		VertexBuffer {
+			primitive_type: primitive_type,
+			usage: usage,
+			vertices: vec![Vertex::default(); vertex_count as usize],
+			buffer: Cell::new(0),
+			capacity: Cell::new(0),
+			dirty: Cell::new(None)
+		}
+	}
+
+	/// Tell whether vertex buffers are stored in graphics memory on this
+	/// system.
+	///
+	/// The answer depends on the OpenGL version of the active context, so a
+	/// window or `Context` must be active on the calling thread. When buffer
+	/// objects are not available, `VertexBuffer` still works, from system
+	/// memory.
+	pub fn is_available() -> bool {
+		gl::has_buffer_objects()
+	}
+
+	/// Get the number of vertices in the buffer.
+	pub fn get_vertex_count(&self) -> u32 {
+		self.vertices.len() as u32
+	}
+
+	/// Get the vertices of the buffer.
+	pub fn get_vertices(&self) -> &[Vertex] {
+		&self.vertices
+	}
+
+	/// Copy vertices into the buffer, starting at vertex `offset`.
+	///
+	/// With an offset of 0, the buffer grows if `vertices` is longer than
+	/// it. Otherwise the vertices must fit in the buffer.
+	///
+	/// Returns false, leaving the buffer unchanged, if they do not.
+	pub fn update(&mut self, vertices: &[Vertex], offset: u32) -> bool {
+		let offset = offset as usize;
+		if offset == 0 && vertices.len() > self.vertices.len() {
+			self.vertices.resize(vertices.len(), Vertex::default());
+		}
+		if offset + vertices.len() > self.vertices.len() {
+			return false
+		}
+		self.vertices[offset..offset + vertices.len()].copy_from_slice(vertices);
+		self.mark_dirty(offset, offset + vertices.len());
+		true
+	}
+
+	/// Replace the contents of the buffer with a copy of another buffer.
+	///
+	/// The primitive type and usage are left unchanged.
+	pub fn update_from(&mut self, other: &VertexBuffer) -> bool {
+		self.vertices.clear();
+		self.vertices.extend_from_slice(&other.vertices);
+		let len = self.vertices.len();
+		self.mark_dirty(0, len);
+		true
+	}
+
+	/// Exchange the contents of two buffers.
+	pub fn swap(&mut self, other: &mut VertexBuffer) {
+		mem::swap(self, other);
+	}
+
+	/// Get the type of the primitives drawn by the buffer.
+	pub fn get_primitive_type(&self) -> PrimitiveType {
+		self.primitive_type
+	}
+
+	/// Set the type of the primitives drawn by the buffer.
+	pub fn set_primitive_type(&mut self, primitive_type: PrimitiveType) {
+		self.primitive_type = primitive_type;
+	}
+
+	/// Get the usage hint of the buffer.
+	pub fn get_usage(&self) -> VertexBufferUsage {
+		self.usage
+	}
+
+	/// Set the usage hint of the buffer.
+	///
+	/// The buffer object is reallocated with the new hint at the next draw.
+	pub fn set_usage(&mut self, usage: VertexBufferUsage) {
+		if usage != self.usage {
+			self.usage = usage;
+			self.capacity.set(0);
+		}
+	}
+
+	/// Draw `count` vertices starting at vertex `first`.
+	///
+	/// The range is clamped to the buffer.
+	pub fn draw_range(&self, target: &mut RenderTarget, states: &RenderStates, first: u32, count: u32) {
+		let (first, count) = self.clamp_range(first, count);
+		if count > 0 {
+			target.draw_vertex_buffer_rs(self, first, count, states);
+		}
+	}
+
+	/// Clamp a range of `count` vertices starting at `first` to the buffer.
+	#[doc(hidden)]
+	pub fn clamp_range(&self, first: u32, count: u32) -> (u32, u32) {
+		let first = first.min(self.get_vertex_count());
+		(first, count.min(self.get_vertex_count() - first))
+	}
+
+	fn mark_dirty(&self, start: usize, end: usize) {
+		let range = match self.dirty.get() {
+			Some((dirty_start, dirty_end)) => (dirty_start.min(start), dirty_end.max(end)),
+			None => (start, end)
+		};
+		self.dirty.set(Some(range));
+	}
+
+	// Send the pending changes to the buffer object and leave it bound.
+	// Needs an active context.
+	fn upload(&self) {
+		let size = mem::size_of::<Vertex>();
+		unsafe {
+			if self.buffer.get() == 0 {
+				let mut buffer = 0;
+				gl::glGenBuffers(1, &mut buffer);
+				self.buffer.set(buffer);
+				self.capacity.set(0);
+			}
+			gl::glBindBuffer(gl::GL_ARRAY_BUFFER, self.buffer.get());
+			if self.capacity.get() != self.vertices.len() {
+				let usage = match self.usage {
+					VertexBufferUsage::Stream => gl::GL_STREAM_DRAW,
+					VertexBufferUsage::Dynamic => gl::GL_DYNAMIC_DRAW,
+					VertexBufferUsage::Static => gl::GL_STATIC_DRAW
+				};
+				gl::glBufferData(gl::GL_ARRAY_BUFFER, (self.vertices.len() * size) as gl::GLsizeiptr,
+				                 self.vertices.as_ptr() as *const _, usage);
+				self.capacity.set(self.vertices.len());
+			} else if let Some((start, end)) = self.dirty.get() {
+				gl::glBufferSubData(gl::GL_ARRAY_BUFFER, (start * size) as gl::GLintptr,
+				                    ((end - start) * size) as gl::GLsizeiptr,
+				                    self.vertices[start..].as_ptr() as *const _);
+			}
+		}
+		self.dirty.set(None);
+	}
+
+	/// Draw vertices from the buffer object for a render target.
+	///
+	/// `draw_primitives` must draw vertices with `states` through CSFML.
+	/// It is first called with degenerate triangles, which makes CSFML set
+	/// up the view, transform, texture and blend mode; the buffer is then
+	/// drawn with those. If buffer objects are not available, the vertices
+	/// are passed to `draw_primitives` instead.
+	///
+	/// The range is clamped to the buffer.
+	#[doc(hidden)]
+	pub fn draw_with<F>(&self, first: u32, count: u32, states: &ffi::sfRenderStates, mut draw_primitives: F)
+		where F: FnMut(&[Vertex], PrimitiveType) {
+		let (first, count) = self.clamp_range(first, count);
+		if count == 0 {
+			return
+		}
+		// More than 4 vertices, so that SFML loads the transform instead of
+		// applying it to the vertices itself
+		draw_primitives(&[Vertex::default(); 6], PrimitiveType::Triangles);
+		if !gl::has_buffer_objects() {
+			let first = first as usize;
+			return draw_primitives(&self.vertices[first..first + count as usize], self.primitive_type)
+		}
+
+		let dropped = DROPPED_BUFFERS.with(|dropped| mem::replace(&mut *dropped.borrow_mut(), Vec::new()));
+		let mode = match self.primitive_type {
+			PrimitiveType::Points => gl::GL_POINTS,
+			PrimitiveType::Lines => gl::GL_LINES,
+			PrimitiveType::LinesStrip => gl::GL_LINE_STRIP,
+			PrimitiveType::Triangles => gl::GL_TRIANGLES,
+			PrimitiveType::TrianglesStrip => gl::GL_TRIANGLE_STRIP,
+			PrimitiveType::TrianglesFan => gl::GL_TRIANGLE_FAN,
+			PrimitiveType::Quads => gl::GL_QUADS
+		};
+		let stride = mem::size_of::<Vertex>() as gl::GLsizei;
+		unsafe {
+			if !dropped.is_empty() {
+				gl::glDeleteBuffers(dropped.len() as gl::GLsizei, dropped.as_ptr());
+			}
+			self.upload();
+			// CSFML unbinds the shader at the end of each draw
+			if !states.shader.is_null() {
+				ffi::sfShader_bind(states.shader as *mut _);
+			}
+			gl::glVertexPointer(2, gl::GL_FLOAT, stride, ptr::null());
+			gl::glColorPointer(4, gl::GL_UNSIGNED_BYTE, stride, 8 as *const _);
+			gl::glTexCoordPointer(2, gl::GL_FLOAT, stride, 12 as *const _);
+			gl::glDrawArrays(mode, first as gl::GLint, count as gl::GLsizei);
+			gl::glBindBuffer(gl::GL_ARRAY_BUFFER, 0);
+			if !states.shader.is_null() {
+				ffi::sfShader_bind(ptr::null_mut());
+			}
+		}
+	}
+}
+
+impl Clone for VertexBuffer {
+	fn clone(&self) -> VertexBuffer {
+		VertexBuffer {
+			primitive_type: self.primitive_type,
+			usage: self.usage,
+			vertices: self.vertices.clone(),
+			buffer: Cell::new(0),
+			capacity: Cell::new(0),
+			dirty: Cell::new(None)
+		}
+	}
+}
+
+impl PartialEq for VertexBuffer {
+	fn eq(&self, other: &VertexBuffer) -> bool {
+		self.primitive_type == other.primitive_type && self.usage == other.usage &&
+			self.vertices == other.vertices
+	}
+}
+
+impl Drop for VertexBuffer {
+	fn drop(&mut self) {
+		let buffer = self.buffer.get();
+		if buffer != 0 {
+			DROPPED_BUFFERS.with(|dropped| dropped.borrow_mut().push(buffer));
+		}
+	}
+}
+
+impl Drawable for VertexBuffer {
+	fn draw(&self, target: &mut RenderTarget, states: &RenderStates) {
+		self.draw_range(target, states, 0, self.get_vertex_count());
+	}
+}
+
+#[test]
+fn vertex_buffer_updates() {
+	use system::Vector2f;
+
+	let vertex = |x: f32| Vertex::new_with_pos(Vector2f::new(x, 0.));
+	let mut buffer = VertexBuffer::new(PrimitiveType::Triangles, 3, VertexBufferUsage::Static);
+	assert!(buffer.update(&[vertex(1.), vertex(2.)], 1));
+	assert_eq!(buffer.vertices[2].position.x, 2.);
+	assert!(!buffer.update(&[vertex(1.), vertex(2.)], 2));
+	assert_eq!(buffer.get_vertex_count(), 3);
+	assert!(buffer.update(&[vertex(4.); 6], 0));
+	assert_eq!(buffer.get_vertex_count(), 6);
+
+	let mut copy = VertexBuffer::new(PrimitiveType::Points, 0, VertexBufferUsage::Stream);
+	assert!(copy.update_from(&buffer));
+	assert_eq!(copy.vertices, buffer.vertices);
+	assert_eq!(copy.get_primitive_type(), PrimitiveType::Points);
+
+	// Changes are queued for upload to the buffer object
+	assert_eq!(buffer.dirty.get(), Some((0, 6)));
+	buffer.dirty.set(None);
+	assert!(buffer.update(&[vertex(5.)], 3));
+	assert!(buffer.update(&[vertex(6.)], 1));
+	assert_eq!(buffer.dirty.get(), Some((1, 4)));
+	assert_eq!(buffer.clone(), buffer);
+
+	assert_eq!(buffer.clamp_range(2, 3), (2, 3));
+	assert_eq!(buffer.clamp_range(4, 10), (4, 2));
+	assert_eq!(buffer.clamp_range(10, 1), (6, 0));
+	assert_eq!(buffer.clamp_range(0xffffffff, 0xffffffff), (6, 0));
+}
@@ -107,3 +107,4 @@ pub mod window;
 pub mod audio;
 pub mod graphics;
 mod ffi;
+mod formats;